@@ -0,0 +1,52 @@
+//! simulates without a window and writes the particle positions of every few steps to csv files
+
+use std::{fs::File, io::Write};
+
+use particles::prelude::*;
+
+const NUM_STEPS: usize = 600;
+const EXPORT_INTERVAL: usize = 60;
+const DELTA_T: f32 = 1.0 / 60.0;
+
+fn main() -> std::io::Result<()> {
+    env_logger::init();
+    let instance = wgpu::Instance::default();
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .expect("no suitable GPU adapter");
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: Some("headless export device"),
+            features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default().using_resolution(adapter.limits()),
+        },
+        None,
+    ))
+    .expect("could not create a device");
+
+    let mut sim_params = SimParams::new();
+    for (i, force) in sim_params.attraction_force.iter_mut().enumerate() {
+        // types attract themselves and repel the others
+        *force = Poly7::const_val(if i % 6 == 0 { 0.5 } else { -0.2 });
+    }
+    let mut engine = ParticlesEngine::builder()
+        .num_particles(2000)
+        .sim_params(sim_params)
+        .seed(1)
+        .build(device, queue);
+
+    std::fs::create_dir_all("export")?;
+    for step in 0..=NUM_STEPS {
+        if step % EXPORT_INTERVAL == 0 {
+            let path = format!("export/particles_{:04}.csv", step);
+            let mut file = File::create(&path)?;
+            writeln!(file, "x,y,z,type")?;
+            for p in engine.read_particles() {
+                writeln!(file, "{},{},{},{}", p.pos[0], p.pos[1], p.pos[2], p.ty)?;
+            }
+            println!("t = {:.2}s: wrote {}", engine.sim_time(), path);
+        }
+        engine.step(DELTA_T);
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! renders the particles into a plain winit window, without the gui of the application
+
+use std::time::Instant;
+
+use particles::prelude::*;
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title("particles: minimal window")
+        .build(&event_loop)
+        .unwrap();
+    let instance = wgpu::Instance::default();
+    let surface = unsafe { instance.create_surface(&window) }.unwrap();
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        compatible_surface: Some(&surface),
+        ..Default::default()
+    }))
+    .expect("no suitable GPU adapter");
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: Some("minimal window device"),
+            features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default().using_resolution(adapter.limits()),
+        },
+        None,
+    ))
+    .expect("could not create a device");
+
+    let size = window.inner_size();
+    let format = surface.get_capabilities(&adapter).formats[0];
+    let mut surface_config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format,
+        width: size.width.max(1),
+        height: size.height.max(1),
+        present_mode: wgpu::PresentMode::Fifo,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: vec![],
+    };
+    surface.configure(&device, &surface_config);
+
+    let mut sim_params = SimParams::new();
+    // a weak constant attraction between all particles within the cut off distance
+    for force in sim_params.attraction_force.iter_mut() {
+        *force = Poly7::const_val(0.5);
+    }
+    let mut engine = ParticlesEngine::builder()
+        .num_particles(4000)
+        .sim_params(sim_params)
+        .size(surface_config.width, surface_config.height)
+        .format(format)
+        .build(device, queue);
+    engine.camera_mut().follow_center_of_mass = true;
+
+    let mut last_frame = Instant::now();
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => *control_flow = ControlFlow::Exit,
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } if size.width > 0 && size.height > 0 => {
+                surface_config.width = size.width;
+                surface_config.height = size.height;
+                surface.configure(engine.device(), &surface_config);
+                engine.resize(size.width, size.height);
+            }
+            Event::RedrawEventsCleared => window.request_redraw(),
+            Event::RedrawRequested(_) => {
+                let delta_t = last_frame.elapsed().as_secs_f32();
+                last_frame = Instant::now();
+                engine.step(delta_t);
+                let frame = match surface.get_current_texture() {
+                    Ok(frame) => frame,
+                    Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                        surface.configure(engine.device(), &surface_config);
+                        return;
+                    }
+                    Err(e) => panic!("{}", e),
+                };
+                let view = frame
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                engine.render(&view);
+                frame.present();
+            }
+            _ => {}
+        }
+    });
+}
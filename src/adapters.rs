@@ -0,0 +1,86 @@
+use wgpu::{Adapter, AdapterInfo, Instance, Limits, Surface};
+
+/// file remembering the adapter picked in the startup dialog
+#[cfg(not(target_arch = "wasm32"))]
+const CHOICE_PATH: &str = "adapter.txt";
+
+/// an adapter that can draw to the window, listed in the startup dialog
+#[derive(Clone, Debug)]
+pub struct AdapterEntry {
+    pub info: AdapterInfo,
+    pub limits: Limits,
+}
+
+impl AdapterEntry {
+    pub fn new(adapter: &Adapter) -> Self {
+        AdapterEntry {
+            info: adapter.get_info(),
+            limits: adapter.limits(),
+        }
+    }
+
+    /// name and backend identify an adapter across runs, the device ids are not stable
+    pub fn matches(&self, info: &AdapterInfo) -> bool {
+        self.info.name == info.name && self.info.backend == info.backend
+    }
+
+    pub fn label(&self) -> String {
+        format!(
+            "{} ({:?}, {:?})",
+            self.info.name, self.info.backend, self.info.device_type
+        )
+    }
+}
+
+/// all adapters of the instance that support the surface
+#[cfg(not(target_arch = "wasm32"))]
+pub fn compatible_adapters(instance: &Instance, surface: &Surface) -> Vec<Adapter> {
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .filter(|adapter| adapter.is_surface_supported(surface))
+        .collect()
+}
+
+/// the browser only hands out the adapter it picks itself
+#[cfg(target_arch = "wasm32")]
+pub fn compatible_adapters(_instance: &Instance, _surface: &Surface) -> Vec<Adapter> {
+    Vec::new()
+}
+
+/// the adapter saved by `save_choice`, as `(backend, name)`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_choice() -> Option<(String, String)> {
+    let text = std::fs::read_to_string(CHOICE_PATH).ok()?;
+    let (backend, name) = text.trim_end().split_once(' ')?;
+    Some((backend.to_string(), name.to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load_choice() -> Option<(String, String)> {
+    None
+}
+
+/// remembers the adapter for the next start
+pub fn save_choice(info: &AdapterInfo) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(e) = std::fs::write(CHOICE_PATH, format!("{:?} {}\n", info.backend, info.name)) {
+        log::warn!("could not save the adapter choice: {}", e);
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = info;
+}
+
+/// the saved adapter, if it is still present and supports the surface
+pub fn find_chosen(instance: &Instance, surface: &Surface) -> Option<Adapter> {
+    let (backend, name) = load_choice()?;
+    let adapter = compatible_adapters(instance, surface)
+        .into_iter()
+        .find(|adapter| {
+            let info = adapter.get_info();
+            format!("{:?}", info.backend) == backend && info.name == name
+        });
+    if adapter.is_none() {
+        log::warn!("the saved adapter {} ({}) is not available", name, backend);
+    }
+    adapter
+}
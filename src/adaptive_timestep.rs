@@ -0,0 +1,45 @@
+/// smallest fraction of the requested time step the limit goes down to,
+/// a single runaway particle must not freeze the simulation
+const MIN_FRACTION: f32 = 0.01;
+
+/// limits the time step so the fastest particle moves at most a fraction of the
+/// cut off distance per step, like the CFL condition of fluid solvers
+pub struct AdaptiveTimestep {
+    pub enabled: bool,
+    /// fraction of the shortest cut off distance the fastest particle may move in one step
+    pub max_step_fraction: f32,
+    /// time step of the last frame after limiting
+    pub effective_delta_t: f32,
+    /// time step of the last frame before limiting
+    pub requested_delta_t: f32,
+}
+
+impl AdaptiveTimestep {
+    pub fn new() -> Self {
+        AdaptiveTimestep {
+            enabled: false,
+            max_step_fraction: 0.1,
+            effective_delta_t: 0.0,
+            requested_delta_t: 0.0,
+        }
+    }
+
+    /// `delta_t` shortened to the largest step `max_speed` allows, the speed comes from
+    /// the reduction and lags a few frames behind
+    pub fn limit(&mut self, delta_t: f32, max_speed: Option<f32>, cut_off_distance: f32) -> f32 {
+        self.requested_delta_t = delta_t;
+        self.effective_delta_t = match max_speed.filter(|_| self.enabled) {
+            Some(max_speed) if max_speed > 0.0 => {
+                let max_delta_t = self.max_step_fraction * cut_off_distance / max_speed;
+                delta_t.min(max_delta_t.max(delta_t * MIN_FRACTION))
+            }
+            _ => delta_t,
+        };
+        self.effective_delta_t
+    }
+
+    /// the last time step was shortened
+    pub fn is_limiting(&self) -> bool {
+        self.effective_delta_t < self.requested_delta_t
+    }
+}
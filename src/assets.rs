@@ -0,0 +1,305 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, Sender},
+    time::{Duration, SystemTime},
+};
+
+use instant::Instant;
+
+use crate::V3;
+
+/// how often the loaded asset files are checked for modifications
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// a triangle mesh in the vertex and index format of `DrawPass`
+pub struct Mesh {
+    pub vertices: Vec<(V3, [f32; 2])>,
+    pub indices: Vec<u16>,
+}
+
+impl Mesh {
+    /// the first model of an obj file, materials are ignored
+    pub fn from_obj_bytes(mut bytes: &[u8]) -> Result<Mesh, String> {
+        let (models, _) = tobj::load_obj_buf(&mut bytes, &tobj::GPU_LOAD_OPTIONS, |_| {
+            Err(tobj::LoadError::OpenFileFailed)
+        })
+        .map_err(|e| e.to_string())?;
+        Self::from_models(models)
+    }
+
+    pub fn from_obj_file(path: &Path) -> Result<Mesh, String> {
+        let (models, _) =
+            tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS).map_err(|e| e.to_string())?;
+        Self::from_models(models)
+    }
+
+    fn from_models(models: Vec<tobj::Model>) -> Result<Mesh, String> {
+        let mesh = &models.first().ok_or("the file contains no model")?.mesh;
+        if mesh.positions.len() / 3 > u16::MAX as usize {
+            return Err(String::from("the mesh has more than 65535 vertices"));
+        }
+        if mesh.texcoords.len() / 2 != mesh.positions.len() / 3 {
+            return Err(String::from("every vertex needs texture coordinates"));
+        }
+        let vertices = mesh
+            .positions
+            .chunks(3)
+            .zip(mesh.texcoords.chunks(2))
+            .map(|(p, tc)| (V3::new(p[0], p[1], p[2]), [tc[0], tc[1]]))
+            .collect();
+        let indices = mesh.indices.iter().map(|i| *i as u16).collect();
+        Ok(Mesh { vertices, indices })
+    }
+}
+
+/// a mesh or texture drawn by the renderer that can be replaced at runtime
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetSlot {
+    CursorMesh,
+    CursorTexture,
+    VectorMesh,
+    VectorTexture,
+}
+
+impl AssetSlot {
+    pub const ALL: [AssetSlot; 4] = [
+        AssetSlot::CursorMesh,
+        AssetSlot::CursorTexture,
+        AssetSlot::VectorMesh,
+        AssetSlot::VectorTexture,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AssetSlot::CursorMesh => "cursor mesh",
+            AssetSlot::CursorTexture => "cursor texture",
+            AssetSlot::VectorMesh => "vector mesh",
+            AssetSlot::VectorTexture => "vector texture",
+        }
+    }
+
+    /// file name in the assets directory
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            AssetSlot::CursorMesh => "cursor.obj",
+            AssetSlot::CursorTexture => "cursor.png",
+            AssetSlot::VectorMesh => "vector.obj",
+            AssetSlot::VectorTexture => "vector.png",
+        }
+    }
+
+    pub fn is_mesh(&self) -> bool {
+        matches!(self, AssetSlot::CursorMesh | AssetSlot::VectorMesh)
+    }
+
+    /// the copy of the asset built into the binary
+    fn embedded_bytes(&self) -> &'static [u8] {
+        match self {
+            AssetSlot::CursorMesh => include_bytes!("../assets/cursor.obj"),
+            AssetSlot::CursorTexture => include_bytes!("../assets/cursor.png"),
+            AssetSlot::VectorMesh => include_bytes!("../assets/vector.obj"),
+            AssetSlot::VectorTexture => include_bytes!("../assets/vector.png"),
+        }
+    }
+
+    /// the built in asset, used until a file was loaded or when loading fails
+    pub fn embedded(&self) -> AssetData {
+        Self::decode(*self, self.embedded_bytes()).expect("the embedded assets are valid")
+    }
+
+    fn decode(self, bytes: &[u8]) -> Result<AssetData, String> {
+        if self.is_mesh() {
+            Mesh::from_obj_bytes(bytes).map(AssetData::Mesh)
+        } else {
+            image::load_from_memory(bytes)
+                .map(|image| AssetData::Texture(image.to_rgba8()))
+                .map_err(|e| e.to_string())
+        }
+    }
+
+    fn load(self, path: &Path) -> Result<AssetData, String> {
+        if self.is_mesh() {
+            // obj files can reference material files next to them, so tobj reads the path itself
+            Mesh::from_obj_file(path).map(AssetData::Mesh)
+        } else {
+            let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+            self.decode(&bytes)
+        }
+    }
+}
+
+pub enum AssetData {
+    Mesh(Mesh),
+    Texture(image::RgbaImage),
+}
+
+/// directories searched for asset files, in order: `PARTICLES_ASSETS`, `assets` in the
+/// working directory, next to the executable and in the crate
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os("PARTICLES_ASSETS") {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs.push(PathBuf::from("assets"));
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        dirs.push(exe_dir.join("assets"));
+    }
+    dirs.push(PathBuf::from(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets"
+    )));
+    dirs
+}
+
+/// the first existing file called `file_name` in the asset search directories
+pub fn resolve(file_name: &str) -> Option<PathBuf> {
+    search_dirs()
+        .into_iter()
+        .map(|dir| dir.join(file_name))
+        .find(|path| path.is_file())
+}
+
+struct LoadResult {
+    slot: AssetSlot,
+    path: PathBuf,
+    data: Result<AssetData, String>,
+}
+
+/// loads replacement meshes and textures on background threads, the renderer starts with the
+/// embedded assets and swaps in every asset that finished loading
+pub struct AssetManager {
+    /// file of every slot, edited in the gui
+    pub paths: [String; 4],
+    /// files dropped onto the window replace the cursor assets if set, else the vector assets
+    pub drop_on_cursor: bool,
+    /// the last load error of every slot that failed to load
+    pub errors: Vec<(AssetSlot, String)>,
+    /// files currently used, with their modification time when they were loaded
+    loaded: Vec<(AssetSlot, PathBuf, Option<SystemTime>)>,
+    pending: usize,
+    sender: Sender<LoadResult>,
+    receiver: Receiver<LoadResult>,
+    last_check: Instant,
+}
+
+impl AssetManager {
+    /// starts loading the asset files found in the search directories
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        let paths = AssetSlot::ALL.map(|slot| {
+            resolve(slot.file_name())
+                .map(|path| path.display().to_string())
+                .unwrap_or_default()
+        });
+        let mut assets = AssetManager {
+            paths,
+            drop_on_cursor: true,
+            errors: Vec::new(),
+            loaded: Vec::new(),
+            pending: 0,
+            sender,
+            receiver,
+            last_check: Instant::now(),
+        };
+        assets.reload_all();
+        assets
+    }
+
+    /// number of assets still loading
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// loads the file of every slot that has one
+    pub fn reload_all(&mut self) {
+        for slot in AssetSlot::ALL {
+            self.reload(slot);
+        }
+    }
+
+    /// loads the file set for `slot`, an empty path keeps the embedded asset
+    pub fn reload(&mut self, slot: AssetSlot) {
+        let path = &self.paths[slot as usize];
+        if !path.is_empty() {
+            self.load(slot, PathBuf::from(path));
+        }
+    }
+
+    /// replaces the mesh or texture of the drop target, depending on the file extension
+    pub fn load_dropped(&mut self, path: &Path) {
+        let is_mesh = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("obj"));
+        let slot = match (self.drop_on_cursor, is_mesh) {
+            (true, true) => AssetSlot::CursorMesh,
+            (true, false) => AssetSlot::CursorTexture,
+            (false, true) => AssetSlot::VectorMesh,
+            (false, false) => AssetSlot::VectorTexture,
+        };
+        self.paths[slot as usize] = path.display().to_string();
+        self.load(slot, path.to_path_buf());
+    }
+
+    fn load(&mut self, slot: AssetSlot, path: PathBuf) {
+        self.pending += 1;
+        let sender = self.sender.clone();
+        let job = move || {
+            let data = slot.load(&path);
+            // the manager is gone when the app shut down while loading
+            let _ = sender.send(LoadResult { slot, path, data });
+        };
+        cfg_if::cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                job();
+            } else {
+                std::thread::spawn(job);
+            }
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// assets that finished loading since the last call, modified files are reloaded
+    pub fn poll(&mut self) -> Vec<(AssetSlot, AssetData)> {
+        if self.last_check.elapsed() >= CHECK_INTERVAL {
+            self.last_check = Instant::now();
+            let mut changed = Vec::new();
+            for (slot, path, modified) in self.loaded.iter_mut() {
+                let current = Self::modified(path);
+                if current.is_some() && current != *modified {
+                    // a failed reload is retried with the next modification
+                    *modified = current;
+                    changed.push((*slot, path.clone()));
+                }
+            }
+            for (slot, path) in changed {
+                self.load(slot, path);
+            }
+        }
+        let mut res = Vec::new();
+        for LoadResult { slot, path, data } in self.receiver.try_iter() {
+            self.pending -= 1;
+            self.errors.retain(|(s, _)| *s != slot);
+            match data {
+                Ok(data) => {
+                    log::info!("loaded {} from {}", slot.name(), path.display());
+                    self.loaded.retain(|(s, _, _)| *s != slot);
+                    let modified = Self::modified(&path);
+                    self.loaded.push((slot, path, modified));
+                    res.push((slot, data));
+                }
+                Err(e) => {
+                    log::error!("could not load {}: {}", path.display(), e);
+                    self.errors
+                        .push((slot, format!("{}: {}", path.display(), e)));
+                }
+            }
+        }
+        res
+    }
+}
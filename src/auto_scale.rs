@@ -0,0 +1,62 @@
+/// fewest particles the auto scaling reduces to
+const MIN_PARTICLES: usize = 100;
+/// frames faster than this fraction of the target count as headroom for more particles
+const HEADROOM: f32 = 0.8;
+
+/// adjusts the number of particles to keep the frame time near a target
+pub struct AutoScale {
+    pub enabled: bool,
+    /// seconds per frame to stay below
+    pub target_frame_time: f32,
+    /// consecutive slow or fast frames before the particle count changes
+    pub num_frames: usize,
+    /// fraction of the particles removed or added per change
+    pub step: f32,
+    /// upper limit when adding particles
+    pub max_particles: usize,
+    slow_frames: usize,
+    fast_frames: usize,
+}
+
+impl AutoScale {
+    pub fn new(max_particles: usize) -> Self {
+        AutoScale {
+            enabled: false,
+            target_frame_time: 1.0 / 60.0,
+            num_frames: 30,
+            step: 0.1,
+            max_particles,
+            slow_frames: 0,
+            fast_frames: 0,
+        }
+    }
+
+    /// records the time of the last frame, returns the new particle count
+    /// once enough frames in a row were too slow or fast enough
+    pub fn frame(&mut self, frame_time: f32, num_particles: usize) -> Option<usize> {
+        if !self.enabled {
+            return None;
+        }
+        if frame_time > self.target_frame_time {
+            self.slow_frames += 1;
+            self.fast_frames = 0;
+        } else if frame_time < self.target_frame_time * HEADROOM {
+            self.fast_frames += 1;
+            self.slow_frames = 0;
+        } else {
+            self.slow_frames = 0;
+            self.fast_frames = 0;
+        }
+        let change = ((num_particles as f32 * self.step) as usize).max(1);
+        let new_count = if self.slow_frames >= self.num_frames {
+            num_particles.saturating_sub(change).max(MIN_PARTICLES)
+        } else if self.fast_frames >= self.num_frames {
+            (num_particles + change).min(self.max_particles)
+        } else {
+            return None;
+        };
+        self.slow_frames = 0;
+        self.fast_frames = 0;
+        (new_count != num_particles).then_some(new_count)
+    }
+}
@@ -0,0 +1,194 @@
+use std::{fs::File, io::Write, str::FromStr, time::Instant};
+
+use crate::{
+    compute::Compute, cursor::get_attractors_raw, framework, precision::ParticlePrecision,
+    reactions::get_reaction_rules_raw, ParticleSystem, SimParams, V3,
+};
+use wgpu::*;
+
+/// simulated seconds per step of a scenario
+const BENCH_DELTA_T: f32 = 1.0 / 60.0;
+/// steps before the clock starts, the pipelines and caches warm up in them
+const WARMUP_STEPS: usize = 10;
+/// the particle counts and force grid dimensions, every count is run with every grid
+const BENCH_PARTICLES: [usize; 3] = [10_000, 50_000, 200_000];
+const BENCH_GRID_DIMENSIONS: [u32; 3] = [8, 16, 32];
+
+/// a headless run of fixed scenarios which times the compute pipeline,
+/// started with `--bench <steps>`
+pub struct BenchConfig {
+    pub num_steps: usize,
+    pub output: String,
+}
+
+impl BenchConfig {
+    /// parses `--bench [steps] [--output file]`, the adapter can be picked with
+    /// WGPU_ADAPTER_NAME and WGPU_BACKEND
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let position = args.iter().position(|arg| arg == "--bench")?;
+        let value = |name: &str| {
+            args.iter()
+                .position(|arg| arg == name)
+                .and_then(|i| args.get(i + 1))
+        };
+        Some(BenchConfig {
+            num_steps: args
+                .get(position + 1)
+                .and_then(|v| usize::from_str(v).ok())
+                .unwrap_or(200)
+                .max(1),
+            output: value("--output")
+                .cloned()
+                .unwrap_or_else(|| String::from("bench.json")),
+        })
+    }
+}
+
+struct ScenarioResult {
+    num_particles: usize,
+    grid_dimensions: u32,
+    ms_per_step: f64,
+}
+
+impl ScenarioResult {
+    fn steps_per_sec(&self) -> f64 {
+        1000.0 / self.ms_per_step
+    }
+}
+
+/// runs every scenario on the default adapter, prints the timings and writes them as json
+pub fn run(config: &BenchConfig) -> std::io::Result<()> {
+    let instance = framework::create_instance();
+    let adapter = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(
+        &instance, None,
+    ))
+    .ok_or_else(|| std::io::Error::other("no GPU adapters found"))?;
+    let info = adapter.get_info();
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &DeviceDescriptor {
+            label: Some("bench device descriptor"),
+            features: Features::empty(),
+            limits: Limits::default().using_resolution(adapter.limits()),
+        },
+        None,
+    ))
+    .map_err(std::io::Error::other)?;
+    println!("benchmarking on {} ({:?})", info.name, info.backend);
+    let mut results = Vec::new();
+    for num_particles in BENCH_PARTICLES {
+        for grid_dimensions in BENCH_GRID_DIMENSIONS {
+            let ms_per_step = time_scenario(
+                &device,
+                &queue,
+                num_particles,
+                grid_dimensions,
+                config.num_steps,
+            );
+            let result = ScenarioResult {
+                num_particles,
+                grid_dimensions,
+                ms_per_step,
+            };
+            println!(
+                "{:>7} particles, {:>2}^3 grid: {:8.3} ms/step, {:8.1} steps/sec",
+                num_particles,
+                grid_dimensions,
+                result.ms_per_step,
+                result.steps_per_sec()
+            );
+            results.push(result);
+        }
+    }
+    let mut file = File::create(&config.output)?;
+    writeln!(file, "{{")?;
+    writeln!(file, "  \"adapter\": {},", json_string(&info.name))?;
+    writeln!(
+        file,
+        "  \"backend\": {},",
+        json_string(&format!("{:?}", info.backend))
+    )?;
+    writeln!(file, "  \"steps\": {},", config.num_steps)?;
+    writeln!(file, "  \"scenarios\": [")?;
+    for (i, r) in results.iter().enumerate() {
+        writeln!(
+            file,
+            "    {{\"particles\": {}, \"grid_dimensions\": [{d}, {d}, {d}], \"ms_per_step\": {:.4}, \"steps_per_sec\": {:.2}}}{}",
+            r.num_particles,
+            r.ms_per_step,
+            r.steps_per_sec(),
+            if i + 1 < results.len() { "," } else { "" },
+            d = r.grid_dimensions,
+        )?;
+    }
+    writeln!(file, "  ]")?;
+    writeln!(file, "}}")?;
+    println!("{} scenarios written to {}", results.len(), config.output);
+    Ok(())
+}
+
+/// a quoted json string, only the characters adapter names may contain are escaped
+fn json_string(s: &str) -> String {
+    let mut res = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                res.push('\\');
+                res.push(c);
+            }
+            c if c.is_control() => res.push(' '),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// milliseconds per step of the default simulation with `num_particles` particles
+/// and a cubic force grid, measured from the submit of the first step to the end of the last
+fn time_scenario(
+    device: &Device,
+    queue: &Queue,
+    num_particles: usize,
+    grid_dimensions: u32,
+    num_steps: usize,
+) -> f64 {
+    let mut sim_params = SimParams::new();
+    sim_params.delta_t = BENCH_DELTA_T;
+    sim_params.force_grid_dimensions = [grid_dimensions; 3];
+    let mut psys = ParticleSystem::new(V3::new(5.0, 2.0, 2.0), 0, 0, 0, 0, &sim_params);
+    psys.set_num_particles(num_particles);
+    let mut compute = Compute::new(
+        device,
+        queue,
+        ParticlePrecision::Full,
+        &psys.particles,
+        &psys.get_layered_force_vectors().0,
+        &get_reaction_rules_raw(&[]).0,
+        &get_attractors_raw(&[]).0,
+    );
+    compute.update_sim_params(device, queue, &sim_params);
+    let step = |compute: &mut Compute| {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("bench command encoder"),
+        });
+        {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("bench compute pass"),
+                timestamp_writes: None,
+            });
+            compute.compute(&mut cpass);
+        }
+        queue.submit(Some(encoder.finish()));
+    };
+    for _ in 0..WARMUP_STEPS {
+        step(&mut compute);
+    }
+    device.poll(Maintain::Wait);
+    let start = Instant::now();
+    for _ in 0..num_steps {
+        step(&mut compute);
+    }
+    device.poll(Maintain::Wait);
+    start.elapsed().as_secs_f64() * 1000.0 / num_steps as f64
+}
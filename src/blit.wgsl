@@ -0,0 +1,22 @@
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) tex_coord: vec2<f32>,
+};
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+
+// a single triangle covering the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+  let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+  var out: VertexOutput;
+  out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+  out.tex_coord = vec2<f32>(uv.x, 1.0 - uv.y);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return textureSample(source, source_sampler, in.tex_coord);
+}
@@ -0,0 +1,74 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundaryAnimationMode {
+    Off,
+    Sine,
+    Keyframes,
+}
+
+/// animates the bounding volume radius over simulated time
+pub struct BoundaryAnimation {
+    pub mode: BoundaryAnimationMode,
+    /// radius the sine modulation oscillates around
+    pub base_radius: f32,
+    /// relative amplitude of the sine modulation
+    pub amplitude: f32,
+    /// seconds per sine period
+    pub period: f32,
+    /// (time, radius) pairs sorted by time, the animation loops after the last one
+    pub keyframes: Vec<(f32, f32)>,
+    pub time: f32,
+}
+
+impl BoundaryAnimation {
+    pub fn new(base_radius: f32) -> Self {
+        BoundaryAnimation {
+            mode: BoundaryAnimationMode::Off,
+            base_radius,
+            amplitude: 0.3,
+            period: 20.0,
+            keyframes: vec![(0.0, base_radius), (10.0, base_radius * 0.5)],
+            time: 0.0,
+        }
+    }
+
+    pub fn radius_at(&self, time: f32) -> Option<f32> {
+        match self.mode {
+            BoundaryAnimationMode::Off => None,
+            BoundaryAnimationMode::Sine => {
+                let phase = time / self.period * std::f32::consts::TAU;
+                Some(self.base_radius * (1.0 + self.amplitude * phase.sin()))
+            }
+            BoundaryAnimationMode::Keyframes => self.keyframe_radius_at(time),
+        }
+    }
+
+    fn keyframe_radius_at(&self, time: f32) -> Option<f32> {
+        let (first, last) = (self.keyframes.first()?, self.keyframes.last()?);
+        if last.0 <= first.0 {
+            return Some(first.1);
+        }
+        let t = first.0 + (time - first.0).rem_euclid(last.0 - first.0);
+        self.keyframes.windows(2).find_map(|w| {
+            let ((t0, r0), (t1, r1)) = (w[0], w[1]);
+            if t >= t0 && t <= t1 && t1 > t0 {
+                Some(r0 + (r1 - r0) * (t - t0) / (t1 - t0))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// advances the animation by delta_t and returns the new radius if the animation is running
+    pub fn advance(&mut self, delta_t: f32) -> Option<f32> {
+        if self.mode == BoundaryAnimationMode::Off {
+            return None;
+        }
+        self.time += delta_t;
+        self.radius_at(self.time).map(|r| r.max(0.1))
+    }
+
+    pub fn add_keyframe(&mut self, time: f32, radius: f32) {
+        self.keyframes.push((time, radius));
+        self.keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{assets::Mesh, V3};
+use cgmath::{InnerSpace, Matrix3, SquareMatrix};
+
+/// half the thickness of the plane brush relative to its size
+const PLANE_THICKNESS: f32 = 0.1;
+/// half the width of the edges of the brush meshes relative to the brush size
+const EDGE_WIDTH: f32 = 0.02;
+/// edges of the circles of the cylinder mesh
+const CIRCLE_SEGMENTS: usize = 24;
+
+/// volume around the cursor that picks the force grid vectors to edit
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum BrushShape {
+    Sphere,
+    Cube,
+    /// a thin square slab facing along the brush z axis
+    Plane,
+    /// a cylinder along the brush z axis as high as it is wide
+    Cylinder,
+}
+
+impl BrushShape {
+    pub const ALL: [BrushShape; 4] = [
+        BrushShape::Sphere,
+        BrushShape::Cube,
+        BrushShape::Plane,
+        BrushShape::Cylinder,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrushShape::Sphere => "sphere",
+            BrushShape::Cube => "cube",
+            BrushShape::Plane => "plane",
+            BrushShape::Cylinder => "cylinder",
+        }
+    }
+}
+
+/// the axes the brush volume is aligned with
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum BrushOrientation {
+    /// the plane and the cylinder face the viewer
+    Camera,
+    World,
+}
+
+impl BrushOrientation {
+    pub const ALL: [BrushOrientation; 2] = [BrushOrientation::Camera, BrushOrientation::World];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrushOrientation::Camera => "camera",
+            BrushOrientation::World => "world",
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Brush {
+    pub shape: BrushShape,
+    pub orientation: BrushOrientation,
+}
+
+impl Brush {
+    pub fn new() -> Self {
+        Brush {
+            shape: BrushShape::Sphere,
+            orientation: BrushOrientation::Camera,
+        }
+    }
+
+    /// the right, up and backward axes of the brush as columns,
+    /// `camera_axes` are the ones of the camera. the sphere always faces the camera
+    pub fn axes(&self, camera_axes: Matrix3<f32>) -> Matrix3<f32> {
+        match (self.shape, self.orientation) {
+            (BrushShape::Sphere, _) | (_, BrushOrientation::Camera) => camera_axes,
+            (_, BrushOrientation::World) => Matrix3::identity(),
+        }
+    }
+
+    /// whether `p` lies in the brush of half extent `size` at `center` aligned with `axes`
+    pub fn contains(&self, center: V3, axes: Matrix3<f32>, size: f32, p: V3) -> bool {
+        let d = p - center;
+        let local = V3::new(d.dot(axes.x), d.dot(axes.y), d.dot(axes.z)) / size;
+        match self.shape {
+            BrushShape::Sphere => local.magnitude2() < 1.0,
+            BrushShape::Cube => local.x.abs() < 1.0 && local.y.abs() < 1.0 && local.z.abs() < 1.0,
+            BrushShape::Plane => {
+                local.x.abs() < 1.0 && local.y.abs() < 1.0 && local.z.abs() < PLANE_THICKNESS
+            }
+            BrushShape::Cylinder => {
+                local.x * local.x + local.y * local.y < 1.0 && local.z.abs() < 1.0
+            }
+        }
+    }
+}
+
+/// the outline of `shape` with a half extent of 1 drawn as the cursor,
+/// the sphere is drawn with the cursor mesh asset instead
+pub fn brush_mesh(shape: BrushShape) -> Option<Mesh> {
+    let mut edges: Vec<(V3, V3)> = Vec::new();
+    match shape {
+        BrushShape::Sphere => return None,
+        BrushShape::Cube => {
+            for axis in 0..3 {
+                for (s, t) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                    let mut a = V3::new(0.0, 0.0, 0.0);
+                    a[(axis + 1) % 3] = s;
+                    a[(axis + 2) % 3] = t;
+                    let mut b = a;
+                    a[axis] = -1.0;
+                    b[axis] = 1.0;
+                    edges.push((a, b));
+                }
+            }
+        }
+        BrushShape::Plane => {
+            let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+            for i in 0..4 {
+                let (a, b) = (corners[i], corners[(i + 1) % 4]);
+                edges.push((V3::new(a.0, a.1, 0.0), V3::new(b.0, b.1, 0.0)));
+            }
+        }
+        BrushShape::Cylinder => {
+            let circle = |i: usize, z: f32| {
+                let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                V3::new(angle.cos(), angle.sin(), z)
+            };
+            for z in [-1.0, 1.0] {
+                edges.extend((0..CIRCLE_SEGMENTS).map(|i| (circle(i, z), circle(i + 1, z))));
+            }
+            for i in (0..CIRCLE_SEGMENTS).step_by(CIRCLE_SEGMENTS / 4) {
+                edges.push((circle(i, -1.0), circle(i, 1.0)));
+            }
+        }
+    }
+    let mut mesh = Mesh {
+        vertices: Vec::new(),
+        indices: Vec::new(),
+    };
+    for (a, b) in edges {
+        push_bar(&mut mesh, a, b);
+    }
+    Some(mesh)
+}
+
+/// appends a square bar of width `2 * EDGE_WIDTH` from `a` to `b`
+fn push_bar(mesh: &mut Mesh, a: V3, b: V3) {
+    let dir = (b - a).normalize();
+    let helper = if dir.x.abs() < 0.9 {
+        V3::unit_x()
+    } else {
+        V3::unit_y()
+    };
+    let u = dir.cross(helper).normalize() * EDGE_WIDTH;
+    let v = dir.cross(u).normalize() * EDGE_WIDTH;
+    let base = mesh.vertices.len() as u16;
+    let corners = [-u - v, u - v, u + v, -u + v];
+    for (end, tex_u) in [(a, 0.0), (b, 1.0)] {
+        for (i, corner) in corners.iter().enumerate() {
+            mesh.vertices.push((end + corner, [tex_u, i as f32 / 3.0]));
+        }
+    }
+    // the four sides, the ends are too small to be seen
+    for i in 0..4 {
+        let j = (i + 1) % 4;
+        mesh.indices
+            .extend([i, j, 4 + i, j, 4 + j, 4 + i].map(|k| base + k));
+    }
+}
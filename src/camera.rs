@@ -1,52 +1,144 @@
 use crate::cursor::Cursor;
 use crate::{framework, zero_v3, V3};
 use cgmath::prelude::*;
-use cgmath::{Deg, Matrix, Matrix4, Quaternion, Rotation3};
+use cgmath::{Deg, Matrix, Matrix4, Quaternion, Rad, Rotation3};
 
 const NEAR_PLANE_DISTANCE: f32 = 0.1;
+const FAR_PLANE_DISTANCE: f32 = 100.0;
+/// the fly camera stops short of looking straight up or down
+const MAX_PITCH_DEGREES: f32 = 89.0;
+
+/// how the camera is steered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+    /// circles around the look at target
+    Orbit,
+    /// first person flight, the mouse turns the camera and the keys move along the view
+    Fly,
+}
+
+impl CameraMode {
+    pub const ALL: [CameraMode; 2] = [CameraMode::Orbit, CameraMode::Fly];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Orbit => "orbit",
+            CameraMode::Fly => "fly",
+        }
+    }
+}
 
 pub struct Camera {
     persp_mat: Matrix4<f32>,
     screen_width: f32,
     screen_height: f32,
     fov_degrees: f32,
+    near: f32,
+    far: f32,
     pub cursor: Cursor,
     pub look_at_distance: Option<(V3, f32)>,
     pos: V3,
     pub units_per_second: f32,
     angle_per_second: f32,
     pub rot: Quaternion<f32>,
+    /// move the look at target along with the particles center of mass
+    pub follow_center_of_mass: bool,
+    /// move the look at target along with the particle of the particle tracker, it takes
+    /// precedence over the center of mass while its position is known
+    pub follow_particle: bool,
+    /// how fast the look at target catches up with the followed point, in 1/s
+    pub follow_smoothing: f32,
+    mode: CameraMode,
+    /// the look at target of the orbit mode while flying
+    orbit_target: Option<(V3, f32)>,
+    /// heading and elevation of the fly camera in degrees
+    yaw: f32,
+    pitch: f32,
+    /// degrees the fly camera turns per pixel of mouse motion
+    pub mouse_sensitivity: f32,
+    /// the mouse turns the fly camera, the pointer is grabbed meanwhile
+    pub mouse_look: bool,
+    /// fraction of the distance to the point under the mouse one wheel step zooms in
+    pub wheel_zoom_sensitivity: f32,
+    /// units one wheel step moves the cursor along the mouse ray while a modifier is held
+    pub wheel_depth_sensitivity: f32,
 }
 
 impl Camera {
     pub fn new(screen_width: f32, screen_height: f32, fov_degrees: f32) -> Self {
         let aspect = screen_width / screen_height;
-        let persp_mat = cgmath::perspective(Deg(fov_degrees), aspect, NEAR_PLANE_DISTANCE, 100.0);
+        let persp_mat = cgmath::perspective(
+            Deg(fov_degrees),
+            aspect,
+            NEAR_PLANE_DISTANCE,
+            FAR_PLANE_DISTANCE,
+        );
         Camera {
             persp_mat,
             screen_width,
             screen_height,
             fov_degrees,
+            near: NEAR_PLANE_DISTANCE,
+            far: FAR_PLANE_DISTANCE,
             cursor: Cursor::new(),
             pos: zero_v3(),
             units_per_second: 10.0,
             angle_per_second: 45.0,
             rot: Quaternion::from_sv(1.0, zero_v3()),
             look_at_distance: Some((zero_v3(), 5.0)),
+            follow_center_of_mass: false,
+            follow_particle: false,
+            follow_smoothing: 2.0,
+            mode: CameraMode::Orbit,
+            orbit_target: None,
+            yaw: 0.0,
+            pitch: 0.0,
+            mouse_sensitivity: 0.15,
+            mouse_look: false,
+            wheel_zoom_sensitivity: 0.1,
+            wheel_depth_sensitivity: 1.0,
         }
     }
 
+    /// width of the view in pixels, half the window for the split screen
+    pub fn screen_width(&self) -> f32 {
+        self.screen_width
+    }
+
     pub fn resize(&mut self, screen_width: f32, screen_height: f32) {
-        let aspect = screen_width / screen_height;
         self.screen_width = screen_width;
         self.screen_height = screen_height;
         self.update_cursor();
-        self.persp_mat = cgmath::perspective(Deg(self.fov_degrees), aspect, 0.1, 100.0);
+        self.update_projection();
+    }
+
+    /// vertical field of view in degrees and the distances of the near and far plane
+    pub fn projection(&self) -> (f32, f32, f32) {
+        (self.fov_degrees, self.near, self.far)
+    }
+
+    /// the far plane is kept behind the near plane
+    pub fn set_projection(&mut self, fov_degrees: f32, near: f32, far: f32) {
+        self.fov_degrees = fov_degrees.clamp(1.0, 179.0);
+        self.near = near.max(0.001);
+        self.far = far.max(self.near * 1.01);
+        self.update_projection();
+        self.update_cursor();
+    }
+
+    fn update_projection(&mut self) {
+        let aspect = self.screen_width / self.screen_height;
+        self.persp_mat = cgmath::perspective(Deg(self.fov_degrees), aspect, self.near, self.far);
     }
 
     pub fn update_cursor(&mut self) {
-        self.cursor
-            .update(self.screen_width, self.screen_height, self.pos, self.rot);
+        self.cursor.update(
+            self.screen_width,
+            self.screen_height,
+            self.pos,
+            self.rot,
+            self.fov_degrees,
+        );
     }
 
     // move is a keyword in Rust so this function can not be named 'move'
@@ -85,6 +177,137 @@ impl Camera {
         }
     }
 
+    /// scales the distance to the look at target, or moves forward by `1 - factor` units
+    pub fn zoom(&mut self, factor: f32) {
+        if let Some((_, distance)) = self.look_at_distance.as_mut() {
+            *distance = (*distance * factor).max(self.near);
+        } else {
+            let rotation_matrix: Matrix4<f32> = self.rot.into();
+            let fwd = rotation_matrix.transpose().z.truncate();
+            self.pos += fwd * (1.0 - factor);
+        }
+    }
+
+    /// zooms in by `steps` wheel steps towards the point under the mouse, which stays
+    /// under the mouse. the point lies at the depth of the look at target, or of the
+    /// cursor while flying
+    pub fn zoom_to_mouse(&mut self, steps: f32) {
+        self.update_cursor();
+        let (eye, dir) = self.cursor.ray;
+        let depth = self
+            .look_at_distance
+            .map_or(self.cursor.distance_from_camera, |(_, distance)| distance);
+        let point = eye + dir * depth;
+        // the look at target stays beyond the near plane
+        let factor = (-steps * self.wheel_zoom_sensitivity)
+            .exp()
+            .max(self.near / depth);
+        // scaling the camera about the point keeps its direction
+        self.pos = -(point + (eye - point) * factor);
+        if let Some((look_at, distance)) = self.look_at_distance.as_mut() {
+            // the camera works with negated world positions
+            *look_at = -point + (*look_at + point) * factor;
+            *distance *= factor;
+        }
+        self.update_cursor();
+    }
+
+    /// moves the camera so the scene follows a drag by (dx, dy) pixels,
+    /// with a look at target this orbits around it
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let distance = self.look_at_distance.map_or(1.0, |(_, distance)| distance);
+        // world units per pixel at the distance of the target
+        let scale = 2.0 * distance * Deg(self.fov_degrees * 0.5).tan() / self.screen_height;
+        let (right, up) = self.right_up();
+        self.pos += right * dx * scale - up * dy * scale;
+    }
+
+    /// eases the look at target towards the followed particle or the center of mass,
+    /// whichever is enabled and known
+    pub fn follow(&mut self, center_of_mass: Option<V3>, particle: Option<V3>, delta_t: f32) {
+        let target = particle
+            .filter(|_| self.follow_particle)
+            .or(center_of_mass.filter(|_| self.follow_center_of_mass));
+        let Some(target) = target else {
+            return;
+        };
+        if let Some((look_at, _)) = self.look_at_distance.as_mut() {
+            // the camera works with negated world positions
+            let f = 1.0 - (-delta_t * self.follow_smoothing).exp();
+            *look_at = look_at.lerp(-target, f);
+        }
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    /// flying drops the look at target, it is restored when orbiting again
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == self.mode {
+            return;
+        }
+        self.mode = mode;
+        match mode {
+            CameraMode::Fly => {
+                self.orbit_target = self.look_at_distance.take();
+                // keep looking where the orbit camera looked
+                let rotation_matrix: Matrix4<f32> = self.rot.into();
+                let look = -rotation_matrix.transpose().z.truncate();
+                self.pitch = Deg::from(Rad((-look.y).clamp(-1.0, 1.0).asin())).0;
+                self.yaw = Deg::from(Rad(look.x.atan2(-look.z))).0;
+                self.look(0.0, 0.0);
+            }
+            CameraMode::Orbit => {
+                self.mouse_look = false;
+                self.look_at_distance = self.orbit_target.take().or(Some((zero_v3(), 5.0)));
+            }
+        }
+    }
+
+    /// whether the pointer should be grabbed for mouse look
+    pub fn mouse_look_active(&self) -> bool {
+        self.mode == CameraMode::Fly && self.mouse_look
+    }
+
+    /// turns the fly camera by a mouse motion of (dx, dy) pixels
+    pub fn look(&mut self, dx: f32, dy: f32) {
+        self.yaw = (self.yaw + dx * self.mouse_sensitivity) % 360.0;
+        self.pitch =
+            (self.pitch + dy * self.mouse_sensitivity).clamp(-MAX_PITCH_DEGREES, MAX_PITCH_DEGREES);
+        self.rot =
+            Quaternion::from_angle_x(Deg(self.pitch)) * Quaternion::from_angle_y(Deg(self.yaw));
+        self.update_cursor();
+    }
+
+    /// right and up vectors of the camera in world space
+    pub fn right_up(&self) -> (V3, V3) {
+        let rotation_matrix: Matrix4<f32> = self.rot.into();
+        let r2 = rotation_matrix.transpose();
+        (r2.x.truncate(), r2.y.truncate())
+    }
+
+    /// pixels covered by one world unit at a distance of one unit from the camera
+    pub fn pixels_per_unit(&self) -> f32 {
+        0.5 * self.screen_height / Deg(self.fov_degrees * 0.5).tan()
+    }
+
+    /// camera position in world space
+    pub fn eye(&self) -> V3 {
+        -self.pos
+    }
+
+    /// where `p` appears on the screen as fractions of its width and height
+    /// from the top left corner, None if it is behind the camera
+    pub fn project(&mut self, p: V3) -> Option<(f32, f32)> {
+        let clip = self.get_view_matrix() * p.extend(1.0);
+        if clip.w <= self.near {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+        Some((0.5 * (ndc.x + 1.0), 0.5 * (1.0 - ndc.y)))
+    }
+
     pub fn get_view_matrix(&mut self) -> Matrix4<f32> {
         if let Some((look_at, distance)) = self.look_at_distance {
             if look_at == self.pos {
@@ -0,0 +1,315 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+use crate::{
+    precision::ParticlePrecision,
+    readback::{Readback, ReadbackCopy},
+    SimParams, V3,
+};
+
+/// mirrors `CoalescenceParams` in coalescence.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct CoalescenceParams {
+    merge_radius: f32,
+    split_mass: f32,
+    split_distance: f32,
+    random_seed: u32,
+    delete_center: [f32; 3],
+    delete_radius: f32,
+}
+
+/// merges close particles of the same type into heavier ones, splits the heavy ones and
+/// drops the ones deleted by the particle brush after the simulation pass. the particles are packed to the front of the particle buffer,
+/// the alive count changes on the GPU and is read back into `Compute::num_particles`
+pub struct Coalescence {
+    /// the pass runs after every simulation pass
+    pub enabled: bool,
+    /// the center and the radius of the sphere the particles are deleted in
+    pub delete_sphere: Option<(V3, f32)>,
+    /// the partner of every particle, written by the simulation pass
+    pub partners_buffer: Buffer,
+    params_buffer: Buffer,
+    /// the alive count and the number of splits of the running pass
+    counters_buffer: Buffer,
+    readback: Readback,
+    /// a readback of the alive count from before the count was overwritten by the CPU
+    stale: bool,
+    bind_group_layout: BindGroupLayout,
+    bind_groups: [BindGroup; 2],
+    begin_pipeline: ComputePipeline,
+    coalesce_pipeline: ComputePipeline,
+    clear_tail_pipeline: ComputePipeline,
+    finish_pipeline: ComputePipeline,
+}
+
+impl Coalescence {
+    pub fn new(
+        device: &Device,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+    ) -> Self {
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("coalescence bind group layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(3, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(4, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(5, BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let params_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("coalescence params buffer"),
+            contents: bytemuck::bytes_of(&CoalescenceParams::zeroed()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let counters_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("coalescence counters buffer"),
+            size: 2 * std::mem::size_of::<u32>() as u64,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let partners_buffer = Self::create_partners_buffer(device, particles_buffers, precision);
+        let bind_groups = Self::create_bind_groups(
+            device,
+            &bind_group_layout,
+            &params_buffer,
+            particles_buffers,
+            &partners_buffer,
+            count_buffer,
+            &counters_buffer,
+        );
+        let [begin_pipeline, coalesce_pipeline, clear_tail_pipeline, finish_pipeline] =
+            Self::create_pipelines(device, &bind_group_layout, precision, workgroup_size);
+        Coalescence {
+            enabled: false,
+            delete_sphere: None,
+            partners_buffer,
+            params_buffer,
+            counters_buffer,
+            readback: Readback::new("particle count readback buffer"),
+            stale: false,
+            bind_group_layout,
+            bind_groups,
+            begin_pipeline,
+            coalesce_pipeline,
+            clear_tail_pipeline,
+            finish_pipeline,
+        }
+    }
+
+    /// a partner for every particle the particle buffers can hold
+    fn create_partners_buffer(
+        device: &Device,
+        particles_buffers: &[Buffer; 2],
+        precision: ParticlePrecision,
+    ) -> Buffer {
+        let capacity = particles_buffers[0].size() / precision.particle_size() as u64;
+        device.create_buffer(&BufferDescriptor {
+            label: Some("coalescence partners buffer"),
+            size: capacity.max(1) * std::mem::size_of::<u32>() as u64,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        params_buffer: &Buffer,
+        particles_buffers: &[Buffer; 2],
+        partners_buffer: &Buffer,
+        count_buffer: &Buffer,
+        counters_buffer: &Buffer,
+    ) -> [BindGroup; 2] {
+        // like the simulation pass, bind group i reads particle buffer i
+        std::array::from_fn(|i| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("coalescence bind group"),
+                layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: particles_buffers[i].as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: particles_buffers[(i + 1) % 2].as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: partners_buffer.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 4,
+                        resource: count_buffer.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 5,
+                        resource: counters_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        })
+    }
+
+    /// the begin, coalesce, clear_tail and finish pipelines
+    fn create_pipelines(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+    ) -> [ComputePipeline; 4] {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("coalescence shader module"),
+            source: ShaderSource::Wgsl(Cow::Owned(format!(
+                "const WORKGROUP_SIZE: u32 = {}u;\n{}",
+                workgroup_size,
+                precision.shader_source(include_str!("coalescence.wgsl"))
+            ))),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("coalescence pipeline layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        ["begin", "coalesce", "clear_tail", "finish"].map(|entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("coalescence pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        })
+    }
+
+    /// rebuilds the pipelines for particle buffers stored with `precision`
+    /// and dispatches of `workgroup_size` particles per workgroup
+    pub fn rebuild_pipelines(
+        &mut self,
+        device: &Device,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+    ) {
+        [
+            self.begin_pipeline,
+            self.coalesce_pipeline,
+            self.clear_tail_pipeline,
+            self.finish_pipeline,
+        ] = Self::create_pipelines(device, &self.bind_group_layout, precision, workgroup_size);
+    }
+
+    /// binds the particle buffers again, call this after they were replaced
+    pub fn update_particles_buffers(
+        &mut self,
+        device: &Device,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+        precision: ParticlePrecision,
+    ) {
+        self.partners_buffer = Self::create_partners_buffer(device, particles_buffers, precision);
+        self.bind_groups = Self::create_bind_groups(
+            device,
+            &self.bind_group_layout,
+            &self.params_buffer,
+            particles_buffers,
+            &self.partners_buffer,
+            count_buffer,
+            &self.counters_buffer,
+        );
+        self.discard_readback();
+    }
+
+    pub fn update_params(&mut self, queue: &Queue, sim_params: &SimParams) {
+        self.enabled = sim_params.coalescence() || self.delete_sphere.is_some();
+        let (delete_center, delete_radius) =
+            self.delete_sphere.unwrap_or((V3::new(0.0, 0.0, 0.0), 0.0));
+        let params = CoalescenceParams {
+            merge_radius: sim_params.merge_radius,
+            split_mass: sim_params.split_mass,
+            split_distance: sim_params.split_distance,
+            // both words of the seed folded into one
+            random_seed: sim_params.random_seed ^ sim_params.random_seed_high,
+            delete_center: delete_center.into(),
+            delete_radius,
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+    }
+
+    /// encodes the pass reading particle buffer `swap`, which was written by the simulation pass.
+    /// `args_buffer` holds the dispatch arguments of the simulation pass
+    pub fn encode<'a>(&'a self, cpass: &mut ComputePass<'a>, swap: usize, args_buffer: &'a Buffer) {
+        cpass.set_bind_group(0, &self.bind_groups[swap], &[]);
+        cpass.set_pipeline(&self.begin_pipeline);
+        cpass.dispatch_workgroups(1, 1, 1);
+        cpass.set_pipeline(&self.coalesce_pipeline);
+        cpass.dispatch_workgroups_indirect(args_buffer, 0);
+        cpass.set_pipeline(&self.clear_tail_pipeline);
+        cpass.dispatch_workgroups_indirect(args_buffer, 0);
+        cpass.set_pipeline(&self.finish_pipeline);
+        cpass.dispatch_workgroups(1, 1, 1);
+    }
+
+    /// copies the alive count of `count_buffer` unless the previous one is still in flight
+    pub fn encode_readback(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        count_buffer: &Buffer,
+    ) {
+        if !self.enabled || !self.readback.is_idle() {
+            return;
+        }
+        self.readback.encode(
+            device,
+            encoder,
+            &[ReadbackCopy {
+                buffer: count_buffer,
+                offset: 0,
+                size: std::mem::size_of::<u32>() as u64,
+            }],
+        );
+        self.stale = false;
+    }
+
+    /// starts mapping the readback buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.readback.after_submit();
+    }
+
+    /// ignores the readback in flight, call this when the CPU overwrites the alive count
+    pub fn discard_readback(&mut self) {
+        self.stale = true;
+    }
+
+    /// the alive count of a finished readback
+    pub fn poll(&mut self, device: &Device) -> Option<usize> {
+        let alive = self
+            .readback
+            .poll(device, bytemuck::pod_read_unaligned::<u32>)?;
+        (!self.stale).then_some(alive as usize)
+    }
+}
@@ -0,0 +1,140 @@
+// merges particles with their partner found by the simulation pass, splits heavy ones and
+// drops the ones within the delete sphere of the particle brush, the results are packed to the front of particlesDst with an atomic counter.
+// WORKGROUP_SIZE is prepended by `Coalescence` in coalescence.rs
+
+// mirrors `CoalescenceParams` in coalescence.rs
+struct CoalescenceParams {
+  // 0.0 disables merging, the partners are not written then
+  merge_radius: f32,
+  // 0.0 disables splitting
+  split_mass: f32,
+  split_distance: f32,
+  random_seed: u32,
+  delete_center: vec3<f32>,
+  // 0.0 deletes nothing
+  delete_radius: f32,
+};
+
+// mirrors `ParticleCount` in indirect.wgsl
+struct ParticleCount {
+  alive: u32,
+  capacity: u32,
+  workgroup_size: u32,
+  _padd: u32,
+};
+
+struct Counters {
+  // particles written to particlesDst so far
+  alive: atomic<u32>,
+  // split particles so far, including the ones without room for their second half
+  splits: atomic<u32>,
+};
+
+const NO_PARTNER: u32 = 0xffffffffu;
+
+@group(0) @binding(0) var<uniform> params: CoalescenceParams;
+@group(0) @binding(1) var<storage, read> particlesSrc: array<ParticleStorage>;
+@group(0) @binding(2) var<storage, read_write> particlesDst: array<ParticleStorage>;
+@group(0) @binding(3) var<storage, read> partners: array<u32>;
+@group(0) @binding(4) var<storage, read_write> particle_count: ParticleCount;
+@group(0) @binding(5) var<storage, read_write> counters: Counters;
+
+// pcg hash
+fn hash(x: u32) -> u32 {
+  let state = x * 747796405u + 2891336453u;
+  let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+  return (word >> 22u) ^ word;
+}
+
+// random number in [0.0..1.0]
+fn rand(seed: u32) -> f32 {
+  return f32(hash(seed)) / 4294967295.0;
+}
+
+// the particles simulated this step
+fn num_alive() -> u32 {
+  return min(particle_count.alive, arrayLength(&particlesSrc));
+}
+
+fn append(p: Particle) {
+  let slot = atomicAdd(&counters.alive, 1u);
+  particlesDst[slot] = store_particle(p);
+}
+
+@compute
+@workgroup_size(1)
+fn begin() {
+  atomicStore(&counters.alive, 0u);
+  atomicStore(&counters.splits, 0u);
+}
+
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn coalesce(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let total = num_alive();
+  let index = invocation_index(global_invocation_id, num_workgroups, WORKGROUP_SIZE);
+  if index >= total {
+    return;
+  }
+  var p = load_particle(particlesSrc[index]);
+  if distance(p.pos.xyz, params.delete_center) < params.delete_radius {
+    return;
+  }
+
+  // only mutual partners merge, so every particle takes part in one merge at most
+  let partner = select(NO_PARTNER, partners[index], params.merge_radius > 0.0);
+  if partner < total && partners[partner] == index {
+    let other = load_particle(particlesSrc[partner]);
+    // a reaction may have changed the type after the partners were found
+    if other.ty == p.ty {
+      // the particle with the lower index takes over the other one
+      if partner < index {
+        return;
+      }
+      let mass = p.mass + other.mass;
+      p.pos = vec4<f32>((p.pos.xyz * p.mass + other.pos.xyz * other.mass) / mass, 1.0);
+      p.vel = vec4<f32>((p.vel.xyz * p.mass + other.vel.xyz * other.mass) / mass, 1.0);
+      p.age = min(p.age, other.age);
+      p.mass = mass;
+    }
+  }
+
+  // the second halves only take the slots freed by merges and the unused capacity
+  if params.split_mass > 0.0 && p.mass > params.split_mass
+    && atomicAdd(&counters.splits, 1u) < particle_count.capacity - total {
+    let seed = hash(index ^ params.random_seed ^ bitcast<u32>(p.pos.x));
+    let z = rand(seed) * 2.0 - 1.0;
+    let angle = rand(hash(seed)) * 6.2831855;
+    let dir = vec3<f32>(sqrt(1.0 - z * z) * vec2<f32>(cos(angle), sin(angle)), z);
+    let offset = 0.5 * max(params.split_distance, 2.0 * params.merge_radius) * dir;
+    let center = p.pos.xyz;
+    p.mass *= 0.5;
+    p.pos = vec4<f32>(center - offset, 1.0);
+    append(p);
+    p.pos = vec4<f32>(center + offset, 1.0);
+  }
+  append(p);
+}
+
+// empties the slots left behind by the merges, they keep no mass in the whole system sums
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn clear_tail(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = invocation_index(global_invocation_id, num_workgroups, WORKGROUP_SIZE);
+  if index < atomicLoad(&counters.alive) || index >= num_alive() {
+    return;
+  }
+  particlesDst[index] = store_particle(Particle(vec4<f32>(0.0, 0.0, 0.0, 1.0), vec4<f32>(), 0u, 0.0, 0.0));
+}
+
+@compute
+@workgroup_size(1)
+fn finish() {
+  particle_count.alive = atomicLoad(&counters.alive);
+}
@@ -0,0 +1,152 @@
+/// number of color stops uploaded to the shaders
+pub const NUM_STOPS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMap {
+    Viridis,
+    Inferno,
+    Fire,
+    Ice,
+    Grayscale,
+}
+
+impl ColorMap {
+    pub const ALL: [ColorMap; 5] = [
+        ColorMap::Viridis,
+        ColorMap::Inferno,
+        ColorMap::Fire,
+        ColorMap::Ice,
+        ColorMap::Grayscale,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorMap::Viridis => "viridis",
+            ColorMap::Inferno => "inferno",
+            ColorMap::Fire => "fire",
+            ColorMap::Ice => "ice",
+            ColorMap::Grayscale => "grayscale",
+        }
+    }
+
+    /// key colors of the map, evenly spaced over [0.0..1.0]
+    fn key_colors(&self) -> &'static [[f32; 3]] {
+        match self {
+            ColorMap::Viridis => &[
+                [0.267, 0.005, 0.329],
+                [0.231, 0.322, 0.545],
+                [0.129, 0.569, 0.549],
+                [0.369, 0.788, 0.384],
+                [0.993, 0.906, 0.144],
+            ],
+            ColorMap::Inferno => &[
+                [0.001, 0.000, 0.014],
+                [0.341, 0.062, 0.429],
+                [0.735, 0.216, 0.330],
+                [0.978, 0.557, 0.035],
+                [0.988, 0.998, 0.645],
+            ],
+            ColorMap::Fire => &[
+                [0.2, 0.0, 0.0],
+                [0.8, 0.1, 0.0],
+                [1.0, 0.5, 0.0],
+                [1.0, 0.9, 0.3],
+                [1.0, 1.0, 1.0],
+            ],
+            ColorMap::Ice => &[
+                [1.0, 1.0, 1.0],
+                [0.6, 0.9, 1.0],
+                [0.1, 0.4, 0.8],
+                [0.0, 0.1, 0.3],
+            ],
+            ColorMap::Grayscale => &[[1.0, 1.0, 1.0], [0.1, 0.1, 0.1]],
+        }
+    }
+
+    /// evaluates the color map at t in [0.0..1.0]
+    pub fn eval(&self, t: f32) -> [f32; 4] {
+        let keys = self.key_colors();
+        let x = t.clamp(0.0, 1.0) * (keys.len() - 1) as f32;
+        let i = (x as usize).min(keys.len() - 2);
+        let f = x - i as f32;
+        let (a, b) = (keys[i], keys[i + 1]);
+        [
+            a[0] + (b[0] - a[0]) * f,
+            a[1] + (b[1] - a[1]) * f,
+            a[2] + (b[2] - a[2]) * f,
+            1.0,
+        ]
+    }
+}
+
+/// a color and where it sits on a `ColorRamp`
+#[derive(Clone, Copy, Debug)]
+pub struct ColorStop {
+    /// position in [0.0..1.0]
+    pub t: f32,
+    /// RGBA, the alpha fades the particles along with their age
+    pub color: [f32; 4],
+}
+
+/// the colors of the particles over their age, edited in the gui
+#[derive(Clone, Debug)]
+pub struct ColorRamp {
+    /// at least two stops sorted by position
+    pub stops: Vec<ColorStop>,
+}
+
+impl ColorRamp {
+    /// the key colors of `color_map`
+    pub fn from_map(color_map: ColorMap) -> Self {
+        let keys = color_map.key_colors();
+        let stops = keys
+            .iter()
+            .enumerate()
+            .map(|(i, &[r, g, b])| ColorStop {
+                t: i as f32 / (keys.len() - 1) as f32,
+                color: [r, g, b, 1.0],
+            })
+            .collect();
+        ColorRamp { stops }
+    }
+
+    /// evaluates the ramp at t in [0.0..1.0], constant before the first and after the last stop
+    pub fn eval(&self, t: f32) -> [f32; 4] {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return [1.0; 4];
+        };
+        if t <= first.t {
+            return first.color;
+        }
+        self.stops
+            .windows(2)
+            .find(|w| t <= w[1].t)
+            .map_or(last.color, |w| {
+                let f = (t - w[0].t) / (w[1].t - w[0].t).max(f32::EPSILON);
+                std::array::from_fn(|i| w[0].color[i] + (w[1].color[i] - w[0].color[i]) * f)
+            })
+    }
+
+    /// the ramp resampled to NUM_STOPS evenly spaced stops
+    pub fn stops(&self) -> [[f32; 4]; NUM_STOPS] {
+        std::array::from_fn(|i| self.eval(i as f32 / (NUM_STOPS - 1) as f32))
+    }
+
+    /// adds a stop with the color the ramp has at `t`, returns its index
+    pub fn insert(&mut self, t: f32) -> usize {
+        let t = t.clamp(0.0, 1.0);
+        let color = self.eval(t);
+        let i = self.stops.partition_point(|stop| stop.t < t);
+        self.stops.insert(i, ColorStop { t, color });
+        i
+    }
+
+    /// moves stop `i` to `t`, keeping the stops sorted, returns its new index
+    pub fn move_stop(&mut self, i: usize, t: f32) -> usize {
+        let mut stop = self.stops.remove(i);
+        stop.t = t.clamp(0.0, 1.0);
+        let i = self.stops.partition_point(|s| s.t < stop.t);
+        self.stops.insert(i, stop);
+        i
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{compute::Compute, sim_params::SimParams};
+
+/// the other side of the A/B comparison, a second simulation with parameters of its own.
+/// the controls always edit the simulation of `App`, swapping the sides lets them edit B
+pub struct Comparison {
+    /// the simulation of the side not being edited
+    pub compute: Compute,
+    pub sim_params: SimParams,
+    /// the simulation of `App` is B, drawn on the right
+    pub editing_b: bool,
+}
+
+impl Comparison {
+    pub fn new(compute: Compute, sim_params: SimParams) -> Self {
+        Comparison {
+            compute,
+            sim_params,
+            editing_b: false,
+        }
+    }
+
+    /// the parameters of this side with the time step and the counts `App` derives every
+    /// frame from the edited side, the two sides share the time and the buffers they count
+    pub fn frame_params(&self, edited: &SimParams) -> SimParams {
+        SimParams {
+            delta_t: edited.delta_t,
+            boundary_scale: edited.boundary_scale,
+            num_force_grid_layers: edited.num_force_grid_layers,
+            force_grid_animated: edited.force_grid_animated,
+            material_grid: edited.material_grid,
+            random_seed: edited.random_seed,
+            random_seed_high: edited.random_seed_high,
+            num_reaction_rules: edited.num_reaction_rules,
+            num_attractors: edited.num_attractors,
+            num_regions: edited.num_regions,
+            ..self.sim_params
+        }
+    }
+
+    /// the simulations drawn on the left and on the right, `edited` is the one of `App`
+    pub fn sides<'a>(&'a self, edited: &'a Compute) -> [&'a Compute; 2] {
+        if self.editing_b {
+            [&self.compute, edited]
+        } else {
+            [edited, &self.compute]
+        }
+    }
+}
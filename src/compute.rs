@@ -1,42 +1,129 @@
-use std::{borrow::Cow, num::NonZeroU64, u64};
+use std::{borrow::Cow, collections::HashMap, num::NonZeroU64};
 
-use crate::{Particle, SimParams};
+use bytemuck::Zeroable;
+use cgmath::InnerSpace;
+use instant::Instant;
+
+use crate::{
+    coalescence::Coalescence,
+    compute_features::ComputeFeatures,
+    cursor::{Attractor, CursorSphere, StirForce},
+    diagnostics::Diagnostics,
+    force_curve::{DEFAULT_LUT_RESOLUTION, NUM_FORCE_CURVES},
+    indirect::{split_workgroups, IndirectDispatch},
+    inspector::{InspectedBuffer, Inspector},
+    links::Links,
+    pair_probe::PairProbe,
+    particle_append::ParticleAppend,
+    particle_sample::ParticleSample,
+    particle_sort::ParticleSort,
+    particle_tracker::ParticleTracker,
+    precision::ParticlePrecision,
+    ray_density::RayDensity,
+    reactions::ReactionRule,
+    reduction::Reduction,
+    regions::RegionParams,
+    renderer::PARTICLE_INDICES,
+    velocity_ops::VelocityOps,
+    Particle, SimParams, V3,
+};
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
     *,
 };
 
-const PARTICLES_PER_GROUP: usize = 64;
+/// the workgroup sizes tried by `autotune_workgroup_size`
+pub const WORKGROUP_SIZES: [u32; 4] = [32, 64, 128, 256];
+const DEFAULT_WORKGROUP_SIZE: u32 = 64;
+/// the most substeps the gui offers per frame
+pub const MAX_SUBSTEPS: u32 = 16;
+/// dispatches timed for every workgroup size
+const AUTOTUNE_DISPATCHES: u32 = 16;
+/// particles converted and written to the particle buffers at a time, so an upload never
+/// holds a second copy of all particles
+const UPLOAD_CHUNK_SIZE: usize = 1 << 16;
 
 pub struct Compute {
     sim_param_buffer: Buffer,
     pub particles_buffers: [Buffer; 2],
     force_grid_buffer: Buffer,
+    reaction_rules_buffer: Buffer,
+    attractors_buffer: Buffer,
+    stir_buffer: Buffer,
+    cursor_sphere_buffer: Buffer,
+    regions_buffer: Buffer,
+    /// the sampled spline force curves, one row per pair of particle types
+    force_curve_texture: Texture,
+    force_curve_view: TextureView,
     bind_group_layout: BindGroupLayout,
     swap_bind_groups: [BindGroup; 2],
     // 0 or 1 depending on which BindGroup is used
     swap: usize,
     pub num_particles: usize,
-    num_workgroups: usize,
-    pipeline: ComputePipeline,
+    /// particles the particle buffers can hold, the count can shrink without an upload
+    capacity: usize,
+    /// the particle count on the GPU and the indirect dispatch and draw arguments
+    pub indirect: IndirectDispatch,
+    /// particles per workgroup, substituted for `WORKGROUP_SIZE` in compute.wgsl
+    pub workgroup_size: u32,
+    /// seconds per dispatch of each workgroup size measured by the last autotuning
+    pub workgroup_timings: Vec<(u32, f64)>,
+    /// compute.wgsl, replaced when it is hot reloaded
+    source: String,
+    /// the features compiled into the pipeline of the next dispatch
+    features: ComputeFeatures,
+    /// a pipeline for every combination of features used so far, dropped when the source,
+    /// the precision or the workgroup size change
+    pipelines: HashMap<ComputeFeatures, ComputePipeline>,
+    /// the cursor sphere of the next dispatch pushes the particles out
+    cursor_collision: bool,
+    pub reduction: Reduction,
+    pub diagnostics: Diagnostics,
+    pub inspector: Inspector,
+    pub velocity_ops: VelocityOps,
+    particle_append: ParticleAppend,
+    pub ray_density: RayDensity,
+    pub coalescence: Coalescence,
+    pub particle_sort: ParticleSort,
+    pub links: Links,
+    pub particle_tracker: ParticleTracker,
+    pub particle_sample: ParticleSample,
+    pub pair_probe: PairProbe,
+    // time step of the next frame, split among the substeps
+    delta_t: f32,
+    /// dispatches of the compute pipeline per frame, each advancing by a fraction of the
+    /// time step
+    pub substeps: u32,
+    /// simulated seconds, the sum of the time steps of all dispatches
+    pub sim_time: f64,
+    /// storage format of the particle buffers, changed with `set_precision`
+    pub precision: ParticlePrecision,
 }
 
 impl Compute {
-    pub fn new(device: &Device, particles: &[Particle], force_grid: &[[f32; 4]]) -> Self {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        precision: ParticlePrecision,
+        particles: &[Particle],
+        force_grid: &[[f32; 4]],
+        reaction_rules: &[ReactionRule],
+        attractors: &[Attractor],
+    ) -> Self {
         let num_particles = particles.len();
-        let num_workgroups =
-            ((num_particles as f32) / (PARTICLES_PER_GROUP as f32)).ceil() as usize;
-        let shader = device.create_shader_module(ShaderModuleDescriptor {
-            label: Some("compute shader module"),
-            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("compute.wgsl"))),
-        });
+        let source = String::from(include_str!("compute.wgsl"));
+        let features = ComputeFeatures::default();
+        let shader =
+            Self::create_shader(device, precision, DEFAULT_WORKGROUP_SIZE, features, &source);
         let sim_params = SimParams::new();
         let sim_param_desc = BufferInitDescriptor {
             label: Some("SimParams buffer init descriptor"),
             contents: bytemuck::bytes_of(&sim_params),
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
         };
         let sim_param_buffer = device.create_buffer_init(&sim_param_desc);
+        let reduction = Reduction::new(device, &sim_params.particle_type_masses, precision);
+        let velocity_ops = VelocityOps::new(device, &sim_params.particle_type_masses, precision);
         let sim_param_entry = BindGroupLayoutEntry {
             binding: 0,
             visibility: ShaderStages::COMPUTE,
@@ -49,17 +136,8 @@ impl Compute {
             },
             count: None,
         };
-        let particles_buffer1 = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("particles src buffer"),
-            contents: bytemuck::cast_slice(particles),
-            usage: BufferUsages::VERTEX | BufferUsages::STORAGE | BufferUsages::COPY_DST,
-        });
-        let particles_buffer2 = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("particles dst buffer"),
-            contents: bytemuck::cast_slice(particles),
-            usage: BufferUsages::VERTEX | BufferUsages::STORAGE | BufferUsages::COPY_DST,
-        });
-        let particles_buffers = [particles_buffer1, particles_buffer2];
+        let particles_buffers =
+            Self::create_particles_buffers(device, queue, precision, num_particles, particles);
 
         let particles_src_entry = BindGroupLayoutEntry {
             binding: 1,
@@ -87,7 +165,7 @@ impl Compute {
         let force_grid_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("force grid buffer"),
             contents: bytemuck::cast_slice(force_grid),
-            usage: BufferUsages::STORAGE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
         });
         let force_grid_entry = BindGroupLayoutEntry {
             binding: 3,
@@ -99,6 +177,119 @@ impl Compute {
             },
             count: None,
         };
+        let reaction_rules_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("reaction rules buffer"),
+            contents: bytemuck::cast_slice(reaction_rules),
+            usage: BufferUsages::STORAGE,
+        });
+        let reaction_rules_entry = BindGroupLayoutEntry {
+            binding: 4,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let attractors_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("attractors buffer"),
+            contents: bytemuck::cast_slice(attractors),
+            usage: BufferUsages::STORAGE,
+        });
+        let attractors_entry = BindGroupLayoutEntry {
+            binding: 5,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let indirect = IndirectDispatch::new(device, PARTICLE_INDICES.len() as u32);
+        indirect.set_count(queue, num_particles, num_particles, DEFAULT_WORKGROUP_SIZE);
+        let particle_count_entry = BindGroupLayoutEntry {
+            binding: 6,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let stir_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("stir force buffer"),
+            contents: bytemuck::bytes_of(&StirForce::zeroed()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let stir_entry = BindGroupLayoutEntry {
+            binding: 7,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let cursor_sphere_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("cursor sphere buffer"),
+            contents: bytemuck::bytes_of(&CursorSphere::zeroed()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let cursor_sphere_entry = BindGroupLayoutEntry {
+            binding: 8,
+            ..stir_entry
+        };
+        let regions_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("regions buffer"),
+            contents: bytemuck::bytes_of(&RegionParams::zeroed()),
+            usage: BufferUsages::STORAGE,
+        });
+        let regions_entry = BindGroupLayoutEntry {
+            binding: 9,
+            ..attractors_entry
+        };
+        let coalescence = Coalescence::new(
+            device,
+            precision,
+            DEFAULT_WORKGROUP_SIZE,
+            &particles_buffers,
+            &indirect.count_buffer,
+        );
+        let particle_sort = ParticleSort::new(
+            device,
+            precision,
+            DEFAULT_WORKGROUP_SIZE,
+            &particles_buffers,
+            &indirect.count_buffer,
+        );
+        let links = Links::new(
+            device,
+            precision,
+            DEFAULT_WORKGROUP_SIZE,
+            &particles_buffers,
+            &indirect.count_buffer,
+            [&particle_sort.ids_buffer, &particle_sort.slots_buffer],
+        );
+        let partners_entry = BindGroupLayoutEntry {
+            binding: 10,
+            ..particles_dst_entry
+        };
+        let (force_curve_texture, force_curve_view) =
+            create_force_curve_texture(device, DEFAULT_LUT_RESOLUTION);
+        let force_curve_entry = BindGroupLayoutEntry {
+            binding: 11,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: false },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
         let bind_group_layout_desc = BindGroupLayoutDescriptor {
             label: Some("compute shader bind group layout entry descriptor"),
             entries: &[
@@ -106,21 +297,18 @@ impl Compute {
                 particles_src_entry,
                 particles_dst_entry,
                 force_grid_entry,
+                reaction_rules_entry,
+                attractors_entry,
+                particle_count_entry,
+                stir_entry,
+                cursor_sphere_entry,
+                regions_entry,
+                partners_entry,
+                force_curve_entry,
             ],
         };
         let bind_group_layout = device.create_bind_group_layout(&bind_group_layout_desc);
-        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("compute pipeline layout descriptor"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
-        });
-        let pipeline_descriptor = ComputePipelineDescriptor {
-            label: Some("compute pipeline descriptor"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: "main",
-        };
-        let pipeline = device.create_compute_pipeline(&pipeline_descriptor);
+        let pipeline = Compute::create_pipeline(device, &bind_group_layout, &shader);
         let particles_buffer_refs = [&particles_buffers[0], &particles_buffers[1]];
 
         let particles_buffers_bind_groups = Compute::create_bind_groups(
@@ -129,6 +317,14 @@ impl Compute {
             &sim_param_buffer,
             &particles_buffer_refs,
             &force_grid_buffer,
+            &reaction_rules_buffer,
+            &attractors_buffer,
+            &indirect.count_buffer,
+            &stir_buffer,
+            &cursor_sphere_buffer,
+            &regions_buffer,
+            &coalescence.partners_buffer,
+            &force_curve_view,
         );
 
         Compute {
@@ -138,18 +334,155 @@ impl Compute {
             swap: 0,
             particles_buffers,
             force_grid_buffer,
+            reaction_rules_buffer,
+            attractors_buffer,
+            stir_buffer,
+            cursor_sphere_buffer,
+            regions_buffer,
+            force_curve_texture,
+            force_curve_view,
             num_particles,
-            num_workgroups,
-            pipeline,
+            capacity: num_particles,
+            indirect,
+            workgroup_size: DEFAULT_WORKGROUP_SIZE,
+            workgroup_timings: Vec::new(),
+            source,
+            features,
+            pipelines: HashMap::from([(features, pipeline)]),
+            cursor_collision: false,
+            reduction,
+            diagnostics: Diagnostics::new(),
+            inspector: Inspector::new(),
+            velocity_ops,
+            particle_append: ParticleAppend::new(device, precision),
+            ray_density: RayDensity::new(device, precision),
+            coalescence,
+            particle_sort,
+            links,
+            particle_tracker: ParticleTracker::new(),
+            particle_sample: ParticleSample::new(),
+            pair_probe: PairProbe::new(device),
+            delta_t: 0.0,
+            substeps: 1,
+            sim_time: 0.0,
+            precision,
+        }
+    }
+
+    /// prepends the particle storage definition, the workgroup size and the features
+    /// of the next dispatch to a compute shader
+    pub fn shader_source(&self, source: &str) -> String {
+        Self::prefixed_source(self.precision, self.workgroup_size, self.features, source)
+    }
+
+    fn prefixed_source(
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+        features: ComputeFeatures,
+        source: &str,
+    ) -> String {
+        format!(
+            "const WORKGROUP_SIZE: u32 = {}u;\n{}\n{}",
+            workgroup_size,
+            features.shader_source(),
+            precision.shader_source(source)
+        )
+    }
+
+    /// the compute shader module of `source` for particle buffers stored with `precision`
+    fn create_shader(
+        device: &Device,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+        features: ComputeFeatures,
+        source: &str,
+    ) -> ShaderModule {
+        device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("compute shader module"),
+            source: ShaderSource::Wgsl(Cow::Owned(Self::prefixed_source(
+                precision,
+                workgroup_size,
+                features,
+                source,
+            ))),
+        })
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        shader: &ShaderModule,
+    ) -> ComputePipeline {
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("compute pipeline layout descriptor"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline_descriptor = ComputePipelineDescriptor {
+            label: Some("compute pipeline descriptor"),
+            layout: Some(&pipeline_layout),
+            module: shader,
+            entry_point: "main",
+        };
+        device.create_compute_pipeline(&pipeline_descriptor)
+    }
+
+    /// swaps in a new compute shader, used for hot reloading.
+    /// `shader` is `source` compiled for the current features
+    pub fn reload_source(&mut self, device: &Device, source: String, shader: &ShaderModule) {
+        self.source = source;
+        self.pipelines.clear();
+        let pipeline = Compute::create_pipeline(device, &self.bind_group_layout, shader);
+        self.pipelines.insert(self.features, pipeline);
+        self.pair_probe.invalidate();
+    }
+
+    /// uses the pipeline for `features` for the next dispatches, compiling it if it is not cached
+    fn specialize(&mut self, device: &Device, features: ComputeFeatures) {
+        self.features = features;
+        if self.pipelines.contains_key(&features) {
+            return;
         }
+        log::info!("compiling the compute shader for {:?}", features);
+        let shader = Self::create_shader(
+            device,
+            self.precision,
+            self.workgroup_size,
+            features,
+            &self.source,
+        );
+        let pipeline = Compute::create_pipeline(device, &self.bind_group_layout, &shader);
+        self.pipelines.insert(features, pipeline);
+    }
+
+    /// drops the cached pipelines and compiles the one of the current features again
+    fn rebuild_pipelines(&mut self, device: &Device) {
+        self.pipelines.clear();
+        self.specialize(device, self.features);
+        self.pair_probe.invalidate();
+        self.coalescence
+            .rebuild_pipelines(device, self.precision, self.workgroup_size);
+        self.particle_sort
+            .rebuild_pipelines(device, self.precision, self.workgroup_size);
+        self.links
+            .rebuild_pipelines(device, self.precision, self.workgroup_size);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_bind_groups(
         device: &Device,
         layout: &BindGroupLayout,
         sim_param_buffer: &Buffer,
         particles_buffers: &[&Buffer; 2],
         force_grid_buffer: &Buffer,
+        reaction_rules_buffer: &Buffer,
+        attractors_buffer: &Buffer,
+        particle_count_buffer: &Buffer,
+        stir_buffer: &Buffer,
+        cursor_sphere_buffer: &Buffer,
+        regions_buffer: &Buffer,
+        partners_buffer: &Buffer,
+        force_curve_view: &TextureView,
     ) -> [BindGroup; 2] {
         // create two bind groups,
         // where the 2 particles buffers alternate between src and dst
@@ -172,42 +505,662 @@ impl Compute {
                     wgpu::BindGroupEntry {
                         binding: 3,
                         resource: force_grid_buffer.as_entire_binding(),
-                    }
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: reaction_rules_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: attractors_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: particle_count_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 7,
+                        resource: stir_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 8,
+                        resource: cursor_sphere_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 9,
+                        resource: regions_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 10,
+                        resource: partners_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 11,
+                        resource: BindingResource::TextureView(force_curve_view),
+                    },
                 ],
                 label: None,
             })
         })
     }
 
-    pub fn upload_particles(&mut self, device: &Device, particles: &[Particle]) {
+    /// two particle buffers with room for `capacity` particles, both start with `particles`
+    fn create_particles_buffers(
+        device: &Device,
+        queue: &Queue,
+        precision: ParticlePrecision,
+        capacity: usize,
+        particles: &[Particle],
+    ) -> [Buffer; 2] {
+        let buffers = ["particles src buffer", "particles dst buffer"].map(|label| {
+            device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size: (capacity.max(1) * precision.particle_size()) as u64,
+                usage: BufferUsages::VERTEX
+                    | BufferUsages::STORAGE
+                    | BufferUsages::COPY_DST
+                    | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        });
+        Self::write_particles(queue, precision, &buffers, 0, particles);
+        buffers
+    }
+
+    /// writes `particles` to both particle buffers from slot `first` on, in chunks
+    fn write_particles(
+        queue: &Queue,
+        precision: ParticlePrecision,
+        buffers: &[Buffer; 2],
+        first: usize,
+        particles: &[Particle],
+    ) {
+        for (i, chunk) in particles.chunks(UPLOAD_CHUNK_SIZE).enumerate() {
+            let bytes = precision.encode_particles(chunk);
+            let offset = (first + i * UPLOAD_CHUNK_SIZE) * precision.particle_size();
+            for buffer in buffers {
+                queue.write_buffer(buffer, offset as u64, &bytes);
+            }
+        }
+    }
+
+    pub fn upload_particles(&mut self, device: &Device, queue: &Queue, particles: &[Particle]) {
+        self.num_particles = particles.len();
+        self.capacity = particles.len();
+        self.indirect.set_count(
+            queue,
+            self.num_particles,
+            self.capacity,
+            self.workgroup_size,
+        );
+        self.particles_buffers =
+            Self::create_particles_buffers(device, queue, self.precision, self.capacity, particles);
+        self.rebind_particles_buffers(device, None);
+        // the ids start over with the new particles
+        self.links.clear();
+        self.links.selection.clear();
+    }
+
+    /// continues from the particles of `other` in its precision. they are copied on the GPU
+    /// along with their alive count, so the CPU neither waits for them nor holds them
+    pub fn copy_particles_from(&mut self, device: &Device, queue: &Queue, other: &Compute) {
+        if other.precision != self.precision {
+            self.rebuild_precision_pipelines(device, other.precision);
+        }
+        self.num_particles = other.num_particles;
+        self.capacity = other.capacity;
+        self.indirect.set_count(
+            queue,
+            self.num_particles,
+            self.capacity,
+            self.workgroup_size,
+        );
+        self.particles_buffers =
+            Self::create_particles_buffers(device, queue, self.precision, self.capacity, &[]);
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("particles copy encoder"),
+        });
+        let particles_buffer = &other.particles_buffers[other.swap];
+        for buffer in &self.particles_buffers {
+            encoder.copy_buffer_to_buffer(particles_buffer, 0, buffer, 0, particles_buffer.size());
+        }
+        // the alive count of `other` can be ahead of its `num_particles`
+        encoder.copy_buffer_to_buffer(
+            &other.indirect.count_buffer,
+            0,
+            &self.indirect.count_buffer,
+            0,
+            std::mem::size_of::<u32>() as u64,
+        );
+        queue.submit(Some(encoder.finish()));
+        self.rebind_particles_buffers(device, None);
+        self.links.clear();
+        self.links.selection.clear();
+        self.coalescence.discard_readback();
+    }
+
+    /// adds `particles` behind the ones alive on the GPU, which keep their simulated state.
+    /// the particle buffers are only replaced when they are too small, then they double
+    pub fn append_particles(&mut self, device: &Device, queue: &Queue, particles: &[Particle]) {
+        if particles.is_empty() {
+            return;
+        }
+        let num_particles = self.num_particles + particles.len();
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("particles append encoder"),
+        });
+        if num_particles > self.capacity {
+            let limits = device.limits();
+            let max_size =
+                (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+            let max_capacity = max_size as usize / self.precision.particle_size();
+            let capacity = (self.capacity * 2).min(max_capacity).max(num_particles);
+            let buffers =
+                Self::create_particles_buffers(device, queue, self.precision, capacity, &[]);
+            // the whole buffer is carried over, the GPU can have more particles alive
+            // than `num_particles`
+            let particles_buffer = &self.particles_buffers[self.swap];
+            for buffer in &buffers {
+                encoder.copy_buffer_to_buffer(
+                    particles_buffer,
+                    0,
+                    buffer,
+                    0,
+                    particles_buffer.size(),
+                );
+            }
+            self.particles_buffers = buffers;
+            self.capacity = capacity;
+            self.indirect.set_capacity(queue, capacity);
+            self.rebind_particles_buffers(device, Some(&mut encoder));
+        }
+        self.particle_append.encode(
+            device,
+            &mut encoder,
+            &self.particles_buffers,
+            &self.indirect.count_buffer,
+            self.precision,
+            particles,
+        );
+        queue.submit(Some(encoder.finish()));
+        // an estimate until the alive count is read back
+        self.num_particles = num_particles.min(self.capacity);
+        self.coalescence.discard_readback();
+    }
+
+    /// binds the passes reading the particle buffers to the current ones. with `keep_ids`
+    /// the particles kept their slots and the encoder carries their ids over
+    fn rebind_particles_buffers(&mut self, device: &Device, keep_ids: Option<&mut CommandEncoder>) {
+        self.coalescence.update_particles_buffers(
+            device,
+            &self.particles_buffers,
+            &self.indirect.count_buffer,
+            self.precision,
+        );
+        self.particle_sort.update_particles_buffers(
+            device,
+            &self.particles_buffers,
+            &self.indirect.count_buffer,
+            self.precision,
+            keep_ids,
+        );
+        self.links.update_particles_buffers(
+            device,
+            &self.particles_buffers,
+            &self.indirect.count_buffer,
+            [
+                &self.particle_sort.ids_buffer,
+                &self.particle_sort.slots_buffer,
+            ],
+        );
+    }
+
+    /// drops the particles past `num_particles` without touching the particle buffers,
+    /// returns false if there are fewer particles and the missing ones have to be appended
+    pub fn shrink_particles(&mut self, queue: &Queue, num_particles: usize) -> bool {
+        if num_particles > self.num_particles {
+            return false;
+        }
+        self.num_particles = num_particles;
+        self.indirect
+            .set_count(queue, num_particles, self.capacity, self.workgroup_size);
+        self.coalescence.discard_readback();
+        true
+    }
+
+    /// waits for the GPU and reads back the particles written by the last compute dispatch
+    pub fn read_particles(&self, device: &Device, queue: &Queue) -> Vec<Particle> {
+        let particles = &self.particles_buffers[self.swap];
+        // the alive count follows the particles, it can be ahead of `num_particles`
+        // when particles merge or split
+        let count_size = std::mem::size_of::<u32>() as u64;
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("particles readback buffer"),
+            size: particles.size() + count_size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("particles readback encoder"),
+        });
+        encoder.copy_buffer_to_buffer(particles, 0, &readback_buffer, 0, particles.size());
+        encoder.copy_buffer_to_buffer(
+            &self.indirect.count_buffer,
+            0,
+            &readback_buffer,
+            particles.size(),
+            count_size,
+        );
+        queue.submit(Some(encoder.finish()));
+        let slice = readback_buffer.slice(..);
+        slice.map_async(MapMode::Read, |_| {});
+        device.poll(Maintain::Wait);
+        let (mut res, alive) = {
+            let data = slice.get_mapped_range();
+            let (particle_bytes, count_bytes) = data.split_at(particles.size() as usize);
+            (
+                self.precision.decode_particles(particle_bytes),
+                bytemuck::pod_read_unaligned::<u32>(count_bytes) as usize,
+            )
+        };
+        readback_buffer.unmap();
+        // the buffers can hold more particles than are alive
+        res.truncate(alive);
+        res
+    }
+
+    /// follows the particle closest to `pos` with the particle tracker and returns its id,
+    /// waits for the GPU
+    pub fn follow_nearest_particle(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        pos: V3,
+    ) -> Option<u32> {
+        let particles = self.read_particles(device, queue);
+        let slot = particles
+            .iter()
+            .map(|p| (V3::new(p.pos[0], p.pos[1], p.pos[2]) - pos).magnitude2())
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1))?
+            .0 as u32;
+        let id = self.read_particle_id(device, queue, slot);
+        self.particle_tracker.follow(id, slot);
+        Some(id)
+    }
+
+    /// the ids and the positions of the particles within `radius` of `pos`, or of the
+    /// closest one if none is, waits for the GPU
+    pub fn particles_near(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        pos: V3,
+        radius: f32,
+    ) -> Vec<(u32, V3)> {
+        let positions: Vec<V3> = self
+            .read_particles(device, queue)
+            .iter()
+            .map(|p| V3::new(p.pos[0], p.pos[1], p.pos[2]))
+            .collect();
+        let mut slots: Vec<usize> = (0..positions.len())
+            .filter(|slot| (positions[*slot] - pos).magnitude2() < radius * radius)
+            .collect();
+        if slots.is_empty() {
+            slots.extend(
+                positions
+                    .iter()
+                    .map(|p| (p - pos).magnitude2())
+                    .enumerate()
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(slot, _)| slot),
+            );
+        }
+        let ids = self.read_particle_ids(device, queue, 0, positions.len() as u32);
+        slots
+            .into_iter()
+            .map(|slot| (ids[slot], positions[slot]))
+            .collect()
+    }
+
+    /// the id of the particle in `slot`, waits for the GPU
+    fn read_particle_id(&self, device: &Device, queue: &Queue, slot: u32) -> u32 {
+        self.read_particle_ids(device, queue, slot, 1)[0]
+    }
+
+    /// the ids of the particles in `count` slots from `first` on, waits for the GPU
+    fn read_particle_ids(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        first: u32,
+        count: u32,
+    ) -> Vec<u32> {
+        let stride = std::mem::size_of::<u32>() as u64;
+        let size = count.max(1) as u64 * stride;
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("particle id readback buffer"),
+            size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("particle id readback encoder"),
+        });
+        encoder.copy_buffer_to_buffer(
+            &self.particle_sort.ids_buffer,
+            first as u64 * stride,
+            &readback_buffer,
+            0,
+            size,
+        );
+        queue.submit(Some(encoder.finish()));
+        let slice = readback_buffer.slice(..);
+        slice.map_async(MapMode::Read, |_| {});
+        device.poll(Maintain::Wait);
+        let ids = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        readback_buffer.unmap();
+        ids
+    }
+
+    /// converts the particle buffers to `precision` and rebuilds every pipeline reading them
+    pub fn set_precision(&mut self, device: &Device, queue: &Queue, precision: ParticlePrecision) {
+        if precision == self.precision {
+            return;
+        }
+        let particles = self.read_particles(device, queue);
+        self.rebuild_precision_pipelines(device, precision);
+        // the particles stay in their slots, so they keep their ids and links
         self.num_particles = particles.len();
-        self.num_workgroups =
-            ((self.num_particles as f32) / (PARTICLES_PER_GROUP as f32)).ceil() as usize;
-        self.particles_buffers[0] = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("particles src buffer"),
-            contents: bytemuck::cast_slice(particles),
-            usage: BufferUsages::VERTEX | BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        self.indirect.set_count(
+            queue,
+            self.num_particles,
+            self.capacity,
+            self.workgroup_size,
+        );
+        self.particles_buffers =
+            Self::create_particles_buffers(device, queue, precision, self.capacity, &particles);
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("particles convert encoder"),
+        });
+        self.rebind_particles_buffers(device, Some(&mut encoder));
+        queue.submit(Some(encoder.finish()));
+        self.swap = 0;
+        self.swap_bind_groups = Self::create_bind_groups(
+            device,
+            &self.bind_group_layout,
+            &self.sim_param_buffer,
+            &[&self.particles_buffers[0], &self.particles_buffers[1]],
+            &self.force_grid_buffer,
+            &self.reaction_rules_buffer,
+            &self.attractors_buffer,
+            &self.indirect.count_buffer,
+            &self.stir_buffer,
+            &self.cursor_sphere_buffer,
+            &self.regions_buffer,
+            &self.coalescence.partners_buffer,
+            &self.force_curve_view,
+        );
+    }
+
+    /// rebuilds every pipeline reading the particle buffers for `precision`
+    fn rebuild_precision_pipelines(&mut self, device: &Device, precision: ParticlePrecision) {
+        self.precision = precision;
+        self.rebuild_pipelines(device);
+        self.reduction.set_precision(device, precision);
+        self.velocity_ops.set_precision(device, precision);
+        self.particle_append.set_precision(device, precision);
+        self.ray_density.set_precision(device, precision);
+    }
+
+    /// rebuilds the compute pipeline with `workgroup_size` particles per workgroup
+    pub fn set_workgroup_size(&mut self, device: &Device, queue: &Queue, workgroup_size: u32) {
+        self.workgroup_size = workgroup_size;
+        self.indirect
+            .set_count(queue, self.num_particles, self.capacity, workgroup_size);
+        self.coalescence.discard_readback();
+        self.rebuild_pipelines(device);
+    }
+
+    /// times the compute pass with every supported workgroup size and keeps the fastest.
+    /// the particles are restored afterwards, so the benchmark does not advance the simulation
+    pub fn autotune_workgroup_size(&mut self, device: &Device, queue: &Queue) {
+        if self.num_particles == 0 {
+            return;
+        }
+        let limits = device.limits();
+        let particles = &self.particles_buffers[self.swap];
+        let backup_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("autotune particles backup buffer"),
+            size: particles.size(),
+            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("autotune backup encoder"),
+        });
+        encoder.copy_buffer_to_buffer(particles, 0, &backup_buffer, 0, particles.size());
+        queue.submit(Some(encoder.finish()));
+
+        let timings: Vec<(u32, f64)> = WORKGROUP_SIZES
+            .into_iter()
+            .filter(|&size| {
+                size <= limits.max_compute_invocations_per_workgroup
+                    && size <= limits.max_compute_workgroup_size_x
+            })
+            .map(|size| {
+                let shader =
+                    Self::create_shader(device, self.precision, size, self.features, &self.source);
+                let pipeline = Self::create_pipeline(device, &self.bind_group_layout, &shader);
+                (size, self.time_dispatches(device, queue, &pipeline, size))
+            })
+            .collect();
+
+        let particles = &self.particles_buffers[self.swap];
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("autotune restore encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&backup_buffer, 0, particles, 0, particles.size());
+        queue.submit(Some(encoder.finish()));
+
+        if let Some(&(fastest, seconds)) = timings.iter().min_by(|a, b| a.1.total_cmp(&b.1)) {
+            log::info!(
+                "workgroup size {} is the fastest with {:.3} ms per dispatch",
+                fastest,
+                seconds * 1000.0
+            );
+            self.set_workgroup_size(device, queue, fastest);
+        }
+        self.workgroup_timings = timings;
+    }
+
+    /// bytes of the buffers growing with the particles and the force grid
+    pub fn buffer_sizes(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            (
+                "particles",
+                self.particles_buffers.iter().map(Buffer::size).sum(),
+            ),
+            ("force grid", self.force_grid_buffer.size()),
+            ("particle ids", self.particle_sort.ids_buffer.size()),
+            ("particle slots", self.particle_sort.slots_buffer.size()),
+            ("merge partners", self.coalescence.partners_buffer.size()),
+        ]
+    }
+
+    /// seconds per dispatch of `pipeline`, measured with timestamp queries when the device
+    /// supports them and with the time until the GPU is idle otherwise
+    fn time_dispatches(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        pipeline: &ComputePipeline,
+        workgroup_size: u32,
+    ) -> f64 {
+        let (workgroups_x, workgroups_y) =
+            split_workgroups((self.num_particles as u32).div_ceil(workgroup_size));
+        let encode_pass = |encoder: &mut CommandEncoder,
+                           timestamp_writes: Option<ComputePassTimestampWrites>,
+                           dispatches: u32| {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("autotune compute pass"),
+                timestamp_writes,
+            });
+            cpass.set_pipeline(pipeline);
+            for i in 0..dispatches {
+                let bind_group = &self.swap_bind_groups[(self.swap + i as usize) % 2];
+                cpass.set_bind_group(0, bind_group, &[]);
+                cpass.dispatch_workgroups(workgroups_x, workgroups_y, 1);
+            }
+        };
+
+        // the first dispatch of a pipeline can include its compilation
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("autotune warm up encoder"),
         });
-        self.particles_buffers[1] = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("particles src buffer"),
-            contents: bytemuck::cast_slice(particles),
-            usage: BufferUsages::VERTEX | BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        encode_pass(&mut encoder, None, 1);
+        queue.submit(Some(encoder.finish()));
+        device.poll(Maintain::Wait);
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("autotune encoder"),
         });
+        if !device.features().contains(Features::TIMESTAMP_QUERY) {
+            encode_pass(&mut encoder, None, AUTOTUNE_DISPATCHES);
+            let start = Instant::now();
+            queue.submit(Some(encoder.finish()));
+            device.poll(Maintain::Wait);
+            return start.elapsed().as_secs_f64() / AUTOTUNE_DISPATCHES as f64;
+        }
+
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: Some("autotune timestamps"),
+            ty: QueryType::Timestamp,
+            count: 2,
+        });
+        let size = 2 * std::mem::size_of::<u64>() as u64;
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("autotune timestamp resolve buffer"),
+            size,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("autotune timestamp readback buffer"),
+            size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let timestamp_writes = ComputePassTimestampWrites {
+            query_set: &query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        };
+        encode_pass(&mut encoder, Some(timestamp_writes), AUTOTUNE_DISPATCHES);
+        encoder.resolve_query_set(&query_set, 0..2, &resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&resolve_buffer, 0, &readback_buffer, 0, size);
+        queue.submit(Some(encoder.finish()));
+        let slice = readback_buffer.slice(..);
+        slice.map_async(MapMode::Read, |_| {});
+        device.poll(Maintain::Wait);
+        let ticks = {
+            let data = slice.get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&data);
+            timestamps[1].saturating_sub(timestamps[0])
+        };
+        readback_buffer.unmap();
+        // the timestamp period is in nanoseconds per tick
+        ticks as f64 * queue.get_timestamp_period() as f64 * 1e-9 / AUTOTUNE_DISPATCHES as f64
     }
 
     pub fn update_force_grid(&mut self, device: &Device, force_grid: &[[f32; 4]]) {
         self.force_grid_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("force grid buffer"),
             contents: bytemuck::cast_slice(force_grid),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        });
+    }
+
+    pub fn update_reaction_rules(&mut self, device: &Device, reaction_rules: &[ReactionRule]) {
+        self.reaction_rules_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("reaction rules buffer"),
+            contents: bytemuck::cast_slice(reaction_rules),
             usage: BufferUsages::STORAGE,
         });
     }
 
-    pub fn update_sim_params(&mut self, device: &Device, sim_params: &SimParams) {
+    pub fn update_attractors(&mut self, device: &Device, attractors: &[Attractor]) {
+        self.attractors_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("attractors buffer"),
+            contents: bytemuck::cast_slice(attractors),
+            usage: BufferUsages::STORAGE,
+        });
+    }
+
+    pub fn update_regions(&mut self, device: &Device, regions: &[RegionParams]) {
+        self.regions_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("regions buffer"),
+            contents: bytemuck::cast_slice(regions),
+            usage: BufferUsages::STORAGE,
+        });
+    }
+
+    /// writes the samples of `ForceCurves::get_raw` into the force curve texture, which is
+    /// replaced if `resolution` changed. the bind groups pick it up with `update_sim_params`
+    pub fn update_force_curves(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        samples: &[[f32; 2]],
+        resolution: u32,
+    ) {
+        if self.force_curve_texture.width() != resolution {
+            (self.force_curve_texture, self.force_curve_view) =
+                create_force_curve_texture(device, resolution);
+        }
+        queue.write_texture(
+            self.force_curve_texture.as_image_copy(),
+            bytemuck::cast_slice(samples),
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(resolution * 8),
+                rows_per_image: Some(NUM_FORCE_CURVES as u32),
+            },
+            self.force_curve_texture.size(),
+        );
+    }
+
+    /// the stirring tool force of the next dispatch
+    pub fn update_stir(&self, queue: &Queue, stir: &StirForce) {
+        queue.write_buffer(&self.stir_buffer, 0, bytemuck::bytes_of(stir));
+    }
+
+    /// the cursor sphere of the next dispatch
+    pub fn update_cursor_sphere(&mut self, queue: &Queue, sphere: &CursorSphere) {
+        self.cursor_collision = sphere.radius > 0.0;
+        queue.write_buffer(&self.cursor_sphere_buffer, 0, bytemuck::bytes_of(sphere));
+    }
+
+    pub fn update_sim_params(&mut self, device: &Device, queue: &Queue, sim_params: &SimParams) {
+        self.reduction
+            .update_masses(queue, &sim_params.particle_type_masses);
+        self.velocity_ops
+            .update_masses(queue, &sim_params.particle_type_masses);
+        self.coalescence.update_params(queue, sim_params);
+        self.particle_sort.update_params(queue, sim_params);
+        self.delta_t = sim_params.delta_t;
+        // every substep advances by its share of the time step
+        let sim_params = &SimParams {
+            delta_t: sim_params.delta_t / self.substeps.max(1) as f32,
+            ..*sim_params
+        };
+        self.links
+            .update_params(queue, sim_params.delta_t, &sim_params.particle_type_masses);
+        self.links.upload(device, queue);
         self.sim_param_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("SimParams buffer init descriptor"),
             contents: bytemuck::bytes_of(sim_params),
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
         });
         self.swap_bind_groups = Self::create_bind_groups(
             device,
@@ -215,14 +1168,163 @@ impl Compute {
             &self.sim_param_buffer,
             &[&self.particles_buffers[0], &self.particles_buffers[1]],
             &self.force_grid_buffer,
+            &self.reaction_rules_buffer,
+            &self.attractors_buffer,
+            &self.indirect.count_buffer,
+            &self.stir_buffer,
+            &self.cursor_sphere_buffer,
+            &self.regions_buffer,
+            &self.coalescence.partners_buffer,
+            &self.force_curve_view,
+        );
+        self.specialize(
+            device,
+            ComputeFeatures::new(sim_params, self.cursor_collision),
         );
     }
 
+    /// the workgroup count comes from the particle count on the GPU. the pipeline is
+    /// dispatched once per substep, each reading the particles the one before wrote
     pub fn compute<'a>(&'a mut self, cpass: &mut ComputePass<'a>) {
-        cpass.set_pipeline(&self.pipeline);
-        cpass.set_bind_group(0, &self.swap_bind_groups[self.swap], &[]);
-        cpass.dispatch_workgroups(self.num_workgroups as u32, 1, 1);
-        self.swap += 1;
-        self.swap %= 2;
+        self.indirect.encode(cpass);
+        // the ids of the links are lost when particles merge or split
+        let links = self.links.active() && !self.coalescence.enabled;
+        for _ in 0..self.substeps.max(1) {
+            cpass.set_pipeline(&self.pipelines[&self.features]);
+            cpass.set_bind_group(0, &self.swap_bind_groups[self.swap], &[]);
+            cpass.dispatch_workgroups_indirect(&self.indirect.args_buffer, 0);
+            self.swap += 1;
+            self.swap %= 2;
+            if links {
+                self.links
+                    .encode(cpass, self.swap, &self.indirect.args_buffer);
+                self.swap += 1;
+                self.swap %= 2;
+            }
+        }
+        self.sim_time += self.delta_t as f64;
+        if self.coalescence.enabled {
+            self.coalescence
+                .encode(cpass, self.swap, &self.indirect.args_buffer);
+            self.swap += 1;
+            self.swap %= 2;
+            // the draw of this frame uses the count after the merges and splits
+            self.indirect.encode(cpass);
+        }
+        if self.particle_sort.due() {
+            self.particle_sort
+                .encode(cpass, self.swap, &self.indirect.args_buffer);
+            self.swap += 1;
+            self.swap %= 2;
+        }
+    }
+
+    /// takes over the alive count after particles merged or split on the GPU
+    pub fn poll_particle_count(&mut self, device: &Device) {
+        if let Some(alive) = self.coalescence.poll(device) {
+            self.num_particles = alive;
+        }
+    }
+
+    /// encodes a pending velocity operation on the particles written by the last compute dispatch
+    pub fn encode_velocity_ops(&mut self, device: &Device, encoder: &mut CommandEncoder) {
+        self.velocity_ops.encode(
+            device,
+            encoder,
+            &self.particles_buffers[self.swap],
+            self.num_particles,
+        );
     }
+
+    /// encodes the readbacks of the particles written by the last compute dispatch
+    pub fn encode_readbacks(&mut self, device: &Device, encoder: &mut CommandEncoder) {
+        let particles = &self.particles_buffers[self.swap];
+        self.coalescence
+            .encode_readback(device, encoder, &self.indirect.count_buffer);
+        self.reduction
+            .encode(device, encoder, particles, self.num_particles);
+        self.diagnostics.encode(
+            device,
+            encoder,
+            particles,
+            self.num_particles,
+            self.precision,
+            self.sim_time,
+        );
+        self.ray_density
+            .encode(device, encoder, particles, self.num_particles);
+        self.particle_tracker.encode_readback(
+            device,
+            encoder,
+            particles,
+            &self.particle_sort.slots_buffer,
+            self.num_particles,
+            self.precision,
+        );
+        self.particle_sample.encode(
+            device,
+            encoder,
+            particles,
+            self.num_particles,
+            self.precision,
+        );
+        let inspected = match self.inspector.source {
+            InspectedBuffer::Particles => particles,
+            InspectedBuffer::ForceGrid => &self.force_grid_buffer,
+            InspectedBuffer::SimParams => &self.sim_param_buffer,
+            InspectedBuffer::ParticleIds => &self.particle_sort.ids_buffer,
+        };
+        self.inspector
+            .encode(device, encoder, inspected, self.precision);
+        let shader = || {
+            Self::create_shader(
+                device,
+                self.precision,
+                self.workgroup_size,
+                self.features,
+                &self.source,
+            )
+        };
+        self.pair_probe.encode(
+            device,
+            encoder,
+            self.features,
+            shader,
+            &self.bind_group_layout,
+            &self.swap_bind_groups[self.swap],
+        );
+    }
+
+    /// maps the readback buffers, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.reduction.after_submit();
+        self.coalescence.after_submit();
+        self.diagnostics.after_submit();
+        self.inspector.after_submit();
+        self.ray_density.after_submit();
+        self.particle_tracker.after_submit();
+        self.particle_sample.after_submit();
+        self.pair_probe.after_submit();
+    }
+}
+
+/// the lookup table of the force curves, `resolution` samples of the value and the slope
+/// of every curve in one row per pair
+fn create_force_curve_texture(device: &Device, resolution: u32) -> (Texture, TextureView) {
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("force curve texture"),
+        size: Extent3d {
+            width: resolution,
+            height: NUM_FORCE_CURVES as u32,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rg32Float,
+        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    (texture, view)
 }
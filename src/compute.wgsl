@@ -1,9 +1,6 @@
-struct Particle {
-  pos: vec4<f32>,
-  vel: vec4<f32>,
-  ty: u32,
-};
-
+// FORCE_GRID_INTERPOLATION, FORCE_GRID_BOUNDARY, CONFINEMENT, FORCE_MODE, FORCE_ACCUMULATION,
+// MASS_COUPLING, FORCE_GRID_ANIMATED, CURSOR_COLLISION, RESPAWN, MERGING, MATERIAL_GRID and FLOOR are prepended by `ComputeFeatures` in compute_features.rs,
+// they replace the SimParams fields of the same name
 // Wrapper type for particle masses to satisfy array stride constraint of : 16 bytes per element
 struct MassWrap {
   @size(16) mass: f32,
@@ -14,17 +11,174 @@ struct Polys {
   
 }
 
+// scales the attraction force polynome with the same index, enabled is 0 for a disabled pair.
+// a curve_range above 0.0 reads the force from the row of force_curves up to that distance
+struct PairForce {
+  scale: f32,
+  enabled: u32,
+  curve_range: f32,
+  // particles of the two types farther apart than this do not attract each other
+  cut_off_distance: f32,
+}
+
+// boids terms of a particle towards its neighbours of another type, neighbours within radius count
+struct Flocking {
+  alignment: f32,
+  cohesion: f32,
+  separation: f32,
+  radius: f32,
+}
+
 struct SimParams {
   attraction_force: array<Poly7, 25>,
+  pair_forces: array<PairForce, 25>,
   particle_type_masses: array<MassWrap, 5>,
   vector_field_dimensions: vec3<u32>,
   deltaT: f32,
   max_velocity: f32,
-  // Bounding volume for all the particles at coordinate system center
+  // the largest of bounding_volume_extents
   bounding_volume_radius: f32,
-  // the maximum distance particles can influence each other
+  // the largest cut off distance of pair_forces
   cut_off_distance: f32,
   distance_exponent: f32,
+  // 0: nearest cell, 1: trilinear, 2: cubic (Catmull-Rom)
+  force_grid_interpolation: u32,
+  // particles are respawned after this many seconds, 0.0 means they live forever
+  particle_lifetime: f32,
+  // 0: clamp to edge, 1: zero force, 2: wrap around
+  force_grid_boundary: u32,
+  // number of force grid layers stored one after the other in force_grid
+  num_force_grid_layers: u32,
+  // the lower 32 bits of the seed of the random numbers generated on the GPU
+  random_seed: u32,
+  // particle positions are scaled by this factor when the bounding volume is resized
+  boundary_scale: f32,
+  // number of rules in reaction_rules
+  num_reaction_rules: u32,
+  // number of point attractors in attractors
+  num_attractors: u32,
+  // 0: wrap around, 1: polynomial restoring force, 2: exponential restoring force, 3: wind tunnel
+  confinement: u32,
+  // the soft confinement starts at this fraction of the bounding volume extents
+  confinement_start: f32,
+  // acceleration towards the center at the bounding volume boundary
+  confinement_strength: f32,
+  // power of the polynomial or sharpness of the exponential confinement
+  confinement_exponent: f32,
+  // boids terms with the same indices as attraction_force
+  flocking: array<Flocking, 25>,
+  // number of regions in regions
+  num_regions: u32,
+  // 0: attraction_force holds forces, 1: it holds potentials, the force is their derivative
+  force_mode: u32,
+  // 1: the layers in force_grid are followed by the keyframes they blend to, with the blend factor in w
+  force_grid_animated: u32,
+  // 0: plain sum of the particle forces, 1: Kahan summation
+  force_accumulation: u32,
+  // particles of the same type closer than this merge after the step, 0.0 disables merging
+  merge_radius: f32,
+  // read by coalescence.wgsl through its own parameters
+  split_mass: f32,
+  split_distance: f32,
+  // 1: the layers and keyframes in force_grid are followed by the damping factors of the material grid
+  material_grid: u32,
+  // Bounding volume for all the particles at coordinate system center, half its size along every axis
+  bounding_volume_extents: vec3<f32>,
+  // fraction of the cut off distance at its end over which the forces fade out, 0.0 cuts them off
+  cutoff_window: f32,
+  // 0: the pair forces grow with both masses, 1: they are divided by the own mass, 2: masses are ignored
+  mass_coupling: u32,
+  // the height of the floor plane
+  floor_height: f32,
+  // fraction of the speed into the floor a particle bounces back with
+  floor_restitution: f32,
+  // a bounce slows a particle along the floor by this multiple of the speed change into it
+  floor_friction: f32,
+  // uniform acceleration of every particle
+  gravity: vec3<f32>,
+  // 1: the particles bounce off the floor plane
+  floor: u32,
+  // the axis the wind tunnel flows along
+  inflow_axis: u32,
+  // speed of the wind tunnel stream, negative values flow towards the negative side
+  inflow_speed: f32,
+  // 0.0: uniform stream, 1.0: parabolic profile still at the walls
+  inflow_profile: f32,
+  // random velocity of the inflowing particles as a fraction of the stream speed
+  inflow_turbulence: f32,
+  // fraction of the particles moved to the emitter plane per second, 0.0 disables it
+  emitter_rate: f32,
+  // position of the emitter plane along the flow, 0.0 at the inflow face, 1.0 at the outflow face
+  emitter_position: f32,
+  // fraction of the cross section the emitter covers
+  emitter_size: f32,
+  // the upper 32 bits of the seed
+  random_seed_high: u32,
+};
+
+// the leading fields of `ParticleCount` in indirect.wgsl
+struct ParticleCount {
+  alive: u32,
+  capacity: u32,
+};
+
+// transient force of the stirring tool, mirrors `StirForce` in cursor.rs
+struct StirForce {
+  center: vec3<f32>,
+  radius: f32,
+  axis: vec3<f32>,
+  strength: f32,
+  // 0: off, 1: radial, 2: swirl
+  mode: u32,
+};
+
+// solid sphere around the cursor, mirrors `CursorSphere` in cursor.rs
+struct CursorSphere {
+  center: vec3<f32>,
+  // 0.0 disables the collision
+  radius: f32,
+};
+
+// axis aligned box overriding some of the parameters inside, mirrors `RegionParams` in regions.rs
+struct Region {
+  min: vec3<f32>,
+  // the REGION_* bits of the overridden parameters
+  overrides: u32,
+  max: vec3<f32>,
+  damping: f32,
+  max_velocity: f32,
+  cut_off_distance: f32,
+  time_scale: f32,
+};
+
+const REGION_DAMPING: u32 = 1u;
+const REGION_MAX_VELOCITY: u32 = 2u;
+const REGION_CUT_OFF_DISTANCE: u32 = 4u;
+const REGION_TIME_SCALE: u32 = 8u;
+
+// the parameters a region can override, at the position of a particle
+struct LocalParams {
+  damping: f32,
+  max_velocity: f32,
+  // multiplies the cut off distances of the pairs
+  cut_off_scale: f32,
+  // the time step of the motion, the aging, the reactions and the emission
+  delta_t: f32,
+};
+
+// a particle of type ty within radius of a catalyst particle turns into product with rate probability per second
+struct ReactionRule {
+  ty: u32,
+  catalyst: u32,
+  product: u32,
+  radius: f32,
+  rate: f32,
+};
+
+// point mass placed with the cursor, a negative strength repels
+struct Attractor {
+  pos: vec3<f32>,
+  strength: f32,
 };
 
 
@@ -55,6 +209,136 @@ fn eval_poly7(x: f32, p: Poly7) -> f32 {
   return p.a * x4 * x3 + p.b * x3 * x3 + p.c * x2 * x3 + p.d * x4 + p.e * x3 + p.f * x2 + p.g * x + p.h;
 }
 
+// slope of the polynomial at x
+fn eval_poly7_derivative(x: f32, p: Poly7) -> f32 {
+  let x2 = x * x;
+  let x4 = x2 * x2;
+  let x3 = x2 * x;
+  return 7.0 * p.a * x3 * x3 + 6.0 * p.b * x2 * x3 + 5.0 * p.c * x4 + 4.0 * p.d * x3 + 3.0 * p.e * x2 + 2.0 * p.f * x + p.g;
+}
+
+// 1 up to the window at the end of the cut off distance, then falling to 0 at it with zero slope,
+// mirrors `smooth_cutoff` in poly7.rs
+fn smooth_cutoff(r: f32, cut_off_distance: f32) -> f32 {
+  let width = params.cutoff_window * cut_off_distance;
+  if width <= 0.0 {
+    return 1.0;
+  }
+  let t = clamp((r - (cut_off_distance - width)) / width, 0.0, 1.0);
+  return 1.0 - t * t * (3.0 - 2.0 * t);
+}
+
+// slope of smooth_cutoff at r
+fn smooth_cutoff_slope(r: f32, cut_off_distance: f32) -> f32 {
+  let width = params.cutoff_window * cut_off_distance;
+  if width <= 0.0 {
+    return 0.0;
+  }
+  let t = clamp((r - (cut_off_distance - width)) / width, 0.0, 1.0);
+  return -6.0 * t * (1.0 - t) / width;
+}
+
+// the value and the slope of the force curve of the pair at `index` at distance r, from
+// the polynome or linearly interpolated between the samples baked into the lookup table
+fn force_curve(index: u32, r: f32) -> vec2<f32> {
+  let range = params.pair_forces[index].curve_range;
+  if range <= 0.0 {
+    let poly = params.attraction_force[index];
+    return vec2<f32>(eval_poly7(r, poly), eval_poly7_derivative(r, poly));
+  }
+  let last = textureDimensions(force_curves).x - 1u;
+  let x = clamp(r / range, 0.0, 1.0) * f32(last);
+  let i = min(u32(x), last - 1u);
+  let a = textureLoad(force_curves, vec2<u32>(i, index), 0).xy;
+  let b = textureLoad(force_curves, vec2<u32>(i + 1u, index), 0).xy;
+  return mix(a, b, x - f32(i));
+}
+
+// the attraction of the pair at `index` at distance r before the mass coupling.
+// a potential pulls towards lower values, dU/dr > 0 attracts
+// the forces fade out towards the cut off distance, a potential is shifted to 0 there
+// first, so the windowed potential is still the one the force is derived from
+fn pair_attraction(index: u32, r: f32, cut_off_distance: f32) -> f32 {
+  let curve = force_curve(index, r);
+  let window = smooth_cutoff(r, cut_off_distance);
+  var force = curve.x * window;
+  if FORCE_MODE == 1u {
+    let shifted = curve.x - force_curve(index, cut_off_distance).x;
+    force = curve.y * window + shifted * smooth_cutoff_slope(r, cut_off_distance);
+  }
+  return force * pow(r, params.distance_exponent) * params.pair_forces[index].scale;
+}
+
+// pcg hash
+fn hash(x: u32) -> u32 {
+  let state = x * 747796405u + 2891336453u;
+  let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+  return (word >> 22u) ^ word;
+}
+
+// random number in [0.0..1.0]
+fn rand(seed: u32) -> f32 {
+  return f32(hash(seed)) / 4294967295.0;
+}
+
+// both words of the seed folded into the one mixed into every random number
+fn seed_word() -> u32 {
+  return params.random_seed ^ hash(params.random_seed_high);
+}
+
+// pushes particles within the radius of the stirring tool outwards or around its axis
+fn stir_acceleration(pos: vec3<f32>) -> vec3<f32> {
+  let d = pos - stir.center;
+  let dist = length(d);
+  if stir.mode == 0u || dist >= stir.radius || dist < 0.001 {
+    return vec3<f32>(0.0);
+  }
+  let falloff = 1.0 - dist / stir.radius;
+  let dir = d / dist;
+  if stir.mode == 1u {
+    return dir * stir.strength * falloff;
+  }
+  return cross(stir.axis, dir) * stir.strength * falloff;
+}
+
+// pushes particles in the boundary layer of the bounding volume back towards the center,
+// separately along every axis
+fn confinement_acceleration(pos: vec3<f32>) -> vec3<f32> {
+  let x = abs(pos) / params.bounding_volume_extents;
+  let depth = max((x - params.confinement_start) / max(1.0 - params.confinement_start, 0.001), vec3<f32>(0.0));
+  let exponent = max(params.confinement_exponent, 0.01);
+  var shape: vec3<f32>;
+  if CONFINEMENT == 1u {
+    // pow is undefined for 0.0
+    shape = select(vec3<f32>(0.0), pow(depth, vec3<f32>(exponent)), depth > vec3<f32>(0.0));
+  } else {
+    shape = (exp(exponent * depth) - 1.0) / (exp(exponent) - 1.0);
+  }
+  return -sign(pos) * shape * params.confinement_strength;
+}
+
+// velocity of the wind tunnel stream at pos, the parabolic profile slows it down towards
+// the walls across the flow
+fn stream_velocity(pos: vec3<f32>) -> vec3<f32> {
+  let axis = params.inflow_axis;
+  let across = pos / params.bounding_volume_extents;
+  let u = across[(axis + 1u) % 3u];
+  let v = across[(axis + 2u) % 3u];
+  let parabola = max(1.0 - u * u, 0.0) * max(1.0 - v * v, 0.0);
+  var velocity = vec3<f32>();
+  velocity[axis] = params.inflow_speed * mix(1.0, parabola, params.inflow_profile);
+  return velocity;
+}
+
+// a random position on the plane across the wind tunnel at `along` on the flow axis,
+// within `size` times the extents of the cross section
+fn tunnel_position(seed: u32, along: f32, size: f32) -> vec3<f32> {
+  let r = vec3<f32>(rand(seed), rand(hash(seed)), rand(hash(hash(seed))));
+  var pos = (r * 2.0 - 1.0) * size * params.bounding_volume_extents;
+  pos[params.inflow_axis] = along;
+  return pos;
+}
+
 fn wrap_symmetrically(val: f32, max: f32) -> f32 {
   if val > max {
     return val - 2.0 * max;
@@ -78,39 +362,201 @@ fn map_range(val: f32, start_a: f32, end_a: f32, start_b: f32, end_b: f32) -> f3
   return valb;
 }
 
-fn force_grid_index(bounding_cube_side_length: f32, vector_field_dimensions: vec3<u32>, v: vec3<f32>) -> u32 {
-  // side length half
-  let slh = bounding_cube_side_length * 0.5;
+@group(0) @binding(0) var<uniform> params : SimParams;
+@group(0) @binding(1) var<storage, read> particlesSrc : array<ParticleStorage>;
+@group(0) @binding(2) var<storage, read_write> particlesDst : array<ParticleStorage>;
+@group(0) @binding(3) var<storage, read> force_grid : array<vec4<f32>>;
+@group(0) @binding(4) var<storage, read> reaction_rules : array<ReactionRule>;
+@group(0) @binding(5) var<storage, read> attractors : array<Attractor>;
+// the particle buffers can hold more particles than are alive
+@group(0) @binding(6) var<storage, read> particle_count : ParticleCount;
+@group(0) @binding(7) var<uniform> stir : StirForce;
+@group(0) @binding(8) var<uniform> cursor_sphere : CursorSphere;
+@group(0) @binding(9) var<storage, read> regions : array<Region>;
+// the nearest particle of the same type within the merge radius, read by coalescence.wgsl
+@group(0) @binding(10) var<storage, read_write> partners : array<u32>;
+// the value and the slope of the spline force curves, one row per pair, see force_curve.rs
+@group(0) @binding(11) var force_curves : texture_2d<f32>;
+
+const NO_PARTNER: u32 = 0xffffffffu;
+
+// the global parameters, overridden by the last region containing pos
+fn local_params(pos: vec3<f32>) -> LocalParams {
+  var local = LocalParams(1.0, params.max_velocity, 1.0, params.deltaT);
+  for (var r = 0u; r < params.num_regions; r++) {
+    let region = regions[r];
+    if any(pos < region.min) || any(pos > region.max) {
+      continue;
+    }
+    if (region.overrides & REGION_DAMPING) != 0u {
+      local.damping = region.damping;
+    }
+    if (region.overrides & REGION_MAX_VELOCITY) != 0u {
+      local.max_velocity = region.max_velocity;
+    }
+    // the largest cut off distance becomes the one of the region, the others are scaled along
+    if (region.overrides & REGION_CUT_OFF_DISTANCE) != 0u {
+      local.cut_off_scale = region.cut_off_distance / max(params.cut_off_distance, 0.001);
+    }
+    if (region.overrides & REGION_TIME_SCALE) != 0u {
+      local.delta_t = params.deltaT * region.time_scale;
+    }
+  }
+  return local;
+}
+
+// keeps the inverse square force finite close to an attractor
+const ATTRACTOR_SOFTENING: f32 = 0.05;
+
+// force vector of a grid cell, indices outside of the grid are handled according to force_grid_boundary
+fn force_grid_cell(layer: u32, i: vec3<i32>) -> vec3<f32> {
+  let dims = vec3<i32>(params.vector_field_dimensions);
+  let layer_offset = layer * u32(dims.x * dims.y * dims.z);
+  var c = i;
+  switch FORCE_GRID_BOUNDARY {
+    case 1u: {
+      if any(i < vec3<i32>(0)) || any(i >= dims) {
+        return vec3<f32>();
+      }
+    }
+    case 2u: {
+      c = ((i % dims) + dims) % dims;
+    }
+    default: {
+      c = clamp(i, vec3<i32>(0), dims - vec3<i32>(1));
+    }
+  }
+  let index = layer_offset + u32(c.x * dims.y * dims.z + c.y * dims.z + c.z);
+  let force = force_grid[index].xyz;
+  if !FORCE_GRID_ANIMATED {
+    return force;
+  }
+  let next = force_grid[index + params.num_force_grid_layers * u32(dims.x * dims.y * dims.z)];
+  return mix(force, next.xyz, next.w);
+}
+
+// continuous grid coordinates of v, cell centers lie on whole numbers
+fn force_grid_coords(v: vec3<f32>) -> vec3<f32> {
+  let slh = params.bounding_volume_extents;
+  let dims = vec3<f32>(params.vector_field_dimensions);
+  return (v + slh) / (2.0 * slh) * dims - vec3<f32>(0.5);
+}
 
-  let ixx = u32(map_range(v.x, -slh, slh, 0.0, f32(vector_field_dimensions.x)));
-  let ixy = u32(map_range(v.y, -slh, slh, 0.0, f32(vector_field_dimensions.y)));
-  let ixz = u32(map_range(v.z, -slh, slh, 0.0, f32(vector_field_dimensions.z)));
-  return ixx * vector_field_dimensions.y * vector_field_dimensions.z + ixy * vector_field_dimensions.z + ixz;
+fn sample_force_grid_trilinear(layer: u32, v: vec3<f32>) -> vec3<f32> {
+  let g = force_grid_coords(v);
+  let i0 = vec3<i32>(floor(g));
+  let f = g - floor(g);
+  let c00 = mix(force_grid_cell(layer, i0), force_grid_cell(layer, i0 + vec3<i32>(0, 0, 1)), f.z);
+  let c01 = mix(force_grid_cell(layer, i0 + vec3<i32>(0, 1, 0)), force_grid_cell(layer, i0 + vec3<i32>(0, 1, 1)), f.z);
+  let c10 = mix(force_grid_cell(layer, i0 + vec3<i32>(1, 0, 0)), force_grid_cell(layer, i0 + vec3<i32>(1, 0, 1)), f.z);
+  let c11 = mix(force_grid_cell(layer, i0 + vec3<i32>(1, 1, 0)), force_grid_cell(layer, i0 + vec3<i32>(1, 1, 1)), f.z);
+  return mix(mix(c00, c01, f.y), mix(c10, c11, f.y), f.x);
 }
 
-@group(0) @binding(0) var<uniform> params : SimParams;
-@group(0) @binding(1) var<storage, read> particlesSrc : array<Particle>;
-@group(0) @binding(2) var<storage, read_write> particlesDst : array<Particle>;
-@group(0) @binding(3) var<storage, read> force_grid : array<vec4<f32>>;
+// Catmull-Rom weights for the samples at -1, 0, 1, 2
+fn catmull_rom_weights(t: f32) -> vec4<f32> {
+  let t2 = t * t;
+  let t3 = t2 * t;
+  return vec4<f32>(
+    -0.5 * t3 + t2 - 0.5 * t,
+    1.5 * t3 - 2.5 * t2 + 1.0,
+    -1.5 * t3 + 2.0 * t2 + 0.5 * t,
+    0.5 * t3 - 0.5 * t2,
+  );
+}
+
+fn sample_force_grid_cubic(layer: u32, v: vec3<f32>) -> vec3<f32> {
+  let g = force_grid_coords(v);
+  let i0 = vec3<i32>(floor(g)) - vec3<i32>(1);
+  let f = g - floor(g);
+  let wx = catmull_rom_weights(f.x);
+  let wy = catmull_rom_weights(f.y);
+  let wz = catmull_rom_weights(f.z);
+  var res = vec3<f32>();
+  for (var x = 0; x < 4; x++) {
+    for (var y = 0; y < 4; y++) {
+      for (var z = 0; z < 4; z++) {
+        res += wx[x] * wy[y] * wz[z] * force_grid_cell(layer, i0 + vec3<i32>(x, y, z));
+      }
+    }
+  }
+  return res;
+}
+
+fn sample_force_grid_layer(layer: u32, v: vec3<f32>) -> vec3<f32> {
+  switch FORCE_GRID_INTERPOLATION {
+    case 1u: {
+      return sample_force_grid_trilinear(layer, v);
+    }
+    case 2u: {
+      return sample_force_grid_cubic(layer, v);
+    }
+    default: {
+      return force_grid_cell(layer, vec3<i32>(floor(force_grid_coords(v) + vec3<f32>(0.5))));
+    }
+  }
+}
+
+// sum of all force grid layers at v
+fn sample_force_grid(v: vec3<f32>) -> vec3<f32> {
+  var res = vec3<f32>();
+  for (var layer = 0u; layer < params.num_force_grid_layers; layer++) {
+    res += sample_force_grid_layer(layer, v);
+  }
+  return res;
+}
+
+// damping factor of the material grid cell nearest to v, the factors of four cells
+// are packed into a vector after the layers and keyframes in force_grid
+fn material_damping(v: vec3<f32>) -> f32 {
+  let dims = vec3<i32>(params.vector_field_dimensions);
+  let c = clamp(vec3<i32>(floor(force_grid_coords(v) + vec3<f32>(0.5))), vec3<i32>(0), dims - vec3<i32>(1));
+  let num_cells = u32(dims.x * dims.y * dims.z);
+  let offset = params.num_force_grid_layers * num_cells * select(1u, 2u, FORCE_GRID_ANIMATED);
+  let cell = u32(c.x * dims.y * dims.z + c.y * dims.z + c.z);
+  return force_grid[offset + cell / 4u][cell % 4u];
+}
 
 // https://github.com/austinEng/Project6-Vulkan-Flocking/blob/master/data/shaders/computeparticles/particle.comp
+// WORKGROUP_SIZE is prepended by Compute::shader_source
 @compute
-@workgroup_size(64)
-fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
-    let total = arrayLength(&particlesSrc);
-    let index = global_invocation_id.x;
+@workgroup_size(WORKGROUP_SIZE)
+fn main(
+    @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let total = min(particle_count.alive, arrayLength(&particlesSrc));
+    let index = invocation_index(global_invocation_id, num_workgroups, WORKGROUP_SIZE);
     if index >= total {
         return;
     }
 
-    var vPos: vec3<f32> = particlesSrc[index].pos.xyz;
-    var vVel: vec3<f32> = particlesSrc[index].vel.xyz;
-    var vParticleType: u32 = particlesSrc[index].ty;
-    let vMass = params.particle_type_masses[particlesSrc[index].ty].mass;
+    let particle = load_particle(particlesSrc[index]);
+    var vPos: vec3<f32> = particle.pos.xyz * params.boundary_scale;
+    var vVel: vec3<f32> = particle.vel.xyz;
+    var vParticleType: u32 = particle.ty;
+    let vMass = params.particle_type_masses[particle.ty].mass * particle.mass;
+    let local = local_params(vPos);
 
     // accumulated acceleration vector
     var cAcc: vec3<f32> = vec3<f32>();
+    // the forces of the other particles, summed separately so the compensation
+    // only covers the many small contributions
+    var pairAcc = vec3<f32>();
+    // low order bits lost by the last addition to pairAcc
+    var compensation = vec3<f32>();
     var cForceCount: i32 = 0;
+    // bit r is set when reaction rule r found a catalyst nearby
+    var reactions: u32 = 0u;
+    // weighted sums of the flocking terms and the number of neighbours of each
+    var alignment = vec3<f32>();
+    var cohesion = vec3<f32>();
+    var separation = vec3<f32>();
+    var num_aligned = 0.0;
+    var num_cohesive = 0.0;
+    // nearest particle of the same type within the merge radius
+    var partner = NO_PARTNER;
+    var partner_distance = params.merge_radius;
 
     var i: u32 = 0u;
     loop {
@@ -121,61 +567,248 @@ fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
           continue;
         }
         
-        let direction = particlesSrc[i].pos.xyz - vPos;
+        let other = load_particle(particlesSrc[i]);
+        let direction = other.pos.xyz - vPos;
         let direction_length = length(direction);
+        for (var r = 0u; r < params.num_reaction_rules; r++) {
+          let rule = reaction_rules[r];
+          if rule.ty == vParticleType && rule.catalyst == other.ty && direction_length < rule.radius {
+            reactions |= 1u << r;
+          }
+        }
+        if MERGING && other.ty == vParticleType && direction_length < partner_distance {
+          partner = i;
+          partner_distance = direction_length;
+        }
         if direction_length < 0.001 {
           continue;
         }
-        if direction_length > params.cut_off_distance {
+        let flocking = params.flocking[other.ty + vParticleType * 5u];
+        if direction_length < flocking.radius {
+          if flocking.alignment != 0.0 {
+            alignment += flocking.alignment * (other.vel.xyz - vVel);
+            num_aligned += 1.0;
+          }
+          if flocking.cohesion != 0.0 {
+            cohesion += flocking.cohesion * direction;
+            num_cohesive += 1.0;
+          }
+          separation -= flocking.separation * direction / (direction_length * direction_length);
+        }
+        let attractionForceIndex: u32 = other.ty + vParticleType * 5u;
+        let pair_force = params.pair_forces[attractionForceIndex];
+        let cut_off_distance = pair_force.cut_off_distance * local.cut_off_scale;
+        if direction_length > cut_off_distance || pair_force.enabled == 0u {
           continue;
         }
-        let distance_factor = pow(direction_length, params.distance_exponent); 
         let directionN: vec3<f32> = normalize(direction);
-        let attractionForceIndex: u32 = particlesSrc[i].ty + vParticleType * 5u;
-        let m2 = params.particle_type_masses[particlesSrc[i].ty].mass;
+        // the mass factor of the acceleration. with gravitational coupling the force is
+        // multiplied by both masses, dividing by the own mass leaves the one of the other
+        var m2 = 1.0;
+        if MASS_COUPLING == 0u {
+          m2 = params.particle_type_masses[other.ty].mass * other.mass;
+        } else if MASS_COUPLING == 1u {
+          m2 = 1.0 / vMass;
+        }
 
         // evaluate attraction force function and add it to the accumulative force
-        // normaly to calculate the force you would multiply by both masses but to calculate the acceleration vector m1 would be devided out again
-        cAcc += directionN * eval_poly7(direction_length,params.attraction_force[attractionForceIndex]) * m2 *  distance_factor;
+        let force = pair_attraction(attractionForceIndex, direction_length, cut_off_distance);
+        let contribution = directionN * force * m2;
+        if FORCE_ACCUMULATION == 1u {
+          let y = contribution - compensation;
+          let t = pairAcc + y;
+          compensation = (t - pairAcc) - y;
+          pairAcc = t;
+        } else {
+          pairAcc += contribution;
+        }
 
         continuing {
             i = i + 1u;
         }
     }
 
-    // apply force grid
-    let fgi = force_grid_index(params.bounding_volume_radius * 2.0, params.vector_field_dimensions, vPos);
-    cAcc += 10.0 * force_grid[fgi].xyz / vMass;
+    cAcc += pairAcc;
+
+    // apply flocking, steering towards the mean velocity and position of the neighbours
+    cAcc += alignment / max(num_aligned, 1.0) + cohesion / max(num_cohesive, 1.0) + separation;
+
+    // apply force grid, a direct acceleration like the pair forces without mass coupling
+    cAcc += 10.0 * sample_force_grid(vPos) / select(vMass, 1.0, MASS_COUPLING == 2u);
+
+    // apply soft confinement
+    if CONFINEMENT == 1u || CONFINEMENT == 2u {
+      cAcc += confinement_acceleration(vPos);
+    }
+
+    // apply point attractors
+    for (var a = 0u; a < params.num_attractors; a++) {
+      let d = attractors[a].pos - vPos;
+      let d2 = dot(d, d) + ATTRACTOR_SOFTENING;
+      cAcc += attractors[a].strength * d * inverseSqrt(d2) / d2;
+    }
+
+    // apply the stirring tool
+    cAcc += stir_acceleration(vPos);
 
-    // deceleration
-    vVel = vVel * exp(-params.deltaT);
+    cAcc += params.gravity;
+
+    // deceleration, scaled by the material the particle is in. in the wind tunnel the
+    // damping drags the particles along with the stream instead
+    var damping = local.damping;
+    var stream = vec3<f32>();
+    if CONFINEMENT == 3u {
+      stream = stream_velocity(vPos);
+    }
+    if MATERIAL_GRID {
+      let material = material_damping(vPos);
+      damping *= material;
+      // sticky materials hold the stream back like a porous obstacle
+      stream /= max(material, 1.0);
+    }
+    vVel = stream + (vVel - stream) * exp(-damping * local.delta_t);
 
     // let cAcc = cForce / params.particle_type_masses[vParticleType].mass;
-    vVel += cAcc * params.deltaT;
+    vVel += cAcc * local.delta_t;
 
     // clamp velocity for a more pleasing simulation
     let vel = length(vVel);
     if vel > 0.001 {
-      vVel = normalize(vVel) * clamp(vel, 0.0, params.max_velocity);
+      vVel = normalize(vVel) * clamp(vel, 0.0, local.max_velocity);
     }
 
     // kinematic update
-    vPos += vVel * params.deltaT;
+    vPos += vVel * local.delta_t;
+
+    // push particles out of the cursor sphere and stop them moving into it
+    if CURSOR_COLLISION {
+      let d = vPos - cursor_sphere.center;
+      let dist = length(d);
+      if dist < cursor_sphere.radius {
+        let normal = select(vec3<f32>(0.0, 1.0, 0.0), d / dist, dist > 0.0001);
+        vPos = cursor_sphere.center + normal * cursor_sphere.radius;
+        vVel -= normal * min(dot(vVel, normal), 0.0);
+      }
+    }
 
     // if length(vPos) > params.bounding_sphere_radius {
         // TODO : mirror velocity on bounding sphere normal
     // }
 
-    // Wrap around boundary
-    vPos.x = wrap_symmetrically(vPos.x, params.bounding_volume_radius);
-    vPos.y = wrap_symmetrically(vPos.y, params.bounding_volume_radius);
-    vPos.z = wrap_symmetrically(vPos.z, params.bounding_volume_radius);
+    if CONFINEMENT == 0u {
+      // Wrap around boundary
+      vPos.x = wrap_symmetrically(vPos.x, params.bounding_volume_extents.x);
+      vPos.y = wrap_symmetrically(vPos.y, params.bounding_volume_extents.y);
+      vPos.z = wrap_symmetrically(vPos.z, params.bounding_volume_extents.z);
+    } else if CONFINEMENT == 3u {
+      // particles leaving through either end of the wind tunnel, and the ones the emitter
+      // picks, enter again with the stream velocity
+      let axis = params.inflow_axis;
+      let extent = params.bounding_volume_extents[axis];
+      let inflow = -select(-1.0, 1.0, params.inflow_speed >= 0.0) * extent;
+      let seed = hash(index ^ seed_word() ^ bitcast<u32>(vPos.z) ^ bitcast<u32>(vVel.x));
+      var entering = false;
+      if abs(vPos[axis]) > extent {
+        vPos = tunnel_position(seed, inflow, 1.0);
+        entering = true;
+      } else if params.emitter_rate > 0.0 && rand(hash(seed ^ 0x9e3779b9u)) < 1.0 - exp(-params.emitter_rate * local.delta_t) {
+        let along = inflow * (1.0 - 2.0 * params.emitter_position);
+        vPos = tunnel_position(hash(seed ^ 0x85ebca6bu), along, params.emitter_size);
+        entering = true;
+      }
+      if entering {
+        let r = vec3<f32>(rand(hash(seed ^ 1u)), rand(hash(seed ^ 2u)), rand(hash(seed ^ 3u)));
+        vVel = stream_velocity(vPos) + (r * 2.0 - 1.0) * params.inflow_turbulence * abs(params.inflow_speed);
+      }
+      vPos.x = wrap_symmetrically(vPos.x, params.bounding_volume_extents.x);
+      vPos.y = wrap_symmetrically(vPos.y, params.bounding_volume_extents.y);
+      vPos.z = wrap_symmetrically(vPos.z, params.bounding_volume_extents.z);
+    } else {
+      // the confinement force may be too weak to stop fast particles
+      vPos = clamp(vPos, -params.bounding_volume_extents, params.bounding_volume_extents);
+    }
+
+    // bounce off the floor plane, the friction of the impact slows the particle along the floor
+    if FLOOR && vPos.y < params.floor_height {
+      vPos.y = params.floor_height;
+      if vVel.y < 0.0 {
+        let impact = -vVel.y * (1.0 + params.floor_restitution);
+        vVel.y = -vVel.y * params.floor_restitution;
+        let speed = length(vVel.xz);
+        if speed > 0.0 {
+          let along = vVel.xz * max(1.0 - params.floor_friction * impact / speed, 0.0);
+          vVel = vec3<f32>(along.x, vVel.y, along.y);
+        }
+      }
+    }
 
     // clamp to boundary
     // vPos.x = clamp(vPos.x, -params.bounding_volume_radius, params.bounding_volume_radius);
     // vPos.y = clamp(vPos.y, -params.bounding_volume_radius, params.bounding_volume_radius);
     // vPos.z = clamp(vPos.z, -params.bounding_volume_radius, params.bounding_volume_radius);
 
+    // respawn particles at a random position when they reach the end of their lifetime
+    // a region slowing down time also slows down aging, reactions and emission
+    var age = particle.age + local.delta_t;
+    var mass = particle.mass;
+    if RESPAWN && age > params.particle_lifetime {
+      let seed = hash(index ^ seed_word() ^ bitcast<u32>(vPos.x) ^ bitcast<u32>(vVel.y));
+      let r = vec3<f32>(rand(seed), rand(hash(seed)), rand(hash(hash(seed))));
+      vPos = (r * 2.0 - 1.0) * params.bounding_volume_extents;
+      vVel = vec3<f32>();
+      age = 0.0;
+      mass = 1.0;
+    }
+
+    // change the particle type according to the first reaction that fires
+    for (var r = 0u; r < params.num_reaction_rules; r++) {
+      if (reactions & (1u << r)) == 0u {
+        continue;
+      }
+      let rule = reaction_rules[r];
+      let seed = hash(index ^ hash(r ^ seed_word()) ^ bitcast<u32>(vPos.y) ^ bitcast<u32>(vVel.z));
+      if rand(seed) < 1.0 - exp(-rule.rate * local.delta_t) {
+        vParticleType = rule.product;
+        break;
+      }
+    }
+
+    if MERGING {
+      partners[index] = partner;
+    }
+
     // Write back
-    particlesDst[index] = Particle(vec4<f32>(vPos, 1.0), vec4<f32>(vVel, 1.0), particlesSrc[index].ty);
-}
\ No newline at end of file
+    particlesDst[index] = store_particle(Particle(vec4<f32>(vPos, 1.0), vec4<f32>(vVel, 1.0), vParticleType, age, mass));
+}
+
+// mirrors `PairProbeParams` in pair_probe.rs
+struct PairProbe {
+  index: u32,
+  count: u32,
+  // the samples reach this fraction of the cut off distance
+  range: f32,
+  _pad: u32,
+}
+
+@group(1) @binding(0) var<uniform> probe : PairProbe;
+// the distance and the attraction of every sample
+@group(1) @binding(1) var<storage, read_write> probe_samples : array<vec2<f32>>;
+
+// samples the attraction of the probed pair like the main loop applies it between two
+// particles, the gui plots it against the curves evaluated on the CPU
+// the workgroup size is PAIR_PROBE_WORKGROUP_SIZE in pair_probe.rs
+@compute
+@workgroup_size(64)
+fn probe_pair_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+  let i = global_invocation_id.x;
+  if i >= probe.count {
+    return;
+  }
+  let pair_force = params.pair_forces[probe.index];
+  let r = pair_force.cut_off_distance * probe.range * f32(i) / f32(max(probe.count, 2u) - 1u);
+  var force = 0.0;
+  if r >= 0.001 && r <= pair_force.cut_off_distance && pair_force.enabled != 0u {
+    force = pair_attraction(probe.index, r, pair_force.cut_off_distance);
+  }
+  probe_samples[i] = vec2<f32>(r, force);
+}
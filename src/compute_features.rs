@@ -0,0 +1,83 @@
+use crate::sim_params::{
+    Confinement, ForceAccumulation, ForceGridBoundary, ForceGridInterpolation, ForceMode,
+    MassCoupling, SimParams,
+};
+
+/// the options of the compute shader that are compiled into it as constants instead of
+/// being branched on for every particle, `Compute` keeps a pipeline per combination in use
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ComputeFeatures {
+    pub force_grid_interpolation: ForceGridInterpolation,
+    pub force_grid_boundary: ForceGridBoundary,
+    pub confinement: Confinement,
+    pub force_mode: ForceMode,
+    pub force_accumulation: ForceAccumulation,
+    pub mass_coupling: MassCoupling,
+    pub force_grid_animated: bool,
+    /// the cursor sphere pushes the particles out
+    pub cursor_collision: bool,
+    /// particles are respawned at random positions at the end of their lifetime
+    pub respawn: bool,
+    /// every particle looks for the nearest one of its type to merge with
+    pub merging: bool,
+    /// the damping is scaled by the material grid
+    pub material_grid: bool,
+    /// the particles bounce off the floor plane
+    pub floor: bool,
+}
+
+impl ComputeFeatures {
+    pub fn new(sim_params: &SimParams, cursor_collision: bool) -> Self {
+        ComputeFeatures {
+            force_grid_interpolation: sim_params.force_grid_interpolation,
+            force_grid_boundary: sim_params.force_grid_boundary,
+            confinement: sim_params.confinement,
+            force_mode: sim_params.force_mode,
+            force_accumulation: sim_params.force_accumulation,
+            mass_coupling: sim_params.mass_coupling,
+            force_grid_animated: sim_params.force_grid_animated != 0,
+            cursor_collision,
+            respawn: sim_params.particle_lifetime > 0.0,
+            merging: sim_params.merge_radius > 0.0,
+            material_grid: sim_params.material_grid != 0,
+            floor: sim_params.floor != 0,
+        }
+    }
+
+    /// the constants compute.wgsl reads the features from
+    pub fn shader_source(&self) -> String {
+        [
+            format!(
+                "const FORCE_GRID_INTERPOLATION: u32 = {}u;",
+                self.force_grid_interpolation as u32
+            ),
+            format!(
+                "const FORCE_GRID_BOUNDARY: u32 = {}u;",
+                self.force_grid_boundary as u32
+            ),
+            format!("const CONFINEMENT: u32 = {}u;", self.confinement as u32),
+            format!("const FORCE_MODE: u32 = {}u;", self.force_mode as u32),
+            format!(
+                "const FORCE_ACCUMULATION: u32 = {}u;",
+                self.force_accumulation as u32
+            ),
+            format!("const MASS_COUPLING: u32 = {}u;", self.mass_coupling as u32),
+            format!(
+                "const FORCE_GRID_ANIMATED: bool = {};",
+                self.force_grid_animated
+            ),
+            format!("const CURSOR_COLLISION: bool = {};", self.cursor_collision),
+            format!("const RESPAWN: bool = {};", self.respawn),
+            format!("const MERGING: bool = {};", self.merging),
+            format!("const MATERIAL_GRID: bool = {};", self.material_grid),
+            format!("const FLOOR: bool = {};", self.floor),
+        ]
+        .join("\n")
+    }
+}
+
+impl Default for ComputeFeatures {
+    fn default() -> Self {
+        ComputeFeatures::new(&SimParams::new(), false)
+    }
+}
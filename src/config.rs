@@ -0,0 +1,333 @@
+use std::io::{Error, ErrorKind};
+
+use winit::{
+    event_loop::EventLoopWindowTarget,
+    monitor::{MonitorHandle, VideoMode},
+    window::{Fullscreen, Window},
+};
+
+use crate::i18n::Language;
+
+/// file the window settings are read from at startup
+pub const CONFIG_PATH: &str = "config.toml";
+/// the body text size of the egui default style
+pub const DEFAULT_FONT_SIZE: f32 = 12.5;
+
+/// how the window covers the monitor
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Windowed = 0,
+    /// a window without decorations the size of the monitor
+    Borderless,
+    /// takes over the video mode of the monitor closest to the window size
+    Exclusive,
+}
+
+impl FullscreenMode {
+    pub const ALL: [FullscreenMode; 3] = [
+        FullscreenMode::Windowed,
+        FullscreenMode::Borderless,
+        FullscreenMode::Exclusive,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FullscreenMode::Windowed => "windowed",
+            FullscreenMode::Borderless => "borderless",
+            FullscreenMode::Exclusive => "exclusive",
+        }
+    }
+}
+
+/// colors of the controls
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UiTheme {
+    Dark,
+    Light,
+}
+
+impl UiTheme {
+    pub const ALL: [UiTheme; 2] = [UiTheme::Dark, UiTheme::Light];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            UiTheme::Dark => "dark",
+            UiTheme::Light => "light",
+        }
+    }
+}
+
+/// the window settings, saved as a small subset of toml with one `key = value` line per setting
+pub struct Config {
+    pub path: String,
+    pub error: Option<String>,
+    /// inner size of the window in physical pixels while it is not fullscreen
+    pub width: u32,
+    pub height: u32,
+    /// replaces the default window title
+    pub title: Option<String>,
+    pub fullscreen: FullscreenMode,
+    /// the mode F11 and Alt+Enter switch a window to, never windowed
+    pub fullscreen_toggle: FullscreenMode,
+    /// index into the monitors of the system, the primary one if it is out of range
+    pub monitor: usize,
+    /// presents in step with the display instead of as fast as possible
+    pub vsync: bool,
+    /// prefers a 16 bit float surface that keeps colors brighter than white, read at startup
+    pub hdr: bool,
+    /// the controls are shown at startup, F1 toggles them
+    pub show_gui: bool,
+    /// physical pixels per point of the controls, the scale factor of the window if unset
+    pub pixels_per_point: Option<f32>,
+    pub theme: UiTheme,
+    /// alpha of the background of the control window
+    pub window_opacity: f32,
+    /// size of the body text in points, the other text styles are scaled along
+    pub font_size: f32,
+    /// the language of the controls
+    pub language: Language,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config {
+            path: String::from(CONFIG_PATH),
+            error: None,
+            width: 1280,
+            height: 720,
+            title: None,
+            fullscreen: FullscreenMode::Windowed,
+            fullscreen_toggle: FullscreenMode::Borderless,
+            monitor: 0,
+            vsync: true,
+            hdr: true,
+            show_gui: true,
+            pixels_per_point: None,
+            theme: UiTheme::Dark,
+            window_opacity: 1.0,
+            font_size: DEFAULT_FONT_SIZE,
+            language: Language::English,
+        }
+    }
+
+    /// the settings of the config file at `path`, a missing file keeps the defaults
+    /// and a broken one is reported in `error`
+    pub fn load(path: &str) -> Self {
+        let mut config = Config::new();
+        config.path = String::from(path);
+        #[cfg(not(target_arch = "wasm32"))]
+        match std::fs::read_to_string(&config.path) {
+            // parse into a copy, a broken file leaves the defaults untouched
+            Ok(text) => {
+                let mut parsed = Config::new();
+                parsed.path = config.path.clone();
+                match from_text(&text, &mut parsed) {
+                    Ok(()) => config = parsed,
+                    Err(e) => {
+                        log::warn!("{}: {}", config.path, e);
+                        config.error = Some(e.to_string());
+                    }
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                log::warn!("could not read {}: {}", config.path, e);
+                config.error = Some(e.to_string());
+            }
+        }
+        config
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return std::fs::write(&self.path, to_text(self));
+        #[cfg(target_arch = "wasm32")]
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "the browser has no config file",
+        ))
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        if self.vsync {
+            wgpu::PresentMode::Fifo
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        }
+    }
+
+    /// takes over the size and monitor of `window`, the size is kept while it is fullscreen
+    pub fn update_from_window(&mut self, window: &Window, target: &EventLoopWindowTarget<()>) {
+        if window.fullscreen().is_none() {
+            let size = window.inner_size();
+            self.width = size.width.max(1);
+            self.height = size.height.max(1);
+        }
+        if let Some(current) = window.current_monitor() {
+            if let Some(i) = target.available_monitors().position(|m| m == current) {
+                self.monitor = i;
+            }
+        }
+    }
+
+    /// the configured monitor
+    pub fn find_monitor(&self, target: &EventLoopWindowTarget<()>) -> Option<MonitorHandle> {
+        target
+            .available_monitors()
+            .nth(self.monitor)
+            .or_else(|| target.primary_monitor())
+    }
+
+    /// switches between windowed and the fullscreen mode of the toggle, a window in the
+    /// other fullscreen mode goes back to windowed as well
+    pub fn toggle_fullscreen(&mut self) {
+        self.fullscreen = match self.fullscreen {
+            FullscreenMode::Windowed => self.fullscreen_toggle,
+            _ => FullscreenMode::Windowed,
+        };
+    }
+
+    /// the winit fullscreen state on `monitor`
+    pub fn fullscreen(&self, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        match self.fullscreen {
+            FullscreenMode::Windowed => None,
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            FullscreenMode::Exclusive => match monitor.and_then(|m| self.video_mode(&m)) {
+                Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                // without a monitor there is no video mode to switch to
+                None => Some(Fullscreen::Borderless(None)),
+            },
+        }
+    }
+
+    /// the largest video mode of `monitor` that fits the window size, its smallest one otherwise
+    fn video_mode(&self, monitor: &MonitorHandle) -> Option<VideoMode> {
+        let area = |mode: &VideoMode| {
+            let size = mode.size();
+            (size.width * size.height, mode.refresh_rate_millihertz())
+        };
+        let fitting = monitor
+            .video_modes()
+            .filter(|mode| mode.size().width <= self.width && mode.size().height <= self.height)
+            .max_by_key(area);
+        fitting.or_else(|| monitor.video_modes().min_by_key(area))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn to_text(config: &Config) -> String {
+    let mut lines = vec![
+        String::from("# particles window settings"),
+        format!("width = {}", config.width),
+        format!("height = {}", config.height),
+    ];
+    if let Some(title) = &config.title {
+        lines.push(format!("title = {:?}", title));
+    }
+    lines.extend([
+        format!("fullscreen = \"{}\"", config.fullscreen.name()),
+        format!(
+            "fullscreen_toggle = \"{}\"",
+            config.fullscreen_toggle.name()
+        ),
+        format!("monitor = {}", config.monitor),
+        format!("vsync = {}", config.vsync),
+        format!("hdr = {}", config.hdr),
+        format!("show_gui = {}", config.show_gui),
+    ]);
+    if let Some(pixels_per_point) = config.pixels_per_point {
+        lines.push(format!("pixels_per_point = {}", pixels_per_point));
+    }
+    lines.extend([
+        format!("theme = \"{}\"", config.theme.name()),
+        format!("window_opacity = {}", config.window_opacity),
+        format!("font_size = {}", config.font_size),
+        format!("language = \"{}\"", config.language.code()),
+    ]);
+    lines.join("\n") + "\n"
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn from_text(text: &str, config: &mut Config) -> std::io::Result<()> {
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |what: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", line_number + 1, what),
+            )
+        };
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid("expected key = value"))?;
+        let (key, value) = (key.trim(), value.trim());
+        let string = || -> std::io::Result<String> {
+            value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .map(|value| value.replace("\\\"", "\"").replace("\\\\", "\\"))
+                .ok_or_else(|| invalid("expected a quoted string"))
+        };
+        let number = || -> std::io::Result<u32> {
+            value
+                .parse()
+                .map_err(|_| invalid("expected a non-negative integer"))
+        };
+        let float = || -> std::io::Result<f32> {
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|value| value.is_finite() && *value > 0.0)
+                .ok_or_else(|| invalid("expected a positive number"))
+        };
+        let boolean = || -> std::io::Result<bool> {
+            value.parse().map_err(|_| invalid("expected true or false"))
+        };
+        match key {
+            "width" => config.width = number()?.max(1),
+            "height" => config.height = number()?.max(1),
+            "title" => config.title = Some(string()?),
+            "fullscreen" => {
+                let name = string()?;
+                config.fullscreen = FullscreenMode::ALL
+                    .into_iter()
+                    .find(|mode| mode.name() == name)
+                    .ok_or_else(|| invalid("unknown fullscreen mode"))?;
+            }
+            "fullscreen_toggle" => {
+                let name = string()?;
+                config.fullscreen_toggle = FullscreenMode::ALL
+                    .into_iter()
+                    .find(|mode| mode.name() == name && *mode != FullscreenMode::Windowed)
+                    .ok_or_else(|| invalid("expected borderless or exclusive"))?;
+            }
+            "monitor" => config.monitor = number()? as usize,
+            "vsync" => config.vsync = boolean()?,
+            "hdr" => config.hdr = boolean()?,
+            "show_gui" => config.show_gui = boolean()?,
+            "pixels_per_point" => config.pixels_per_point = Some(float()?.clamp(0.25, 8.0)),
+            "theme" => {
+                let name = string()?;
+                config.theme = UiTheme::ALL
+                    .into_iter()
+                    .find(|theme| theme.name() == name)
+                    .ok_or_else(|| invalid("unknown theme"))?;
+            }
+            "window_opacity" => config.window_opacity = float()?.min(1.0),
+            "font_size" => config.font_size = float()?.clamp(4.0, 64.0),
+            "language" => {
+                let code = string()?;
+                config.language = Language::ALL
+                    .into_iter()
+                    .find(|language| language.code() == code)
+                    .ok_or_else(|| invalid("unknown language"))?;
+            }
+            _ => log::warn!("config file line {}: unknown key {}", line_number + 1, key),
+        }
+    }
+    Ok(())
+}
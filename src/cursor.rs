@@ -1,11 +1,14 @@
-use crate::{grid::Grid, zero_v3, V3};
-use cgmath::{InnerSpace, Matrix, Matrix3, MetricSpace, Quaternion, SquareMatrix};
+use crate::{brush::Brush, grid::Grid, particle_brush::ParticleBrush, zero_v3, V3};
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Angle, Deg, InnerSpace, Matrix, Matrix3, MetricSpace, Quaternion, SquareMatrix};
+use rayon::prelude::*;
 use winit::event::VirtualKeyCode;
 
 pub struct Cursor {
     pub pos: V3,
     pub distance_from_camera: f32,
     pub outer_radius: f32,
+    /// radius of the solid sphere around the cursor while `collide` is on
     pub inner_radius: f32,
     pub mouse_pos_x: f32,
     pub mouse_pos_y: f32,
@@ -14,6 +17,42 @@ pub struct Cursor {
     pub mouse_down_on: Option<(V3, Matrix3<f32>)>,
     pub rot: Matrix3<f32>,
     pub edit_mode: EditMode,
+    /// the volume of `edit_mode.falloff_dist` around the cursor that picks the vectors to edit
+    pub brush: Brush,
+    /// point masses placed with the right mouse button
+    pub attractors: Vec<Attractor>,
+    /// strength of newly placed attractors
+    pub attractor_strength: f32,
+    /// newly placed attractors push particles away
+    pub attractor_repel: bool,
+    pub depth_mode: DepthMode,
+    /// search radius around the mouse ray for `DepthMode::ParticleCluster`
+    pub cluster_radius: f32,
+    /// origin and direction of the mouse ray, scaled so that
+    /// `distance_from_camera` times the direction is the unsnapped cursor offset
+    pub ray: (V3, V3),
+    /// dragging with the left mouse button selects vectors instead of editing them
+    pub select: bool,
+    pub selection_shape: SelectionShape,
+    /// the region dragged out last, kept while it is selected
+    pub marquee: Option<Marquee>,
+    /// indices of the selected force grid vectors
+    pub selection: Vec<usize>,
+    /// dragging with the left mouse button stirs the particles within `outer_radius`
+    /// instead of editing vectors
+    pub stir: bool,
+    pub stir_mode: StirMode,
+    /// acceleration at the cursor position
+    pub stir_strength: f32,
+    /// pull the particles inwards or swirl them the other way round
+    pub stir_reverse: bool,
+    /// the cursor is a solid sphere of `inner_radius` the particles are pushed out of
+    pub collide: bool,
+    /// dragging with the left mouse button paints the material grid within the brush
+    /// instead of editing vectors
+    pub paint: bool,
+    /// spawns or deletes particles within `outer_radius` while dragging
+    pub particle_brush: ParticleBrush,
 }
 
 impl Cursor {
@@ -30,6 +69,24 @@ impl Cursor {
             mouse_pos_x: 0.,
             mouse_pos_y: 0.,
             edit_mode: EditMode::default(),
+            brush: Brush::new(),
+            attractors: Vec::new(),
+            attractor_strength: 1.0,
+            attractor_repel: false,
+            depth_mode: DepthMode::Fixed,
+            cluster_radius: 0.5,
+            ray: (zero_v3(), zero_v3()),
+            select: false,
+            selection_shape: SelectionShape::Box,
+            marquee: None,
+            selection: Vec::new(),
+            stir: false,
+            stir_mode: StirMode::Swirl,
+            stir_strength: 10.0,
+            stir_reverse: false,
+            collide: false,
+            paint: false,
+            particle_brush: ParticleBrush::new(),
         }
     }
 
@@ -39,6 +96,7 @@ impl Cursor {
         screen_height: f32,
         camera_position: V3,
         rot: Quaternion<f32>,
+        fov_degrees: f32,
     ) {
         let swh = screen_width * 0.5;
         let shh = screen_height * 0.5;
@@ -50,10 +108,52 @@ impl Cursor {
         let right = rotm.x;
         let up = rotm.y;
         let dir = -rotm.z;
-        let offset = dir + right * aspect * ((mouse_x - swh) / swh) + up * ((-mouse_y + shh) / shh);
+        // half the height of the view at a distance of 1
+        let half_height = Deg(fov_degrees * 0.5).tan();
+        let offset = dir
+            + right * aspect * half_height * ((mouse_x - swh) / swh)
+            + up * half_height * ((-mouse_y + shh) / shh);
         let res = -camera_position + offset * self.distance_from_camera;
         self.pos = res;
         self.rot = rotm;
+        self.ray = (-camera_position, offset);
+    }
+
+    /// moves the cursor along the mouse ray onto the plane of force grid cell centers
+    /// nearest to `distance_from_camera`
+    pub fn snap_to_grid_plane(&mut self, grid: &Grid<V3>) {
+        let (origin, dir) = self.ray;
+        // the planes facing the camera the most give the least grazing intersections
+        let Some(axis) = (0..3).max_by(|a, b| dir[*a].abs().total_cmp(&dir[*b].abs())) else {
+            return;
+        };
+        if dir[axis] == 0.0 {
+            return;
+        }
+        let nearest = grid
+            .plane_coordinates(axis)
+            .into_iter()
+            .map(|c| (c - origin[axis]) / dir[axis])
+            .filter(|t| *t > 0.0)
+            .min_by(|a, b| {
+                (a - self.distance_from_camera)
+                    .abs()
+                    .total_cmp(&(b - self.distance_from_camera).abs())
+            });
+        if let Some(t) = nearest {
+            self.pos = origin + dir * t;
+        }
+    }
+
+    /// origin and normalized direction of the mouse ray
+    pub fn normalized_ray(&self) -> (V3, V3) {
+        (self.ray.0, self.ray.1.normalize())
+    }
+
+    /// moves the cursor to `depth` along the normalized mouse ray
+    pub fn snap_to_depth(&mut self, depth: f32) {
+        let (origin, dir) = self.normalized_ray();
+        self.pos = origin + dir * depth;
     }
 
     pub fn process_input(&mut self, keys: &[VirtualKeyCode]) {
@@ -79,35 +179,63 @@ impl Cursor {
     pub fn mouse_moved(&mut self, mouse_x: f32, mouse_y: f32, grid: &mut Grid<V3>) {
         self.mouse_pos_x = mouse_x;
         self.mouse_pos_y = mouse_y;
+        if self.stir || self.paint || self.particle_brush.enabled {
+            return;
+        }
+        if self.select {
+            if let (Some(marquee), Some(_)) = (self.marquee.as_mut(), self.mouse_down_on) {
+                marquee.end = self.pos;
+            }
+            return;
+        }
         if let Some((md_pos, _mdrot)) = self.mouse_down_on {
             let v_pos_dir = grid.get_instances();
-            for (ix, md_v) in self
+            let edited: Vec<V3> = self
                 .modify_vector_indices
-                .iter()
+                .par_iter()
                 .zip(&self.mouse_down_vectors)
-            {
-                let displacement = self.edit_mode.get_vector(
-                    v_pos_dir[*ix].1,
-                    v_pos_dir[*ix].0,
-                    md_pos,
-                    self.pos,
-                    self.rot,
-                );
-                // grid.grid[*ix] = self.pos - md_pos;
-                match self.edit_mode.ra {
-                    RelAbE::Relative => grid.grid[*ix] = displacement + md_v,
-                    RelAbE::Absolute => grid.grid[*ix] = displacement,
-                }
+                .map(|(ix, md_v)| {
+                    let displacement = self.edit_mode.get_vector(
+                        v_pos_dir[*ix].1,
+                        v_pos_dir[*ix].0,
+                        md_pos,
+                        self.pos,
+                        self.rot,
+                    );
+                    // grid.grid[*ix] = self.pos - md_pos;
+                    match self.edit_mode.ra {
+                        RelAbE::Relative => displacement + md_v,
+                        RelAbE::Absolute => displacement,
+                    }
+                })
+                .collect();
+            for (ix, v) in self.modify_vector_indices.iter().zip(edited) {
+                grid.grid[*ix] = v;
             }
         }
     }
 
     pub fn mouse_down(&mut self, grid: &Grid<V3>) {
         self.mouse_down_on = Some((self.pos, self.rot));
+        if self.stir || self.paint || self.particle_brush.enabled {
+            return;
+        }
+        if self.select {
+            self.marquee = Some(Marquee {
+                shape: self.selection_shape,
+                start: self.pos,
+                end: self.pos,
+            });
+            return;
+        }
         self.modify_vector_indices.clear();
         self.mouse_down_vectors.clear();
+        let axes = self.brush.axes(self.rot);
         for (ix, (vpos, _)) in grid.get_instances().iter().enumerate() {
-            if self.pos.distance(*vpos) < self.edit_mode.falloff_dist {
+            if self
+                .brush
+                .contains(self.pos, axes, self.edit_mode.falloff_dist, *vpos)
+            {
                 self.modify_vector_indices.push(ix);
                 self.mouse_down_vectors.push(grid.grid[ix]);
             }
@@ -115,10 +243,216 @@ impl Cursor {
         dbg!(&self.modify_vector_indices);
     }
 
-    pub fn mouse_up(&mut self) {
+    pub fn mouse_up(&mut self, grid: &Grid<V3>) {
+        if self.select && self.mouse_down_on.is_some() {
+            if let Some(marquee) = self.marquee {
+                self.selection = grid
+                    .get_instances()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (pos, _))| marquee.contains(*pos))
+                    .map(|(ix, _)| ix)
+                    .collect();
+            }
+        }
         self.mouse_down_on = None;
         self.modify_vector_indices.clear();
+        self.particle_brush.reset();
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+        self.marquee = None;
+    }
+
+    /// the vectors edited by the brush while dragging, else the selection
+    pub fn highlighted_vectors(&self) -> &[usize] {
+        if self.modify_vector_indices.is_empty() {
+            &self.selection
+        } else {
+            &self.modify_vector_indices
+        }
+    }
+
+    /// applies `op` to every selected vector of `grid`
+    pub fn apply_to_selection(&self, grid: &mut Grid<V3>, op: SelectionOp) {
+        // the selection is stale if the grid was replaced by a smaller one
+        let selection: Vec<usize> = self
+            .selection
+            .iter()
+            .copied()
+            .filter(|ix| *ix < grid.grid.len())
+            .collect();
+        let edited: Vec<V3> = selection
+            .iter()
+            .map(|ix| {
+                let v = grid.grid[*ix];
+                match op {
+                    SelectionOp::SetConstant(c) => c,
+                    SelectionOp::Scale(factor) => v * factor,
+                    SelectionOp::Rotate { axis, angle } => {
+                        Matrix3::from_axis_angle(axis.normalize(), Deg(angle)) * v
+                    }
+                    SelectionOp::Zero => zero_v3(),
+                    SelectionOp::Smooth => {
+                        let neighbours = grid.neighbours(*ix);
+                        let sum = neighbours.iter().fold(v, |sum, n| sum + grid.grid[*n]);
+                        sum / (neighbours.len() + 1) as f32
+                    }
+                }
+            })
+            .collect();
+        for (ix, v) in selection.iter().zip(edited) {
+            grid.grid[*ix] = v;
+        }
+    }
+
+    /// the force of the stirring tool, only on while dragging.
+    /// `axis` is the viewing direction, swirls turn around it
+    pub fn stir_force(&self, axis: V3) -> StirForce {
+        let active = self.stir && self.mouse_down_on.is_some();
+        let sign = if self.stir_reverse { -1.0 } else { 1.0 };
+        StirForce {
+            center: self.pos.into(),
+            radius: self.outer_radius,
+            axis: axis.into(),
+            strength: sign * self.stir_strength,
+            mode: if active { self.stir_mode as u32 } else { 0 },
+            _padd: [0; 3],
+        }
     }
+
+    /// the solid sphere around the cursor, a radius of 0 while `collide` is off
+    pub fn sphere(&self) -> CursorSphere {
+        CursorSphere {
+            center: self.pos.into(),
+            radius: if self.collide { self.inner_radius } else { 0.0 },
+        }
+    }
+
+    /// places an attractor at the cursor position
+    pub fn place_attractor(&mut self) {
+        let sign = if self.attractor_repel { -1.0 } else { 1.0 };
+        self.attractors.push(Attractor {
+            pos: self.pos.into(),
+            strength: sign * self.attractor_strength,
+        });
+    }
+}
+
+/// a point mass pulling particles with an inverse square force,
+/// a negative strength pushes them away
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Attractor {
+    pub pos: [f32; 3],
+    pub strength: f32,
+}
+
+/// the attractors for the attractor buffer, which always holds at least one attractor
+pub fn get_attractors_raw(attractors: &[Attractor]) -> (Vec<Attractor>, u32) {
+    if attractors.is_empty() {
+        (vec![Attractor::zeroed()], 0)
+    } else {
+        (attractors.to_vec(), attractors.len() as u32)
+    }
+}
+
+/// how the stirring tool moves the particles around the cursor
+#[repr(u32)]
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum StirMode {
+    /// pushes the particles away from the cursor
+    Radial = 1,
+    /// turns the particles around the viewing direction
+    Swirl,
+}
+
+impl StirMode {
+    pub const ALL: [StirMode; 2] = [StirMode::Radial, StirMode::Swirl];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StirMode::Radial => "radial",
+            StirMode::Swirl => "swirl",
+        }
+    }
+}
+
+/// transient force of the stirring tool, mirrors `StirForce` in compute.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct StirForce {
+    pub center: [f32; 3],
+    pub radius: f32,
+    pub axis: [f32; 3],
+    /// acceleration at the center, fading out linearly towards the radius
+    pub strength: f32,
+    /// 0 while not stirring, else a `StirMode`
+    pub mode: u32,
+    pub _padd: [u32; 3],
+}
+
+/// sphere the particles can not enter, mirrors `CursorSphere` in compute.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CursorSphere {
+    pub center: [f32; 3],
+    /// 0 disables the collision
+    pub radius: f32,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum SelectionShape {
+    /// the box spanned by the two corners of the drag
+    Box,
+    /// the sphere around the start of the drag reaching to its end
+    Sphere,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Marquee {
+    pub shape: SelectionShape,
+    pub start: V3,
+    pub end: V3,
+}
+
+impl Marquee {
+    pub fn contains(&self, p: V3) -> bool {
+        match self.shape {
+            SelectionShape::Box => (0..3).all(|axis| {
+                let (a, b) = (self.start[axis], self.end[axis]);
+                a.min(b) <= p[axis] && p[axis] <= a.max(b)
+            }),
+            SelectionShape::Sphere => p.distance(self.start) <= self.end.distance(self.start),
+        }
+    }
+}
+
+/// an operation applied to all selected vectors at once
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SelectionOp {
+    SetConstant(V3),
+    Scale(f32),
+    /// rotates by `angle` degrees about `axis`
+    Rotate {
+        axis: V3,
+        angle: f32,
+    },
+    Zero,
+    /// averages every vector with its neighbours in the grid
+    Smooth,
+}
+
+/// how the depth of the cursor along the mouse ray is chosen
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum DepthMode {
+    /// `distance_from_camera`, set with the scroll wheel
+    Fixed,
+    /// the force grid plane nearest to `distance_from_camera`
+    GridPlane,
+    /// the densest particle cluster along the ray
+    ParticleCluster,
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
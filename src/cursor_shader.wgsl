@@ -14,11 +14,12 @@ struct Transform {
 fn vs_main(
         @location(0) in_pos: vec3<f32>,
         @location(1) tex_coord: vec2<f32>,
+        // the scale of the mesh in w
         @location(2) instance_pos: vec4<f32>,
         ) -> VertexOutput {
     var out: VertexOutput;
     let rotated_vertex = camera_rotation.transform * vec4<f32>(in_pos, 1.0);
-    out.out_pos = u_transform.transform * vec4<f32>(rotated_vertex.xyz + instance_pos.xyz, 1.0);
+    out.out_pos = u_transform.transform * vec4<f32>(rotated_vertex.xyz * instance_pos.w + instance_pos.xyz, 1.0);
     out.tex_coord = tex_coord;
     return out;
 }
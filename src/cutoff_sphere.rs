@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::InnerSpace;
+
+use crate::{assets::Mesh, V3};
+
+/// subdivisions of the icosahedron the sphere mesh is made of
+const SUBDIVISIONS: u32 = 3;
+
+/// what the cut off sphere is centered on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CutoffAnchor {
+    Cursor,
+    /// the particle followed by `ParticleTracker`
+    Particle,
+}
+
+impl CutoffAnchor {
+    pub const ALL: [CutoffAnchor; 2] = [CutoffAnchor::Cursor, CutoffAnchor::Particle];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CutoffAnchor::Cursor => "cursor",
+            CutoffAnchor::Particle => "particle",
+        }
+    }
+}
+
+/// mirrors `SphereParams` in cutoff_sphere.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct CutoffSphereParams {
+    /// rgb and the opacity at the silhouette
+    pub color: [f32; 4],
+    /// the camera position, w unused
+    pub eye: [f32; 4],
+}
+
+/// a translucent sphere with the radius of the cut off distance, so the reach of the
+/// attraction forces can be seen while tuning it
+pub struct CutoffSphere {
+    pub enabled: bool,
+    pub anchor: CutoffAnchor,
+    pub color: [f32; 3],
+    pub opacity: f32,
+    /// the position of the followed particle, read back by `ParticleTracker`
+    pub particle_position: Option<V3>,
+}
+
+impl CutoffSphere {
+    pub fn new() -> Self {
+        CutoffSphere {
+            enabled: false,
+            anchor: CutoffAnchor::Cursor,
+            color: [0.3, 0.7, 1.0],
+            opacity: 0.5,
+            particle_position: None,
+        }
+    }
+
+    /// the center of the sphere, None while it is hidden
+    pub fn center(&self, cursor: V3) -> Option<V3> {
+        if !self.enabled {
+            return None;
+        }
+        match self.anchor {
+            CutoffAnchor::Cursor => Some(cursor),
+            CutoffAnchor::Particle => self.particle_position,
+        }
+    }
+
+    pub fn params(&self, eye: V3) -> CutoffSphereParams {
+        let [r, g, b] = self.color;
+        CutoffSphereParams {
+            color: [r, g, b, self.opacity],
+            eye: eye.extend(1.0).into(),
+        }
+    }
+}
+
+/// a sphere of radius 1 made of a subdivided icosahedron, the texture coordinates
+/// are the longitude and latitude
+pub fn icosphere() -> Mesh {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut positions: Vec<V3> = [
+        (-1.0, t, 0.0),
+        (1.0, t, 0.0),
+        (-1.0, -t, 0.0),
+        (1.0, -t, 0.0),
+        (0.0, -1.0, t),
+        (0.0, 1.0, t),
+        (0.0, -1.0, -t),
+        (0.0, 1.0, -t),
+        (t, 0.0, -1.0),
+        (t, 0.0, 1.0),
+        (-t, 0.0, -1.0),
+        (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| V3::new(x, y, z).normalize())
+    .collect();
+    let mut triangles: Vec<[u16; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+    for _ in 0..SUBDIVISIONS {
+        // the vertex in the middle of every edge, shared by the triangles on both sides
+        let mut midpoints: HashMap<(u16, u16), u16> = HashMap::new();
+        let mut midpoint = |a: u16, b: u16| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push((positions[a as usize] + positions[b as usize]).normalize());
+                (positions.len() - 1) as u16
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+    let vertices = positions
+        .iter()
+        .map(|p| {
+            let u = 0.5 + p.z.atan2(p.x) / std::f32::consts::TAU;
+            let v = 0.5 - p.y.asin() / std::f32::consts::PI;
+            (*p, [u, v])
+        })
+        .collect();
+    Mesh {
+        vertices,
+        indices: triangles.concat(),
+    }
+}
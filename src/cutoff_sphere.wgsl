@@ -0,0 +1,51 @@
+// a translucent sphere around the cursor or a particle with the radius of the cut off distance,
+// brighter towards its silhouette so the particles inside stay visible
+
+struct VertexOutput {
+    @builtin(position) out_pos: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
+};
+
+struct Transform {
+    transform: mat4x4<f32>,
+};
+
+// mirrors `CutoffSphereParams` in cutoff_sphere.rs
+struct SphereParams {
+    // rgb and the opacity at the silhouette
+    color: vec4<f32>,
+    eye: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u_transform: Transform;
+
+@vertex
+fn vs_main(
+        @location(0) in_pos: vec3<f32>,
+        @location(1) tex_coord: vec2<f32>,
+        // xyz: center, w: radius
+        @location(2) instance_pos: vec4<f32>,
+        ) -> VertexOutput {
+    var out: VertexOutput;
+    let world_pos = in_pos * instance_pos.w + instance_pos.xyz;
+    out.out_pos = u_transform.transform * vec4<f32>(world_pos, 1.0);
+    out.normal = in_pos;
+    out.world_pos = world_pos;
+    return out;
+}
+
+@group(1)@binding(0)
+var texture: texture_2d<f32>;
+@group(1)@binding(1)
+var t_sampler: sampler;
+
+@group(2) @binding(0) var<uniform> params: SphereParams;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let view_dir = normalize(params.eye.xyz - in.world_pos);
+    let rim = 1.0 - abs(dot(normalize(in.normal), view_dir));
+    let alpha = params.color.a * (0.15 + 0.85 * rim * rim);
+    return vec4<f32>(params.color.rgb, alpha) * textureSample(texture, t_sampler, vec2<f32>(0.5));
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::{sim_params::SimParams, Particle};
+
+/// cells per axis of the grid the spatial entropy bins the positions into
+const ENTROPY_CELLS: usize = 8;
+
+/// measures of a sample of the particles, see `BehaviorDetector`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BehaviorMetrics {
+    /// groups of at least `min_cluster_size` sampled particles linked by close neighbours
+    pub clusters: usize,
+    /// mean squared deviation of the velocities from their mean
+    pub velocity_variance: f32,
+    /// entropy of the positions binned into a grid over the bounding volume, 0 when all
+    /// particles share a cell and 1 when they are spread evenly
+    pub spatial_entropy: f32,
+}
+
+/// samples the particles now and then and flags the parameters once the system keeps
+/// a few clusters that neither freeze nor fly apart, the regimes worth a look when the
+/// explorer searches unattended
+pub struct BehaviorDetector {
+    pub enabled: bool,
+    /// simulated seconds between two samples
+    pub interval: f32,
+    /// the most particles of a sample, taken evenly spaced from the particle buffer
+    pub sample_size: usize,
+    /// sampled particles closer than this are in one cluster, the distance grows with the
+    /// gaps a sparser sample leaves
+    pub link_distance: f32,
+    pub min_cluster_size: usize,
+    /// the ranges the metrics have to be in for an interesting regime
+    pub clusters: (usize, usize),
+    pub velocity_variance: (f32, f32),
+    pub spatial_entropy: (f32, f32),
+    /// samples in a row within all ranges before the regime is flagged
+    pub required_samples: u32,
+    /// writes the scene and a screenshot of a flagged regime into `directory`
+    pub auto_save: bool,
+    pub directory: String,
+    /// moves the explorer on to new random parameters after a flagged regime or once
+    /// a candidate ran `candidate_time` simulated seconds without one
+    pub auto_explore: bool,
+    pub candidate_time: f32,
+    /// the metrics of the last sample
+    pub metrics: Option<BehaviorMetrics>,
+    /// the current parameters were flagged
+    pub flagged: bool,
+    /// simulated seconds since the current parameters were applied
+    pub candidate_age: f32,
+    /// regimes flagged since the detector was created, numbers the saved files
+    pub num_flagged: usize,
+    pub error: Option<String>,
+    /// samples in a row within all ranges
+    streak: u32,
+    since_sample: f32,
+}
+
+impl BehaviorDetector {
+    pub fn new() -> Self {
+        BehaviorDetector {
+            enabled: false,
+            interval: 2.0,
+            sample_size: 4096,
+            link_distance: 0.2,
+            min_cluster_size: 8,
+            clusters: (2, 40),
+            velocity_variance: (0.001, 10.0),
+            spatial_entropy: (0.2, 0.8),
+            required_samples: 3,
+            auto_save: false,
+            directory: String::from("interesting"),
+            auto_explore: false,
+            candidate_time: 30.0,
+            metrics: None,
+            flagged: false,
+            candidate_age: 0.0,
+            num_flagged: 0,
+            error: None,
+            streak: 0,
+            since_sample: 0.0,
+        }
+    }
+
+    /// advances by `delta_t` simulated seconds, returns whether a sample is due
+    pub fn due(&mut self, delta_t: f32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.candidate_age += delta_t;
+        self.since_sample += delta_t;
+        if self.since_sample < self.interval {
+            return false;
+        }
+        self.since_sample = 0.0;
+        true
+    }
+
+    /// starts over with new parameters
+    pub fn restart(&mut self) {
+        self.metrics = None;
+        self.flagged = false;
+        self.candidate_age = 0.0;
+        self.streak = 0;
+        self.since_sample = 0.0;
+    }
+
+    /// whether the current candidate ran out of time
+    pub fn candidate_expired(&self) -> bool {
+        self.candidate_age >= self.candidate_time
+    }
+
+    /// measures `sample`, which holds every `stride`th particle,
+    /// returns true when the regime is flagged by it
+    pub fn evaluate(&mut self, sample: &[Particle], stride: usize, sim_params: &SimParams) -> bool {
+        if sample.is_empty() {
+            return false;
+        }
+        let link_distance = self.link_distance * (stride as f32).cbrt();
+        let metrics = BehaviorMetrics {
+            clusters: count_clusters(sample, link_distance, self.min_cluster_size),
+            velocity_variance: velocity_variance(sample),
+            spatial_entropy: spatial_entropy(sample, sim_params.bounding_volume_radius),
+        };
+        self.metrics = Some(metrics);
+        let within = |(min, max): (f32, f32), value: f32| value >= min && value <= max;
+        let interesting = (self.clusters.0..=self.clusters.1).contains(&metrics.clusters)
+            && within(self.velocity_variance, metrics.velocity_variance)
+            && within(self.spatial_entropy, metrics.spatial_entropy);
+        self.streak = if interesting { self.streak + 1 } else { 0 };
+        if self.flagged || self.streak < self.required_samples {
+            return false;
+        }
+        self.flagged = true;
+        self.num_flagged += 1;
+        true
+    }
+
+    /// the scene file and the screenshot of the last flagged regime
+    pub fn save_paths(&self) -> (String, String) {
+        let name = format!("{}/regime_{:03}", self.directory, self.num_flagged);
+        (format!("{}.txt", name), format!("{}.png", name))
+    }
+}
+
+/// the clusters of at least `min_size` particles, particles closer than `link_distance`
+/// are joined in a union find over a hash grid of cells as large as the link distance
+fn count_clusters(sample: &[Particle], link_distance: f32, min_size: usize) -> usize {
+    let link_distance = link_distance.max(f32::EPSILON);
+    let cell = |p: &Particle| p.pos.map(|x| (x / link_distance).floor() as i32);
+    let mut cells: HashMap<[i32; 3], Vec<usize>> = HashMap::new();
+    for (i, p) in sample.iter().enumerate() {
+        let [x, y, z, _] = cell(p);
+        cells.entry([x, y, z]).or_default().push(i);
+    }
+    let mut parents: Vec<usize> = (0..sample.len()).collect();
+    for (i, p) in sample.iter().enumerate() {
+        let [x, y, z, _] = cell(p);
+        for neighbour in (0..27).map(|n| [x + n % 3 - 1, y + n / 3 % 3 - 1, z + n / 9 - 1]) {
+            for &j in cells.get(&neighbour).into_iter().flatten() {
+                let q = &sample[j];
+                let distance_squared: f32 = (0..3).map(|k| (q.pos[k] - p.pos[k]).powi(2)).sum();
+                if j > i && distance_squared < link_distance * link_distance {
+                    let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+                    parents[a] = b;
+                }
+            }
+        }
+    }
+    let mut sizes: HashMap<usize, usize> = HashMap::new();
+    for i in 0..sample.len() {
+        *sizes.entry(find(&mut parents, i)).or_default() += 1;
+    }
+    sizes.values().filter(|&&size| size >= min_size).count()
+}
+
+/// the root of the set of `i`, halving the paths on the way
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+fn velocity_variance(sample: &[Particle]) -> f32 {
+    let n = sample.len() as f32;
+    let mean: [f32; 3] = std::array::from_fn(|k| sample.iter().map(|p| p.vel[k]).sum::<f32>() / n);
+    sample
+        .iter()
+        .map(|p| (0..3).map(|k| (p.vel[k] - mean[k]).powi(2)).sum::<f32>())
+        .sum::<f32>()
+        / n
+}
+
+/// the shannon entropy of the occupied cells of a grid over the bounding volume, divided by
+/// the largest entropy the sample could have
+fn spatial_entropy(sample: &[Particle], radius: f32) -> f32 {
+    let index = |x: f32| {
+        let t = (x / radius.max(f32::EPSILON) * 0.5 + 0.5) * ENTROPY_CELLS as f32;
+        (t.max(0.0) as usize).min(ENTROPY_CELLS - 1)
+    };
+    let mut counts = vec![0usize; ENTROPY_CELLS.pow(3)];
+    for p in sample {
+        let [x, y, z] = [0, 1, 2].map(|k| index(p.pos[k]));
+        counts[x + (y + z * ENTROPY_CELLS) * ENTROPY_CELLS] += 1;
+    }
+    let n = sample.len() as f32;
+    let entropy: f32 = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f32 / n;
+            -p * p.ln()
+        })
+        .sum();
+    let max_entropy = (counts.len().min(sample.len()) as f32).ln();
+    if max_entropy > 0.0 {
+        entropy / max_entropy
+    } else {
+        0.0
+    }
+}
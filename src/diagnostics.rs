@@ -0,0 +1,285 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::OpenOptions,
+    io::Write,
+};
+
+use crate::{
+    precision::ParticlePrecision,
+    readback::{Readback, ReadbackCopy},
+    MassWrap, Particle, V3,
+};
+use cgmath::{InnerSpace, Zero};
+use instant::Instant;
+use wgpu::*;
+
+/// number of bins of the speed histogram
+pub const NUM_SPEED_BINS: usize = 20;
+/// number of samples kept for plotting
+const HISTORY_LEN: usize = 600;
+
+/// system wide metrics of one particle readback
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    /// wall clock seconds since the diagnostics were created
+    pub time: f64,
+    /// simulated seconds when the particles were read back
+    pub sim_time: f64,
+    pub kinetic_energy: f32,
+    pub momentum: V3,
+    pub center_of_mass: V3,
+    /// upper bound of the last histogram bin
+    pub max_speed: f32,
+    pub speed_histogram: [u32; NUM_SPEED_BINS],
+    pub type_stats: [TypeStats; 5],
+}
+
+/// metrics of the particles of one type
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TypeStats {
+    pub count: usize,
+    pub mean_speed: f32,
+    /// mean number of particles in the clusters of this type
+    pub mean_cluster_size: f32,
+}
+
+/// periodically reads the particles back from the GPU and records metrics about them
+pub struct Diagnostics {
+    pub enabled: bool,
+    /// seconds between two samples
+    pub interval: f32,
+    /// append every sample to `csv_path`
+    pub log_to_csv: bool,
+    pub csv_path: String,
+    pub csv_error: Option<String>,
+    pub history: VecDeque<Sample>,
+    /// particles of the same type closer than this belong to the same cluster
+    pub cluster_distance: f32,
+    readback: Readback,
+    // simulated time and particle precision of the readback in flight
+    pending_sim_time: f64,
+    pending_precision: ParticlePrecision,
+    start: Instant,
+    last_sample: Instant,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Diagnostics {
+            enabled: false,
+            interval: 1.0,
+            log_to_csv: false,
+            csv_path: String::from("metrics.csv"),
+            csv_error: None,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+            cluster_distance: 0.2,
+            readback: Readback::new("diagnostics readback buffer"),
+            pending_sim_time: 0.0,
+            pending_precision: ParticlePrecision::Full,
+            start: Instant::now(),
+            last_sample: Instant::now(),
+        }
+    }
+
+    /// copies the particles into the readback buffer when the next sample is due
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        particles: &Buffer,
+        num_particles: usize,
+        precision: ParticlePrecision,
+        sim_time: f64,
+    ) {
+        if !self.enabled
+            || num_particles == 0
+            || !self.readback.is_idle()
+            || self.last_sample.elapsed().as_secs_f32() < self.interval
+        {
+            return;
+        }
+        self.last_sample = Instant::now();
+        self.pending_sim_time = sim_time;
+        self.pending_precision = precision;
+        // the buffer can hold more particles than are alive
+        self.readback.encode(
+            device,
+            encoder,
+            &[ReadbackCopy {
+                buffer: particles,
+                offset: 0,
+                size: (num_particles * precision.particle_size()) as u64,
+            }],
+        );
+    }
+
+    /// starts mapping the readback buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.readback.after_submit();
+    }
+
+    /// records a sample once the readback buffer is mapped
+    pub fn poll(&mut self, device: &Device, masses: &[MassWrap; 5]) {
+        let precision = self.pending_precision;
+        let Some(particles) = self
+            .readback
+            .poll(device, |data| precision.decode_particles(data))
+        else {
+            return;
+        };
+        let sample = self.sample(&particles, masses);
+        if self.log_to_csv {
+            if let Err(e) = self.append_csv(&sample) {
+                self.csv_error = Some(e.to_string());
+                self.log_to_csv = false;
+            }
+        }
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(sample);
+    }
+
+    fn sample(&self, particles: &[Particle], masses: &[MassWrap; 5]) -> Sample {
+        let mut kinetic_energy = 0.0;
+        let mut momentum = V3::zero();
+        let mut mass_pos = V3::zero();
+        let mut total_mass = 0.0;
+        let mut speeds = Vec::with_capacity(particles.len());
+        let mut type_stats = [TypeStats::default(); 5];
+        for p in particles {
+            let m = masses[p.ty as usize % 5].mass * p.mass;
+            let vel = V3::new(p.vel[0], p.vel[1], p.vel[2]);
+            let speed = vel.magnitude();
+            kinetic_energy += 0.5 * m * speed * speed;
+            momentum += vel * m;
+            mass_pos += V3::new(p.pos[0], p.pos[1], p.pos[2]) * m;
+            total_mass += m;
+            speeds.push(speed);
+            let stats = &mut type_stats[p.ty as usize % 5];
+            stats.count += 1;
+            stats.mean_speed += speed;
+        }
+        let clusters = count_clusters(particles, self.cluster_distance);
+        for (stats, clusters) in type_stats.iter_mut().zip(clusters) {
+            if stats.count > 0 {
+                stats.mean_speed /= stats.count as f32;
+                stats.mean_cluster_size = stats.count as f32 / clusters as f32;
+            }
+        }
+        let max_speed = speeds.iter().copied().fold(0.0f32, f32::max).max(0.001);
+        let mut speed_histogram = [0; NUM_SPEED_BINS];
+        for speed in speeds {
+            let bin = (speed / max_speed * NUM_SPEED_BINS as f32) as usize;
+            speed_histogram[bin.min(NUM_SPEED_BINS - 1)] += 1;
+        }
+        Sample {
+            time: self.start.elapsed().as_secs_f64(),
+            sim_time: self.pending_sim_time,
+            kinetic_energy,
+            momentum,
+            center_of_mass: if total_mass > 0.0 {
+                mass_pos / total_mass
+            } else {
+                V3::zero()
+            },
+            max_speed,
+            speed_histogram,
+            type_stats,
+        }
+    }
+
+    fn append_csv(&self, sample: &Sample) -> std::io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.csv_path)?;
+        if file.metadata()?.len() == 0 {
+            write!(
+                file,
+                "time,sim_time,kinetic_energy,momentum_x,momentum_y,momentum_z,\
+                 center_of_mass_x,center_of_mass_y,center_of_mass_z,max_speed"
+            )?;
+            for i in 0..NUM_SPEED_BINS {
+                write!(file, ",speed_bin_{}", i)?;
+            }
+            writeln!(file)?;
+        }
+        let (p, c) = (sample.momentum, sample.center_of_mass);
+        write!(
+            file,
+            "{},{},{},{},{},{},{},{},{},{}",
+            sample.time,
+            sample.sim_time,
+            sample.kinetic_energy,
+            p.x,
+            p.y,
+            p.z,
+            c.x,
+            c.y,
+            c.z,
+            sample.max_speed
+        )?;
+        for count in sample.speed_histogram {
+            write!(file, ",{}", count)?;
+        }
+        writeln!(file)
+    }
+}
+
+/// number of clusters per particle type, particles of the same type closer than
+/// `distance` are linked and every linked group counts as one cluster
+fn count_clusters(particles: &[Particle], distance: f32) -> [usize; 5] {
+    let cell_of = |p: &Particle| {
+        let c = |x: f32| (x / distance).floor() as i32;
+        (c(p.pos[0]), c(p.pos[1]), c(p.pos[2]))
+    };
+    let mut cells: HashMap<(i32, i32, i32, u32), Vec<usize>> = HashMap::new();
+    for (i, p) in particles.iter().enumerate() {
+        let (x, y, z) = cell_of(p);
+        cells.entry((x, y, z, p.ty % 5)).or_default().push(i);
+    }
+    // union find over the particle indices
+    let mut parent: Vec<usize> = (0..particles.len()).collect();
+    let distance_sq = distance * distance;
+    for (i, p) in particles.iter().enumerate() {
+        let (x, y, z) = cell_of(p);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(cell) = cells.get(&(x + dx, y + dy, z + dz, p.ty % 5)) else {
+                        continue;
+                    };
+                    for &j in cell.iter().filter(|&&j| j > i) {
+                        let q = &particles[j];
+                        let d = V3::new(
+                            q.pos[0] - p.pos[0],
+                            q.pos[1] - p.pos[1],
+                            q.pos[2] - p.pos[2],
+                        );
+                        if d.magnitude2() < distance_sq {
+                            let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                            parent[a] = b;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let mut clusters = [0; 5];
+    for (i, p) in particles.iter().enumerate() {
+        if parent[i] == i {
+            clusters[p.ty as usize % 5] += 1;
+        }
+    }
+    clusters
+}
+
+/// representative of the group of `i`, halves the path on the way
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
@@ -1,9 +1,11 @@
 use std::borrow::Cow;
 use std::mem;
+use std::ops::Range;
 
+use crate::assets::Mesh;
 use crate::camera::Camera;
 use crate::renderer::Vertex;
-use crate::{Particle, V3};
+use crate::Particle;
 use cgmath::{Matrix, Matrix4, Vector3};
 use wgpu::util::DeviceExt;
 use wgpu::*;
@@ -51,13 +53,28 @@ pub struct DrawBuffer {
     pub index_buffer_length: usize,
     pub instance_buffer: Buffer,
     pub instance_buffer_length: usize,
-    pub texture: Texture,
+    /// kept alive for the texture bind group
+    pub _texture: Texture,
     pub texture_bind_group: BindGroup,
     pub texture_bind_group_layout: BindGroupLayout,
 }
 
 impl DrawBuffer {
     pub fn new(device: &Device, queue: &Queue, texture_as_bytes: &[u8]) -> Self {
+        let image = image::load_from_memory(texture_as_bytes).expect("could not load texture");
+        Self::from_rgba(device, queue, &image.to_rgba8())
+    }
+
+    /// a draw buffer with a single white texel, for shaders that do not need a texture
+    pub fn new_untextured(device: &Device, queue: &Queue) -> Self {
+        Self::from_rgba(
+            device,
+            queue,
+            &image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])),
+        )
+    }
+
+    pub fn from_rgba(device: &Device, queue: &Queue, rgba: &image::RgbaImage) -> Self {
         let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Vertex Buffer"),
             size: 0,
@@ -71,7 +88,7 @@ impl DrawBuffer {
             usage: BufferUsages::INDEX,
         });
         let (texture, texture_bind_group, texture_bind_group_layout) =
-            DrawBuffer::create_texture(device, queue, texture_as_bytes);
+            DrawBuffer::create_texture(device, queue, rgba);
         let instance_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("instance buffer"),
             size: 0,
@@ -85,7 +102,7 @@ impl DrawBuffer {
             index_buffer_length: 0,
             instance_buffer,
             instance_buffer_length: 0,
-            texture,
+            _texture: texture,
             texture_bind_group,
             texture_bind_group_layout,
         }
@@ -94,10 +111,8 @@ impl DrawBuffer {
     pub fn create_texture(
         device: &Device,
         queue: &Queue,
-        bytes: &[u8],
+        rgba: &image::RgbaImage,
     ) -> (Texture, BindGroup, BindGroupLayout) {
-        let image = image::load_from_memory(bytes).expect("could not load texture");
-        let rgba = image.to_rgba8();
         let dimensions = rgba.dimensions();
 
         let texture_size = wgpu::Extent3d {
@@ -117,7 +132,7 @@ impl DrawBuffer {
         });
         queue.write_texture(
             tex.as_image_copy(),
-            &rgba,
+            rgba,
             ImageDataLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * dimensions.0),
@@ -184,11 +199,84 @@ pub struct MatrixBindGroup {
     pub camera_rotation_matrix: Option<Buffer>,
 }
 
+/// an additional uniform buffer with pass specific parameters
+pub struct ParamsBindGroup {
+    pub layout: BindGroupLayout,
+    pub bind_group: BindGroup,
+    pub buffer: Buffer,
+}
+
+impl ParamsBindGroup {
+    pub fn new(device: &Device, contents: &[u8], prefix: &str) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} params buffer", prefix)),
+            contents,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some(&format!("{} params bind group layout", prefix)),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: BufferSize::new(contents.len() as u64),
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some(&format!("{} params bind group", prefix)),
+            layout: &layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        ParamsBindGroup {
+            layout,
+            bind_group,
+            buffer,
+        }
+    }
+}
+
+/// the bind group layouts of a draw pass pipeline in the order of their group indices
+fn bind_group_layouts<'a>(
+    matrix_bind_group: Option<&'a MatrixBindGroup>,
+    texture_bind_group_layout: &'a BindGroupLayout,
+    params_bind_group: Option<&'a ParamsBindGroup>,
+) -> Vec<&'a BindGroupLayout> {
+    let mut layouts = Vec::new();
+    if let Some(mbg) = matrix_bind_group {
+        layouts.push(&mbg.layout);
+    }
+    layouts.push(texture_bind_group_layout);
+    if let Some(pbg) = params_bind_group {
+        layouts.push(&pbg.layout);
+    }
+    layouts
+}
+
+/// the settings a draw pass builds its pipeline from
+pub struct DrawPassDescriptor<'a> {
+    pub prefix: &'a str,
+    pub shader_src: Cow<'static, str>,
+    pub topology: PrimitiveTopology,
+    pub instance_layout: VertexBufferLayout<'static>,
+    /// contents of a uniform buffer that is bound after the texture
+    pub params: Option<&'a [u8]>,
+    pub bcreate_viewmatrix: bool,
+    pub bcreate_camera_rotation: bool,
+}
+
 pub struct DrawPass {
     pub prefix: String,
     pub pipeline: RenderPipeline,
     pub draw_buffer: DrawBuffer,
     pub matrix_bind_group: Option<MatrixBindGroup>,
+    pub params_bind_group: Option<ParamsBindGroup>,
     pub shader: ShaderModule,
     pub topology: PrimitiveTopology,
     pub instance_layout: VertexBufferLayout<'static>,
@@ -200,69 +288,62 @@ impl DrawPass {
         device: &Device,
         queue: &Queue,
         draw_buffer: DrawBuffer,
-        shader: ShaderModule,
         camera: &mut Camera,
-        topology: PrimitiveTopology,
-        instance_layout: VertexBufferLayout<'static>,
-        bcreate_viewmatrix: bool,
-        bcreate_camera_rotation: bool,
-        prefix: &str,
+        desc: DrawPassDescriptor,
     ) -> Self {
-        let (pipeline, matrix_bind_group) = DrawPass::create_pipeline(
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(&format!("{} shader module", desc.prefix)),
+            source: ShaderSource::Wgsl(desc.shader_src),
+        });
+        let matrix_bind_group = Self::create_matrix_bind_group(
             device,
             queue,
+            camera,
+            desc.bcreate_viewmatrix,
+            desc.bcreate_camera_rotation,
+        );
+        // the pipeline layout needs the params bind group from the start
+        let params_bind_group = desc
+            .params
+            .map(|contents| ParamsBindGroup::new(device, contents, desc.prefix));
+        let pipeline = DrawPass::create_pipeline(
+            device,
             surface_config,
             &shader,
-            camera,
-            topology,
-            &draw_buffer.texture_bind_group_layout,
-            &instance_layout,
-            bcreate_viewmatrix,
-            bcreate_camera_rotation,
-            prefix,
+            &bind_group_layouts(
+                matrix_bind_group.as_ref(),
+                &draw_buffer.texture_bind_group_layout,
+                params_bind_group.as_ref(),
+            ),
+            desc.topology,
+            &desc.instance_layout,
+            desc.prefix,
         );
         DrawPass {
-            prefix: String::from(prefix),
+            prefix: String::from(desc.prefix),
             pipeline,
             draw_buffer,
             matrix_bind_group,
+            params_bind_group,
             shader,
-            topology,
-            instance_layout,
+            topology: desc.topology,
+            instance_layout: desc.instance_layout,
         }
     }
 
     fn create_pipeline(
         device: &Device,
-        queue: &Queue,
         surface_config: &SurfaceConfiguration,
         shader: &ShaderModule,
-        camera: &mut Camera,
+        bind_group_layouts: &[&BindGroupLayout],
         primitive_topology: PrimitiveTopology,
-        texture_bind_group_layout: &BindGroupLayout,
         instance_layout: &VertexBufferLayout,
-        bcreate_viewmatrix: bool,
-        bcreate_camera_rotation: bool,
         prefix: &str,
-    ) -> (RenderPipeline, Option<MatrixBindGroup>) {
-        let mut bind_group_layouts = Vec::new();
-        let matrix_bind_group = Self::create_matrix_bind_group(
-            device,
-            queue,
-            camera,
-            bcreate_viewmatrix,
-            bcreate_camera_rotation,
-        );
-
-        if let Some(mbg) = matrix_bind_group.as_ref() {
-            bind_group_layouts.push(&mbg.layout);
-        }
-
-        bind_group_layouts.push(texture_bind_group_layout);
+    ) -> RenderPipeline {
         dbg!(&bind_group_layouts);
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some(&format!("{} pipeline layout", prefix)),
-            bind_group_layouts: &bind_group_layouts,
+            bind_group_layouts,
             push_constant_ranges: &[],
         });
 
@@ -272,111 +353,66 @@ impl DrawPass {
             attributes: &vertex_attr_array![0 => Float32x3, 1 => Float32x2],
         };
 
-        (
-            device.create_render_pipeline(&RenderPipelineDescriptor {
-                label: Some(&format!("{} render pipeline", prefix)),
-                layout: Some(&pipeline_layout),
-                vertex: VertexState {
-                    module: shader,
-                    entry_point: "vs_main",
-                    buffers: &[vertex_layout, instance_layout.clone()],
-                },
-                fragment: Some(FragmentState {
-                    module: shader,
-                    entry_point: "fs_main",
-                    targets: &[Some(ColorTargetState {
-                        format: surface_config.format,
-                        blend: Some(BlendState {
-                            color: BlendComponent {
-                                src_factor: BlendFactor::SrcAlpha,
-                                dst_factor: BlendFactor::One,
-                                operation: BlendOperation::Add,
-                            },
-                            alpha: BlendComponent {
-                                src_factor: BlendFactor::One,
-                                dst_factor: BlendFactor::One,
-                                operation: BlendOperation::Max,
-                            },
-                        }),
-                        write_mask: ColorWrites::ALL,
-                    })],
-                }),
-                primitive: PrimitiveState {
-                    topology: primitive_topology,
-                    front_face: FrontFace::Cw,
-                    ..Default::default()
-                },
-                depth_stencil: Some(DepthStencilState {
-                    format: TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
-                    depth_compare: CompareFunction::LessEqual,
-                    stencil: StencilState::default(),
-                    bias: DepthBiasState::default(),
-                }),
-                // no multisample
-                multisample: MultisampleState {
-                    ..Default::default()
-                },
-                multiview: None,
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(&format!("{} render pipeline", prefix)),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[vertex_layout, instance_layout.clone()],
+            },
+            fragment: Some(FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: surface_config.format,
+                    blend: Some(BlendState {
+                        color: BlendComponent {
+                            src_factor: BlendFactor::SrcAlpha,
+                            dst_factor: BlendFactor::One,
+                            operation: BlendOperation::Add,
+                        },
+                        alpha: BlendComponent {
+                            src_factor: BlendFactor::One,
+                            dst_factor: BlendFactor::One,
+                            operation: BlendOperation::Max,
+                        },
+                    }),
+                    write_mask: ColorWrites::ALL,
+                })],
             }),
-            matrix_bind_group,
-        )
+            primitive: PrimitiveState {
+                topology: primitive_topology,
+                front_face: FrontFace::Cw,
+                ..Default::default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::LessEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            // no multisample
+            multisample: MultisampleState {
+                ..Default::default()
+            },
+            multiview: None,
+        })
     }
 
     pub fn from_object_and_texture(
         surface_config: &SurfaceConfiguration,
         device: &Device,
         queue: &Queue,
-        shader_src: Cow<'static, str>,
-        obj_path: &str,
-        texture_bytes: &[u8],
+        mesh: &Mesh,
+        texture: &image::RgbaImage,
         camera: &mut Camera,
-        instance_layout: VertexBufferLayout<'static>,
-        bcreate_viewmatrix: bool,
-        bcreate_camera_rotation: bool,
-        prefix: &str,
+        desc: DrawPassDescriptor,
     ) -> DrawPass {
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Renderer: wgsl cursor shader module"),
-            source: wgpu::ShaderSource::Wgsl(shader_src),
-        });
-        let draw_buffer = DrawBuffer::new(device, queue, texture_bytes);
-        let mut res = DrawPass::new(
-            surface_config,
-            device,
-            queue,
-            draw_buffer,
-            shader,
-            camera,
-            PrimitiveTopology::TriangleList,
-            instance_layout,
-            bcreate_viewmatrix,
-            bcreate_camera_rotation,
-            prefix,
-        );
-        let obj = tobj::load_obj(obj_path, &tobj::GPU_LOAD_OPTIONS).expect("could not load object");
-        let vertices: Vec<V3> = obj.0[0]
-            .mesh
-            .positions
-            .chunks(3)
-            .map(|c| V3::new(c[0], c[1], c[2]))
-            .collect();
-        let texture_coordinates: Vec<[f32; 2]> = obj.0[0]
-            .mesh
-            .texcoords
-            .chunks(2)
-            .map(|tc| [tc[0], tc[1]])
-            .collect();
-        let indices: Vec<u16> = obj.0[0].mesh.indices.iter().map(|i| *i as u16).collect();
-        res.update_vertex_buffer(
-            device,
-            &vertices
-                .iter()
-                .copied()
-                .zip(texture_coordinates)
-                .collect::<Vec<_>>(),
-        );
-        res.update_index_buffer(device, &indices);
+        let draw_buffer = DrawBuffer::from_rgba(device, queue, texture);
+        let mut res = DrawPass::new(surface_config, device, queue, draw_buffer, camera, desc);
+        res.set_mesh(device, mesh);
         // this puts up only a single instance at the origin
         res.update_instance_buffer(device, &[0., 0., 0., 1.], 1);
         res
@@ -392,26 +428,88 @@ impl DrawPass {
         let bcreate_viewmatrix = self
             .matrix_bind_group
             .as_ref()
-            .map_or(false, |bg| bg.view_matrix.is_some());
+            .is_some_and(|bg| bg.view_matrix.is_some());
         let bcreate_camera_rotation = self
             .matrix_bind_group
             .as_ref()
-            .map_or(false, |bg| bg.camera_rotation_matrix.is_some());
-        let (pipeline, matrix_bind_group) = DrawPass::create_pipeline(
+            .is_some_and(|bg| bg.camera_rotation_matrix.is_some());
+        self.matrix_bind_group = Self::create_matrix_bind_group(
             device,
             queue,
+            camera,
+            bcreate_viewmatrix,
+            bcreate_camera_rotation,
+        );
+        self.pipeline = DrawPass::create_pipeline(
+            device,
             surface_config,
             &self.shader,
-            camera,
+            &self.bind_group_layouts(),
             self.topology,
-            &self.draw_buffer.texture_bind_group_layout,
             &self.instance_layout,
-            bcreate_viewmatrix,
-            bcreate_camera_rotation,
             &self.prefix,
         );
-        self.pipeline = pipeline;
-        self.matrix_bind_group = matrix_bind_group;
+    }
+
+    /// replaces the shader module and rebuilds the pipeline around it
+    pub fn set_shader(
+        &mut self,
+        surface_config: &SurfaceConfiguration,
+        device: &Device,
+        queue: &Queue,
+        camera: &mut Camera,
+        shader: ShaderModule,
+    ) {
+        self.shader = shader;
+        self.recreate_pipeline(surface_config, device, queue, camera);
+    }
+
+    pub fn set_mesh(&mut self, device: &Device, mesh: &Mesh) {
+        self.update_vertex_buffer(device, &mesh.vertices);
+        self.update_index_buffer(device, &mesh.indices);
+    }
+
+    /// replaces the texture and rebuilds the pipeline around its bind group
+    pub fn set_texture(
+        &mut self,
+        surface_config: &SurfaceConfiguration,
+        device: &Device,
+        queue: &Queue,
+        camera: &mut Camera,
+        rgba: &image::RgbaImage,
+    ) {
+        let (texture, bind_group, bind_group_layout) =
+            DrawBuffer::create_texture(device, queue, rgba);
+        self.draw_buffer._texture = texture;
+        self.draw_buffer.texture_bind_group = bind_group;
+        self.draw_buffer.texture_bind_group_layout = bind_group_layout;
+        self.recreate_pipeline(surface_config, device, queue, camera);
+    }
+
+    pub fn update_params_buffer(&self, queue: &Queue, contents: &[u8]) {
+        if let Some(pbg) = self.params_bind_group.as_ref() {
+            queue.write_buffer(&pbg.buffer, 0, contents);
+        }
+    }
+
+    /// the bind group layouts of the pipeline in the order of their group indices
+    pub fn bind_group_layouts(&self) -> Vec<&BindGroupLayout> {
+        bind_group_layouts(
+            self.matrix_bind_group.as_ref(),
+            &self.draw_buffer.texture_bind_group_layout,
+            self.params_bind_group.as_ref(),
+        )
+    }
+
+    fn set_params_bind_group<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        if let Some(pbg) = self.params_bind_group.as_ref() {
+            let index = if self.matrix_bind_group.is_some() {
+                2
+            } else {
+                1
+            };
+            rpass.set_bind_group(index, &pbg.bind_group, &[]);
+        }
     }
 
     fn create_matrix_bind_group(
@@ -532,6 +630,18 @@ impl DrawPass {
         }
     }
 
+    /// replaces the view matrix with `mx`, for passes with a camera of their own
+    pub fn write_view_matrix(&self, queue: &Queue, mx: Matrix4<f32>) {
+        if let Some(view_matrix_buffer) = self
+            .matrix_bind_group
+            .as_ref()
+            .and_then(|bg| bg.view_matrix.as_ref())
+        {
+            let mx_ref: &[f32; 16] = mx.as_ref();
+            queue.write_buffer(view_matrix_buffer, 0, bytemuck::cast_slice(mx_ref));
+        }
+    }
+
     pub fn update_camera_rotation_matrix(&mut self, queue: &Queue, camera: &mut Camera) {
         if let Some(view_matrix_buffer) = self
             .matrix_bind_group
@@ -545,6 +655,18 @@ impl DrawPass {
         }
     }
 
+    /// replaces the camera rotation with `mx`, which turns the mesh in world space
+    pub fn update_rotation_matrix(&self, queue: &Queue, mx: Matrix4<f32>) {
+        if let Some(rotation_matrix_buffer) = self
+            .matrix_bind_group
+            .as_ref()
+            .and_then(|bg| bg.camera_rotation_matrix.as_ref())
+        {
+            let mx_ref: &[f32; 16] = mx.as_ref();
+            queue.write_buffer(rotation_matrix_buffer, 0, bytemuck::cast_slice(mx_ref));
+        }
+    }
+
     pub fn update_vertex_buffer(&mut self, device: &Device, vertices: &[(Vector3<f32>, [f32; 2])]) {
         let vertex_data: Vec<Vertex> = vertices
             .iter()
@@ -588,6 +710,12 @@ impl DrawPass {
     }
 
     pub fn render<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        let indices = 0..self.draw_buffer.index_buffer_length as u32;
+        self.render_index_ranges(rpass, std::slice::from_ref(&indices));
+    }
+
+    /// draws every instance once per range of the index buffer
+    pub fn render_index_ranges<'a>(&'a self, rpass: &mut RenderPass<'a>, ranges: &[Range<u32>]) {
         rpass.set_pipeline(&self.pipeline);
         if let Some(MatrixBindGroup {
             layout: _,
@@ -601,24 +729,48 @@ impl DrawPass {
         } else {
             rpass.set_bind_group(0, &self.draw_buffer.texture_bind_group, &[]);
         }
+        self.set_params_bind_group(rpass);
         rpass.set_vertex_buffer(0, self.draw_buffer.vertex_buffer.slice(..)); // slot 0
         rpass.set_index_buffer(self.draw_buffer.index_buffer.slice(..), IndexFormat::Uint16);
         rpass.set_vertex_buffer(1, self.draw_buffer.instance_buffer.slice(..));
         // rpass.draw(0..(self.vertex_buffer_length as u32), 0..1); // vertex range, instance range
-        rpass.draw_indexed(
-            0..(self.draw_buffer.index_buffer_length as u32),
-            0,
-            0..self.draw_buffer.instance_buffer_length as u32,
+        for range in ranges {
+            rpass.draw_indexed(
+                range.clone(),
+                0,
+                0..self.draw_buffer.instance_buffer_length as u32,
+            );
+        }
+    }
+
+    /// draws the instances in `instance_buffer`, the draw arguments are read from
+    /// `indirect_buffer` at `indirect_offset`, so the GPU decides how many are drawn
+    pub fn render_indirect<'a>(
+        &'a self,
+        rpass: &mut RenderPass<'a>,
+        instance_buffer: &'a Buffer,
+        indirect_buffer: &'a Buffer,
+        indirect_offset: BufferAddress,
+    ) {
+        self.render_indirect_with(
+            &self.pipeline,
+            rpass,
+            instance_buffer,
+            indirect_buffer,
+            indirect_offset,
         );
     }
 
-    pub fn render_with_instance_buffer<'a>(
+    /// like `render_indirect` with another pipeline made with `bind_group_layouts`
+    pub fn render_indirect_with<'a>(
         &'a self,
+        pipeline: &'a RenderPipeline,
         rpass: &mut RenderPass<'a>,
         instance_buffer: &'a Buffer,
-        instance_buffer_length: usize,
+        indirect_buffer: &'a Buffer,
+        indirect_offset: BufferAddress,
     ) {
-        rpass.set_pipeline(&self.pipeline);
+        rpass.set_pipeline(pipeline);
         if let Some(MatrixBindGroup {
             layout: _,
             bind_group: matrix_bind_group,
@@ -629,14 +781,10 @@ impl DrawPass {
             rpass.set_bind_group(0, matrix_bind_group, &[]);
         }
         rpass.set_bind_group(1, &self.draw_buffer.texture_bind_group, &[]);
+        self.set_params_bind_group(rpass);
         rpass.set_vertex_buffer(0, self.draw_buffer.vertex_buffer.slice(..)); // slot 0
         rpass.set_index_buffer(self.draw_buffer.index_buffer.slice(..), IndexFormat::Uint16);
         rpass.set_vertex_buffer(1, instance_buffer.slice(..));
-        // rpass.draw(0..(self.vertex_buffer_length as u32), 0..1); // vertex range, instance range
-        rpass.draw_indexed(
-            0..(self.draw_buffer.index_buffer_length as u32),
-            0,
-            0..instance_buffer_length as u32,
-        );
+        rpass.draw_indexed_indirect(indirect_buffer, indirect_offset);
     }
 }
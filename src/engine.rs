@@ -0,0 +1,320 @@
+use wgpu::*;
+
+use crate::{
+    camera::Camera, compute::Compute, cursor::get_attractors_raw, force_curve::ForceCurves,
+    precision::ParticlePrecision, reactions::get_reaction_rules_raw, reduction::ReductionResult,
+    renderer::Renderer, sim_params::SimParams, Particle, ParticleSystem, V3,
+};
+
+/// configures a `ParticlesEngine`, created with `ParticlesEngine::builder`
+#[derive(Clone, Copy)]
+pub struct EngineBuilder {
+    num_particles: usize,
+    precision: ParticlePrecision,
+    sim_params: SimParams,
+    seed: u64,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+}
+
+impl Default for EngineBuilder {
+    fn default() -> Self {
+        EngineBuilder {
+            num_particles: 1000,
+            precision: ParticlePrecision::Full,
+            sim_params: SimParams::new(),
+            seed: 0,
+            width: 1280,
+            height: 720,
+            format: TextureFormat::Rgba8UnormSrgb,
+        }
+    }
+}
+
+impl EngineBuilder {
+    pub fn num_particles(mut self, num_particles: usize) -> Self {
+        self.num_particles = num_particles;
+        self
+    }
+
+    pub fn precision(mut self, precision: ParticlePrecision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// the forces, masses and bounds of the simulation
+    pub fn sim_params(mut self, sim_params: SimParams) -> Self {
+        self.sim_params = sim_params;
+        self
+    }
+
+    /// seeds the particle spawning and the random numbers generated on the GPU
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// size of the render target in pixels
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// format of the render target, usually the format the surface is configured with
+    pub fn format(mut self, format: TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// spawns the particles and creates every GPU resource on `device`
+    pub fn build(self, device: Device, queue: Queue) -> ParticlesEngine {
+        let mut sim_params = self.sim_params;
+        sim_params.set_seed(self.seed);
+        let lattice = (self.num_particles as f32).cbrt().ceil() as usize;
+        let mut psys = ParticleSystem::new(
+            V3::new(5.0, 2.0, 2.0),
+            lattice,
+            lattice,
+            lattice,
+            self.seed,
+            &sim_params,
+        );
+        psys.set_num_particles(self.num_particles);
+        let (force_vectors, num_layers) = psys.get_layered_force_vectors();
+        sim_params.num_force_grid_layers = num_layers;
+        let surface_config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: self.format,
+            width: self.width.max(1),
+            height: self.height.max(1),
+            present_mode: PresentMode::Fifo,
+            alpha_mode: CompositeAlphaMode::Auto,
+            view_formats: vec![self.format],
+        };
+        let mut renderer = Renderer::init(&surface_config, device, queue);
+        renderer.set_particle_precision(self.precision);
+        let mut compute = Compute::new(
+            &renderer.device,
+            &renderer.queue,
+            self.precision,
+            &psys.particles,
+            &force_vectors,
+            &get_reaction_rules_raw(&[]).0,
+            &get_attractors_raw(&[]).0,
+        );
+        compute.update_sim_params(&renderer.device, &renderer.queue, &sim_params);
+        ParticlesEngine {
+            psys,
+            sim_params,
+            compute,
+            renderer,
+            surface_config,
+        }
+    }
+}
+
+/// the simulation and the particle renderer without any window or gui,
+/// for embedding the particles into other wgpu applications
+pub struct ParticlesEngine {
+    psys: ParticleSystem,
+    sim_params: SimParams,
+    compute: Compute,
+    renderer: Renderer,
+    surface_config: SurfaceConfiguration,
+}
+
+impl ParticlesEngine {
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.renderer.device
+    }
+
+    pub fn queue(&self) -> &Queue {
+        &self.renderer.queue
+    }
+
+    pub fn sim_params(&self) -> &SimParams {
+        &self.sim_params
+    }
+
+    /// changes are uploaded with the next `step`
+    pub fn sim_params_mut(&mut self) -> &mut SimParams {
+        &mut self.sim_params
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.renderer.camera
+    }
+
+    pub fn particle_system(&self) -> &ParticleSystem {
+        &self.psys
+    }
+
+    /// changes are uploaded with `upload_particle_system`
+    pub fn particle_system_mut(&mut self) -> &mut ParticleSystem {
+        &mut self.psys
+    }
+
+    /// the GPU buffers and passes of the simulation
+    pub fn compute(&self) -> &Compute {
+        &self.compute
+    }
+
+    pub fn compute_mut(&mut self) -> &mut Compute {
+        &mut self.compute
+    }
+
+    /// the device, the camera and the pipelines drawing the particles
+    pub fn renderer(&self) -> &Renderer {
+        &self.renderer
+    }
+
+    pub fn renderer_mut(&mut self) -> &mut Renderer {
+        &mut self.renderer
+    }
+
+    pub fn num_particles(&self) -> usize {
+        self.compute.num_particles
+    }
+
+    /// simulated seconds since the engine was built
+    pub fn sim_time(&self) -> f64 {
+        self.compute.sim_time
+    }
+
+    /// energy, momentum and center of mass, lagging a few steps behind the simulation
+    pub fn reduction_result(&self) -> Option<ReductionResult> {
+        self.compute.reduction.result
+    }
+
+    /// times the compute pass with different workgroup sizes and returns the fastest,
+    /// which is used from then on
+    pub fn autotune_workgroup_size(&mut self) -> u32 {
+        self.compute
+            .autotune_workgroup_size(&self.renderer.device, &self.renderer.queue);
+        self.compute.workgroup_size
+    }
+
+    /// advances the simulation by `delta_t` seconds
+    pub fn step(&mut self, delta_t: f32) {
+        let device = &self.renderer.device;
+        let queue = &self.renderer.queue;
+        self.sim_params.delta_t = delta_t;
+        self.compute
+            .update_sim_params(device, queue, &self.sim_params);
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("engine command encoder"),
+        });
+        {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("engine compute pass"),
+                timestamp_writes: None,
+            });
+            self.compute.compute(&mut cpass);
+        }
+        self.compute.encode_velocity_ops(device, &mut encoder);
+        self.compute.encode_readbacks(device, &mut encoder);
+        queue.submit(Some(encoder.finish()));
+        self.compute.after_submit();
+        self.compute.reduction.poll(device);
+        self.compute.poll_particle_count(device);
+        self.compute.particle_tracker.poll(device);
+        self.compute.pair_probe.poll(device);
+        self.renderer.cutoff_sphere.particle_position = self.compute.particle_tracker.position;
+        self.renderer.camera.follow(
+            self.compute
+                .reduction
+                .result
+                .map(|result| result.center_of_mass),
+            self.compute.particle_tracker.position,
+            delta_t,
+        );
+    }
+
+    /// draws the particles into `view`, which has the size and format of the builder
+    pub fn render(&mut self, view: &TextureView) {
+        self.renderer.update_view(&self.sim_params);
+        let mut encoder = self
+            .renderer
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("engine render encoder"),
+            });
+        self.renderer
+            .encode_scene(&mut encoder, view, &self.compute, false);
+        self.renderer.queue.submit(Some(encoder.finish()));
+    }
+
+    /// resizes the render target, e.g. after the window was resized
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.surface_config.width = width.max(1);
+        self.surface_config.height = height.max(1);
+        self.renderer.resize(&self.surface_config);
+    }
+
+    /// reads the particles back from the GPU, blocks until the copy is done
+    pub fn read_particles(&self) -> Vec<Particle> {
+        self.compute
+            .read_particles(&self.renderer.device, &self.renderer.queue)
+    }
+
+    /// follows the particle closest to `pos`, its position is read back with every step
+    /// into `compute().particle_tracker`, returns the id of the particle
+    pub fn follow_nearest_particle(&mut self, pos: V3) -> Option<u32> {
+        self.compute
+            .follow_nearest_particle(&self.renderer.device, &self.renderer.queue, pos)
+    }
+
+    /// drops the last particles or spawns new ones, the others keep their simulated state
+    pub fn set_num_particles(&mut self, num_particles: usize) {
+        let device = &self.renderer.device;
+        let queue = &self.renderer.queue;
+        if self.compute.shrink_particles(queue, num_particles) {
+            self.psys.particles.truncate(num_particles);
+            return;
+        }
+        let alive = self.compute.num_particles;
+        let spawned = self.psys.spawn_particles(alive, num_particles - alive);
+        self.compute.append_particles(device, queue, &spawned);
+        self.psys.particles.truncate(alive);
+        self.psys.particles.extend(spawned);
+    }
+
+    /// respawns all particles at random positions
+    pub fn respawn_particles(&mut self) {
+        self.psys.respawn_particles();
+        self.upload_particle_system();
+    }
+
+    /// replaces the simulated particles, the force grid and the material grid with the ones
+    /// of the particle system
+    pub fn upload_particle_system(&mut self) {
+        let device = &self.renderer.device;
+        self.compute
+            .upload_particles(device, &self.renderer.queue, &self.psys.particles);
+        let (force_vectors, num_layers) = self.psys.get_layered_force_vectors();
+        self.sim_params.num_force_grid_layers = num_layers;
+        self.sim_params.material_grid = self.psys.material_grid().enabled as u32;
+        self.compute.update_force_grid(device, &force_vectors);
+    }
+
+    /// replaces the attraction polynomes of the pairs with a spline in `curves` and bakes
+    /// the polynomes into the lookup table if `curves.bake_polys` is set. call it again after
+    /// the polynomes or the cut off distance changed, replacing the sim params resets the
+    /// pairs to their polynomes
+    pub fn set_force_curves(&mut self, curves: &ForceCurves) {
+        let poly_range = self.sim_params.cut_off_distance;
+        curves.apply(&mut self.sim_params, poly_range);
+        self.compute.update_force_curves(
+            &self.renderer.device,
+            &self.renderer.queue,
+            &curves.get_raw(&self.sim_params, poly_range),
+            curves.lut_width(),
+        );
+    }
+}
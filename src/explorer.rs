@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+
+use cgmath::Vector2;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    poly7::Poly7,
+    presets,
+    sim_params::{MatrixSymmetry, SimParams},
+};
+
+const NUM_TYPES: usize = 5;
+/// points a force polynome is sampled at to mutate it, the degree of the polynome plus one
+const PROFILE_POINTS: usize = 8;
+
+/// the explored part of the simulation parameters: the attraction polynomes and the masses
+#[derive(Clone)]
+pub struct ParameterSet {
+    /// the number and the origin of the set, e.g. "4: mutation"
+    pub label: String,
+    pub attraction_force: [Poly7; 25],
+    pub masses: [f32; NUM_TYPES],
+    /// the mean forces between the particle types, see `presets::force_thumbnail`
+    pub thumbnail: [[f32; NUM_TYPES]; NUM_TYPES],
+}
+
+impl ParameterSet {
+    fn new(label: String, sim_params: &SimParams) -> Self {
+        ParameterSet {
+            label,
+            attraction_force: sim_params.attraction_force,
+            masses: sim_params.particle_type_masses.map(|m| m.mass),
+            thumbnail: presets::force_thumbnail(sim_params),
+        }
+    }
+
+    /// `sim_params` with the forces and masses of the set
+    pub fn sim_params(&self, sim_params: &SimParams) -> SimParams {
+        let mut res = *sim_params;
+        res.attraction_force = self.attraction_force;
+        for (mass, &m) in res.particle_type_masses.iter_mut().zip(&self.masses) {
+            mass.mass = m;
+        }
+        res
+    }
+}
+
+/// searches for interesting particle rules by randomizing, mutating and breeding the forces
+/// and masses, the sets tried last are kept to go back to
+pub struct Explorer {
+    /// the newest set first
+    pub history: VecDeque<ParameterSet>,
+    /// number of sets kept in the history
+    pub history_len: usize,
+    /// the force profiles are drawn from -force_range..force_range
+    pub force_range: f32,
+    /// range of the particle type masses
+    pub mass_range: (f32, f32),
+    pub randomize_masses: bool,
+    /// fraction of the ranges a mutation moves the forces and masses by at most
+    pub mutation_strength: f32,
+    /// respawns the particles with every new set, so the rules start from the same chaos
+    pub respawn: bool,
+    /// indices into `history` of the sets bred by `breed`
+    pub parents: [Option<usize>; 2],
+    /// the number of the next set, part of its label
+    next_number: usize,
+    rng: StdRng,
+}
+
+impl Explorer {
+    pub fn new() -> Self {
+        Explorer {
+            history: VecDeque::new(),
+            history_len: 12,
+            force_range: 2.0,
+            mass_range: (0.5, 3.0),
+            randomize_masses: true,
+            mutation_strength: 0.2,
+            respawn: true,
+            parents: [None; 2],
+            next_number: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// the parameters before the first exploration are kept, so they can be reverted to
+    fn record_start(&mut self, sim_params: &SimParams) {
+        if self.history.is_empty() {
+            self.push(String::from("start"), *sim_params, MatrixSymmetry::Free);
+        }
+    }
+
+    /// keeps `sim_params` made to satisfy `symmetry` as the newest set
+    fn push(
+        &mut self,
+        label: String,
+        sim_params: SimParams,
+        symmetry: MatrixSymmetry,
+    ) -> SimParams {
+        let mut sim_params = sim_params;
+        sim_params.enforce_symmetry(symmetry);
+        let number = self.next_number;
+        self.next_number += 1;
+        let set = ParameterSet::new(format!("{}: {}", number, label), &sim_params);
+        self.history.push_front(set);
+        self.history.truncate(self.history_len.max(1));
+        // the parents moved one place back
+        self.parents = self
+            .parents
+            .map(|parent| parent.map(|i| i + 1).filter(|&i| i < self.history.len()));
+        sim_params
+    }
+
+    /// new random forces and masses
+    pub fn randomize(&mut self, sim_params: &SimParams, symmetry: MatrixSymmetry) -> SimParams {
+        self.record_start(sim_params);
+        let mut res = *sim_params;
+        let range = self.force_range.max(f32::EPSILON);
+        for poly in res.attraction_force.iter_mut() {
+            *poly = profile(std::array::from_fn(|_| self.rng.gen_range(-range..range)));
+        }
+        if self.randomize_masses {
+            let (min, max) = self.mass_range_sorted();
+            for mass in res.particle_type_masses.iter_mut() {
+                mass.mass = self.rng.gen_range(min..=max);
+            }
+        }
+        self.push(String::from("random"), res, symmetry)
+    }
+
+    /// the forces and masses of `sim_params`, each moved randomly by up to the mutation strength
+    pub fn mutate(&mut self, sim_params: &SimParams, symmetry: MatrixSymmetry) -> SimParams {
+        self.record_start(sim_params);
+        let mut res = *sim_params;
+        let step = self.mutation_strength * self.force_range;
+        for poly in res.attraction_force.iter_mut() {
+            let values = sample(poly);
+            *poly = profile(values.map(|v| v + self.rng.gen_range(-1.0..=1.0) * step));
+        }
+        if self.randomize_masses {
+            let (min, max) = self.mass_range_sorted();
+            let step = self.mutation_strength * (max - min);
+            for mass in res.particle_type_masses.iter_mut() {
+                mass.mass = (mass.mass + self.rng.gen_range(-1.0..=1.0) * step).clamp(min, max);
+            }
+        }
+        self.push(String::from("mutation"), res, symmetry)
+    }
+
+    /// every pair force and mass taken from one of the two parents at random
+    pub fn breed(&mut self, sim_params: &SimParams, symmetry: MatrixSymmetry) -> Option<SimParams> {
+        let [Some(a), Some(b)] = self.parents else {
+            return None;
+        };
+        let (a, b) = (self.history.get(a)?.clone(), self.history.get(b)?.clone());
+        let mut res = *sim_params;
+        for (i, poly) in res.attraction_force.iter_mut().enumerate() {
+            *poly = self.pick(&a, &b).attraction_force[i];
+        }
+        for (i, mass) in res.particle_type_masses.iter_mut().enumerate() {
+            mass.mass = self.pick(&a, &b).masses[i];
+        }
+        let label = format!("child of {} and {}", number(&a), number(&b));
+        Some(self.push(label, res, symmetry))
+    }
+
+    fn pick<'a>(&mut self, a: &'a ParameterSet, b: &'a ParameterSet) -> &'a ParameterSet {
+        if self.rng.gen() {
+            a
+        } else {
+            b
+        }
+    }
+
+    fn mass_range_sorted(&self) -> (f32, f32) {
+        let (a, b) = self.mass_range;
+        (a.min(b).max(0.01), a.max(b).max(0.01))
+    }
+}
+
+/// the number in the label of a set
+fn number(set: &ParameterSet) -> &str {
+    set.label.split(':').next().unwrap_or_default()
+}
+
+/// the values of `poly` at equidistant distances from 0 to 1
+fn sample(poly: &Poly7) -> [f32; PROFILE_POINTS] {
+    std::array::from_fn(|i| poly.eval(i as f32 / (PROFILE_POINTS - 1) as f32))
+}
+
+/// force polynome through values at equidistant distances from 0 to 1
+fn profile(values: [f32; PROFILE_POINTS]) -> Poly7 {
+    let points =
+        std::array::from_fn(|i| Vector2::new(i as f32 / (PROFILE_POINTS - 1) as f32, values[i]));
+    Poly7::from_points(points).unwrap_or_else(Poly7::zero)
+}
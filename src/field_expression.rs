@@ -0,0 +1,479 @@
+use cgmath::InnerSpace;
+use rayon::prelude::*;
+
+use crate::{grid::Grid, V3};
+
+/// fills a force grid layer from a formula of the cell position, e.g.
+/// `(-y, x, 0) * 0.5 + (0, 0, sin(x))`. the formula may use the coordinates `x`, `y`, `z`,
+/// the distance `r` from the origin, the position vector `p`, the constants `pi` and `e`,
+/// vectors `(a, b, c)` with their components `.x`, `.y`, `.z`, the operators `+ - * / ^`
+/// and the functions listed in `FUNCTIONS`. operators and functions of one argument work
+/// on every component of a vector
+pub struct FieldExpression {
+    pub text: String,
+    /// shows the arrows of the formula instead of the active layer until it is applied
+    pub preview: bool,
+    pub error: Option<String>,
+    /// the evaluated formula, kept while the text and the grid stay the same
+    cached: Option<(String, Grid<V3>)>,
+}
+
+impl FieldExpression {
+    pub fn new() -> Self {
+        FieldExpression {
+            text: String::from("(-y, x, 0) * 0.5 + (0, 0, sin(x))"),
+            preview: false,
+            error: None,
+            cached: None,
+        }
+    }
+
+    /// the formula evaluated on the cells of `grid`, `None` with `error` set when it fails
+    pub fn evaluate(&mut self, grid: &Grid<V3>) -> Option<&Grid<V3>> {
+        let up_to_date = self.cached.as_ref().is_some_and(|(text, cached)| {
+            *text == self.text
+                && cached.dimensions() == grid.dimensions()
+                && cached.bounds.pos == grid.bounds.pos
+                && cached.bounds.dir == grid.bounds.dir
+        });
+        if !up_to_date {
+            self.cached = None;
+            let result = parse(&self.text).and_then(|expr| evaluate_grid(&expr, grid));
+            match result {
+                Ok(evaluated) => {
+                    self.error = None;
+                    self.cached = Some((self.text.clone(), evaluated));
+                }
+                Err(e) => self.error = Some(e),
+            }
+        }
+        self.cached.as_ref().map(|(_, grid)| grid)
+    }
+}
+
+/// the functions a formula can call, with their number of arguments
+pub const FUNCTIONS: [(&str, usize); 19] = [
+    ("sin", 1),
+    ("cos", 1),
+    ("tan", 1),
+    ("asin", 1),
+    ("acos", 1),
+    ("atan", 1),
+    ("atan2", 2),
+    ("sqrt", 1),
+    ("exp", 1),
+    ("ln", 1),
+    ("abs", 1),
+    ("floor", 1),
+    ("min", 2),
+    ("max", 2),
+    ("pow", 2),
+    ("length", 1),
+    ("normalize", 1),
+    ("dot", 2),
+    ("cross", 2),
+];
+
+#[derive(Clone, Copy, Debug)]
+enum Value {
+    Scalar(f32),
+    Vector(V3),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Number(f32),
+    X,
+    Y,
+    Z,
+    R,
+    P,
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Vector(Box<[Expr; 3]>),
+    Component(Box<Expr>, usize),
+    Call(&'static str, Vec<Expr>),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Token {
+    Number(f32),
+    Ident(usize, usize),
+    Op(Op),
+    Open,
+    Close,
+    Comma,
+    Dot,
+    End,
+}
+
+/// splits the formula into tokens, each with the column it starts at
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        let column = i + 1;
+        i += 1;
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '^' => Token::Op(Op::Pow),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '.' if !chars.get(i).is_some_and(|(_, c)| c.is_ascii_digit()) => Token::Dot,
+            c if c.is_ascii_digit() || c == '.' => {
+                let is = |i: usize, f: fn(char) -> bool| chars.get(i).is_some_and(|&(_, c)| f(c));
+                while is(i, |c| c.is_ascii_digit() || c == '.') {
+                    i += 1;
+                }
+                // an exponent like 1e-3
+                let sign = is(i + 1, |c| c == '+' || c == '-') as usize;
+                if is(i, |c| c == 'e' || c == 'E') && is(i + 1 + sign, |c| c.is_ascii_digit()) {
+                    i += 1 + sign;
+                    while is(i, |c| c.is_ascii_digit()) {
+                        i += 1;
+                    }
+                }
+                let byte_end = chars.get(i).map_or(text.len(), |&(b, _)| b);
+                let number = &text[start..byte_end];
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| format!("at {}: invalid number {}", column, number))?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while chars
+                    .get(i)
+                    .is_some_and(|(_, c)| c.is_alphanumeric() || *c == '_')
+                {
+                    i += 1;
+                }
+                let byte_end = chars.get(i).map_or(text.len(), |&(b, _)| b);
+                Token::Ident(start, byte_end)
+            }
+            c => return Err(format!("at {}: unexpected '{}'", column, c)),
+        };
+        tokens.push((token, column));
+    }
+    tokens.push((Token::End, chars.len() + 1));
+    Ok(tokens)
+}
+
+/// recursive descent over the tokens, `^` binds tighter than a sign and is right associative
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+}
+
+fn parse(text: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        text,
+        tokens: tokenize(text)?,
+        next: 0,
+    };
+    let expr = parser.sum()?;
+    match parser.peek() {
+        Token::End => Ok(expr),
+        _ => Err(parser.error("expected an operator")),
+    }
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Token {
+        self.tokens[self.next].0
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek();
+        self.next = (self.next + 1).min(self.tokens.len() - 1);
+        token
+    }
+
+    fn error(&self, message: &str) -> String {
+        let (token, column) = self.tokens[self.next];
+        match token {
+            Token::End => format!("at the end: {}", message),
+            _ => format!("at {}: {}", column, message),
+        }
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), String> {
+        if self.peek() != expected {
+            return Err(self.error(&format!("expected {}", what)));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while let Token::Op(op @ (Op::Add | Op::Sub)) = self.peek() {
+            self.advance();
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while let Token::Op(op @ (Op::Mul | Op::Div)) = self.peek() {
+            self.advance();
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Token::Op(Op::Sub) => {
+                self.advance();
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Token::Op(Op::Add) => {
+                self.advance();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.postfix()?;
+        if self.peek() != Token::Op(Op::Pow) {
+            return Ok(base);
+        }
+        self.advance();
+        Ok(Expr::Binary(
+            Op::Pow,
+            Box::new(base),
+            Box::new(self.unary()?),
+        ))
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        while self.peek() == Token::Dot {
+            self.advance();
+            let error = self.error("expected the component x, y or z");
+            let component = match self.advance() {
+                Token::Ident(start, end) => match &self.text[start..end] {
+                    "x" => 0,
+                    "y" => 1,
+                    "z" => 2,
+                    _ => return Err(error),
+                },
+                _ => return Err(error),
+            };
+            expr = Expr::Component(Box::new(expr), component);
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Token::Number(n) => {
+                self.advance();
+                Ok(Expr::Number(n))
+            }
+            Token::Open => {
+                self.advance();
+                let first = self.sum()?;
+                if self.peek() == Token::Close {
+                    self.advance();
+                    return Ok(first);
+                }
+                self.expect(Token::Comma, "',' or ')'")?;
+                let second = self.sum()?;
+                self.expect(Token::Comma, "',' before the third component")?;
+                let third = self.sum()?;
+                self.expect(Token::Close, "')' after the third component")?;
+                Ok(Expr::Vector(Box::new([first, second, third])))
+            }
+            Token::Ident(start, end) => self.identifier(&self.text[start..end]),
+            Token::End => Err(self.error("expected a value")),
+            _ => Err(self.error("expected a number, a name or '('")),
+        }
+    }
+
+    fn identifier(&mut self, name: &str) -> Result<Expr, String> {
+        let variable = match name {
+            "x" => Some(Expr::X),
+            "y" => Some(Expr::Y),
+            "z" => Some(Expr::Z),
+            "r" => Some(Expr::R),
+            "p" => Some(Expr::P),
+            "pi" => Some(Expr::Number(std::f32::consts::PI)),
+            "e" => Some(Expr::Number(std::f32::consts::E)),
+            _ => None,
+        };
+        if let Some(variable) = variable {
+            self.advance();
+            return Ok(variable);
+        }
+        let Some(&(function, num_args)) = FUNCTIONS.iter().find(|(f, _)| *f == name) else {
+            return Err(self.error(&format!("unknown name {}", name)));
+        };
+        self.advance();
+        self.expect(Token::Open, &format!("'(' after {}", function))?;
+        let mut args = vec![self.sum()?];
+        while self.peek() == Token::Comma {
+            self.advance();
+            args.push(self.sum()?);
+        }
+        self.expect(Token::Close, "')' after the arguments")?;
+        if args.len() != num_args {
+            return Err(format!(
+                "{} takes {} argument{}, got {}",
+                function,
+                num_args,
+                if num_args == 1 { "" } else { "s" },
+                args.len()
+            ));
+        }
+        Ok(Expr::Call(function, args))
+    }
+}
+
+/// the formula at the centers of the cells of `grid`, the result has to be a finite vector
+fn evaluate_grid(expr: &Expr, grid: &Grid<V3>) -> Result<Grid<V3>, String> {
+    let mut res = grid.clone();
+    res.grid = (0..grid.num_instances())
+        .into_par_iter()
+        .map(|ix| {
+            let pos = grid.position(ix);
+            match evaluate(expr, pos)? {
+                Value::Vector(v) if v.x.is_finite() && v.y.is_finite() && v.z.is_finite() => Ok(v),
+                Value::Vector(_) => Err(format!(
+                    "not finite at ({:.2}, {:.2}, {:.2})",
+                    pos.x, pos.y, pos.z
+                )),
+                Value::Scalar(_) => Err(String::from(
+                    "the result is a number, expected a vector like (x, y, z)",
+                )),
+            }
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(res)
+}
+
+fn evaluate(expr: &Expr, pos: V3) -> Result<Value, String> {
+    Ok(match expr {
+        Expr::Number(n) => Value::Scalar(*n),
+        Expr::X => Value::Scalar(pos.x),
+        Expr::Y => Value::Scalar(pos.y),
+        Expr::Z => Value::Scalar(pos.z),
+        Expr::R => Value::Scalar(pos.magnitude()),
+        Expr::P => Value::Vector(pos),
+        Expr::Neg(a) => map(evaluate(a, pos)?, |a| -a),
+        Expr::Binary(op, a, b) => binary(*op, evaluate(a, pos)?, evaluate(b, pos)?)?,
+        Expr::Vector(components) => {
+            let mut v = V3::new(0.0, 0.0, 0.0);
+            for (axis, component) in components.iter().enumerate() {
+                v[axis] = scalar(evaluate(component, pos)?, "a vector component")?;
+            }
+            Value::Vector(v)
+        }
+        Expr::Component(a, axis) => match evaluate(a, pos)? {
+            Value::Vector(v) => Value::Scalar(v[*axis]),
+            Value::Scalar(_) => return Err(String::from("a number has no components")),
+        },
+        Expr::Call(function, args) => {
+            let args = args
+                .iter()
+                .map(|arg| evaluate(arg, pos))
+                .collect::<Result<Vec<_>, _>>()?;
+            call(function, &args)?
+        }
+    })
+}
+
+/// `f` applied to a number or to every component of a vector
+fn map(a: Value, f: impl Fn(f32) -> f32) -> Value {
+    match a {
+        Value::Scalar(a) => Value::Scalar(f(a)),
+        Value::Vector(v) => Value::Vector(V3::new(f(v.x), f(v.y), f(v.z))),
+    }
+}
+
+/// `f` applied to two numbers or componentwise, a number is paired with every component
+fn zip(a: Value, b: Value, f: impl Fn(f32, f32) -> f32) -> Value {
+    match (a, b) {
+        (Value::Scalar(a), Value::Scalar(b)) => Value::Scalar(f(a, b)),
+        (Value::Vector(a), Value::Scalar(b)) => map(Value::Vector(a), |a| f(a, b)),
+        (Value::Scalar(a), Value::Vector(b)) => map(Value::Vector(b), |b| f(a, b)),
+        (Value::Vector(a), Value::Vector(b)) => {
+            Value::Vector(V3::new(f(a.x, b.x), f(a.y, b.y), f(a.z, b.z)))
+        }
+    }
+}
+
+fn scalar(a: Value, what: &str) -> Result<f32, String> {
+    match a {
+        Value::Scalar(a) => Ok(a),
+        Value::Vector(_) => Err(format!("{} has to be a number, not a vector", what)),
+    }
+}
+
+fn vector(a: Value, function: &str) -> Result<V3, String> {
+    match a {
+        Value::Vector(v) => Ok(v),
+        Value::Scalar(_) => Err(format!("{} takes vectors, not numbers", function)),
+    }
+}
+
+fn binary(op: Op, a: Value, b: Value) -> Result<Value, String> {
+    Ok(match op {
+        Op::Add => zip(a, b, |a, b| a + b),
+        Op::Sub => zip(a, b, |a, b| a - b),
+        Op::Mul => zip(a, b, |a, b| a * b),
+        Op::Div => zip(a, b, |a, b| a / b),
+        Op::Pow => zip(a, Value::Scalar(scalar(b, "an exponent")?), f32::powf),
+    })
+}
+
+fn call(function: &str, args: &[Value]) -> Result<Value, String> {
+    let unary = |f: fn(f32) -> f32| map(args[0], f);
+    Ok(match function {
+        "sin" => unary(f32::sin),
+        "cos" => unary(f32::cos),
+        "tan" => unary(f32::tan),
+        "asin" => unary(f32::asin),
+        "acos" => unary(f32::acos),
+        "atan" => unary(f32::atan),
+        "atan2" => zip(args[0], args[1], f32::atan2),
+        "sqrt" => unary(f32::sqrt),
+        "exp" => unary(f32::exp),
+        "ln" => unary(f32::ln),
+        "abs" => unary(f32::abs),
+        "floor" => unary(f32::floor),
+        "min" => zip(args[0], args[1], f32::min),
+        "max" => zip(args[0], args[1], f32::max),
+        "pow" => binary(Op::Pow, args[0], args[1])?,
+        "length" => Value::Scalar(vector(args[0], function)?.magnitude()),
+        "normalize" => {
+            let v = vector(args[0], function)?;
+            let length = v.magnitude();
+            Value::Vector(if length > 0.0 { v / length } else { v })
+        }
+        "dot" => Value::Scalar(vector(args[0], function)?.dot(vector(args[1], function)?)),
+        "cross" => Value::Vector(vector(args[0], function)?.cross(vector(args[1], function)?)),
+        _ => unreachable!("{} is in FUNCTIONS", function),
+    })
+}
@@ -0,0 +1,124 @@
+use std::io::{Error, ErrorKind};
+
+use crate::{grid::Grid, V3};
+
+/// a force grid layer saved as a NumPy `.npy` array of shape (x, y, z, 3) in C order,
+/// so fields can be made with numpy or Blender and loaded at any grid resolution
+pub struct FieldFile {
+    pub path: String,
+    pub error: Option<String>,
+}
+
+impl FieldFile {
+    pub fn new() -> Self {
+        FieldFile {
+            path: String::from("field.npy"),
+            error: None,
+        }
+    }
+
+    pub fn save(&self, grid: &Grid<V3>) -> std::io::Result<()> {
+        std::fs::write(&self.path, to_npy(grid.dimensions(), &grid.grid))
+    }
+
+    /// replaces the vectors of `grid`, a field of another resolution is resampled onto it
+    pub fn load(&self, grid: &mut Grid<V3>) -> std::io::Result<()> {
+        let bytes = std::fs::read(&self.path)?;
+        let (size, values) = from_npy(&bytes)?;
+        grid.resample_from(size, &values);
+        Ok(())
+    }
+}
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+fn invalid(what: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, what.to_string())
+}
+
+fn to_npy(size: [usize; 3], values: &[V3]) -> Vec<u8> {
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}, {}, 3), }}",
+        size[0], size[1], size[2]
+    );
+    // the data starts 64 byte aligned, the header ends with a newline
+    let unpadded = MAGIC.len() + 4 + header.len() + 1;
+    header.extend(std::iter::repeat_n(' ', (64 - unpadded % 64) % 64));
+    header.push('\n');
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + header.len() + values.len() * 12);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for v in values {
+        for c in [v.x, v.y, v.z] {
+            bytes.extend_from_slice(&c.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// the grid size and the vectors of a little endian f4 or f8 array of shape (x, y, z, 3)
+fn from_npy(bytes: &[u8]) -> std::io::Result<([usize; 3], Vec<V3>)> {
+    if bytes.len() < 10 || &bytes[..6] != MAGIC {
+        return Err(invalid("not a .npy file"));
+    }
+    // version 1 stores the header length in 2 bytes, version 2 and 3 in 4
+    let (header_start, header_len) = match bytes[6] {
+        1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+        2 | 3 if bytes.len() >= 12 => (
+            12,
+            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+        ),
+        _ => return Err(invalid("unsupported .npy version")),
+    };
+    let data = bytes
+        .get(header_start + header_len..)
+        .ok_or_else(|| invalid("truncated header"))?;
+    let header = std::str::from_utf8(&bytes[header_start..header_start + header_len])
+        .map_err(|_| invalid("header is not text"))?;
+    if header.contains("'fortran_order': True") {
+        return Err(invalid(
+            "fortran order is not supported, save a C order array",
+        ));
+    }
+    let float_size = if header.contains("'<f4'") {
+        4
+    } else if header.contains("'<f8'") {
+        8
+    } else {
+        return Err(invalid(
+            "only little endian float32 and float64 arrays are supported",
+        ));
+    };
+    let shape: Vec<usize> = header
+        .split("'shape':")
+        .nth(1)
+        .and_then(|rest| rest.split(['(', ')']).nth(1))
+        .ok_or_else(|| invalid("missing shape"))?
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| invalid("invalid shape")))
+        .collect::<std::io::Result<_>>()?;
+    let size = match shape[..] {
+        [x, y, z, 3] if x > 0 && y > 0 && z > 0 => [x, y, z],
+        _ => return Err(invalid("expected an array of shape (x, y, z, 3)")),
+    };
+    let num_floats = size.iter().product::<usize>() * 3;
+    if data.len() < num_floats * float_size {
+        return Err(invalid("truncated data"));
+    }
+    let floats: Vec<f32> = data[..num_floats * float_size]
+        .chunks_exact(float_size)
+        .map(|c| match float_size {
+            4 => f32::from_le_bytes([c[0], c[1], c[2], c[3]]),
+            _ => f64::from_le_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]) as f32,
+        })
+        .collect();
+    let values = floats
+        .chunks_exact(3)
+        .map(|c| V3::new(c[0], c[1], c[2]))
+        .collect();
+    Ok((size, values))
+}
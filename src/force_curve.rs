@@ -0,0 +1,396 @@
+use egui_plot::PlotPoints;
+
+use crate::{
+    poly7::{smooth_cutoff, smooth_cutoff_slope, Poly7},
+    sim_params::{MatrixSymmetry, SimParams},
+};
+
+/// samples of every curve in the force curve texture, spread evenly up to its last knot
+/// or the largest cut off distance
+pub const DEFAULT_LUT_RESOLUTION: u32 = 256;
+/// the range of `ForceCurves::resolution`
+pub const MIN_LUT_RESOLUTION: u32 = 16;
+pub const MAX_LUT_RESOLUTION: u32 = 4096;
+/// one row of the force curve texture per pair of particle types
+pub const NUM_FORCE_CURVES: usize = 25;
+/// knots of a spline made from a polynome, at the sample points of the polynome plot
+const DEFAULT_KNOTS: usize = 8;
+/// a spline has at least its first and its last knot
+pub const MIN_KNOTS: usize = 2;
+/// knots closer than this along the distance are kept apart while dragging
+const MIN_KNOT_SPACING: f32 = 0.01;
+
+/// how the spline passes through its knots
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplineKind {
+    /// the slope at a knot points from the knot before it to the knot after it
+    CatmullRom,
+    /// the slope at every knot is set by hand
+    Hermite,
+}
+
+impl SplineKind {
+    pub const ALL: [SplineKind; 2] = [SplineKind::CatmullRom, SplineKind::Hermite];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SplineKind::CatmullRom => "Catmull-Rom",
+            SplineKind::Hermite => "cubic Hermite",
+        }
+    }
+}
+
+/// a control point of a spline, `x` is the distance between the particles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Knot {
+    pub x: f32,
+    pub y: f32,
+    /// the slope of a Hermite spline at the knot, Catmull-Rom splines ignore it
+    pub tangent: f32,
+}
+
+/// the attraction between two particle types along their distance. splines stay between
+/// their knots where a polynome through the same points would overshoot
+#[derive(Clone, Debug, PartialEq)]
+pub enum ForceCurve {
+    /// the polynome in `SimParams::attraction_force` with the same index
+    Poly7,
+    /// knots sorted by their distance, the curve holds the value of the last one beyond it
+    Spline { kind: SplineKind, knots: Vec<Knot> },
+}
+
+impl ForceCurve {
+    /// a spline through the polynome at the sample points of the polynome plot
+    pub fn spline_from_poly(kind: SplineKind, poly: &Poly7) -> Self {
+        let derivative = poly.derivative();
+        let knots = (0..DEFAULT_KNOTS)
+            .map(|i| {
+                let x = i as f32 / (DEFAULT_KNOTS - 1) as f32;
+                Knot {
+                    x,
+                    y: poly.eval(x),
+                    tangent: derivative.eval(x),
+                }
+            })
+            .collect();
+        ForceCurve::Spline { kind, knots }
+    }
+
+    /// the value and the slope at distance `x`, `poly` is the polynome of the pair
+    pub fn eval(&self, x: f32, poly: &Poly7) -> (f32, f32) {
+        match self {
+            ForceCurve::Poly7 => (poly.eval(x), poly.derivative().eval(x)),
+            ForceCurve::Spline { kind, knots } => eval_spline(*kind, knots, x),
+        }
+    }
+
+    /// switches to a polynome for None, a spline made from a polynome keeps its shape
+    /// and Hermite splines start with the slopes of the Catmull-Rom spline
+    pub fn set_kind(&mut self, new_kind: Option<SplineKind>, poly: &Poly7) {
+        *self = match (new_kind, &*self) {
+            (None, _) => ForceCurve::Poly7,
+            (Some(new_kind), ForceCurve::Poly7) => Self::spline_from_poly(new_kind, poly),
+            (Some(new_kind), ForceCurve::Spline { kind, knots }) => {
+                let mut knots = knots.clone();
+                if *kind == SplineKind::CatmullRom {
+                    let tangents: Vec<f32> = (0..knots.len())
+                        .map(|i| catmull_rom_slope(&knots, i))
+                        .collect();
+                    for (knot, tangent) in knots.iter_mut().zip(tangents) {
+                        knot.tangent = tangent;
+                    }
+                }
+                ForceCurve::Spline {
+                    kind: new_kind,
+                    knots,
+                }
+            }
+        };
+    }
+
+    /// like `Poly7::windowed_force`, what the compute shader applies at distance `x`
+    pub fn windowed_force(
+        &self,
+        x: f32,
+        poly: &Poly7,
+        potential: bool,
+        cut_off_distance: f32,
+        window: f32,
+    ) -> f32 {
+        if x > cut_off_distance {
+            return 0.0;
+        }
+        let (value, slope) = self.eval(x, poly);
+        let w = smooth_cutoff(x, cut_off_distance, window);
+        if potential {
+            let dw = smooth_cutoff_slope(x, cut_off_distance, window);
+            slope * w + (value - self.eval(cut_off_distance, poly).0) * dw
+        } else {
+            value * w
+        }
+    }
+
+    pub fn plot_points(&self, poly: &Poly7, x_max: f32) -> PlotPoints {
+        (0..=100)
+            .map(|i| {
+                let x = x_max * i as f32 * 0.01;
+                [x as f64, self.eval(x, poly).0 as f64]
+            })
+            .collect()
+    }
+
+    pub fn windowed_plot_points(
+        &self,
+        poly: &Poly7,
+        potential: bool,
+        cut_off_distance: f32,
+        window: f32,
+    ) -> PlotPoints {
+        (0..=100)
+            .map(|i| {
+                let x = cut_off_distance * i as f32 * 0.01;
+                let y = self.windowed_force(x, poly, potential, cut_off_distance, window);
+                [x as f64, y as f64]
+            })
+            .collect()
+    }
+
+    /// the distance the texture row of a spline reaches, 0.0 for a polynome
+    pub fn range(&self) -> f32 {
+        match self {
+            ForceCurve::Poly7 => 0.0,
+            ForceCurve::Spline { knots, .. } => knots.last().map_or(0.0, |k| k.x),
+        }
+    }
+}
+
+/// the value and the slope of the spline at `x`, flat before the first and after the last knot
+fn eval_spline(kind: SplineKind, knots: &[Knot], x: f32) -> (f32, f32) {
+    let (Some(first), Some(last)) = (knots.first(), knots.last()) else {
+        return (0.0, 0.0);
+    };
+    if x <= first.x {
+        return (first.y, 0.0);
+    }
+    if x >= last.x {
+        return (last.y, 0.0);
+    }
+    let i = knots
+        .partition_point(|k| k.x <= x)
+        .clamp(1, knots.len() - 1)
+        - 1;
+    let (k0, k1) = (knots[i], knots[i + 1]);
+    let h = (k1.x - k0.x).max(f32::EPSILON);
+    let (m0, m1) = match kind {
+        SplineKind::Hermite => (k0.tangent, k1.tangent),
+        SplineKind::CatmullRom => (catmull_rom_slope(knots, i), catmull_rom_slope(knots, i + 1)),
+    };
+    let t = (x - k0.x) / h;
+    let (t2, t3) = (t * t, t * t * t);
+    let value = (2.0 * t3 - 3.0 * t2 + 1.0) * k0.y
+        + (t3 - 2.0 * t2 + t) * h * m0
+        + (-2.0 * t3 + 3.0 * t2) * k1.y
+        + (t3 - t2) * h * m1;
+    let slope = ((6.0 * t2 - 6.0 * t) * k0.y
+        + (3.0 * t2 - 4.0 * t + 1.0) * h * m0
+        + (-6.0 * t2 + 6.0 * t) * k1.y
+        + (3.0 * t2 - 2.0 * t) * h * m1)
+        / h;
+    (value, slope)
+}
+
+/// the slope at knot `i` from its neighbours, one sided at the ends
+fn catmull_rom_slope(knots: &[Knot], i: usize) -> f32 {
+    let before = knots[i.saturating_sub(1)];
+    let after = knots[(i + 1).min(knots.len() - 1)];
+    (after.y - before.y) / (after.x - before.x).max(f32::EPSILON)
+}
+
+/// the force curves of all pairs of particle types. the splines and, while `bake_polys`
+/// is set, the polynomes are sampled into the lookup table texture of the compute shader
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForceCurves {
+    pub curves: [ForceCurve; NUM_FORCE_CURVES],
+    /// the compute shader reads the polynomes from the lookup table instead of
+    /// evaluating them for every pair of particles
+    pub bake_polys: bool,
+    /// samples per curve in the lookup table
+    pub resolution: u32,
+    /// what the lookup table was last baked from
+    baked: Option<BakeInputs>,
+}
+
+/// everything the lookup table depends on, it is only baked again after one of them changed
+#[derive(Clone, Debug, PartialEq)]
+struct BakeInputs {
+    curves: [ForceCurve; NUM_FORCE_CURVES],
+    polys: [[f32; 8]; NUM_FORCE_CURVES],
+    poly_range: f32,
+    resolution: u32,
+    bake_polys: bool,
+}
+
+impl Default for ForceCurves {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForceCurves {
+    pub fn new() -> Self {
+        ForceCurves {
+            curves: std::array::from_fn(|_| ForceCurve::Poly7),
+            bake_polys: true,
+            resolution: DEFAULT_LUT_RESOLUTION,
+            baked: None,
+        }
+    }
+
+    /// turns every curve back into its polynome, the lookup table settings stay
+    pub fn reset(&mut self) {
+        self.curves = std::array::from_fn(|_| ForceCurve::Poly7);
+    }
+
+    /// the lookup table is baked again with the next `bake`, e.g. after its texture was replaced
+    pub fn invalidate(&mut self) {
+        self.baked = None;
+    }
+
+    /// the width of the lookup table, `resolution` within the supported range
+    pub fn lut_width(&self) -> u32 {
+        self.resolution
+            .clamp(MIN_LUT_RESOLUTION, MAX_LUT_RESOLUTION)
+    }
+
+    /// the distance the row of a curve reaches in the lookup table, 0.0 if the shader
+    /// evaluates its polynome. `poly_range` is the largest cut off distance in use
+    fn row_range(&self, curve: &ForceCurve, poly_range: f32) -> f32 {
+        match curve {
+            ForceCurve::Poly7 if self.bake_polys => poly_range,
+            _ => curve.range(),
+        }
+    }
+
+    /// tells the compute shader which pairs read their curve from the lookup table
+    pub fn apply(&self, sim_params: &mut SimParams, poly_range: f32) {
+        for (pair_force, curve) in sim_params.pair_forces.iter_mut().zip(&self.curves) {
+            pair_force.curve_range = self.row_range(curve, poly_range);
+        }
+    }
+
+    /// the value and the slope of every curve at `lut_width` distances from 0 to the end
+    /// of its row, one row per pair. the rows of polynomes that are not baked are 0
+    pub fn get_raw(&self, sim_params: &SimParams, poly_range: f32) -> Vec<[f32; 2]> {
+        let resolution = self.lut_width();
+        let mut res = Vec::with_capacity(NUM_FORCE_CURVES * resolution as usize);
+        for (curve, poly) in self.curves.iter().zip(&sim_params.attraction_force) {
+            let range = self.row_range(curve, poly_range);
+            res.extend((0..resolution).map(|i| {
+                if range <= 0.0 {
+                    return [0.0; 2];
+                }
+                let x = range * i as f32 / (resolution - 1) as f32;
+                let (value, slope) = curve.eval(x, poly);
+                [value, slope]
+            }));
+        }
+        res
+    }
+
+    /// the samples of `get_raw` if the curves, the polynomes or the settings changed since
+    /// the last bake, None otherwise
+    pub fn bake(&mut self, sim_params: &SimParams, poly_range: f32) -> Option<Vec<[f32; 2]>> {
+        let inputs = BakeInputs {
+            curves: self.curves.clone(),
+            polys: sim_params.attraction_force.map(|poly| poly.coeffs),
+            poly_range,
+            resolution: self.resolution,
+            bake_polys: self.bake_polys,
+        };
+        if self.baked.as_ref() == Some(&inputs) {
+            return None;
+        }
+        self.baked = Some(inputs);
+        Some(self.get_raw(sim_params, poly_range))
+    }
+
+    /// ties the curves of every pair to the transposed ones like `SimParams::enforce_symmetry`,
+    /// the curves above the diagonal win
+    pub fn enforce_symmetry(&mut self, symmetry: MatrixSymmetry) {
+        for y in 0..5 {
+            for x in y..5 {
+                self.mirror(x + y * 5, symmetry);
+            }
+        }
+    }
+
+    /// copies the curve at `index` to the transposed pair like `SimParams::mirror_force`
+    pub fn mirror(&mut self, index: usize, symmetry: MatrixSymmetry) {
+        let (x, y) = (index % 5, index / 5);
+        let transposed = y + x * 5;
+        let negated = |curve: &ForceCurve| match curve {
+            ForceCurve::Poly7 => ForceCurve::Poly7,
+            ForceCurve::Spline { kind, knots } => ForceCurve::Spline {
+                kind: *kind,
+                knots: knots
+                    .iter()
+                    .map(|k| Knot {
+                        x: k.x,
+                        y: -k.y,
+                        tangent: -k.tangent,
+                    })
+                    .collect(),
+            },
+        };
+        match symmetry {
+            MatrixSymmetry::Free => {}
+            MatrixSymmetry::Symmetric => {
+                self.curves[transposed] = self.curves[index].clone();
+            }
+            // like the polynome, the curve of a type on itself is flattened to 0
+            MatrixSymmetry::Antisymmetric if x == y => {
+                if let ForceCurve::Spline { knots, .. } = &mut self.curves[index] {
+                    for knot in knots.iter_mut() {
+                        knot.y = 0.0;
+                        knot.tangent = 0.0;
+                    }
+                }
+            }
+            MatrixSymmetry::Antisymmetric => {
+                self.curves[transposed] = negated(&self.curves[index]);
+            }
+        }
+    }
+}
+
+/// moves knot `i` to (`x`, `y`), between its neighbours. the first knot stays at distance 0
+pub fn move_knot(knots: &mut [Knot], i: usize, x: f32, y: f32) {
+    let min = if i == 0 {
+        0.0
+    } else {
+        knots[i - 1].x + MIN_KNOT_SPACING
+    };
+    let max = match knots.get(i + 1) {
+        Some(next) => next.x - MIN_KNOT_SPACING,
+        None => f32::MAX,
+    };
+    knots[i].x = if i == 0 {
+        0.0
+    } else {
+        x.clamp(min, max.max(min))
+    };
+    knots[i].y = y;
+}
+
+/// adds a knot on the curve in the middle of the widest gap between two knots
+pub fn insert_knot(kind: SplineKind, knots: &mut Vec<Knot>) {
+    let Some(i) = (0..knots.len().saturating_sub(1)).max_by(|a, b| {
+        let gap = |i: usize| knots[i + 1].x - knots[i].x;
+        gap(*a).total_cmp(&gap(*b))
+    }) else {
+        return;
+    };
+    let x = 0.5 * (knots[i].x + knots[i + 1].x);
+    let (y, tangent) = eval_spline(kind, knots, x);
+    knots.insert(i + 1, Knot { x, y, tangent });
+}
@@ -1,11 +1,22 @@
+use crate::adapters::{self, AdapterEntry};
+use crate::config::Config;
+use crate::panel_surface::PanelSurface;
 use crate::renderer::Renderer;
+use crate::safe_mode::{self, SafeMode};
+use crate::startup::Launcher;
 use crate::{gui::Gui, App};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use wgpu::{
-    Adapter, Dx12Compiler, Gles3MinorVersion, Instance, InstanceDescriptor, InstanceFlags, Surface,
+    Adapter, Device, Dx12Compiler, Gles3MinorVersion, Instance, InstanceDescriptor, InstanceFlags,
+    Queue, Surface, SurfaceConfiguration, SurfaceError, SurfaceTexture, TextureFormat,
 };
 use winit::{
-    event::{self, WindowEvent},
-    event_loop::{ControlFlow, EventLoop, EventLoopBuilder},
+    event::{self, DeviceEvent, WindowEvent},
+    event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopWindowTarget},
+    window::{CursorGrabMode, Window, WindowBuilder, WindowId},
 };
 
 #[rustfmt::skip]
@@ -24,6 +35,10 @@ pub fn cast_slice<T>(data: &[T]) -> &[u8] {
     unsafe { from_raw_parts(data.as_ptr() as *const u8, size_of_val(data)) }
 }
 
+/// timestamp queries time the compute pass when autotuning its workgroup size
+const OPTIONAL_FEATURES: wgpu::Features = wgpu::Features::TIMESTAMP_QUERY;
+const REQUIRED_FEATURES: wgpu::Features = wgpu::Features::empty();
+
 struct Setup {
     window: winit::window::Window,
     event_loop: EventLoop<()>,
@@ -33,16 +48,25 @@ struct Setup {
     adapter: wgpu::Adapter, // what is the difference btw Adapter and Device ?
     device: wgpu::Device,
     queue: wgpu::Queue,
+    safe_mode: Option<SafeMode>,
+    config: Config,
 }
 
-async fn setup(title: &str) -> Setup {
+async fn setup(title: &str, config_path: &str) -> Setup {
+    let safe_mode = SafeMode::detect();
+    if let Some(safe_mode) = safe_mode {
+        log::warn!("starting in {}", safe_mode.description());
+    }
+    safe_mode::acquire_crash_lock();
+
     #[cfg(not(target_arch = "wasm32"))]
     {
         env_logger::init();
     };
 
+    let config = Config::load(config_path);
     let event_loop = EventLoopBuilder::with_user_event().build();
-    let window = create_window(title, &event_loop);
+    let window = create_window(title, &config, &event_loop);
     let instance = create_instance();
 
     log::info!("Initializing the surface...");
@@ -53,15 +77,23 @@ async fn setup(title: &str) -> Setup {
         (size, surface)
     };
 
-    let adapter = create_adapter(&instance, &surface).await;
+    let adapter = if safe_mode.is_some() {
+        create_fallback_adapter(&instance, &surface).await
+    } else if std::env::var_os("WGPU_ADAPTER_NAME").is_none() {
+        // an adapter picked in an earlier run, the environment variable overrides it
+        match adapters::find_chosen(&instance, &surface) {
+            Some(adapter) => adapter,
+            None => create_adapter(&instance, &surface).await,
+        }
+    } else {
+        create_adapter(&instance, &surface).await
+    };
     // check features
-    let optional_features = wgpu::Features::empty();
-    let required_features = wgpu::Features::empty();
     let adapter_features = adapter.features();
     assert!(
-        adapter_features.contains(required_features),
+        adapter_features.contains(REQUIRED_FEATURES),
         "Adapter does not support required features for this example: {:?}",
-        required_features - adapter_features
+        REQUIRED_FEATURES - adapter_features
     );
     println!("Features: {:?}", adapter_features);
 
@@ -71,36 +103,67 @@ async fn setup(title: &str) -> Setup {
         println!("Using {} ({:?})", adapter_info.name, adapter_info.backend);
     }
 
+    let (device, queue) = request_device(&adapter, safe_mode).await;
+
+    Setup {
+        window,
+        event_loop,
+        instance,
+        size,
+        surface,
+        adapter,
+        device,
+        queue,
+        safe_mode,
+        config,
+    }
+}
+
+async fn request_device(adapter: &Adapter, safe_mode: Option<SafeMode>) -> (Device, Queue) {
     #[cfg(not(target_arch = "wasm32"))]
-    let needed_limits = wgpu::Limits::default().using_resolution(adapter.limits());
+    let needed_limits = if safe_mode.is_some() {
+        wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits())
+    } else {
+        wgpu::Limits::default().using_resolution(adapter.limits())
+    };
 
     #[cfg(target_arch = "wasm32")]
-    let needed_limits =
-        wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits());
+    let needed_limits = {
+        let _ = safe_mode;
+        wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits())
+    };
 
     let trace_dir = std::env::var("WGPU_TRACE");
-    let (device, queue) = adapter
+    adapter
         .request_device(
             &wgpu::DeviceDescriptor {
                 label: Some("Framework: device descriptor"),
-                features: (optional_features & adapter_features) | required_features,
+                features: (OPTIONAL_FEATURES & adapter.features()) | REQUIRED_FEATURES,
                 limits: needed_limits,
             },
             trace_dir.ok().as_ref().map(std::path::Path::new),
         )
         .await
-        .expect("Cannot request GPU device");
+        .expect("Cannot request GPU device")
+}
 
-    Setup {
-        window,
-        event_loop,
-        instance,
-        size,
-        surface,
-        adapter,
-        device,
-        queue,
-    }
+/// raises the returned flag once the device is lost, other errors still panic.
+/// wgpu 0.18 has no device lost callback, a lost device shows up as uncaptured errors
+fn watch_device_lost(device: &Device) -> Arc<AtomicBool> {
+    let lost = Arc::new(AtomicBool::new(false));
+    let lost_cb = lost.clone();
+    device.on_uncaptured_error(Box::new(move |error| {
+        let is_lost = matches!(error, wgpu::Error::OutOfMemory { .. })
+            || error.to_string().to_lowercase().contains("lost");
+        if is_lost {
+            log::error!("device lost: {}", error);
+            lost_cb.store(true, Ordering::Release);
+        } else {
+            log::error!("wgpu error: {}", error);
+            panic!("wgpu error: {}", error);
+        }
+    }));
+    lost
 }
 
 async fn create_adapter(instance: &Instance, surface: &Surface) -> Adapter {
@@ -109,9 +172,36 @@ async fn create_adapter(instance: &Instance, surface: &Surface) -> Adapter {
         .expect("No suitable GPU adapters found on the system!")
 }
 
-fn create_window(title: &str, event_loop: &EventLoop<()>) -> winit::window::Window {
+/// prefers the software adapter and otherwise the least demanding one
+async fn create_fallback_adapter(instance: &Instance, surface: &Surface) -> Adapter {
+    let fallback = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::LowPower,
+            force_fallback_adapter: true,
+            compatible_surface: Some(surface),
+        })
+        .await;
+    match fallback {
+        Some(adapter) => adapter,
+        None => create_adapter(instance, surface).await,
+    }
+}
+
+fn create_window(
+    title: &str,
+    config: &Config,
+    event_loop: &EventLoop<()>,
+) -> winit::window::Window {
     let mut builder = winit::window::WindowBuilder::new();
-    builder = builder.with_title(title);
+    builder = builder
+        .with_title(config.title.as_deref().unwrap_or(title))
+        .with_fullscreen(config.fullscreen(config.find_monitor(event_loop)));
+    // the canvas is fitted to the page below
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        builder =
+            builder.with_inner_size(winit::dpi::PhysicalSize::new(config.width, config.height));
+    }
     #[cfg(windows_OFF)] // TODO
     {
         use winit::platform::windows::WindowBuilderExtWindows;
@@ -145,7 +235,7 @@ fn create_window(title: &str, event_loop: &EventLoop<()>) -> winit::window::Wind
     window
 }
 
-fn create_instance() -> Instance {
+pub(crate) fn create_instance() -> Instance {
     // rendering backend (OpenGL, Vulkan, DirectX, ...)
     let backend = wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all);
     // wgpu instance creates adapters and surfaces
@@ -164,19 +254,21 @@ fn start(
         event_loop,
         instance,
         size,
-        surface,
-        adapter,
+        mut surface,
+        mut adapter,
         device,
         queue,
+        safe_mode,
+        config,
     }: Setup,
 ) {
-    let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+    let format = surface_format(&surface, &adapter, config.hdr);
     let mut surface_config = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         format,
         width: size.width,
         height: size.height,
-        present_mode: wgpu::PresentMode::Fifo,
+        present_mode: config.present_mode(),
         alpha_mode: wgpu::CompositeAlphaMode::Auto,
         view_formats: vec![format],
     };
@@ -184,24 +276,49 @@ fn start(
     surface.configure(&device, &surface_config);
 
     log::info!("Initializing the example...");
-    let mut gui = Gui::new(&window, &event_loop);
-    let renderer = Renderer::init(&surface_config, device, queue);
-    let mut app = App::new(renderer);
-    let context = egui::Context::default();
-    context.set_pixels_per_point(window.scale_factor() as f32);
+    let mut device_lost = watch_device_lost(&device);
+    let mut fullscreen = (config.fullscreen, config.monitor);
+    let mut gui = Gui::new(&event_loop, config);
+    // the app is initialized once the startup dialog is confirmed
+    let mut launcher = Some(Launcher::new(
+        device,
+        queue,
+        &surface_config,
+        safe_mode,
+        list_adapters(&instance, &surface, &adapter),
+        adapter.get_info(),
+    ));
+    let mut app: Option<App> = None;
+    // the gui zooms the context to its pixels per point every frame
+    let mut context = egui::Context::default();
+    let mut cursor_grabbed = false;
+    let mut panel: Option<PanelWindow> = None;
 
     log::info!("Entering render loop...");
-    event_loop.run(move |event, _, control_flow| {
-        let _ = (&instance, &adapter); // force ownership by the closure
+    event_loop.run(move |event, target, control_flow| {
         *control_flow = if cfg!(feature = "metal-auto-capture") {
             ControlFlow::Exit
         } else {
             ControlFlow::Poll
         };
-        app.update();
+        if let Some(app) = app.as_mut() {
+            app.update();
+        }
 
         match event {
+            event::Event::WindowEvent { window_id, event }
+                if Some(window_id) == panel.as_ref().map(PanelWindow::id) =>
+            {
+                if let (Some(panel), Some(app)) = (panel.as_mut(), app.as_mut()) {
+                    panel.on_window_event(&event, &mut app.renderer, &mut gui);
+                }
+            }
             event::Event::RedrawEventsCleared => {
+                // the browser does not report resizes of the page to the canvas
+                #[cfg(target_arch = "wasm32")]
+                if let Some(size) = fit_canvas_to_page(&window, &surface_config) {
+                    resize(size, &surface, &mut surface_config, &mut app, &launcher);
+                }
                 window.request_redraw();
             }
             event::Event::WindowEvent {
@@ -213,43 +330,130 @@ fn start(
                     },
                 ..
             } => {
-                log::info!("Resizing to {:?}", size);
-                surface_config.width = size.width.max(1);
-                surface_config.height = size.height.max(1);
-                surface.configure(&app.renderer.device, &surface_config);
-                app.renderer.resize(&surface_config);
+                resize(size, &surface, &mut surface_config, &mut app, &launcher);
+            }
+            event::Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } => {
+                if let Some(app) = app.as_mut() {
+                    app.mouse_motion(delta.0, delta.1);
+                }
+            }
+            event::Event::LoopDestroyed => {
+                safe_mode::release_crash_lock();
             }
             event::Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => {
                     *control_flow = ControlFlow::Exit;
                 }
                 _ => {
-                    // forward events to egui
-                    let _ = gui.winit_state.on_window_event(&context, &event);
-                    app.winit_update(&event);
+                    // forward events to egui, a detached panel takes its input from its own window
+                    if panel.is_none() {
+                        let _ = gui.winit_state.on_window_event(&context, &event);
+                    }
+                    if let Some(app) = app.as_mut() {
+                        app.winit_update(&event);
+                    }
                 }
             },
-            event::Event::RedrawRequested(_) => {
-                let frame = match surface.get_current_texture() {
-                    Ok(frame) => frame,
-                    Err(_) => {
-                        surface.configure(&app.renderer.device, &surface_config);
-                        surface
-                            .get_current_texture()
-                            .expect("Failed to acquire next swap chain texture!")
+            event::Event::RedrawRequested(window_id) if window_id == window.id() => {
+                if device_lost.load(Ordering::Acquire) {
+                    let Some((device, queue)) = recreate_device(&adapter, safe_mode) else {
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    };
+                    device_lost = watch_device_lost(&device);
+                    surface.configure(&device, &surface_config);
+                    if let Some(app) = app.as_mut() {
+                        app.recover_device(device, queue, &surface_config);
+                    } else if let Some(launcher) = launcher.as_mut() {
+                        launcher.recover_device(device, queue, &surface_config);
+                    }
+                    // the new egui renderer needs the font textures again
+                    context = egui::Context::default();
+                }
+                let device = current_device(&app, &launcher);
+                let frame = match acquire_frame(&surface, device, &surface_config) {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => return,
+                    Err(SurfaceError::Lost) => {
+                        log::warn!("surface lost, recreating it");
+                        surface = unsafe { instance.create_surface(&window) }
+                            .expect("Failed to recreate the surface!");
+                        surface.configure(device, &surface_config);
+                        return;
+                    }
+                    Err(e) => {
+                        log::error!("{}", e);
+                        *control_flow = ControlFlow::Exit;
+                        return;
                     }
                 };
 
-                let output = gui.update(&context, &window, &mut app);
+                let mut launch = false;
+                if let Some(app) = app.as_mut() {
+                    if let Some(panel) = panel.as_mut().filter(|_| app.renderer.panel.is_some()) {
+                        let input = panel.winit_state.take_egui_input(&panel.window);
+                        let output = gui.show(&panel.context, input, app);
+                        app.renderer.render(
+                            &frame,
+                            egui::FullOutput::default(),
+                            &mut app.compute,
+                            app.comparison.as_mut(),
+                            &context,
+                            context.pixels_per_point(),
+                        );
+                        panel.render(output, &mut app.renderer);
+                    } else {
+                        let output = gui.update(&context, &window, app);
 
-                app.renderer.render(
-                    &frame,
-                    output,
-                    &mut app.compute,
-                    &context,
-                    window.scale_factor() as f32,
-                );
+                        app.renderer.render(
+                            &frame,
+                            output,
+                            &mut app.compute,
+                            app.comparison.as_mut(),
+                            &context,
+                            context.pixels_per_point(),
+                        );
+                    }
+                } else if let Some(launcher) = launcher.as_mut() {
+                    let output;
+                    (output, launch) = gui.startup(&context, &window, launcher);
+                    if let Some(entry) = launcher.switch_adapter.take() {
+                        if let Some((new_adapter, device, queue)) =
+                            switch_adapter(&instance, &surface, &entry, safe_mode)
+                        {
+                            adapters::save_choice(&entry.info);
+                            adapter = new_adapter;
+                            device_lost = watch_device_lost(&device);
+                            surface_config.format =
+                                surface_format(&surface, &adapter, gui.config.hdr);
+                            surface_config.view_formats = vec![surface_config.format];
+                            surface.configure(&device, &surface_config);
+                            launcher.recover_device(device, queue, &surface_config);
+                            launcher.current_adapter = entry.info;
+                            // the frame is drawn with the old egui renderer, skip presenting it
+                            context = egui::Context::default();
+                            return;
+                        }
+                    }
+                    launcher.render(
+                        &frame,
+                        output,
+                        &context,
+                        &surface_config,
+                        context.pixels_per_point(),
+                    );
+                }
                 frame.present();
+
+                if let Some(launcher) = launch.then(|| launcher.take()).flatten() {
+                    let renderer = Renderer::init(&surface_config, launcher.device, launcher.queue);
+                    app = Some(App::new(renderer, &launcher.config, safe_mode));
+                    // a fresh context uploads the font textures to the new egui renderer
+                    context = egui::Context::default();
+                }
             }
 
             _ => {}
@@ -258,27 +462,327 @@ fn start(
         if gui.exit_app {
             *control_flow = ControlFlow::Exit;
         }
-        let (instances_raw, num_instances) = app.psys.get_instances();
-        app.renderer.sub_rpass_particles.update_instance_buffer(
-            &app.renderer.device,
-            &instances_raw,
-            num_instances,
-        );
+        if let Some(app) = app.as_mut() {
+            let grab = app.renderer.camera.mouse_look_active();
+            if grab != cursor_grabbed {
+                grab_cursor(&window, grab);
+                cursor_grabbed = grab;
+            }
+            let config = &mut gui.config;
+            if std::mem::take(&mut app.toggle_fullscreen) {
+                config.toggle_fullscreen();
+            }
+            if (config.fullscreen, config.monitor) != fullscreen {
+                fullscreen = (config.fullscreen, config.monitor);
+                window.set_fullscreen(config.fullscreen(config.find_monitor(target)));
+            }
+            if config.present_mode() != surface_config.present_mode {
+                surface_config.present_mode = config.present_mode();
+                surface.configure(&app.renderer.device, &surface_config);
+            }
+            if std::mem::take(&mut gui.save_config) {
+                config.update_from_window(&window, target);
+                config.error = config.save().err().map(|e| e.to_string());
+            }
+            sync_panel(
+                &mut panel,
+                &mut gui.detach_panel,
+                target,
+                &instance,
+                &adapter,
+                &mut app.renderer,
+            );
+        }
 
         // gui.app.update();
     });
 }
 
+/// the window the control panel is detached into, with egui state of its own
+struct PanelWindow {
+    window: Window,
+    winit_state: egui_winit::State,
+    context: egui::Context,
+}
+
+impl PanelWindow {
+    fn new(target: &EventLoopWindowTarget<()>) -> Option<Self> {
+        let window = WindowBuilder::new()
+            .with_title("Particles controls")
+            .with_inner_size(winit::dpi::LogicalSize::new(420.0, 720.0))
+            .build(target)
+            .map_err(|e| log::error!("could not open the control panel window: {}", e))
+            .ok()?;
+        Some(PanelWindow {
+            winit_state: egui_winit::State::new(egui::ViewportId::ROOT, target, None, None),
+            context: egui::Context::default(),
+            window,
+        })
+    }
+
+    fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    fn on_window_event(&mut self, event: &WindowEvent, renderer: &mut Renderer, gui: &mut Gui) {
+        match event {
+            // closing the panel puts the controls back into the main window
+            WindowEvent::CloseRequested => gui.detach_panel = false,
+            WindowEvent::Resized(size) => self.resize(*size, renderer),
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                self.resize(**new_inner_size, renderer);
+                let _ = self.winit_state.on_window_event(&self.context, event);
+            }
+            _ => {
+                let _ = self.winit_state.on_window_event(&self.context, event);
+            }
+        }
+    }
+
+    fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>, renderer: &mut Renderer) {
+        if let Some(surface) = renderer.panel.as_mut() {
+            surface.resize(&renderer.device, size.width, size.height);
+        }
+        self.context
+            .set_pixels_per_point(self.window.scale_factor() as f32);
+    }
+
+    fn render(&self, output: egui::FullOutput, renderer: &mut Renderer) {
+        let Some(surface) = renderer.panel.as_ref() else {
+            return;
+        };
+        match acquire_frame(&surface.surface, &renderer.device, &surface.config) {
+            Ok(Some(frame)) => {
+                let scale_factor = self.window.scale_factor() as f32;
+                renderer.render_panel(&frame, output, &self.context, scale_factor);
+                frame.present();
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("could not draw the control panel: {}", e),
+        }
+    }
+}
+
+/// opens or closes the panel window to match `detach`, a panel surface lost with
+/// the device is created again
+fn sync_panel(
+    panel: &mut Option<PanelWindow>,
+    detach: &mut bool,
+    target: &EventLoopWindowTarget<()>,
+    instance: &Instance,
+    adapter: &Adapter,
+    renderer: &mut Renderer,
+) {
+    if !*detach {
+        // the surface goes before its window
+        renderer.panel = None;
+        *panel = None;
+        return;
+    }
+    if renderer.panel.is_some() {
+        return;
+    }
+    if panel.is_none() {
+        *panel = PanelWindow::new(target);
+    }
+    let Some(window) = panel.as_mut() else {
+        *detach = false;
+        return;
+    };
+    let surface = match unsafe { instance.create_surface(&window.window) } {
+        Ok(surface) => surface,
+        Err(e) => {
+            log::error!("could not create the control panel surface: {}", e);
+            *detach = false;
+            *panel = None;
+            return;
+        }
+    };
+    let size = window.window.inner_size();
+    // the panel only draws the controls, it stays out of hdr
+    let format = surface_format(&surface, adapter, false);
+    renderer.panel = Some(PanelSurface::new(
+        &renderer.device,
+        surface,
+        format,
+        size.width,
+        size.height,
+    ));
+    // a fresh context uploads the font textures to the new egui renderer
+    window.context = egui::Context::default();
+    window
+        .context
+        .set_pixels_per_point(window.window.scale_factor() as f32);
+}
+
+/// hides the pointer and keeps it in the window for mouse look,
+/// platforms without locking confine it instead
+fn grab_cursor(window: &winit::window::Window, grab: bool) {
+    let result = if grab {
+        window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+    } else {
+        window.set_cursor_grab(CursorGrabMode::None)
+    };
+    if let Err(e) = result {
+        log::warn!("could not grab the cursor: {}", e);
+    }
+    window.set_cursor_visible(!grab);
+}
+
+/// the format to configure `surface` with, a 16 bit float one for `hdr` if the surface offers
+/// it and an srgb one otherwise. the shaders write linear colors, so the first format is the
+/// last resort only
+fn surface_format(surface: &Surface, adapter: &Adapter, hdr: bool) -> TextureFormat {
+    let formats = surface.get_capabilities(adapter).formats;
+    log::info!("surface formats: {:?}", formats);
+    formats
+        .iter()
+        .copied()
+        .find(|&format| hdr && format == TextureFormat::Rgba16Float)
+        .or_else(|| formats.iter().copied().find(TextureFormat::is_srgb))
+        .or_else(|| formats.first().copied())
+        .unwrap_or(TextureFormat::Rgba8UnormSrgb)
+}
+
+fn resize(
+    size: winit::dpi::PhysicalSize<u32>,
+    surface: &Surface,
+    surface_config: &mut SurfaceConfiguration,
+    app: &mut Option<App>,
+    launcher: &Option<Launcher>,
+) {
+    log::info!("Resizing to {:?}", size);
+    // a minimized window reports a zero size on some platforms
+    if size.width == 0 || size.height == 0 {
+        return;
+    }
+    surface_config.width = size.width.max(1);
+    surface_config.height = size.height.max(1);
+    surface.configure(current_device(app, launcher), surface_config);
+    if let Some(app) = app.as_mut() {
+        app.renderer.resize(surface_config);
+    }
+}
+
+/// resizes the canvas to the page body, returns the new size if it changed
+#[cfg(target_arch = "wasm32")]
+fn fit_canvas_to_page(
+    window: &winit::window::Window,
+    surface_config: &SurfaceConfiguration,
+) -> Option<winit::dpi::PhysicalSize<u32>> {
+    let body = web_sys::window()?.document()?.body()?;
+    let size =
+        winit::dpi::PhysicalSize::new(body.client_width() as u32, body.client_height() as u32);
+    if size.width == surface_config.width && size.height == surface_config.height {
+        return None;
+    }
+    window.set_inner_size(size);
+    Some(size)
+}
+
+/// reconfigures an outdated surface and skips frames that time out,
+/// only errors that reconfiguring does not fix are returned
+fn acquire_frame(
+    surface: &Surface,
+    device: &Device,
+    surface_config: &SurfaceConfiguration,
+) -> Result<Option<SurfaceTexture>, SurfaceError> {
+    match surface.get_current_texture() {
+        Ok(frame) => Ok(Some(frame)),
+        Err(SurfaceError::Timeout) => Ok(None),
+        Err(SurfaceError::Outdated | SurfaceError::Lost) => {
+            surface.configure(device, surface_config);
+            match surface.get_current_texture() {
+                Ok(frame) => Ok(Some(frame)),
+                Err(SurfaceError::Timeout | SurfaceError::Outdated) => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn recreate_device(adapter: &Adapter, safe_mode: Option<SafeMode>) -> Option<(Device, Queue)> {
+    log::warn!("requesting a new device");
+    Some(pollster::block_on(request_device(adapter, safe_mode)))
+}
+
+/// the event loop can not wait for a device on the web
+#[cfg(target_arch = "wasm32")]
+fn recreate_device(_adapter: &Adapter, _safe_mode: Option<SafeMode>) -> Option<(Device, Queue)> {
+    log::error!("the device was lost, reload the page");
+    None
+}
+
+/// the adapters offered in the startup dialog, at least the one in use
+fn list_adapters(instance: &Instance, surface: &Surface, adapter: &Adapter) -> Vec<AdapterEntry> {
+    let mut entries: Vec<AdapterEntry> = adapters::compatible_adapters(instance, surface)
+        .iter()
+        .map(AdapterEntry::new)
+        .collect();
+    if !entries
+        .iter()
+        .any(|entry| entry.matches(&adapter.get_info()))
+    {
+        entries.insert(0, AdapterEntry::new(adapter));
+    }
+    entries
+}
+
+/// opens a device on the adapter picked in the startup dialog
+#[cfg(not(target_arch = "wasm32"))]
+fn switch_adapter(
+    instance: &Instance,
+    surface: &Surface,
+    entry: &AdapterEntry,
+    safe_mode: Option<SafeMode>,
+) -> Option<(Adapter, Device, Queue)> {
+    let Some(adapter) = adapters::compatible_adapters(instance, surface)
+        .into_iter()
+        .find(|adapter| entry.matches(&adapter.get_info()))
+    else {
+        log::error!("the adapter {} is gone", entry.label());
+        return None;
+    };
+    log::info!("switching to {}", entry.label());
+    let (device, queue) = pollster::block_on(request_device(&adapter, safe_mode));
+    Some((adapter, device, queue))
+}
+
+/// the event loop can not wait for a device on the web
+#[cfg(target_arch = "wasm32")]
+fn switch_adapter(
+    _instance: &Instance,
+    _surface: &Surface,
+    _entry: &AdapterEntry,
+    _safe_mode: Option<SafeMode>,
+) -> Option<(Adapter, Device, Queue)> {
+    None
+}
+
+/// the device is owned by the launcher until the app is initialized
+fn current_device<'a>(app: &'a Option<App>, launcher: &'a Option<Launcher>) -> &'a wgpu::Device {
+    match (app, launcher) {
+        (Some(app), _) => &app.renderer.device,
+        (None, Some(launcher)) => &launcher.device,
+        (None, None) => unreachable!("either the app or the launcher owns the device"),
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
-pub fn wgpu_main() {
-    let setup = pollster::block_on(setup("Particles"));
+pub fn wgpu_main(title: &str, config_path: &str) {
+    let setup = pollster::block_on(setup(title, config_path));
     start(setup);
 }
 
 #[cfg(target_arch = "wasm32")]
-pub fn wgpu_main() {
+pub fn wgpu_main(title: &str, config_path: &str) {
+    let (title, config_path) = (String::from(title), String::from(config_path));
     wasm_bindgen_futures::spawn_local(async move {
-        let setup = setup("Particles").await;
+        let setup = setup(&title, &config_path).await;
         start(setup);
     });
 }
@@ -0,0 +1,185 @@
+use cgmath::InnerSpace;
+
+use crate::{
+    cursor::{Marquee, SelectionShape},
+    V3,
+};
+
+/// number of grid lines per direction of a grid plane
+const GRID_PLANE_LINES: usize = 11;
+/// line segments of each of the three circles drawn around a spherical selection
+const CIRCLE_SEGMENTS: usize = 32;
+
+// color indices of lines_shader.wgsl
+const COLOR_BOX: f32 = 0.0;
+const COLOR_X: f32 = 1.0;
+const COLOR_Y: f32 = 2.0;
+const COLOR_Z: f32 = 3.0;
+const COLOR_GRID: f32 = 4.0;
+const COLOR_SELECTION: f32 = 5.0;
+const COLOR_REGION: f32 = 6.0;
+const COLOR_FLOOR: f32 = 7.0;
+const COLOR_EMITTER: f32 = 8.0;
+
+/// helper lines drawn around the unit cube, which is scaled to the bounding volume
+pub struct Gizmos {
+    pub bounding_box: bool,
+    pub axes: bool,
+    pub grid_planes: bool,
+    /// the opposite corners of the enabled simulation regions in world space, set every frame
+    pub regions: Vec<(V3, V3)>,
+    /// the axis and the offset in world space of the plane of the slice view, set every frame
+    pub slice_plane: Option<(usize, f32)>,
+    /// the height in world space of the floor plane while the particles bounce off it,
+    /// set every frame
+    pub floor: Option<f32>,
+    /// the corners in world space of the wind tunnel emitter while it emits, set every frame
+    pub emitter: Option<[V3; 4]>,
+}
+
+impl Gizmos {
+    pub fn new() -> Self {
+        Gizmos {
+            bounding_box: true,
+            axes: true,
+            grid_planes: false,
+            regions: Vec::new(),
+            slice_plane: None,
+            floor: None,
+            emitter: None,
+        }
+    }
+
+    /// vertices and line list indices of all enabled gizmos and the outline of the vector
+    /// selection, which is given in world space and scaled down by `bounding_volume_radius`.
+    /// the bounding box spans `bounding_volume_extents` on either side of the origin
+    pub fn get_lines(
+        &self,
+        marquee: Option<&Marquee>,
+        bounding_volume_radius: f32,
+        bounding_volume_extents: V3,
+    ) -> (Vec<(V3, [f32; 2])>, Vec<u16>) {
+        let half = bounding_volume_extents / bounding_volume_radius;
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut line = |a: V3, b: V3, color: f32| {
+            indices.push(vertices.len() as u16);
+            indices.push(vertices.len() as u16 + 1);
+            vertices.push((a, [color, 0.0]));
+            vertices.push((b, [color, 0.0]));
+        };
+        if self.bounding_box {
+            for (a, b) in box_edges(-half, half) {
+                line(a, b, COLOR_BOX);
+            }
+        }
+        if self.axes {
+            let origin = V3::new(0.0, 0.0, 0.0);
+            line(origin, V3::new(1.0, 0.0, 0.0), COLOR_X);
+            line(origin, V3::new(0.0, 1.0, 0.0), COLOR_Y);
+            line(origin, V3::new(0.0, 0.0, 1.0), COLOR_Z);
+        }
+        if self.grid_planes {
+            // the floor of the bounding volume
+            for i in 0..GRID_PLANE_LINES {
+                let t = i as f32 / (GRID_PLANE_LINES - 1) as f32 * 2.0 - 1.0;
+                let (x, z) = (t * half.x, t * half.z);
+                line(
+                    V3::new(x, -half.y, -half.z),
+                    V3::new(x, -half.y, half.z),
+                    COLOR_GRID,
+                );
+                line(
+                    V3::new(-half.x, -half.y, z),
+                    V3::new(half.x, -half.y, z),
+                    COLOR_GRID,
+                );
+            }
+        }
+        if let Some(height) = self.floor {
+            // a grid across the bounding volume at the height of the floor
+            let y = height / bounding_volume_radius;
+            for i in 0..GRID_PLANE_LINES {
+                let t = i as f32 / (GRID_PLANE_LINES - 1) as f32 * 2.0 - 1.0;
+                let (x, z) = (t * half.x, t * half.z);
+                line(V3::new(x, y, -half.z), V3::new(x, y, half.z), COLOR_FLOOR);
+                line(V3::new(-half.x, y, z), V3::new(half.x, y, z), COLOR_FLOOR);
+            }
+        }
+        if let Some(corners) = self.emitter {
+            let corners = corners.map(|c| c / bounding_volume_radius);
+            for i in 0..4 {
+                line(corners[i], corners[(i + 1) % 4], COLOR_EMITTER);
+            }
+        }
+        for (min, max) in &self.regions {
+            for (a, b) in box_edges(min / bounding_volume_radius, max / bounding_volume_radius) {
+                line(a, b, COLOR_REGION);
+            }
+        }
+        if let Some((axis, offset)) = self.slice_plane {
+            // the outline of the plane across the bounding box, in the color of its axis
+            let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+            let corner = |a: f32, b: f32| {
+                let mut p = V3::new(0.0, 0.0, 0.0);
+                p[axis] = offset / bounding_volume_radius;
+                p[u] = a * half[u];
+                p[v] = b * half[v];
+                p
+            };
+            let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+            for (i, &(a0, b0)) in corners.iter().enumerate() {
+                let (a1, b1) = corners[(i + 1) % 4];
+                line(corner(a0, b0), corner(a1, b1), COLOR_X + axis as f32);
+            }
+        }
+        if let Some(marquee) = marquee {
+            let start = marquee.start / bounding_volume_radius;
+            let end = marquee.end / bounding_volume_radius;
+            match marquee.shape {
+                SelectionShape::Box => {
+                    for (a, b) in box_edges(start, end) {
+                        line(a, b, COLOR_SELECTION);
+                    }
+                }
+                SelectionShape::Sphere => {
+                    let radius = (end - start).magnitude();
+                    let point = |axis: usize, i: usize| {
+                        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                        let mut p = V3::new(0.0, 0.0, 0.0);
+                        p[axis] = angle.cos() * radius;
+                        p[(axis + 1) % 3] = angle.sin() * radius;
+                        start + p
+                    };
+                    for axis in 0..3 {
+                        for i in 0..CIRCLE_SEGMENTS {
+                            line(point(axis, i), point(axis, i + 1), COLOR_SELECTION);
+                        }
+                    }
+                }
+            }
+        }
+        (vertices, indices)
+    }
+}
+
+/// the edges of the axis aligned box with the opposite corners `a` and `b`
+fn box_edges(a: V3, b: V3) -> Vec<(V3, V3)> {
+    let corner = |i: usize| {
+        V3::new(
+            if i & 1 == 0 { a.x } else { b.x },
+            if i & 2 == 0 { a.y } else { b.y },
+            if i & 4 == 0 { a.z } else { b.z },
+        )
+    };
+    // connect every corner with the corners differing in exactly one coordinate
+    let mut edges = Vec::with_capacity(12);
+    for i in 0..8 {
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                edges.push((corner(i), corner(i | bit)));
+            }
+        }
+    }
+    edges
+}
@@ -0,0 +1,70 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, Vector4};
+
+use crate::V3;
+
+/// skips the vector field glyphs outside of the view and thins out the distant ones,
+/// so dense force grids stay interactive
+pub struct GlyphCulling {
+    pub enabled: bool,
+    /// every glyph closer to the camera than this is drawn, beyond it only every 2nd,
+    /// then every 4th ... along each axis of the grid
+    pub full_detail_distance: f32,
+    /// the widest spacing of the drawn glyphs far away from the camera
+    pub max_stride: u32,
+    /// number of glyphs drawn in the last frame
+    pub visible: usize,
+}
+
+impl GlyphCulling {
+    pub fn new() -> Self {
+        GlyphCulling {
+            enabled: true,
+            full_detail_distance: 5.0,
+            max_stride: 8,
+            visible: 0,
+        }
+    }
+
+    /// whether the glyph of grid cell `cell` at `pos` with bounding sphere `radius` is drawn
+    pub fn keeps(&self, view: &GlyphView, cell: [u32; 3], pos: V3, radius: f32) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        if !view.frustum.intersects_sphere(pos, radius) {
+            return false;
+        }
+        let distance = (pos - view.eye).magnitude();
+        // powers of two, so the glyphs kept further away are a subset of the nearer ones
+        let stride = ((distance / self.full_detail_distance).log2().floor().exp2() as u32)
+            .clamp(1, self.max_stride.max(1));
+        cell.iter().all(|i| i % stride == 0)
+    }
+}
+
+/// camera state the glyphs are culled against
+pub struct GlyphView {
+    pub frustum: Frustum,
+    /// camera position in world space
+    pub eye: V3,
+}
+
+/// the six planes bounding the view volume, the normals point inwards
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// extracts the planes from a view projection matrix with depth in [0..1]
+    pub fn from_matrix(m: Matrix4<f32>) -> Self {
+        let (r0, r1, r2, r3) = (m.row(0), m.row(1), m.row(2), m.row(3));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2]
+            .map(|plane| plane / plane.truncate().magnitude().max(f32::EPSILON));
+        Frustum { planes }
+    }
+
+    pub fn intersects_sphere(&self, center: V3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+    }
+}
@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{assets::Mesh, V3};
+
+/// segments around the cone
+const CONE_SEGMENTS: u16 = 8;
+
+/// mirrors `GlyphLodParams` in vector_field_shader.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct GlyphLodParams {
+    /// pixels covered by one world unit at a distance of one unit from the camera
+    pub pixels_per_unit: f32,
+    pub full_arrow_pixels: f32,
+    pub cone_pixels: f32,
+    /// length of the arrow mesh before it is scaled by the vector
+    pub glyph_length: f32,
+    /// the cone and line segment vertices start here in the packed vertex buffer
+    pub cone_first_vertex: u32,
+    pub line_first_vertex: u32,
+    /// 1 draws every glyph as full arrow
+    pub num_lods: u32,
+    pub _padd: u32,
+}
+
+/// simpler meshes for the vector field glyphs that only cover a few pixels on screen,
+/// the variants are packed into one mesh and drawn with one index range each
+pub struct GlyphLod {
+    pub enabled: bool,
+    /// glyphs longer than this on screen are drawn as full arrows
+    pub full_arrow_pixels: f32,
+    /// glyphs longer than this on screen are drawn as cones, shorter ones as line segments
+    pub cone_pixels: f32,
+    /// index range of every variant in the packed mesh
+    pub index_ranges: Vec<Range<u32>>,
+    cone_first_vertex: u32,
+    line_first_vertex: u32,
+    glyph_length: f32,
+}
+
+impl GlyphLod {
+    pub fn new() -> Self {
+        GlyphLod {
+            enabled: true,
+            full_arrow_pixels: 40.0,
+            cone_pixels: 12.0,
+            index_ranges: Vec::new(),
+            cone_first_vertex: u32::MAX,
+            line_first_vertex: u32::MAX,
+            glyph_length: 1.0,
+        }
+    }
+
+    /// `arrow` followed by a cone and a line segment of the same size,
+    /// meshes too large to append to are returned without the variants
+    pub fn pack(&mut self, arrow: &Mesh) -> Mesh {
+        let (min, max) = arrow.vertices.iter().fold(
+            (
+                V3::new(f32::MAX, f32::MAX, f32::MAX),
+                V3::new(f32::MIN, f32::MIN, f32::MIN),
+            ),
+            |(min, max), (p, _)| {
+                (
+                    V3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                    V3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+                )
+            },
+        );
+        let mut mesh = Mesh {
+            vertices: arrow.vertices.clone(),
+            indices: arrow.indices.clone(),
+        };
+        self.glyph_length = (max.y - min.y).max(f32::EPSILON);
+        self.index_ranges.clear();
+        self.index_ranges.push(0..arrow.indices.len() as u32);
+        self.cone_first_vertex = u32::MAX;
+        self.line_first_vertex = u32::MAX;
+        let extra_vertices = CONE_SEGMENTS as usize + 2 + 8;
+        if arrow.vertices.is_empty() || arrow.vertices.len() + extra_vertices > u16::MAX as usize {
+            return mesh;
+        }
+        // the variants take the mean texture coordinate, so they keep the color of the arrow
+        let tex_coord = arrow
+            .vertices
+            .iter()
+            .fold([0.0; 2], |sum, (_, tc)| [sum[0] + tc[0], sum[1] + tc[1]])
+            .map(|sum| sum / arrow.vertices.len() as f32);
+        let radius = max
+            .x
+            .abs()
+            .max(min.x.abs())
+            .max(max.z.abs())
+            .max(min.z.abs());
+
+        self.cone_first_vertex = mesh.vertices.len() as u32;
+        let first = mesh.vertices.len() as u16;
+        let start = mesh.indices.len() as u32;
+        mesh.vertices.push((V3::new(0.0, max.y, 0.0), tex_coord));
+        mesh.vertices.push((V3::new(0.0, min.y, 0.0), tex_coord));
+        for i in 0..CONE_SEGMENTS {
+            let angle = i as f32 / CONE_SEGMENTS as f32 * std::f32::consts::TAU;
+            let p = V3::new(angle.cos() * radius, min.y, angle.sin() * radius);
+            mesh.vertices.push((p, tex_coord));
+        }
+        for i in 0..CONE_SEGMENTS {
+            let (a, b) = (first + 2 + i, first + 2 + (i + 1) % CONE_SEGMENTS);
+            // side towards the tip and the base
+            mesh.indices
+                .extend_from_slice(&[a, b, first, b, a, first + 1]);
+        }
+        self.index_ranges.push(start..mesh.indices.len() as u32);
+
+        self.line_first_vertex = mesh.vertices.len() as u32;
+        let first = mesh.vertices.len() as u16;
+        let start = mesh.indices.len() as u32;
+        // two crossed thin quads along the vector
+        let width = radius / 3.0;
+        for side in [V3::new(width, 0.0, 0.0), V3::new(0.0, 0.0, width)] {
+            for y in [min.y, max.y] {
+                for s in [-1.0, 1.0] {
+                    mesh.vertices
+                        .push((side * s + V3::new(0.0, y, 0.0), tex_coord));
+                }
+            }
+        }
+        for quad in [first, first + 4] {
+            mesh.indices.extend_from_slice(&[
+                quad,
+                quad + 1,
+                quad + 2,
+                quad + 1,
+                quad + 3,
+                quad + 2,
+            ]);
+        }
+        self.index_ranges.push(start..mesh.indices.len() as u32);
+        mesh
+    }
+
+    /// index ranges of the variants in use
+    pub fn drawn_ranges(&self) -> &[Range<u32>] {
+        let num_lods = if self.enabled {
+            self.index_ranges.len()
+        } else {
+            1
+        };
+        &self.index_ranges[..num_lods.min(self.index_ranges.len())]
+    }
+
+    pub fn params(&self, pixels_per_unit: f32) -> GlyphLodParams {
+        GlyphLodParams {
+            pixels_per_unit,
+            full_arrow_pixels: self.full_arrow_pixels,
+            cone_pixels: self.cone_pixels,
+            glyph_length: self.glyph_length,
+            cone_first_vertex: self.cone_first_vertex,
+            line_first_vertex: self.line_first_vertex,
+            num_lods: self.drawn_ranges().len() as u32,
+            _padd: 0,
+        }
+    }
+}
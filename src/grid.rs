@@ -1,8 +1,12 @@
+use crate::glyph_culling::{GlyphCulling, GlyphView};
 use crate::V3;
-use cgmath::{MetricSpace, Vector3};
+use cgmath::{InnerSpace, MetricSpace, Vector3, Zero};
 use egui::ahash::HashSet;
+use rayon::prelude::*;
+use std::ops::Mul;
 
 /// AABB
+#[derive(Clone)]
 pub struct Bounds {
     /// bottom left front corner
     pub pos: Vector3<f32>,
@@ -29,23 +33,24 @@ impl Bounds {
     pub fn back(&self) -> f32 {
         self.pos.z + self.dir.z
     }
-    pub fn set_centered(&mut self, bounding_box_side_length: f32) {
-        let bbs = bounding_box_side_length;
-        self.pos = V3::new(-bbs, -bbs, -bbs) * 0.5;
-        self.dir = V3::new(bbs, bbs, bbs);
+    /// a box of `size` along every axis centered at the origin
+    pub fn set_centered(&mut self, size: V3) {
+        self.pos = -0.5 * size;
+        self.dir = size;
     }
     pub fn center(&self) -> V3 {
         self.pos + 0.5 * self.dir
     }
 }
 
+#[derive(Clone)]
 pub struct Grid<T> {
     pub grid: Vec<T>,
     size: Vector3<u32>,
     pub bounds: Bounds,
 }
 
-impl<T: Clone> Grid<T> {
+impl<T: Clone + Sync> Grid<T> {
     pub fn new_uniform(n_x: usize, n_y: usize, n_z: usize, bounds: Bounds, t: &T) -> Self {
         let cap = n_x * n_y * n_z;
         let mut grid = Vec::with_capacity(cap);
@@ -62,6 +67,112 @@ impl<T: Clone> Grid<T> {
             bounds,
         }
     }
+
+    pub fn num_instances(&self) -> usize {
+        (self.size.x * self.size.y * self.size.z) as usize
+    }
+
+    fn cell(&self, ix: usize) -> [i64; 3] {
+        let (sy, sz) = (self.size.y as usize, self.size.z as usize);
+        [ix / (sy * sz), (ix / sz) % sy, ix % sz].map(|c| c as i64)
+    }
+
+    /// number of cells along every axis
+    pub fn dimensions(&self) -> [usize; 3] {
+        [self.size.x, self.size.y, self.size.z].map(|n| n as usize)
+    }
+
+    /// the center of the cell with index `ix`
+    pub fn position(&self, ix: usize) -> V3 {
+        let ix = ix as u32;
+        let i_x = ix / (self.size.y * self.size.z);
+        let i_y = (ix / self.size.z) % self.size.y;
+        let i_z = ix % self.size.z;
+        V3::new(
+            self.bounds.left() + self.bounds.dir.x * (((i_x as f32) + 0.5) / (self.size.x as f32)),
+            self.bounds.bottom()
+                + self.bounds.dir.y * (((i_y as f32) + 0.5) / (self.size.y as f32)),
+            self.bounds.front() + self.bounds.dir.z * (((i_z as f32) + 0.5) / (self.size.z as f32)),
+        )
+    }
+
+    pub fn get_positions(&self) -> Vec<[f32; 4]> {
+        (0..self.num_instances())
+            .into_par_iter()
+            .map(|ix| self.position(ix).extend(1.0).into())
+            .collect()
+    }
+}
+
+impl<T> Grid<T>
+where
+    T: Copy + Send + Sync + Zero + Mul<f32, Output = T>,
+{
+    /// replaces the values with `values` of a grid of `size` cells spanning the same bounds,
+    /// trilinearly interpolated between the cell centers when the sizes differ
+    pub fn resample_from(&mut self, size: [usize; 3], values: &[T]) {
+        if size == self.dimensions() {
+            self.grid.copy_from_slice(values);
+            return;
+        }
+        let dimensions = self.dimensions();
+        let source =
+            |cell: [usize; 3]| values[cell[0] * size[1] * size[2] + cell[1] * size[2] + cell[2]];
+        self.grid = (0..self.num_instances())
+            .into_par_iter()
+            .map(|ix| {
+                let cell = self.cell(ix);
+                // position of the cell center in cells of the source grid
+                let x: [f32; 3] = std::array::from_fn(|axis| {
+                    ((cell[axis] as f32 + 0.5) / dimensions[axis] as f32 * size[axis] as f32 - 0.5)
+                        .clamp(0.0, (size[axis] - 1) as f32)
+                });
+                let lo: [usize; 3] = std::array::from_fn(|axis| {
+                    (x[axis] as usize).min(size[axis].saturating_sub(2))
+                });
+                let t: [f32; 3] = std::array::from_fn(|axis| x[axis] - lo[axis] as f32);
+                let mut sum = T::zero();
+                for corner in 0..8 {
+                    let mut weight = 1.0;
+                    let corner_cell: [usize; 3] = std::array::from_fn(|axis| {
+                        let upper = corner >> axis & 1 == 1;
+                        weight *= if upper { t[axis] } else { 1.0 - t[axis] };
+                        (lo[axis] + upper as usize).min(size[axis] - 1)
+                    });
+                    sum = sum + source(corner_cell) * weight;
+                }
+                sum
+            })
+            .collect();
+    }
+
+    /// changes the number of cells along every axis, the values are resampled
+    /// to span the same bounds
+    pub fn resize(&mut self, size: [usize; 3]) {
+        let old_size = self.dimensions();
+        if size == old_size {
+            return;
+        }
+        let values = std::mem::take(&mut self.grid);
+        self.size = Vector3::new(size[0] as u32, size[1] as u32, size[2] as u32);
+        self.resample_from(old_size, &values);
+    }
+}
+
+/// floats per vector field instance: position, direction and color
+const INSTANCE_FLOATS: usize = 12;
+
+/// highlights the vector when it is selected
+fn instance_raw(pos: &[f32; 4], dir: &V3, selected: bool) -> [f32; INSTANCE_FLOATS] {
+    if selected {
+        [
+            pos[0], pos[1], pos[2], pos[3], dir.x, dir.y, dir.z, 1.0, 1.0, 1.0, 1.0, 1.0,
+        ]
+    } else {
+        [
+            pos[0], pos[1], pos[2], pos[3], dir.x, dir.y, dir.z, 1.0, 0.5, 0.5, 0.5, 0.2,
+        ]
+    }
 }
 
 impl Grid<Vector3<f32>> {
@@ -94,18 +205,13 @@ impl Grid<Vector3<f32>> {
         }
     }
 
-    pub fn get_force_vectors(&self) -> Vec<[f32; 4]> {
+    pub fn get_force_vectors(&self, strength: f32) -> Vec<[f32; 4]> {
         self.grid
-            .iter()
-            .copied()
-            .map(|v| [v.x, v.y, v.z, 1.0])
+            .par_iter()
+            .map(|v| [v.x * strength, v.y * strength, v.z * strength, 1.0])
             .collect()
     }
 
-    pub fn num_instances(&self) -> usize {
-        (self.size.x * self.size.y * self.size.z) as usize
-    }
-
     pub fn get_indices(&self, center: V3, radius: f32) -> Vec<usize> {
         let mut res = Vec::new();
         for (ix, (pos, _dir)) in self.get_instances().iter().enumerate() {
@@ -116,31 +222,261 @@ impl Grid<Vector3<f32>> {
         res
     }
 
-    pub fn get_positions(&self) -> Vec<[f32; 4]> {
-        let mut res = Vec::new();
-        for i_x in 0..self.size.x {
-            for i_y in 0..self.size.y {
-                for i_z in 0..self.size.z {
-                    let p = [
-                        self.bounds.left()
-                            + self.bounds.dir.x * (((i_x as f32) + 0.5) / (self.size.x as f32)),
-                        self.bounds.bottom()
-                            + self.bounds.dir.y * (((i_y as f32) + 0.5) / (self.size.y as f32)),
-                        self.bounds.front()
-                            + self.bounds.dir.z * (((i_z as f32) + 0.5) / (self.size.z as f32)),
-                        1.0,
-                    ];
-                    res.push(p);
+    /// index of the cell at the integer coordinates `cell`, clamped to the grid
+    fn clamped_index(&self, cell: [i64; 3]) -> usize {
+        let size = [self.size.x, self.size.y, self.size.z].map(|n| n as i64);
+        let [x, y, z] = std::array::from_fn(|axis| cell[axis].clamp(0, size[axis] - 1));
+        (x * size[1] * size[2] + y * size[2] + z) as usize
+    }
+
+    /// distance between two cell centers along every axis
+    fn spacing(&self) -> V3 {
+        V3::new(
+            self.bounds.dir.x / self.size.x as f32,
+            self.bounds.dir.y / self.size.y as f32,
+            self.bounds.dir.z / self.size.z as f32,
+        )
+    }
+
+    /// central difference of `values` along `axis` at the cell `ix`, one sided at the edges
+    fn derivative<T>(&self, values: &[T], ix: usize, axis: usize) -> T
+    where
+        T: Copy + std::ops::Sub<Output = T> + std::ops::Div<f32, Output = T>,
+    {
+        let cell = self.cell(ix);
+        let (mut lo, mut hi) = (cell, cell);
+        lo[axis] -= 1;
+        hi[axis] += 1;
+        let (lo, hi) = (self.clamped_index(lo), self.clamped_index(hi));
+        let steps = (self.cell(hi)[axis] - self.cell(lo)[axis]).max(1) as f32;
+        (values[hi] - values[lo]) / (steps * self.spacing()[axis])
+    }
+
+    /// blurs the field with a gaussian of `sigma` cells, separately along every axis
+    pub fn gaussian_smooth(&mut self, sigma: f32) {
+        let radius = (sigma * 2.0).ceil().max(1.0) as i64;
+        let weights: Vec<f32> = (-radius..=radius)
+            .map(|d| (-(d * d) as f32 / (2.0 * sigma * sigma)).exp())
+            .collect();
+        let total: f32 = weights.iter().sum();
+        for axis in 0..3 {
+            let blurred = (0..self.grid.len())
+                .into_par_iter()
+                .map(|ix| {
+                    let cell = self.cell(ix);
+                    (-radius..=radius)
+                        .zip(&weights)
+                        .map(|(d, w)| {
+                            let mut c = cell;
+                            c[axis] += d;
+                            self.grid[self.clamped_index(c)] * *w
+                        })
+                        .sum::<V3>()
+                        / total
+                })
+                .collect();
+            self.grid = blurred;
+        }
+    }
+
+    /// scales every vector to unit length, zero vectors stay zero
+    pub fn normalize(&mut self) {
+        self.grid.par_iter_mut().for_each(|v| {
+            if v.magnitude2() > 0.0 {
+                *v = v.normalize();
+            }
+        });
+    }
+
+    /// the curl of the field in every cell
+    pub fn curl(&self) -> Vec<V3> {
+        (0..self.grid.len())
+            .into_par_iter()
+            .map(|ix| {
+                let dx = self.derivative(&self.grid, ix, 0);
+                let dy = self.derivative(&self.grid, ix, 1);
+                let dz = self.derivative(&self.grid, ix, 2);
+                V3::new(dy.z - dz.y, dz.x - dx.z, dx.y - dy.x)
+            })
+            .collect()
+    }
+
+    /// the divergence of the field in every cell
+    pub fn divergence(&self) -> Vec<f32> {
+        let components: [Vec<f32>; 3] =
+            std::array::from_fn(|axis| self.grid.iter().map(|v| v[axis]).collect());
+        (0..self.grid.len())
+            .into_par_iter()
+            .map(|ix| {
+                (0..3)
+                    .map(|axis| self.derivative(&components[axis], ix, axis))
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// value of the cell at `cell`, zero outside of the grid
+    fn value_or_zero(&self, values: &[f32], cell: [i64; 3]) -> f32 {
+        let size = [self.size.x, self.size.y, self.size.z].map(|n| n as i64);
+        if (0..3).all(|axis| (0..size[axis]).contains(&cell[axis])) {
+            values[self.clamped_index(cell)]
+        } else {
+            0.0
+        }
+    }
+
+    /// removes the divergent part of the field like the pressure projection of a fluid
+    /// solver: solves for the pressure with `iterations` jacobi steps and subtracts its gradient.
+    /// the pressure is zero outside of the grid, so the flow can leave through the boundary
+    pub fn project_divergence_free(&mut self, iterations: usize) {
+        let divergence = self.divergence();
+        let h = self.spacing();
+        // the laplacian matching the central differences reaches two cells in every direction
+        let weights = [h.x, h.y, h.z].map(|h| 1.0 / (4.0 * h * h));
+        let diagonal: f32 = weights.iter().sum::<f32>() * 2.0;
+        let offset = |cell: [i64; 3], axis: usize, d: i64| {
+            let mut c = cell;
+            c[axis] += d;
+            c
+        };
+        let mut pressure = vec![0.0f32; self.grid.len()];
+        for _ in 0..iterations {
+            pressure = (0..self.grid.len())
+                .into_par_iter()
+                .map(|ix| {
+                    let cell = self.cell(ix);
+                    let neighbours: f32 = (0..3)
+                        .map(|axis| {
+                            (self.value_or_zero(&pressure, offset(cell, axis, -2))
+                                + self.value_or_zero(&pressure, offset(cell, axis, 2)))
+                                * weights[axis]
+                        })
+                        .sum();
+                    (neighbours - divergence[ix]) / diagonal
+                })
+                .collect();
+        }
+        let gradient: Vec<V3> = (0..self.grid.len())
+            .into_par_iter()
+            .map(|ix| {
+                let cell = self.cell(ix);
+                V3::from(std::array::from_fn(|axis| {
+                    (self.value_or_zero(&pressure, offset(cell, axis, 1))
+                        - self.value_or_zero(&pressure, offset(cell, axis, -1)))
+                        / (2.0 * h[axis])
+                }))
+            })
+            .collect();
+        self.grid
+            .par_iter_mut()
+            .zip(gradient)
+            .for_each(|(v, g)| *v -= g);
+    }
+
+    /// makes the field mirror symmetric about the plane through the grid center normal to
+    /// `axis`: the half on the negative side, or on the positive side with `from_upper`,
+    /// is reflected onto the other half. vectors on the plane lose their component along `axis`
+    pub fn mirror(&mut self, axis: usize, from_upper: bool) {
+        let n = self.dimensions()[axis] as i64;
+        self.grid = (0..self.grid.len())
+            .into_par_iter()
+            .map(|ix| {
+                let cell = self.cell(ix);
+                let mut mirrored = cell;
+                mirrored[axis] = n - 1 - cell[axis];
+                if cell == mirrored {
+                    let mut v = self.grid[ix];
+                    v[axis] = 0.0;
+                    return v;
                 }
+                if (cell[axis] < mirrored[axis]) != from_upper {
+                    return self.grid[ix];
+                }
+                let mut v = self.grid[self.clamped_index(mirrored)];
+                v[axis] = -v[axis];
+                v
+            })
+            .collect();
+    }
+
+    /// turns the field by 90 degrees about `axis` through the grid center, counterclockwise
+    /// looking against the axis. the grid needs as many cells along both other axes,
+    /// else the field is left as it is and false is returned
+    pub fn rotate_quarter(&mut self, axis: usize) -> bool {
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let dimensions = self.dimensions();
+        if dimensions[a] != dimensions[b] {
+            return false;
+        }
+        let n = dimensions[a] as i64;
+        self.grid = (0..self.grid.len())
+            .into_par_iter()
+            .map(|ix| {
+                let cell = self.cell(ix);
+                // the cell turned onto this one
+                let mut source = cell;
+                source[a] = cell[b];
+                source[b] = n - 1 - cell[a];
+                let v = self.grid[self.clamped_index(source)];
+                let mut res = v;
+                res[a] = -v[b];
+                res[b] = v[a];
+                res
+            })
+            .collect();
+        true
+    }
+
+    /// repeats the block of `size` cells starting at the cell `origin` across the whole grid,
+    /// the block is clamped to the grid
+    pub fn tile(&mut self, origin: [usize; 3], size: [usize; 3]) {
+        let dimensions = self.dimensions();
+        let origin: [usize; 3] = std::array::from_fn(|axis| origin[axis].min(dimensions[axis] - 1));
+        let size: [i64; 3] =
+            std::array::from_fn(|axis| size[axis].clamp(1, dimensions[axis] - origin[axis]) as i64);
+        self.grid = (0..self.grid.len())
+            .into_par_iter()
+            .map(|ix| {
+                let cell = self.cell(ix);
+                let source = std::array::from_fn(|axis| {
+                    let origin = origin[axis] as i64;
+                    origin + (cell[axis] - origin).rem_euclid(size[axis])
+                });
+                self.grid[self.clamped_index(source)]
+            })
+            .collect();
+    }
+
+    /// indices of the cells sharing a face with the cell at `ix`
+    pub fn neighbours(&self, ix: usize) -> Vec<usize> {
+        let (sy, sz) = (self.size.y as usize, self.size.z as usize);
+        let cell = [ix / (sy * sz), (ix / sz) % sy, ix % sz];
+        let size = [self.size.x as usize, sy, sz];
+        let strides = [sy * sz, sz, 1];
+        let mut res = Vec::with_capacity(6);
+        for axis in 0..3 {
+            if cell[axis] > 0 {
+                res.push(ix - strides[axis]);
+            }
+            if cell[axis] + 1 < size[axis] {
+                res.push(ix + strides[axis]);
             }
         }
         res
     }
 
+    /// coordinates along `axis` of the planes through the cell centers
+    pub fn plane_coordinates(&self, axis: usize) -> Vec<f32> {
+        let n = self.size[axis];
+        (0..n)
+            .map(|i| self.bounds.pos[axis] + self.bounds.dir[axis] * ((i as f32 + 0.5) / n as f32))
+            .collect()
+    }
+
     pub fn get_instances(&self) -> Vec<(V3, V3)> {
         let positions = self.get_positions();
         positions
-            .iter()
+            .par_iter()
             .zip(&self.grid)
             .map(|(pos, dir)| (V3::new(pos[0], pos[1], pos[2]), *dir))
             .collect()
@@ -150,22 +486,39 @@ impl Grid<Vector3<f32>> {
         let positions = self.get_positions();
         let index_set = HashSet::from_iter(selected_indices.iter());
         positions
-            .iter()
+            .par_iter()
+            .zip(&self.grid)
+            .enumerate()
+            .flat_map_iter(|(ix, (pos, dir))| instance_raw(pos, dir, index_set.contains(&ix)))
+            .collect()
+    }
+
+    /// the instances of the glyphs `culling` keeps, selected glyphs are only culled
+    /// outside of the view, returns the instance data and the number of instances
+    pub fn get_visible_instances_raw(
+        &self,
+        selected_indices: &[usize],
+        culling: &GlyphCulling,
+        view: &GlyphView,
+    ) -> (Vec<f32>, usize) {
+        let positions = self.get_positions();
+        let index_set = HashSet::from_iter(selected_indices.iter());
+        let instances: Vec<f32> = positions
+            .par_iter()
             .zip(&self.grid)
             .enumerate()
-            .flat_map(|(ix, (pos, dir))| {
-                if index_set.contains(&ix) {
-                    [
-                        pos[0], pos[1], pos[2], pos[3], dir.x, dir.y, dir.z, 1.0, 1.0, 1.0, 1.0,
-                        1.0,
-                    ]
-                } else {
-                    [
-                        pos[0], pos[1], pos[2], pos[3], dir.x, dir.y, dir.z, 1.0, 0.5, 0.5, 0.5,
-                        0.2,
-                    ]
+            .filter(|(ix, (pos, dir))| {
+                let pos = V3::new(pos[0], pos[1], pos[2]);
+                // the arrow mesh is scaled by 0.3 across and 0.1 * length along the vector
+                let radius = 0.3 + 0.1 * dir.magnitude();
+                if index_set.contains(ix) {
+                    return !culling.enabled || view.frustum.intersects_sphere(pos, radius);
                 }
+                culling.keeps(view, self.cell(*ix).map(|c| c as u32), pos, radius)
             })
-            .collect()
+            .flat_map_iter(|(ix, (pos, dir))| instance_raw(pos, dir, index_set.contains(&ix)))
+            .collect();
+        let num_instances = instances.len() / INSTANCE_FLOATS;
+        (instances, num_instances)
     }
 }
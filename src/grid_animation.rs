@@ -0,0 +1,64 @@
+use crate::{grid::Grid, V3};
+
+/// a snapshot of a force grid layer at a point of the animation loop
+pub struct GridKeyframe {
+    /// seconds into the loop
+    pub time: f32,
+    pub grid: Grid<V3>,
+}
+
+/// plays back the keyframes of the force grid layers in a loop over simulated time,
+/// the compute shader blends between the two keyframes around the current time
+pub struct GridAnimation {
+    pub enabled: bool,
+    /// seconds until the loop starts over
+    pub duration: f32,
+    /// seconds into the loop
+    pub time: f32,
+}
+
+impl GridAnimation {
+    pub fn new() -> Self {
+        GridAnimation {
+            enabled: false,
+            duration: 10.0,
+            time: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, delta_t: f32) {
+        if self.enabled {
+            self.time = (self.time + delta_t).rem_euclid(self.duration.max(0.01));
+        }
+    }
+
+    /// the keyframes before and after the current time and how far the time is between them,
+    /// `keyframes` have to be sorted by time
+    pub fn blend<'a>(
+        &self,
+        keyframes: &'a [GridKeyframe],
+    ) -> Option<(&'a GridKeyframe, &'a GridKeyframe, f32)> {
+        let duration = self.duration.max(0.01);
+        // before the first keyframe the last one of the previous loop is blended from
+        let prev = keyframes
+            .iter()
+            .rposition(|k| k.time <= self.time)
+            .unwrap_or(keyframes.len().checked_sub(1)?);
+        let next = (prev + 1) % keyframes.len();
+        let (from, to) = (&keyframes[prev], &keyframes[next]);
+        let mut span = (to.time - from.time).rem_euclid(duration);
+        if span == 0.0 {
+            span = duration;
+        }
+        let blend = (self.time - from.time).rem_euclid(duration) / span;
+        Some((from, to, blend.clamp(0.0, 1.0)))
+    }
+}
+
+/// inserts a keyframe keeping `keyframes` sorted, a keyframe at the same time is replaced
+pub fn insert_keyframe(keyframes: &mut Vec<GridKeyframe>, keyframe: GridKeyframe) {
+    match keyframes.binary_search_by(|k| k.time.total_cmp(&keyframe.time)) {
+        Ok(i) => keyframes[i] = keyframe,
+        Err(i) => keyframes.insert(i, keyframe),
+    }
+}
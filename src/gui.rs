@@ -1,46 +1,158 @@
-use cgmath::Vector2;
+use cgmath::{InnerSpace, Vector2};
 use egui::*;
-use egui_plot::{Line, Plot};
+use egui_plot::{
+    Bar, BarChart, Legend, Line, LineStyle, Plot, PlotBounds, PlotPoint, PlotPoints, Points,
+};
 use instant::Instant;
 
-use crate::{camera::Camera, cursor::Falloff, poly7::Poly7, App, SimParams};
+use crate::{
+    adapters::AdapterEntry,
+    assets::{AssetManager, AssetSlot},
+    auto_scale::AutoScale,
+    boundary_animation::BoundaryAnimationMode,
+    brush::{BrushOrientation, BrushShape},
+    camera::{Camera, CameraMode},
+    colormap::{ColorMap, ColorRamp},
+    compute::{MAX_SUBSTEPS, WORKGROUP_SIZES},
+    config::{Config, FullscreenMode, UiTheme, DEFAULT_FONT_SIZE},
+    cursor::{Cursor, DepthMode, Falloff, SelectionOp, SelectionShape, StirMode},
+    cutoff_sphere::CutoffAnchor,
+    diagnostics::{Diagnostics, NUM_SPEED_BINS},
+    field_expression::FUNCTIONS,
+    force_curve::{
+        insert_knot, move_knot, ForceCurve, SplineKind, MAX_LUT_RESOLUTION, MIN_KNOTS,
+        MIN_LUT_RESOLUTION,
+    },
+    gizmos::Gizmos,
+    glyph_lod::GlyphLod,
+    i18n::{Language, Translations},
+    input::GamepadInput,
+    inspector::{InspectedBuffer, Inspector, MAX_INSPECTED_ELEMENTS},
+    material::MATERIAL_PRESETS,
+    memory::format_bytes,
+    palette::Palette,
+    particle_brush::ParticleBrushMode,
+    particle_sort::ParticleSort,
+    poly7::Poly7,
+    precision::ParticlePrecision,
+    presets::PRESETS,
+    reactions::{Reaction, MAX_REACTION_RULES},
+    recorder::RecordOutput,
+    regions::{Region, MAX_REGIONS},
+    remote::{RemoteControl, PARAM_NAMES},
+    render_scale::{MAX_RENDER_SCALE, MIN_RENDER_SCALE},
+    renderer::{ParticleRenderParams, Renderer},
+    scene::reproducibility_hash,
+    sim_params::{
+        Confinement, ForceAccumulation, ForceGridBoundary, ForceGridInterpolation, ForceMode,
+        MassCoupling, MatrixSymmetry,
+    },
+    slice_view::{SliceView, SLICE_VIEW_SIZE},
+    startup::{Launcher, QualityPreset, StartupConfig},
+    velocity_ops::VelocityOp,
+    zero_v3, App, SimParams, MAX_NUM_PARTICLES, V3,
+};
+
+/// pixels around a sample point of the polynome plot that start dragging it
+const POLY_POINT_GRAB_RADIUS: f32 = 10.0;
+/// the magnitude labels are drawn for this many of the highlighted vectors at most
+const MAX_MAGNITUDE_LABELS: usize = 256;
+/// the english names of the particle types, translated for the controls
+const ELEMENT_NAMES: [&str; 5] = ["Earth", "Water", "Fire", "Air", "Ether"];
 
 pub struct Gui {
     pub winit_state: egui_winit::State,
-    pub scale_factor: f32,
     pub exit_app: bool,
+    /// the controls are shown in a window of their own instead of over the particles
+    pub detach_panel: bool,
+    /// the window settings, the main loop applies changes to them
+    pub config: Config,
+    /// the main loop writes `config` back to its file
+    pub save_config: bool,
     gui_mode: GuiMode,
+    /// the texts of the controls in the language of the config
+    translations: Translations,
     element_text: [String; 5],
     last_update_inst: Instant,
     last_cursor: Option<Pos2>,
     poly_index: usize,
     copy_poly: Option<Poly7>,
+    /// parameters of the operations on the vector selection
+    selection_constant: [f32; 3],
+    selection_scale: f32,
+    selection_axis: usize,
+    selection_angle: f32,
+    /// parameters of the vector field operations
+    smooth_sigma: f32,
+    projection_iterations: usize,
+    /// the axis the field is mirrored along and rotated about
+    field_op_axis: usize,
+    /// the positive half of the field is mirrored onto the negative one
+    mirror_from_upper: bool,
+    /// first cell and size of the block repeated across the field
+    tile_origin: [usize; 3],
+    tile_size: [usize; 3],
+    /// sample point of the polynome plot being dragged
+    dragged_poly_point: Option<usize>,
+    /// knot of the selected spline force curve being dragged
+    dragged_knot: Option<usize>,
+    /// knot of the selected spline force curve edited below its plot
+    selected_knot: Option<usize>,
+    /// stop of the age color ramp edited with the color button
+    color_stop: usize,
+    poly_snap: f32,
+    /// the sample points are clamped to plus minus this value
+    poly_limit: f32,
+    /// particle texture atlas for the icons of the type legend, loaded on first use
+    atlas: Option<TextureHandle>,
+    /// the magnitudes of the brushed or selected vectors are written next to them
+    magnitude_labels: bool,
+    /// particle count and force grid dimensions whose buffer sizes the memory panel
+    /// predicts, the current ones until they are edited
+    planned_memory: Option<(usize, [u32; 3])>,
+    /// the cut off distance slider sets the one of every pair of types
+    link_cut_offs: bool,
 }
 
 impl Gui {
-    pub fn new(
-        winit_window: &winit::window::Window,
-        event_loop: &winit::event_loop::EventLoop<()>,
-    ) -> Self {
+    pub fn new(event_loop: &winit::event_loop::EventLoop<()>, config: Config) -> Self {
         let last_update_inst = Instant::now();
         let winit_state = egui_winit::State::new(ViewportId::ROOT, event_loop, None, None);
-        let element_text = [
-            String::from("Earth"),
-            String::from("Water"),
-            String::from("Fire"),
-            String::from("Air"),
-            String::from("Ether"),
-        ];
+        let translations = Translations::new(config.language);
+        let element_text = ELEMENT_NAMES.map(|name| String::from(translations.tr(name)));
         Gui {
             winit_state,
             gui_mode: GuiMode::Main,
-            scale_factor: winit_window.scale_factor() as f32,
             last_update_inst,
             last_cursor: None,
             exit_app: false,
+            detach_panel: false,
+            config,
+            save_config: false,
+            translations,
             element_text,
             poly_index: 0,
             copy_poly: None,
+            selection_constant: [0.0, 1.0, 0.0],
+            selection_scale: 2.0,
+            selection_axis: 1,
+            selection_angle: 90.0,
+            smooth_sigma: 1.0,
+            projection_iterations: 200,
+            field_op_axis: 0,
+            mirror_from_upper: false,
+            tile_origin: [0; 3],
+            tile_size: [4; 3],
+            dragged_poly_point: None,
+            dragged_knot: None,
+            selected_knot: None,
+            color_stop: 0,
+            poly_snap: 0.0,
+            poly_limit: 10.0,
+            atlas: None,
+            magnitude_labels: false,
+            planned_memory: None,
+            link_cut_offs: true,
         }
     }
 
@@ -50,34 +162,364 @@ impl Gui {
         winit_window: &winit::window::Window,
         app: &mut App,
     ) -> FullOutput {
+        self.apply_scale(ctx, winit_window);
         let input = self.winit_state.take_egui_input(winit_window);
+        self.show(ctx, input, app)
+    }
+
+    /// physical pixels per point of the controls in `winit_window`
+    pub fn pixels_per_point(&self, winit_window: &winit::window::Window) -> f32 {
+        self.config
+            .pixels_per_point
+            .unwrap_or(winit_window.scale_factor() as f32)
+    }
+
+    /// zooms the controls to the configured pixels per point,
+    /// the mouse positions are converted with it from the next frame on
+    fn apply_scale(&mut self, ctx: &Context, winit_window: &winit::window::Window) {
+        let zoom_factor = self.pixels_per_point(winit_window) / winit_window.scale_factor() as f32;
+        if ctx.zoom_factor() != zoom_factor {
+            ctx.set_zoom_factor(zoom_factor);
+        }
+        self.winit_state.update_pixels_per_point(ctx, winit_window);
+    }
+
+    /// the theme, window opacity and font size of the config
+    fn apply_style(&self, ctx: &Context) {
+        let config = &self.config;
+        let mut style = Style {
+            visuals: match config.theme {
+                UiTheme::Dark => Visuals::dark(),
+                UiTheme::Light => Visuals::light(),
+            },
+            ..Style::default()
+        };
+        let fill = style.visuals.window_fill;
+        style.visuals.window_fill = Color32::from_rgba_unmultiplied(
+            fill.r(),
+            fill.g(),
+            fill.b(),
+            (config.window_opacity * 255.0) as u8,
+        );
+        let font_scale = config.font_size / DEFAULT_FONT_SIZE;
+        for font_id in style.text_styles.values_mut() {
+            font_id.size *= font_scale;
+        }
+        if *ctx.style() != style {
+            ctx.set_style(style);
+        }
+    }
+
+    /// the controls for `input` of the window they are shown in,
+    /// a detached panel fills its window
+    pub fn show(&mut self, ctx: &Context, input: RawInput, app: &mut App) -> FullOutput {
+        self.apply_style(ctx);
         ctx.begin_frame(input);
+        if ctx.input(|i| i.key_pressed(Key::F1)) {
+            self.config.show_gui = !self.config.show_gui;
+        }
+        if self.detach_panel {
+            CentralPanel::default().show(ctx, |ui| {
+                ScrollArea::vertical().show(ui, |ui| self.contents(ui, app));
+            });
+        } else if self.config.show_gui {
+            Window::new("Particles")
+                .default_size(Vec2::new(300.0, 100.0))
+                .show(ctx, |ui| self.contents(ui, app));
+        }
+        // a detached panel does not cover the particles
+        if self.magnitude_labels && !self.detach_panel {
+            Self::draw_magnitude_labels(ctx, app);
+        }
+        // the slice texture is registered with the renderer of the main window only
+        if app.renderer.slice_view.enabled && !self.detach_panel {
+            Self::show_slice_view(ctx, app);
+        }
+        self.last_update_inst = Instant::now();
+        ctx.end_frame()
+    }
+
+    /// writes the magnitude of the highlighted vectors of the active layer
+    /// over the particles, projected through the camera
+    fn draw_magnitude_labels(ctx: &Context, app: &mut App) {
+        let painter =
+            ctx.layer_painter(LayerId::new(Order::Background, Id::new("magnitude labels")));
+        let screen = ctx.screen_rect();
+        let font = TextStyle::Small.resolve(&ctx.style());
+        let grid = app.psys.force_grid();
+        let camera = &mut app.renderer.camera;
+        // the selection is stale if the grid was replaced by a smaller one
+        let indices: Vec<usize> = camera
+            .cursor
+            .highlighted_vectors()
+            .iter()
+            .copied()
+            .filter(|ix| *ix < grid.grid.len())
+            .take(MAX_MAGNITUDE_LABELS)
+            .collect();
+        for ix in indices {
+            let Some((x, y)) = camera.project(grid.position(ix)) else {
+                continue;
+            };
+            let pos = screen.min + Vec2::new(x * screen.width(), y * screen.height());
+            if !screen.contains(pos) {
+                continue;
+            }
+            painter.text(
+                pos,
+                Align2::CENTER_BOTTOM,
+                format!("{:.2}", grid.grid[ix].magnitude()),
+                font.clone(),
+                Color32::WHITE,
+            );
+        }
+    }
+
+    fn contents(&mut self, ui: &mut Ui, app: &mut App) {
+        if self.translations.language != self.config.language {
+            self.set_language(self.config.language);
+        }
+        self.last_cursor = ui.input(|i| i.pointer.interact_pos());
+        if let Some(mouse_pos) = self.last_cursor {
+            ui.label(format!(
+                "Mouse Position: ({:.1},{:.1})",
+                mouse_pos.x, mouse_pos.y
+            ));
+        }
+        if let Some(safe_mode) = app.safe_mode {
+            ui.colored_label(Color32::YELLOW, safe_mode.description());
+        }
+        self.edit_window_layout(ui);
+        CollapsingHeader::new(self.translations.tr("GUI settings"))
+            .show(ui, |ui| self.edit_gui_settings(ui));
+        match self.gui_mode {
+            GuiMode::Main => self.main(ui, app),
+            GuiMode::Cursor => self.vector_field(ui, app),
+        }
+
+        for (kind, error) in &app.shader_watcher.errors {
+            ui.colored_label(Color32::RED, format!("{}: {}", kind.file_name(), error));
+        }
+
+        let elapsed = self.last_update_inst.elapsed();
+        ui.label(format!("Frametime: {:.2?}", elapsed));
+        ui.label(format!("Simulated time: {:.2} s", app.compute.sim_time));
+        ui.label(format!(
+            "CPU jobs: {:.2?} on {} threads",
+            app.jobs.frame_time,
+            app.jobs.num_threads()
+        ));
+    }
+
+    /// switches the texts of the controls and the names of the particle types to `language`
+    fn set_language(&mut self, language: Language) {
+        self.translations = Translations::new(language);
+        self.element_text = ELEMENT_NAMES.map(|name| String::from(self.translations.tr(name)));
+    }
+
+    fn edit_window_layout(&mut self, ui: &mut Ui) {
+        let tr = &self.translations;
+        ui.horizontal(|ui| {
+            // the browser has one canvas only
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.checkbox(&mut self.detach_panel, tr.tr("detach controls"))
+                .on_hover_text(tr.tr("moves the controls into a window of their own"));
+            ui.label(tr.tr("fullscreen: "));
+            let config = &mut self.config;
+            ComboBox::from_id_source("fullscreen mode")
+                .selected_text(config.fullscreen.name())
+                .show_ui(ui, |ui| {
+                    for mode in FullscreenMode::ALL {
+                        ui.selectable_value(&mut config.fullscreen, mode, mode.name());
+                    }
+                });
+            ui.add(DragValue::new(&mut config.monitor).prefix("monitor "))
+                .on_hover_text("index of the monitor the window goes fullscreen on");
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr.tr("F11 / Alt+Enter: "));
+            let config = &mut self.config;
+            ComboBox::from_id_source("fullscreen toggle")
+                .selected_text(config.fullscreen_toggle.name())
+                .show_ui(ui, |ui| {
+                    for mode in FullscreenMode::ALL {
+                        if mode != FullscreenMode::Windowed {
+                            ui.selectable_value(&mut config.fullscreen_toggle, mode, mode.name());
+                        }
+                    }
+                })
+                .response
+                .on_hover_text(tr.tr("the fullscreen mode the hotkeys switch the window to"));
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.config.vsync, "vsync");
+            ui.label(tr.tr("F1 hides the controls"));
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .button(tr.tr("save window settings"))
+                .on_hover_text(format!(
+                    "writes the window settings to {}",
+                    self.config.path
+                ))
+                .clicked()
+            {
+                self.save_config = true;
+            }
+        });
+        if let Some(error) = &self.config.error {
+            ui.colored_label(Color32::RED, error);
+        }
+    }
+
+    fn edit_gui_settings(&mut self, ui: &mut Ui) {
+        let tr = &self.translations;
+        let config = &mut self.config;
+        ui.horizontal(|ui| {
+            let mut custom_scale = config.pixels_per_point.is_some();
+            if ui
+                .checkbox(&mut custom_scale, tr.tr("ui scale"))
+                .on_hover_text(tr.tr("overrides the scale factor of the display"))
+                .changed()
+            {
+                config.pixels_per_point = custom_scale.then(|| ui.ctx().pixels_per_point());
+            }
+            if let Some(pixels_per_point) = config.pixels_per_point.as_mut() {
+                ui.add(
+                    DragValue::new(pixels_per_point)
+                        .speed(0.01)
+                        .clamp_range(0.5..=4.0)
+                        .suffix(" px/pt"),
+                );
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr.tr("theme: "));
+            for theme in UiTheme::ALL {
+                ui.selectable_value(&mut config.theme, theme, theme.name());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr.tr("window opacity: "));
+            ui.add(Slider::new(&mut config.window_opacity, 0.2..=1.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr.tr("font size: "));
+            ui.add(Slider::new(&mut config.font_size, 8.0..=32.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr.tr("language: "));
+            ComboBox::from_id_source("language")
+                .selected_text(config.language.name())
+                .show_ui(ui, |ui| {
+                    for language in Language::ALL {
+                        ui.selectable_value(&mut config.language, language, language.name());
+                    }
+                });
+        });
+        ui.label(tr.tr("saved with the window settings"));
+    }
 
-        let window = Window::new("Particles");
-        window
+    /// the startup dialog, returns true once the simulation should be launched
+    pub fn startup(
+        &mut self,
+        ctx: &Context,
+        winit_window: &winit::window::Window,
+        launcher: &mut Launcher,
+    ) -> (FullOutput, bool) {
+        self.apply_scale(ctx, winit_window);
+        self.apply_style(ctx);
+        let input = self.winit_state.take_egui_input(winit_window);
+        ctx.begin_frame(input);
+        let config = &mut launcher.config;
+        let mut launch = false;
+        Window::new("Startup")
             .default_size(Vec2::new(300.0, 100.0))
             .show(ctx, |ui| {
-                self.last_cursor = ui.input(|i| i.pointer.interact_pos());
-                if let Some(mouse_pos) = self.last_cursor {
-                    ui.label(format!(
-                        "Mouse Position: ({:.1},{:.1})",
-                        mouse_pos.x, mouse_pos.y
-                    ));
+                if let Some(safe_mode) = launcher.safe_mode {
+                    ui.colored_label(Color32::YELLOW, safe_mode.description());
+                }
+                ui.horizontal(|ui| {
+                    ui.label("quality: ");
+                    for preset in QualityPreset::ALL {
+                        if ui
+                            .selectable_label(config.quality == preset, preset.name())
+                            .clicked()
+                        {
+                            *config = StartupConfig::from_preset(preset, config.seed);
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("num particles: ");
+                    ui.add(
+                        Slider::new(&mut config.num_particles, 1..=MAX_NUM_PARTICLES)
+                            .logarithmic(true),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("vector field dimensions: ");
+                    for d in config.force_grid_dimensions.iter_mut() {
+                        ui.add(DragValue::new(d).clamp_range(1..=64));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("seed: ");
+                    Self::seed_field(ui, &mut config.seed);
+                    if ui.button("reroll").clicked() {
+                        config.seed = rand::random();
+                    }
+                });
+                if let Some(entry) =
+                    Self::pick_adapter(ui, &launcher.adapters, &launcher.current_adapter)
+                {
+                    launcher.switch_adapter = Some(entry);
                 }
-                match self.gui_mode {
-                    GuiMode::Main => self.main(ui, app),
-                    GuiMode::Cursor => self.vector_field(ui, app),
+                launch = ui.button("start").clicked();
+                if ui.button("exit").clicked() {
+                    self.exit_app = true;
                 }
-
-                let elapsed = self.last_update_inst.elapsed();
-                ui.label(format!("Frametime: {:.2?}", elapsed));
             });
+        (ctx.end_frame(), launch)
+    }
 
-        self.last_update_inst = Instant::now();
-        ctx.end_frame()
+    /// lists the adapters with their main limits, picking one opens a new device on it
+    fn pick_adapter(
+        ui: &mut Ui,
+        adapters: &[AdapterEntry],
+        current_adapter: &wgpu::AdapterInfo,
+    ) -> Option<AdapterEntry> {
+        let mut picked = None;
+        CollapsingHeader::new("Adapter")
+            .default_open(adapters.len() > 1)
+            .show(ui, |ui| {
+                for entry in adapters {
+                    let current = entry.matches(current_adapter);
+                    let limits = &entry.limits;
+                    if ui
+                        .selectable_label(current, entry.label())
+                        .on_hover_text(format!(
+                            "max buffer size: {} MiB\n\
+                             max storage buffer binding: {} MiB\n\
+                             max workgroup size: {}\n\
+                             max 2d texture size: {}",
+                            limits.max_buffer_size >> 20,
+                            limits.max_storage_buffer_binding_size >> 20,
+                            limits.max_compute_workgroup_size_x,
+                            limits.max_texture_dimension_2d,
+                        ))
+                        .clicked()
+                        && !current
+                    {
+                        picked = Some(entry.clone());
+                    }
+                }
+                ui.label("the choice is saved for the next start");
+            });
+        picked
     }
 
     fn vector_field(&mut self, ui: &mut Ui, app: &mut App) {
+        Self::edit_force_grid_layers(ui, app);
         let cursor = &mut app.renderer.camera.cursor;
         ui.horizontal(|ui| {
             ui.label(format!(
@@ -86,11 +528,15 @@ impl Gui {
             ));
         });
         if ui.button("center vector field").clicked() {
-            app.psys.force_grid = app.sim_params.new_force_grid_centered();
+            *app.psys.force_grid_mut() = app.sim_params.new_force_grid_centered();
         }
         if ui.button("zero vector field").clicked() {
-            app.psys.force_grid = app.sim_params.new_force_grid_zero();
+            *app.psys.force_grid_mut() = app.sim_params.new_force_grid_zero();
         }
+        Self::edit_field_file(ui, app);
+        Self::edit_field_expression(ui, app);
+        self.edit_field_operations(ui, app);
+        let cursor = &mut app.renderer.camera.cursor;
         ui.horizontal(|ui| {
             ui.label("radius: ");
             ui.add(Slider::new(&mut cursor.outer_radius, 0.1..=10.0));
@@ -99,10 +545,27 @@ impl Gui {
             ui.label("cursor camera distance: ");
             ui.add(Slider::new(&mut cursor.distance_from_camera, 0.1..=10.0));
         });
+        ui.horizontal(|ui| {
+            ui.label("cursor depth: ");
+            ui.selectable_value(&mut cursor.depth_mode, DepthMode::Fixed, "fixed");
+            ui.selectable_value(&mut cursor.depth_mode, DepthMode::GridPlane, "grid plane");
+            ui.selectable_value(
+                &mut cursor.depth_mode,
+                DepthMode::ParticleCluster,
+                "particle cluster",
+            );
+        });
+        if cursor.depth_mode == DepthMode::ParticleCluster {
+            ui.horizontal(|ui| {
+                ui.label("cluster search radius: ");
+                ui.add(Slider::new(&mut cursor.cluster_radius, 0.05..=5.0).logarithmic(true));
+            });
+        }
         ui.horizontal(|ui| {
             ui.label("edit strength: ");
             ui.add(Slider::new(&mut cursor.edit_mode.strength, 0.1..=10.0));
         });
+        Self::edit_brush(ui, cursor);
         ui.label("selection fall off");
         ui.horizontal(|ui| {
             ui.label("falloff dist");
@@ -115,205 +578,3108 @@ impl Gui {
             Falloff::InverseDistance,
             "inverse distance",
         );
+        self.edit_selection(ui, app);
+        ui.checkbox(&mut self.magnitude_labels, "magnitude labels")
+            .on_hover_text("writes the length of the brushed or selected vectors next to them");
+        Self::edit_attractors(ui, &mut app.renderer.camera.cursor);
+        Self::edit_stir(ui, &mut app.renderer.camera.cursor);
+        self.edit_particle_brush(ui, &mut app.renderer.camera.cursor);
+        Self::edit_material(ui, app);
+        Self::edit_cursor_sphere(ui, &mut app.renderer.camera.cursor);
         if ui.button("back to main menu").clicked() {
             self.gui_mode = GuiMode::Main;
         }
     }
 
-    fn main(&mut self, ui: &mut Ui, app: &mut App) {
-        if ui.button("Edit Cursor").clicked() {
-            self.gui_mode = GuiMode::Cursor;
-        }
-        let mut num_particles = app.psys.particles.len();
-        ui.horizontal(|ui| {
-            ui.label("num particles: ");
-            if ui.add(Slider::new(&mut num_particles, 1..=50000)).changed() {
-                app.psys.set_num_particles(num_particles);
-                app.compute
-                    .upload_particles(&app.renderer.device, &app.psys.particles)
-            }
-        });
+    fn edit_particle_size(ui: &mut Ui, params: &mut ParticleRenderParams) {
         ui.horizontal(|ui| {
             ui.label("particle size: ");
-            if ui
-                .add(Slider::new(&mut app.psys.particle_size, 0.01..=1.0))
-                .changed()
-            {
-                app.psys.update_particle_size(&mut app.renderer);
-            }
-        });
-        ui.vertical_centered_justified(|ui| {
-            Self::edit_time_controls(ui, app);
-            self.edit_cutoff(ui, &mut app.sim_params);
-            Self::edit_view_distance(ui, app);
-            Self::edit_camera_speed(ui, &mut app.renderer.camera);
-            Self::edit_max_velocity(ui, &mut app.sim_params);
-            Self::edit_distance_exponent(ui, &mut app.sim_params);
-            Self::edit_bounding_volume_radius(ui, app);
+            ui.add(Slider::new(&mut params.particle_size, 0.001..=1.0).logarithmic(true))
+                .on_hover_text("in world units, distant particles get smaller");
         });
         ui.horizontal(|ui| {
-            ui.separator();
-            self.edit_masses(ui, &mut app.sim_params);
-            ui.separator();
-            self.edit_polys(ui);
+            ui.label("on screen: ");
+            ui.add(
+                DragValue::new(&mut params.min_pixels)
+                    .prefix("min: ")
+                    .suffix(" px")
+                    .clamp_range(0.0..=64.0),
+            );
+            ui.add(
+                DragValue::new(&mut params.max_pixels)
+                    .prefix("max: ")
+                    .suffix(" px")
+                    .clamp_range(0.0..=512.0),
+            )
+            .on_hover_text("0 disables the limit");
         });
-        self.edit_poly(ui, &mut app.sim_params.attraction_force[self.poly_index]);
     }
 
-    fn edit_time_controls(ui: &mut Ui, app: &mut App) {
-        if app.speed.is_some() {
-            if ui.button("pause").clicked() {
-                app.speed = None;
+    fn edit_auto_scale(ui: &mut Ui, auto_scale: &mut AutoScale) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut auto_scale.enabled, "auto scale particles")
+                .on_hover_text("removes particles while frames are too slow, adds them back later");
+            if auto_scale.enabled {
+                let mut target_ms = auto_scale.target_frame_time * 1000.0;
+                if ui
+                    .add(
+                        DragValue::new(&mut target_ms)
+                            .prefix("target: ")
+                            .suffix(" ms")
+                            .speed(0.1)
+                            .clamp_range(1.0..=100.0),
+                    )
+                    .changed()
+                {
+                    auto_scale.target_frame_time = target_ms / 1000.0;
+                }
+                ui.add(
+                    DragValue::new(&mut auto_scale.num_frames)
+                        .prefix("after ")
+                        .suffix(" frames")
+                        .clamp_range(1..=600),
+                );
             }
-        } else if ui.button("play").clicked() {
-            app.speed = Some(1.0);
-        }
-        if let Some(speed) = app.speed.as_mut() {
-            ui.horizontal(|ui| {
-                ui.label("speedup: ");
-                ui.add(Slider::new(speed, 0.1..=10.).logarithmic(true));
-            });
-        }
+        });
     }
 
-    fn edit_camera_speed(ui: &mut Ui, camera: &mut Camera) {
+    fn edit_field_file(ui: &mut Ui, app: &mut App) {
         ui.horizontal(|ui| {
-            ui.label("camera speed");
-            ui.add(Slider::new(&mut camera.units_per_second, 2.0..=20.0).logarithmic(true));
+            ui.label("field file: ");
+            ui.text_edit_singleline(&mut app.field_file.path);
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button("save field")
+                .on_hover_text("the active layer as .npy array of shape (x, y, z, 3)")
+                .clicked()
+            {
+                app.save_field();
+            }
+            if ui
+                .button("load field")
+                .on_hover_text("fields of another resolution are resampled onto the grid")
+                .clicked()
+            {
+                app.load_field();
+            }
         });
+        if let Some(e) = app.field_file.error.as_ref() {
+            ui.colored_label(Color32::RED, e);
+        }
     }
 
-    fn edit_view_distance(ui: &mut Ui, app: &mut App) {
-        if let Some((_, distance)) = app.renderer.camera.look_at_distance.as_mut() {
+    fn edit_field_expression(ui: &mut Ui, app: &mut App) {
+        CollapsingHeader::new("Field expression").show(ui, |ui| {
+            ui.add(TextEdit::multiline(&mut app.field_expression.text).desired_rows(2))
+                .on_hover_text(
+                    "a vector of the cell position x, y, z, e.g. (-y, x, 0) * 0.5 + (0, 0, sin(x))",
+                );
+            ui.label(format!(
+                "variables: x, y, z, r, p, pi, e\nfunctions: {}",
+                FUNCTIONS.map(|(name, _)| name).join(", ")
+            ));
             ui.horizontal(|ui| {
-                ui.label("view distance: ");
-                ui.add(Slider::new(distance, 0.1..=20.0).logarithmic(true));
+                ui.checkbox(&mut app.field_expression.preview, "preview")
+                    .on_hover_text(
+                        "shows the arrows of the expression instead of the active layer",
+                    );
+                if ui.button("apply to layer").clicked() {
+                    app.apply_field_expression();
+                }
             });
-        }
+            if let Some(e) = app.field_expression.error.as_ref() {
+                ui.colored_label(Color32::RED, e);
+            }
+        });
     }
 
-    fn edit_max_velocity(ui: &mut Ui, sim_params: &mut SimParams) {
-        ui.horizontal(|ui| {
-            ui.label("max velocity: ");
-            ui.add(Slider::new(&mut sim_params.max_velocity, 0.1..=100.0).logarithmic(true));
+    fn edit_field_operations(&mut self, ui: &mut Ui, app: &mut App) {
+        CollapsingHeader::new("Field ops").show(ui, |ui| {
+            let grid = app.psys.force_grid_mut();
+            ui.horizontal(|ui| {
+                if ui.button("smooth").clicked() {
+                    grid.gaussian_smooth(self.smooth_sigma);
+                }
+                ui.add(
+                    DragValue::new(&mut self.smooth_sigma)
+                        .prefix("sigma: ")
+                        .suffix(" cells")
+                        .speed(0.05)
+                        .clamp_range(0.1..=8.0),
+                );
+            });
+            ui.horizontal(|ui| {
+                if ui
+                    .button("divergence free")
+                    .on_hover_text("removes sources and sinks, the flow keeps circulating")
+                    .clicked()
+                {
+                    grid.project_divergence_free(self.projection_iterations);
+                }
+                ui.add(
+                    DragValue::new(&mut self.projection_iterations)
+                        .prefix("iterations: ")
+                        .clamp_range(1..=5000),
+                );
+            });
+            ui.horizontal(|ui| {
+                if ui.button("normalize").clicked() {
+                    grid.normalize();
+                }
+                if ui
+                    .button("curl")
+                    .on_hover_text("replaces every vector with the curl of the field")
+                    .clicked()
+                {
+                    grid.grid = grid.curl();
+                }
+            });
+            self.edit_field_symmetry(ui, grid);
+            self.edit_field_tiling(ui, grid);
         });
     }
 
-    fn edit_distance_exponent(ui: &mut Ui, sim_params: &mut SimParams) {
+    fn edit_field_symmetry(&mut self, ui: &mut Ui, grid: &mut crate::Grid<V3>) {
         ui.horizontal(|ui| {
-            ui.label("distance exponent: ");
-            ui.add(Slider::new(&mut sim_params.distance_exponent, -5.0..=5.0));
+            ui.label("axis: ");
+            for (i, name) in ["x", "y", "z"].into_iter().enumerate() {
+                ui.selectable_value(&mut self.field_op_axis, i, name);
+            }
         });
-    }
-
-    fn edit_bounding_volume_radius(ui: &mut Ui, app: &mut App) {
+        let axis = self.field_op_axis;
         ui.horizontal(|ui| {
-            ui.label("bounding volume size :");
-            let mut val = app.sim_params.bounding_volume_radius * 2.0;
-            if ui.add(Slider::new(&mut val, 0.5..=10.0)).changed() {
-                app.sim_params.bounding_volume_radius = val * 0.5;
-                app.psys
-                    .force_grid
-                    .bounds
-                    .set_centered(app.sim_params.bounding_volume_radius * 2.0);
+            if ui
+                .button("mirror")
+                .on_hover_text("reflects one half of the field onto the other")
+                .clicked()
+            {
+                grid.mirror(axis, self.mirror_from_upper);
             }
+            ui.selectable_value(&mut self.mirror_from_upper, false, "- to +");
+            ui.selectable_value(&mut self.mirror_from_upper, true, "+ to -");
         });
+        let dimensions = grid.dimensions();
+        let square = dimensions[(axis + 1) % 3] == dimensions[(axis + 2) % 3];
+        if ui
+            .add_enabled(square, Button::new("rotate 90°"))
+            .on_disabled_hover_text("needs as many cells along both other axes")
+            .clicked()
+        {
+            grid.rotate_quarter(axis);
+        }
     }
 
-    fn edit_polys(&mut self, ui: &mut Ui) {
-        ui.vertical(|ui| {
-            ui.colored_label(Color32::GREEN, "polynome selection matrix");
-            for y in 0..5 {
-                ui.horizontal(|ui| {
-                    for x in 0..5 {
-                        ui.radio_value(&mut self.poly_index, x + y * 5, "");
-                    }
-                });
+    fn edit_field_tiling(&mut self, ui: &mut Ui, grid: &mut crate::Grid<V3>) {
+        let dimensions = grid.dimensions();
+        ui.horizontal(|ui| {
+            ui.label("block from cell: ");
+            for (c, n) in self.tile_origin.iter_mut().zip(dimensions) {
+                ui.add(DragValue::new(c).clamp_range(0..=n - 1));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("block size: ");
+            for (c, n) in self.tile_size.iter_mut().zip(dimensions) {
+                ui.add(DragValue::new(c).clamp_range(1..=n));
             }
         });
+        if ui
+            .button("tile")
+            .on_hover_text("repeats the block across the whole field")
+            .clicked()
+        {
+            grid.tile(self.tile_origin, self.tile_size);
+        }
     }
 
-    fn edit_poly(&mut self, ui: &mut Ui, poly: &mut Poly7) {
-        ui.colored_label(Color32::GREEN, "selected attraction_force polynome");
-        let line = Line::new(poly.plot_points());
-        Plot::new("poly plot")
-            .view_aspect(2.0)
-            .show(ui, |plot_ui| plot_ui.line(line));
+    fn edit_selection(&mut self, ui: &mut Ui, app: &mut App) {
+        let cursor = &mut app.renderer.camera.cursor;
+        ui.colored_label(Color32::GREEN, "selection");
         ui.horizontal(|ui| {
-            let xs: [f32; 8] = std::array::from_fn(|i| (i as f32) / 7.0);
-            let mut ys = xs.map(|x| poly.eval(x));
-            let mut changed = false;
-            for y in ys.iter_mut() {
-                changed |= ui
-                    .add(Slider::new(y, -10.0..=10.0).orientation(SliderOrientation::Vertical))
-                    .changed();
+            if ui.checkbox(&mut cursor.select, "drag to select").changed() {
+                cursor.stir &= !cursor.select;
+                cursor.paint &= !cursor.select;
+                cursor.particle_brush.enabled &= !cursor.select;
             }
-            if changed {
-                let points = [
-                    Vector2::new(xs[0], ys[0]),
-                    Vector2::new(xs[1], ys[1]),
-                    Vector2::new(xs[2], ys[2]),
-                    Vector2::new(xs[3], ys[3]),
-                    Vector2::new(xs[4], ys[4]),
-                    Vector2::new(xs[5], ys[5]),
-                    Vector2::new(xs[6], ys[6]),
-                    Vector2::new(xs[7], ys[7]),
-                ];
-                if let Some(p) = Poly7::from_points(points) {
-                    *poly = p;
-                }
+            ui.selectable_value(&mut cursor.selection_shape, SelectionShape::Box, "box");
+            ui.selectable_value(
+                &mut cursor.selection_shape,
+                SelectionShape::Sphere,
+                "sphere",
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(format!("{} vectors selected", cursor.selection.len()));
+            if ui.button("clear").clicked() {
+                cursor.clear_selection();
             }
         });
+        if cursor.selection.is_empty() {
+            return;
+        }
+        let mut op = None;
         ui.horizontal(|ui| {
-            for (i, n) in (0..8).zip(Poly7::coeff_names()) {
-                Gui::labeled_drag_value(ui, &mut poly.coeffs[i], n);
+            if ui.button("set to").clicked() {
+                op = Some(SelectionOp::SetConstant(V3::from(self.selection_constant)));
+            }
+            for c in self.selection_constant.iter_mut() {
+                ui.add(DragValue::new(c).speed(0.05));
             }
         });
         ui.horizontal(|ui| {
-            if ui.button("copy").clicked() {
-                self.copy_poly = Some(*poly);
+            if ui.button("scale by").clicked() {
+                op = Some(SelectionOp::Scale(self.selection_scale));
             }
-            if let Some(cp) = self.copy_poly {
-                if ui.button("paste").clicked() {
-                    *poly = cp;
-                }
+            ui.add(DragValue::new(&mut self.selection_scale).speed(0.05));
+        });
+        ui.horizontal(|ui| {
+            if ui.button("rotate by").clicked() {
+                let mut axis = zero_v3();
+                axis[self.selection_axis] = 1.0;
+                op = Some(SelectionOp::Rotate {
+                    axis,
+                    angle: self.selection_angle,
+                });
             }
-            if ui.button("invert").clicked() {
-                poly.invert();
+            ui.add(DragValue::new(&mut self.selection_angle).suffix("°"));
+            ui.label("about");
+            for (i, name) in ["x", "y", "z"].into_iter().enumerate() {
+                ui.selectable_value(&mut self.selection_axis, i, name);
             }
+        });
+        ui.horizontal(|ui| {
             if ui.button("zero").clicked() {
-                *poly = Poly7::zero();
+                op = Some(SelectionOp::Zero);
+            }
+            if ui.button("smooth").clicked() {
+                op = Some(SelectionOp::Smooth);
             }
         });
+        if let Some(op) = op {
+            let cursor = &app.renderer.camera.cursor;
+            cursor.apply_to_selection(app.psys.force_grid_mut(), op);
+        }
     }
 
-    fn labeled_drag_value(ui: &mut Ui, val: &mut f32, label: &str) {
+    fn edit_attractors(ui: &mut Ui, cursor: &mut Cursor) {
+        ui.colored_label(Color32::GREEN, "attractors (right click to place)");
         ui.horizontal(|ui| {
-            ui.label(label);
-            ui.add(DragValue::new(val).speed(0.01));
+            ui.label("new attractor strength: ");
+            ui.add(Slider::new(&mut cursor.attractor_strength, 0.1..=100.0).logarithmic(true));
+            ui.checkbox(&mut cursor.attractor_repel, "repel");
         });
+        let mut remove = None;
+        for (i, attractor) in cursor.attractors.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                for c in attractor.pos.iter_mut() {
+                    ui.add(DragValue::new(c).speed(0.05));
+                }
+                ui.add(Slider::new(&mut attractor.strength, -100.0..=100.0).text("strength"));
+                if ui.button("remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            cursor.attractors.remove(i);
+        }
+        if !cursor.attractors.is_empty() && ui.button("remove all attractors").clicked() {
+            cursor.attractors.clear();
+        }
     }
 
-    fn edit_masses(&self, ui: &mut Ui, sim_params: &mut SimParams) {
-        ui.vertical(|ui| {
-            ui.colored_label(Color32::GREEN, "Masses");
-            for (i, mass) in sim_params.particle_type_masses.iter_mut().enumerate() {
-                ui.horizontal(|ui| {
-                    ui.add(
-                        DragValue::new(&mut mass.mass)
-                            .prefix(&self.element_text[i])
-                            .speed(0.01)
-                            .clamp_range(0.01..=10.0),
-                    );
-                });
+    fn edit_brush(ui: &mut Ui, cursor: &mut Cursor) {
+        ui.horizontal(|ui| {
+            ui.label("brush: ");
+            for shape in BrushShape::ALL {
+                ui.selectable_value(&mut cursor.brush.shape, shape, shape.name());
             }
-        });
+        })
+        .response
+        .on_hover_text("the vectors within the falloff distance of the cursor are edited");
+        if cursor.brush.shape == BrushShape::Sphere {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("aligned with: ");
+            for orientation in BrushOrientation::ALL {
+                ui.selectable_value(
+                    &mut cursor.brush.orientation,
+                    orientation,
+                    orientation.name(),
+                );
+            }
+        });
+    }
+
+    fn edit_stir(ui: &mut Ui, cursor: &mut Cursor) {
+        ui.colored_label(Color32::GREEN, "stirring");
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut cursor.stir, "drag to stir the particles")
+                .on_hover_text("pushes the particles within the cursor radius while dragging")
+                .changed()
+            {
+                // stirring, painting, selecting and the particle brush all take over
+                // the left mouse button
+                cursor.select &= !cursor.stir;
+                cursor.paint &= !cursor.stir;
+                cursor.particle_brush.enabled &= !cursor.stir;
+            }
+            for mode in StirMode::ALL {
+                ui.selectable_value(&mut cursor.stir_mode, mode, mode.name());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("stir strength: ");
+            ui.add(Slider::new(&mut cursor.stir_strength, 0.1..=100.0).logarithmic(true));
+            ui.checkbox(&mut cursor.stir_reverse, "reverse");
+        });
+    }
+
+    fn edit_particle_brush(&self, ui: &mut Ui, cursor: &mut Cursor) {
+        ui.colored_label(Color32::GREEN, "particle brush");
+        let brush = &mut cursor.particle_brush;
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut brush.enabled, "drag to spawn or delete particles")
+                .on_hover_text("within the cursor radius")
+                .changed()
+            {
+                cursor.select &= !brush.enabled;
+                cursor.stir &= !brush.enabled;
+                cursor.paint &= !brush.enabled;
+            }
+            for mode in ParticleBrushMode::ALL {
+                ui.selectable_value(&mut brush.mode, mode, mode.name());
+            }
+        });
+        if brush.mode != ParticleBrushMode::Spawn {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("type: ");
+            ComboBox::from_id_source("particle brush type")
+                .selected_text(&self.element_text[brush.ty as usize])
+                .show_ui(ui, |ui| {
+                    for (ty, name) in self.element_text.iter().enumerate() {
+                        ui.selectable_value(&mut brush.ty, ty as u32, name);
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("particles per second: ");
+            ui.add(Slider::new(&mut brush.rate, 1.0..=10000.0).logarithmic(true));
+        });
+        ui.horizontal(|ui| {
+            ui.label("velocity spread: ");
+            ui.add(Slider::new(&mut brush.velocity_spread, 0.0..=20.0))
+                .on_hover_text("the largest random speed of spawned particles along every axis");
+        });
+    }
+
+    fn edit_material(ui: &mut Ui, app: &mut App) {
+        let cursor = &mut app.renderer.camera.cursor;
+        let material = app.psys.material_grid_mut();
+        ui.colored_label(Color32::GREEN, "material");
+        ui.checkbox(&mut material.enabled, "damp the particles by the material")
+            .on_hover_text("multiplies the damping of the particles by the factor of their cell");
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut cursor.paint, "drag to paint")
+                .on_hover_text("paints the damping factor into the cells within the brush")
+                .changed()
+            {
+                cursor.select &= !cursor.paint;
+                cursor.stir &= !cursor.paint;
+                cursor.particle_brush.enabled &= !cursor.paint;
+                material.enabled |= cursor.paint;
+            }
+            for (name, value) in MATERIAL_PRESETS {
+                if ui
+                    .selectable_label(material.paint_value == value, name)
+                    .clicked()
+                {
+                    material.paint_value = value;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("damping factor: ");
+            ui.add(Slider::new(&mut material.paint_value, 0.0..=50.0));
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!cursor.selection.is_empty(), Button::new("fill selection"))
+                .clicked()
+            {
+                material.fill(&cursor.selection);
+            }
+            if ui.button("clear material").clicked() {
+                material.clear();
+            }
+        });
+    }
+
+    fn edit_cursor_sphere(ui: &mut Ui, cursor: &mut Cursor) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut cursor.collide, "solid cursor")
+                .on_hover_text("particles are pushed out of a sphere around the cursor");
+            ui.add_enabled(
+                cursor.collide,
+                Slider::new(&mut cursor.inner_radius, 0.05..=5.0)
+                    .logarithmic(true)
+                    .text("radius"),
+            );
+        });
+    }
+
+    fn edit_force_grid_layers(ui: &mut Ui, app: &mut App) {
+        ui.colored_label(Color32::GREEN, "vector field layers");
+        let mut remove = None;
+        let num_layers = app.psys.force_grid_layers.len();
+        for (i, layer) in app.psys.force_grid_layers.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut app.psys.active_layer, i, "");
+                ui.checkbox(&mut layer.enabled, "");
+                ui.text_edit_singleline(&mut layer.name);
+                ui.add(Slider::new(&mut layer.strength, -5.0..=5.0).text("strength"));
+                if num_layers > 1 && ui.button("remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            app.psys.remove_force_grid_layer(i);
+        }
+        if ui.button("add layer").clicked() {
+            app.psys.add_force_grid_layer(&app.sim_params);
+        }
+        Self::edit_grid_animation(ui, app);
+    }
+
+    /// keyframes of the active layer, played back in a loop when the animation is enabled
+    fn edit_grid_animation(ui: &mut Ui, app: &mut App) {
+        let psys = &mut app.psys;
+        let animation = &mut psys.grid_animation;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut animation.enabled, "animate keyframes");
+            ui.add(
+                Slider::new(&mut animation.duration, 0.1..=120.0)
+                    .logarithmic(true)
+                    .text("loop seconds"),
+            );
+        });
+        let duration = animation.duration;
+        ui.add(Slider::new(&mut animation.time, 0.0..=duration).text("time"));
+        animation.time = animation.time.min(duration);
+        let layer = &mut psys.force_grid_layers[psys.active_layer];
+        let mut remove = None;
+        let mut sort = false;
+        for (i, keyframe) in layer.keyframes.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                sort |= ui
+                    .add(
+                        DragValue::new(&mut keyframe.time)
+                            .prefix("keyframe at ")
+                            .suffix(" s")
+                            .speed(0.05)
+                            .clamp_range(0.0..=duration),
+                    )
+                    .changed();
+                if ui
+                    .button("edit")
+                    .on_hover_text("replaces the layer with the keyframe to edit it")
+                    .clicked()
+                {
+                    layer.grid = keyframe.grid.clone();
+                }
+                if ui.button("remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if sort {
+            layer.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+        if let Some(i) = remove {
+            layer.keyframes.remove(i);
+        }
+        if ui
+            .button("add keyframe")
+            .on_hover_text("snapshots the active layer at the current time")
+            .clicked()
+        {
+            psys.add_grid_keyframe();
+        }
+    }
+
+    fn main(&mut self, ui: &mut Ui, app: &mut App) {
+        if ui.button(self.translations.tr("Edit Cursor")).clicked() {
+            self.gui_mode = GuiMode::Cursor;
+        }
+        let mut num_particles = app.psys.particles.len();
+        ui.horizontal(|ui| {
+            ui.label(self.translations.tr("num particles: "));
+            if ui
+                .add(Slider::new(&mut num_particles, 1..=MAX_NUM_PARTICLES).logarithmic(true))
+                .changed()
+            {
+                app.resize_particles(num_particles);
+            }
+        });
+        Self::memory_warnings(ui, &app.memory_warnings);
+        Self::edit_auto_scale(ui, &mut app.auto_scale);
+        let mut half_precision = app.compute.precision == ParticlePrecision::Half;
+        if ui
+            .checkbox(
+                &mut half_precision,
+                self.translations.tr("half precision particles"),
+            )
+            .on_hover_text(
+                self.translations
+                    .tr("stores positions and velocities as 16 bit floats"),
+            )
+            .changed()
+        {
+            app.set_particle_precision(if half_precision {
+                ParticlePrecision::Half
+            } else {
+                ParticlePrecision::Full
+            });
+        }
+        Self::edit_workgroup_size(ui, app);
+        Self::edit_particle_sort(ui, &mut app.compute.particle_sort);
+        Self::edit_particle_size(ui, &mut app.renderer.particle_render_params);
+        Self::edit_render_scale(ui, &mut app.renderer);
+        Self::edit_motion_blur(ui, &mut app.renderer);
+        Self::edit_slice_view(ui, app);
+        Self::edit_seed(ui, app);
+        ui.vertical_centered_justified(|ui| {
+            Self::edit_time_controls(ui, app, &self.translations);
+            self.edit_cutoff(ui, app);
+            Self::edit_view_distance(ui, app);
+            Self::edit_camera_speed(ui, &mut app.renderer.camera);
+            Self::edit_wheel_sensitivity(ui, &mut app.renderer.camera);
+            Self::edit_camera_follow(ui, app);
+            Self::edit_max_velocity(ui, &mut app.sim_params);
+            Self::edit_distance_exponent(ui, &mut app.sim_params);
+            self.edit_particle_lifetime(ui, app);
+            self.edit_type_sizes(ui, app);
+            self.edit_type_tints(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Camera")).show(ui, |ui| {
+            Self::edit_camera_mode(ui, &mut app.renderer.camera);
+            Self::edit_projection(ui, &mut app.renderer.camera);
+            Self::edit_gamepad(ui, &mut app.gamepad);
+        });
+        CollapsingHeader::new(self.translations.tr("Presets")).show(ui, |ui| {
+            Self::preset_gallery(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Explore")).show(ui, |ui| {
+            Self::explorer(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Behavior detector")).show(ui, |ui| {
+            Self::edit_detector(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Comparison")).show(ui, |ui| {
+            Self::edit_comparison(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Scene file")).show(ui, |ui| {
+            Self::edit_scene_file(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Environment")).show(ui, |ui| {
+            Self::edit_bounding_volume_radius(ui, app);
+            Self::edit_gizmos(ui, &mut app.renderer.gizmos);
+            Self::edit_glyph_culling(ui, app);
+            Self::edit_glyph_lod(ui, &mut app.renderer.glyph_lod);
+            Self::edit_boundary_animation(ui, app);
+            Self::edit_force_grid_interpolation(ui, &mut app.sim_params);
+            Self::edit_force_grid_boundary(ui, &mut app.sim_params);
+            Self::edit_confinement(ui, &mut app.sim_params);
+            Self::edit_gravity(ui, &mut app.sim_params);
+        });
+        CollapsingHeader::new(self.translations.tr("Flocking")).show(ui, |ui| {
+            self.edit_flocking(ui, &mut app.sim_params);
+        });
+        CollapsingHeader::new(self.translations.tr("Velocities")).show(ui, |ui| {
+            Self::edit_velocities(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Reactions")).show(ui, |ui| {
+            self.edit_reactions(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Coalescence")).show(ui, |ui| {
+            Self::edit_coalescence(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Links")).show(ui, |ui| {
+            Self::edit_links(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Regions")).show(ui, |ui| {
+            Self::edit_regions(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Assets")).show(ui, |ui| {
+            Self::edit_assets(ui, &mut app.assets);
+        });
+        CollapsingHeader::new(self.translations.tr("Particle types")).show(ui, |ui| {
+            self.particle_legend(ui, &mut app.compute.diagnostics);
+        });
+        CollapsingHeader::new(self.translations.tr("Diagnostics")).show(ui, |ui| {
+            Self::diagnostics(ui, &mut app.compute.diagnostics);
+        });
+        CollapsingHeader::new(self.translations.tr("Memory")).show(ui, |ui| {
+            self.edit_memory(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Recording")).show(ui, |ui| {
+            Self::edit_recording(ui, app);
+        });
+        CollapsingHeader::new(self.translations.tr("Remote control")).show(ui, |ui| {
+            Self::edit_remote_control(ui, &mut app.remote);
+        });
+        CollapsingHeader::new(self.translations.tr("Buffer inspector")).show(ui, |ui| {
+            Self::buffer_inspector(ui, &mut app.compute.inspector);
+        });
+        CollapsingHeader::new(self.translations.tr("Pair force probe")).show(ui, |ui| {
+            self.pair_force_probe(ui, app);
+        });
+        ui.horizontal(|ui| {
+            ui.separator();
+            self.edit_masses(ui, &mut app.sim_params);
+            ui.separator();
+            self.edit_polys(ui, app);
+            ui.separator();
+            Self::edit_pair_forces(ui, app);
+        });
+        self.edit_curve_kind(ui, app);
+        if let ForceCurve::Spline { .. } = app.force_curves.curves[self.poly_index] {
+            self.edit_spline(ui, app);
+            return;
+        }
+        let before = app.sim_params.attraction_force[self.poly_index].coeffs;
+        self.edit_poly(
+            ui,
+            &mut app.sim_params.attraction_force[self.poly_index],
+            app.sim_params.force_mode,
+            app.sim_params.pair_forces[self.poly_index].cut_off_distance,
+            app.sim_params.cutoff_window,
+        );
+        if app.sim_params.attraction_force[self.poly_index].coeffs != before {
+            app.sim_params
+                .mirror_force(self.poly_index, app.matrix_symmetry);
+        }
+    }
+
+    fn edit_gizmos(ui: &mut Ui, gizmos: &mut Gizmos) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut gizmos.bounding_box, "bounding box");
+            ui.checkbox(&mut gizmos.axes, "axes");
+            ui.checkbox(&mut gizmos.grid_planes, "grid plane");
+        });
+    }
+
+    fn edit_glyph_culling(ui: &mut Ui, app: &mut App) {
+        let culling = &mut app.renderer.glyph_culling;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut culling.enabled, "cull vector field")
+                .on_hover_text("skips the glyphs outside of the view and thins out distant ones");
+            ui.label(format!(
+                "{} of {} glyphs drawn",
+                culling.visible,
+                app.psys.force_grid().num_instances()
+            ));
+        });
+        if !culling.enabled {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("full detail distance: ");
+            ui.add(Slider::new(&mut culling.full_detail_distance, 0.5..=50.0).logarithmic(true));
+            ui.label("max spacing: ");
+            ComboBox::from_id_source("glyph spacing")
+                .selected_text(culling.max_stride.to_string())
+                .show_ui(ui, |ui| {
+                    for stride in [1, 2, 4, 8, 16] {
+                        ui.selectable_value(&mut culling.max_stride, stride, stride.to_string());
+                    }
+                });
+        });
+    }
+
+    fn edit_glyph_lod(ui: &mut Ui, glyph_lod: &mut GlyphLod) {
+        ui.checkbox(&mut glyph_lod.enabled, "simplify small glyphs")
+            .on_hover_text("draws glyphs covering few pixels as cones or line segments");
+        if !glyph_lod.enabled {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("full arrow above: ");
+            ui.add(
+                DragValue::new(&mut glyph_lod.full_arrow_pixels)
+                    .suffix(" px")
+                    .clamp_range(glyph_lod.cone_pixels..=1000.0),
+            );
+            ui.label("cone above: ");
+            ui.add(
+                DragValue::new(&mut glyph_lod.cone_pixels)
+                    .suffix(" px")
+                    .clamp_range(0.0..=glyph_lod.full_arrow_pixels),
+            );
+        });
+    }
+
+    fn buffer_inspector(ui: &mut Ui, inspector: &mut Inspector) {
+        ui.horizontal(|ui| {
+            for source in InspectedBuffer::ALL {
+                ui.selectable_value(&mut inspector.source, source, source.name());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("first element: ");
+            ui.add(DragValue::new(&mut inspector.offset));
+            ui.label("count: ");
+            ui.add(DragValue::new(&mut inspector.count).clamp_range(1..=MAX_INSPECTED_ELEMENTS));
+        });
+        if ui.button("read back").clicked() {
+            inspector.requested = true;
+        }
+        let Some((source, rows)) = inspector.rows.as_ref() else {
+            return;
+        };
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            Grid::new("buffer inspector").striped(true).show(ui, |ui| {
+                for column in source.columns() {
+                    ui.strong(*column);
+                }
+                ui.end_row();
+                for row in rows {
+                    for cell in row {
+                        // NaN and inf are usually the first sign of a broken simulation
+                        if cell.contains("NaN") || cell.contains("inf") {
+                            ui.colored_label(Color32::RED, cell);
+                        } else {
+                            ui.monospace(cell);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    /// the attraction of the selected pair sampled by the compute shader, plotted against
+    /// the curve evaluated on the CPU
+    fn pair_force_probe(&self, ui: &mut Ui, app: &mut App) {
+        let index = self.poly_index;
+        let probe = &mut app.compute.pair_probe;
+        ui.label(format!(
+            "pair: type {} towards type {}",
+            index / 5,
+            index % 5
+        ));
+        ui.horizontal(|ui| {
+            if ui.button("sample on the GPU").clicked() {
+                probe.index = index;
+                probe.requested = true;
+            }
+            ui.checkbox(&mut probe.live, "live")
+                .on_hover_text("samples every frame, the GPU lags a frame behind the edits");
+        });
+        if probe.live {
+            probe.index = index;
+        }
+        let Some(result) = probe.result.as_ref().filter(|result| result.index == index) else {
+            return;
+        };
+        let sim_params = &app.sim_params;
+        let potential = sim_params.force_mode == ForceMode::Potential;
+        let pair_force = sim_params.pair_forces[index];
+        let cpu: Vec<[f32; 2]> = result
+            .samples
+            .iter()
+            .map(|[r, _]| {
+                let r = *r;
+                if pair_force.enabled == 0 || r < 0.001 || r > pair_force.cut_off_distance {
+                    return [r, 0.0];
+                }
+                let force = app.force_curves.curves[index].windowed_force(
+                    r,
+                    &sim_params.attraction_force[index],
+                    potential,
+                    pair_force.cut_off_distance,
+                    sim_params.cutoff_window,
+                );
+                [
+                    r,
+                    force * r.powf(sim_params.distance_exponent) * pair_force.scale,
+                ]
+            })
+            .collect();
+        let (max_error, max_force) = result.samples.iter().zip(&cpu).fold(
+            (0.0f32, 0.0f32),
+            |(error, force), ([_, gpu], [_, cpu])| {
+                (error.max((gpu - cpu).abs()), force.max(cpu.abs()))
+            },
+        );
+        // the lookup table deviates a little from the curves, a layout mismatch a lot
+        let color = if max_error > 0.01 * max_force.max(1.0) {
+            Color32::RED
+        } else {
+            Color32::GREEN
+        };
+        ui.colored_label(color, format!("largest difference: {:.6}", max_error));
+        let to_points = |samples: &[[f32; 2]]| {
+            samples
+                .iter()
+                .map(|[r, force]| [*r as f64, *force as f64])
+                .collect::<Vec<_>>()
+        };
+        Plot::new("pair force probe plot")
+            .legend(Legend::default())
+            .view_aspect(2.0)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(to_points(&cpu)).name("CPU"));
+                plot_ui.points(
+                    Points::new(to_points(&result.samples))
+                        .radius(2.0)
+                        .color(Color32::YELLOW)
+                        .name("GPU"),
+                );
+            });
+    }
+
+    fn edit_recording(ui: &mut Ui, app: &mut App) {
+        let recorder = &mut app.renderer.recorder;
+        if recorder.is_recording() {
+            ui.label(format!("recording frame {}", recorder.frame_index));
+            if ui.button("stop recording").clicked() {
+                recorder.stop();
+            }
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("resolution: ");
+            ui.add(DragValue::new(&mut recorder.width).clamp_range(16..=8192));
+            ui.label("x");
+            ui.add(DragValue::new(&mut recorder.height).clamp_range(16..=8192));
+        });
+        ui.horizontal(|ui| {
+            ui.label("time step: ");
+            ui.add(Slider::new(&mut recorder.fixed_delta_t, 0.001..=0.1).logarithmic(true));
+        });
+        ui.horizontal(|ui| {
+            ui.selectable_value(
+                &mut recorder.output,
+                RecordOutput::PngSequence,
+                "png sequence",
+            );
+            ui.selectable_value(&mut recorder.output, RecordOutput::Ffmpeg, "ffmpeg");
+            ui.selectable_value(&mut recorder.output, RecordOutput::Screenshot, "screenshot")
+                .on_hover_text("a single png, the recording stops after one frame");
+        });
+        ui.horizontal(|ui| {
+            ui.label(match recorder.output {
+                RecordOutput::PngSequence => "directory: ",
+                RecordOutput::Ffmpeg => "video file: ",
+                RecordOutput::Screenshot => "png file: ",
+            });
+            ui.text_edit_singleline(&mut recorder.path);
+        });
+        if let Some(e) = recorder.error.as_ref() {
+            ui.colored_label(Color32::RED, e);
+        }
+        if ui.button("start recording").clicked() {
+            app.renderer.start_recording();
+        }
+    }
+
+    fn edit_remote_control(ui: &mut Ui, remote: &mut RemoteControl) {
+        let mut listening = remote.is_listening();
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut listening, "listen for OSC on UDP port")
+                .changed()
+            {
+                if listening {
+                    if let Err(e) = remote.start() {
+                        remote.error = Some(e.to_string());
+                    }
+                } else {
+                    remote.stop();
+                }
+            }
+            ui.add_enabled(!listening, DragValue::new(&mut remote.port));
+        });
+        if listening {
+            ui.label(format!("{} messages received", remote.num_received));
+            if let Some(address) = &remote.last_address {
+                ui.label(format!("last: {}", address));
+            }
+        }
+        if let Some(e) = remote.error.as_ref() {
+            ui.colored_label(Color32::RED, e);
+        }
+        ui.collapsing("addresses", |ui| {
+            ui.label(format!(
+                "/param/<name> <value>, name one of {}",
+                PARAM_NAMES.join(", ")
+            ));
+            ui.label("/mass <type> <mass>");
+            ui.label("/camera/zoom <factor>, /camera/pan <dx> <dy>, /camera/look <dx> <dy>");
+            ui.label("/camera/follow <0 or 1>");
+            ui.label("/preset <index or name>, /respawn");
+        });
+    }
+
+    /// icon of every type next to its statistics from the last diagnostics sample
+    fn particle_legend(&mut self, ui: &mut Ui, diagnostics: &mut Diagnostics) {
+        let atlas = self.atlas.get_or_insert_with(|| {
+            let rgba = image::load_from_memory(include_bytes!("../assets/all_textures.png"))
+                .expect("embedded particle atlas")
+                .to_rgba8();
+            let size = [rgba.width() as usize, rgba.height() as usize];
+            let image = ColorImage::from_rgba_unmultiplied(size, rgba.as_raw());
+            ui.ctx()
+                .load_texture("particle atlas", image, TextureOptions::LINEAR)
+        });
+        ui.checkbox(&mut diagnostics.enabled, "record metrics");
+        ui.horizontal(|ui| {
+            ui.label("cluster distance: ");
+            ui.add(Slider::new(&mut diagnostics.cluster_distance, 0.01..=2.0).logarithmic(true))
+                .on_hover_text("particles of the same type closer than this form a cluster");
+        });
+        let type_stats = diagnostics.history.back().map(|sample| sample.type_stats);
+        Grid::new("particle legend").striped(true).show(ui, |ui| {
+            for label in ["", "type", "count", "mean speed", "mean cluster size"] {
+                ui.label(label);
+            }
+            ui.end_row();
+            for (i, name) in self.element_text.iter().enumerate() {
+                // the atlas holds the five type textures side by side
+                let uv =
+                    Rect::from_min_max(pos2(i as f32 * 0.2, 0.0), pos2((i + 1) as f32 * 0.2, 1.0));
+                ui.add(Image::new((atlas.id(), vec2(24.0, 24.0))).uv(uv));
+                ui.label(name);
+                if let Some(stats) = type_stats.as_ref().map(|stats| stats[i]) {
+                    ui.label(stats.count.to_string());
+                    ui.label(format!("{:.3}", stats.mean_speed));
+                    ui.label(format!("{:.1}", stats.mean_cluster_size));
+                }
+                ui.end_row();
+            }
+        });
+        if type_stats.is_none() {
+            ui.label("the statistics appear with the first metrics sample");
+        }
+    }
+
+    fn memory_warnings(ui: &mut Ui, warnings: &[String]) {
+        for warning in warnings {
+            ui.colored_label(Color32::RED, warning);
+        }
+    }
+
+    /// sizes of the buffers, the dispatch of the simulation, the limits of the device and
+    /// the sizes a planned particle count and grid resolution would need
+    fn edit_memory(&mut self, ui: &mut Ui, app: &mut App) {
+        let mut sizes = app.compute.buffer_sizes();
+        sizes.extend(app.renderer.buffer_sizes());
+        for (name, size) in sizes.iter() {
+            ui.label(format!("{}: {}", name, format_bytes(*size)));
+        }
+        let total: u64 = sizes.iter().map(|(_, size)| size).sum();
+        ui.label(format!("estimated VRAM use: {}", format_bytes(total)))
+            .on_hover_text("the buffers above, without the pipelines and the small buffers");
+        ui.separator();
+        let num_particles = app.compute.num_particles;
+        let current = app.memory_plan(num_particles, app.sim_params.force_grid_dimensions);
+        let (workgroups_x, workgroups_y) = current.workgroups();
+        let invocations = workgroups_x as u64 * workgroups_y as u64 * current.workgroup_size as u64;
+        ui.label(format!(
+            "workgroups: {} x {} of {} particles",
+            workgroups_x, workgroups_y, current.workgroup_size
+        ));
+        ui.label(format!(
+            "occupancy: {:.1} %",
+            100.0 * num_particles as f64 / invocations.max(1) as f64
+        ))
+        .on_hover_text("invocations of the dispatch with a particle to simulate");
+        ui.separator();
+        let limits = app.renderer.device.limits();
+        ui.label(format!(
+            "max buffer size: {}",
+            format_bytes(limits.max_buffer_size)
+        ));
+        ui.label(format!(
+            "max storage buffer binding: {}",
+            format_bytes(limits.max_storage_buffer_binding_size as u64)
+        ));
+        ui.label(format!(
+            "max workgroups per dimension: {}",
+            limits.max_compute_workgroups_per_dimension
+        ));
+        ui.label(format!(
+            "max workgroup size: {}",
+            limits.max_compute_workgroup_size_x
+        ));
+        ui.separator();
+        let (mut planned_particles, mut planned_dimensions) = self
+            .planned_memory
+            .unwrap_or((num_particles, current.force_grid_dimensions));
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("planned particles: ");
+            changed |= ui
+                .add(
+                    DragValue::new(&mut planned_particles)
+                        .speed(1000.0)
+                        .clamp_range(1..=MAX_NUM_PARTICLES),
+                )
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("planned dimensions: ");
+            for d in planned_dimensions.iter_mut() {
+                changed |= ui.add(DragValue::new(d).clamp_range(1..=64)).changed();
+            }
+        });
+        if changed {
+            self.planned_memory = Some((planned_particles, planned_dimensions));
+        }
+        let plan = app.memory_plan(planned_particles, planned_dimensions);
+        ui.label(format!(
+            "particle buffers: 2 x {}",
+            format_bytes(plan.particle_buffer_size())
+        ));
+        ui.label(format!(
+            "force grid: {}",
+            format_bytes(plan.force_grid_buffer_size())
+        ));
+        ui.label(format!(
+            "glyph instances: {}",
+            format_bytes(plan.glyph_buffer_size())
+        ));
+        ui.label(format!(
+            "peak while growing: {}",
+            format_bytes(plan.peak_size())
+        ))
+        .on_hover_text("the particle buffers are copied into larger ones, both exist at once");
+        let warnings = plan.warnings(&limits);
+        if warnings.is_empty() {
+            ui.colored_label(Color32::GREEN, "fits the limits of the device");
+        }
+        Self::memory_warnings(ui, &warnings);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(warnings.is_empty(), Button::new("apply"))
+                .clicked()
+            {
+                app.resize_particles(planned_particles);
+                app.set_force_grid_dimensions(planned_dimensions);
+                self.planned_memory = None;
+            }
+            if ui.button("reset").clicked() {
+                self.planned_memory = None;
+            }
+        });
+    }
+
+    fn diagnostics(ui: &mut Ui, diagnostics: &mut Diagnostics) {
+        ui.checkbox(&mut diagnostics.enabled, "record metrics");
+        ui.horizontal(|ui| {
+            ui.label("interval: ");
+            ui.add(Slider::new(&mut diagnostics.interval, 0.1..=10.0).logarithmic(true));
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut diagnostics.log_to_csv, "log to csv")
+                .changed()
+            {
+                diagnostics.csv_error = None;
+            }
+            ui.text_edit_singleline(&mut diagnostics.csv_path);
+        });
+        if let Some(e) = diagnostics.csv_error.as_ref() {
+            ui.colored_label(Color32::RED, e);
+        }
+        let Some(last) = diagnostics.history.back() else {
+            return;
+        };
+        let (p, c) = (last.momentum, last.center_of_mass);
+        ui.label(format!("kinetic energy: {:.2}", last.kinetic_energy));
+        ui.label(format!("momentum: ({:.2}, {:.2}, {:.2})", p.x, p.y, p.z));
+        ui.label(format!(
+            "center of mass: ({:.2}, {:.2}, {:.2})",
+            c.x, c.y, c.z
+        ));
+        let energy: Vec<[f64; 2]> = diagnostics
+            .history
+            .iter()
+            .map(|s| [s.sim_time, s.kinetic_energy as f64])
+            .collect();
+        let momentum: Vec<[f64; 2]> = diagnostics
+            .history
+            .iter()
+            .map(|s| [s.sim_time, s.momentum.magnitude() as f64])
+            .collect();
+        Plot::new("metrics plot")
+            .view_aspect(2.0)
+            .x_axis_label("simulated time [s]")
+            .legend(Legend::default())
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(energy).name("kinetic energy"));
+                plot_ui.line(Line::new(momentum).name("momentum"));
+            });
+        let bin_width = (last.max_speed / NUM_SPEED_BINS as f32) as f64;
+        let bars = last
+            .speed_histogram
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| Bar::new((i as f64 + 0.5) * bin_width, count as f64))
+            .collect();
+        Plot::new("speed histogram")
+            .view_aspect(2.0)
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(BarChart::new(bars).width(bin_width).name("speed"))
+            });
+    }
+
+    fn edit_reactions(&self, ui: &mut Ui, app: &mut App) {
+        let mut remove = None;
+        Grid::new("reactions").striped(true).show(ui, |ui| {
+            for label in ["", "type", "near", "becomes", "radius", "rate / s", ""] {
+                ui.label(label);
+            }
+            ui.end_row();
+            for (i, reaction) in app.reactions.iter_mut().enumerate() {
+                let rule = &mut reaction.rule;
+                ui.checkbox(&mut reaction.enabled, "");
+                for (j, ty) in [&mut rule.ty, &mut rule.catalyst, &mut rule.product]
+                    .into_iter()
+                    .enumerate()
+                {
+                    ComboBox::from_id_source(("reaction", i, j))
+                        .selected_text(&self.element_text[*ty as usize])
+                        .show_ui(ui, |ui| {
+                            for (k, name) in self.element_text.iter().enumerate() {
+                                ui.selectable_value(ty, k as u32, name);
+                            }
+                        });
+                }
+                ui.add(
+                    DragValue::new(&mut rule.radius)
+                        .speed(0.01)
+                        .clamp_range(0.0..=10.0),
+                );
+                ui.add(
+                    DragValue::new(&mut rule.rate)
+                        .speed(0.01)
+                        .clamp_range(0.0..=100.0),
+                );
+                if ui.button("remove").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(i) = remove {
+            app.reactions.remove(i);
+        }
+        if app.reactions.len() < MAX_REACTION_RULES && ui.button("add reaction").clicked() {
+            app.reactions.push(Reaction::new(0, 0, 0));
+        }
+    }
+
+    /// springs between particles picked around the cursor
+    fn edit_links(ui: &mut Ui, app: &mut App) {
+        let links = &mut app.compute.links;
+        ui.label(format!(
+            "{} picked particles, {} links",
+            links.selection.len(),
+            links.links.len()
+        ));
+        ui.horizontal(|ui| {
+            if ui
+                .button("pick at cursor")
+                .on_hover_text(
+                    "adds the particles within the pick radius of the cursor, \
+                     at least the closest one",
+                )
+                .clicked()
+            {
+                let picked = app.compute.particles_near(
+                    &app.renderer.device,
+                    &app.renderer.queue,
+                    app.renderer.camera.cursor.pos,
+                    app.compute.links.pick_radius,
+                );
+                app.compute.links.pick(picked);
+            }
+            let links = &mut app.compute.links;
+            ui.add(
+                DragValue::new(&mut links.pick_radius)
+                    .speed(0.005)
+                    .clamp_range(0.0..=10.0)
+                    .prefix("radius: "),
+            );
+            if ui.button("clear picks").clicked() {
+                links.selection.clear();
+            }
+        });
+        let links = &mut app.compute.links;
+        ui.horizontal(|ui| {
+            ui.label("stiffness: ");
+            ui.add(Slider::new(&mut links.stiffness, 0.1..=1000.0).logarithmic(true))
+                .on_hover_text("of new links, stiff links need substeps to stay stable");
+        });
+        ui.horizontal(|ui| {
+            ui.label("damping: ");
+            ui.add(Slider::new(&mut links.damping, 0.0..=10.0))
+                .on_hover_text("slows the linked particles down relative to each other");
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button("link as chain")
+                .on_hover_text("links every picked particle to the one picked after it")
+                .clicked()
+            {
+                links.link_chain();
+            }
+            if ui
+                .button("link nearby")
+                .on_hover_text("links every two picked particles closer than the max length")
+                .clicked()
+            {
+                links.link_nearby();
+            }
+            ui.add(
+                DragValue::new(&mut links.max_length)
+                    .speed(0.005)
+                    .clamp_range(0.0..=10.0)
+                    .prefix("max length: "),
+            );
+        });
+        if ui.button("remove links").clicked() {
+            links.clear();
+        }
+        if app.compute.coalescence.enabled && app.compute.links.active() {
+            ui.colored_label(
+                Color32::YELLOW,
+                "the links are paused while particles merge, split or get deleted",
+            );
+        }
+        ui.label("new particle buffers drop the links");
+    }
+
+    fn edit_coalescence(ui: &mut Ui, app: &mut App) {
+        let sim_params = &mut app.sim_params;
+        ui.horizontal(|ui| {
+            ui.label("merge radius (0 = off): ");
+            ui.add(Slider::new(&mut sim_params.merge_radius, 0.0..=1.0))
+                .on_hover_text("particles of the same type closer than this merge into one");
+        });
+        ui.horizontal(|ui| {
+            ui.label("split mass (0 = off): ");
+            ui.add(Slider::new(&mut sim_params.split_mass, 0.0..=16.0))
+                .on_hover_text(
+                    "particles heavier than this multiple of the mass of their type split in two, \
+                     as long as merges or a shrunk particle count left room for them",
+                );
+        });
+        ui.horizontal(|ui| {
+            ui.label("split distance: ");
+            ui.add(Slider::new(&mut sim_params.split_distance, 0.01..=2.0).logarithmic(true));
+        });
+        if sim_params.coalescence() {
+            ui.label(format!(
+                "alive particles: {} of {}",
+                app.compute.num_particles,
+                app.psys.particles.len()
+            ));
+        }
+    }
+
+    fn edit_regions(ui: &mut Ui, app: &mut App) {
+        let extents = app.sim_params.bounding_volume_extents;
+        let mut remove = None;
+        for (i, region) in app.regions.iter_mut().enumerate() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut region.enabled, format!("region {}", i));
+                if ui.button("remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+            for (label, corner) in [("min: ", &mut region.min), ("max: ", &mut region.max)] {
+                ui.horizontal(|ui| {
+                    ui.label(label);
+                    let corner: &mut [f32; 3] = corner.as_mut();
+                    for (c, e) in corner.iter_mut().zip(extents) {
+                        ui.add(DragValue::new(c).speed(0.05).clamp_range(-e..=e));
+                    }
+                });
+            }
+            let overrides = [
+                ("damping", &mut region.damping, 1.0, 0.0..=100.0),
+                (
+                    "max velocity",
+                    &mut region.max_velocity,
+                    100.0,
+                    0.0..=1000.0,
+                ),
+                (
+                    "cut off distance",
+                    &mut region.cut_off_distance,
+                    1.0,
+                    0.0..=10.0,
+                ),
+                ("time scale", &mut region.time_scale, 1.0, 0.0..=10.0),
+            ];
+            for (label, value, default, range) in overrides {
+                ui.horizontal(|ui| {
+                    let mut enabled = value.is_some();
+                    if ui.checkbox(&mut enabled, label).changed() {
+                        *value = enabled.then_some(default);
+                    }
+                    if let Some(value) = value {
+                        ui.add(DragValue::new(value).speed(0.01).clamp_range(range));
+                    }
+                });
+            }
+        }
+        if let Some(i) = remove {
+            app.regions.remove(i);
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            if app.regions.len() >= MAX_REGIONS {
+                ui.label(format!("at most {} regions", MAX_REGIONS));
+                return;
+            }
+            if ui.button("add region").clicked() {
+                let half = V3::from(extents) * 0.5;
+                app.regions.push(Region::new(-half, half));
+            }
+            let marquee = app.renderer.camera.cursor.marquee.as_ref();
+            let dragged = marquee.and_then(Region::from_marquee);
+            if ui
+                .add_enabled(dragged.is_some(), Button::new("add dragged box"))
+                .on_disabled_hover_text("drag out a box selection first")
+                .clicked()
+            {
+                app.regions.extend(dragged);
+            }
+        });
+    }
+
+    /// edits all 64 bits of `seed` as text, a drag value would round it to an f64.
+    /// returns true when a different seed was entered
+    fn seed_field(ui: &mut Ui, seed: &mut u64) -> bool {
+        let id = ui.id().with("seed text");
+        // the text being typed is kept until the seed changes elsewhere
+        let mut text = ui
+            .data_mut(|d| d.get_temp::<(u64, String)>(id))
+            .filter(|(shown, _)| shown == seed)
+            .map_or_else(|| seed.to_string(), |(_, text)| text);
+        let response = ui.add(TextEdit::singleline(&mut text).desired_width(170.0));
+        let parsed = text.trim().parse::<u64>();
+        let changed = match parsed {
+            Ok(parsed) if response.changed() && parsed != *seed => {
+                *seed = parsed;
+                true
+            }
+            Err(_) if response.lost_focus() => {
+                text = seed.to_string();
+                false
+            }
+            _ => false,
+        };
+        ui.data_mut(|d| d.insert_temp(id, (*seed, text)));
+        changed
+    }
+
+    fn edit_seed(ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            ui.label("seed: ");
+            let mut seed = app.psys.seed;
+            if Self::seed_field(ui, &mut seed) {
+                app.psys.reseed(seed);
+            }
+            let mut respawn = false;
+            if ui.button("reroll").clicked() {
+                app.psys.reseed(rand::random());
+                respawn = true;
+            }
+            if ui.button("copy").clicked() {
+                ui.output_mut(|o| o.copied_text = app.psys.seed.to_string());
+            }
+            let mut sim_params = app.sim_params;
+            sim_params.set_seed(app.psys.seed);
+            let hash = format!("{:016x}", reproducibility_hash(&sim_params));
+            ui.label(format!("hash: {}", hash))
+                .on_hover_text("a fingerprint of the seed and the parameters a scene file saves");
+            if ui.button("copy hash").clicked() {
+                ui.output_mut(|o| o.copied_text = hash);
+            }
+            respawn |= ui.button("restart").clicked();
+            if respawn {
+                app.psys.respawn_particles();
+                app.compute.upload_particles(
+                    &app.renderer.device,
+                    &app.renderer.queue,
+                    &app.psys.particles,
+                );
+            }
+        });
+    }
+
+    fn edit_time_controls(ui: &mut Ui, app: &mut App, tr: &Translations) {
+        if app.speed.is_some() {
+            if ui.button(tr.tr("pause")).clicked() {
+                app.speed = None;
+            }
+        } else if ui.button(tr.tr("play")).clicked() {
+            app.speed = Some(1.0);
+        }
+        if let Some(speed) = app.speed.as_mut() {
+            ui.horizontal(|ui| {
+                ui.label(tr.tr("speedup: "));
+                ui.add(Slider::new(speed, 0.1..=10.).logarithmic(true));
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label(tr.tr("substeps: "));
+            ui.add(Slider::new(&mut app.compute.substeps, 1..=MAX_SUBSTEPS))
+                .on_hover_text(
+                    tr.tr("compute dispatches per frame, each with a fraction of the time step"),
+                );
+        });
+        let adaptive = &mut app.adaptive_timestep;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut adaptive.enabled, tr.tr("adaptive time step"))
+                .on_hover_text(
+                    tr.tr("shortens the time step while the fastest particle is too fast"),
+                );
+            if adaptive.enabled {
+                ui.label(tr.tr("max step: "));
+                ui.add(Slider::new(&mut adaptive.max_step_fraction, 0.01..=1.0).logarithmic(true))
+                    .on_hover_text(
+                        tr.tr("fraction of the cut off distance a particle may move per step"),
+                    );
+            }
+        });
+        let limiting = if adaptive.is_limiting() {
+            " (limited)"
+        } else {
+            ""
+        };
+        ui.label(format!(
+            "effective dt: {:.5} s{}",
+            adaptive.effective_delta_t, limiting
+        ));
+        if let Some(result) = app.compute.reduction.result {
+            ui.label(format!("max speed: {:.3}", result.max_speed));
+        }
+    }
+
+    fn edit_render_scale(ui: &mut Ui, renderer: &mut Renderer) {
+        ui.horizontal(|ui| {
+            ui.label("render scale: ");
+            let mut scale = renderer.render_scale();
+            let response = ui
+                .add(Slider::new(&mut scale, MIN_RENDER_SCALE..=MAX_RENDER_SCALE))
+                .on_hover_text(
+                    "resolution of the particles and the vector field relative to the window",
+                );
+            if response.changed() {
+                renderer.set_render_scale(scale);
+            }
+        });
+    }
+
+    fn edit_motion_blur(ui: &mut Ui, renderer: &mut Renderer) {
+        ui.horizontal(|ui| {
+            ui.label("motion blur: ");
+            let mut strength = renderer.motion_blur_strength();
+            let response = ui
+                .add(Slider::new(&mut strength, 0.0..=4.0))
+                .on_hover_text("time steps the particles are smeared over, 0 disables the blur");
+            if response.changed() {
+                renderer.set_motion_blur_strength(strength);
+            }
+        });
+    }
+
+    fn edit_slice_view(ui: &mut Ui, app: &mut App) {
+        let slice_view = &mut app.renderer.slice_view;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut slice_view.enabled, "slice view")
+                .on_hover_text("shows the particles in a slab of the volume from the side");
+            if !slice_view.enabled {
+                return;
+            }
+            ComboBox::from_id_source("slice axis")
+                .selected_text(SliceView::axis_name(slice_view.axis))
+                .show_ui(ui, |ui| {
+                    for axis in 0..3 {
+                        ui.selectable_value(&mut slice_view.axis, axis, SliceView::axis_name(axis));
+                    }
+                });
+        });
+        if !slice_view.enabled {
+            return;
+        }
+        let extent = app.sim_params.bounding_volume_extents[slice_view.axis];
+        ui.horizontal(|ui| {
+            ui.label("slice offset: ");
+            ui.add(Slider::new(&mut slice_view.offset, -extent..=extent));
+        });
+        ui.horizontal(|ui| {
+            ui.label("slice thickness: ");
+            ui.add(
+                Slider::new(
+                    &mut slice_view.half_thickness,
+                    0.01..=app.sim_params.bounding_volume_radius,
+                )
+                .logarithmic(true),
+            )
+            .on_hover_text("half the thickness of the slab");
+        });
+    }
+
+    /// the slice image in a window of its own, dragging it up and down moves the slice plane
+    fn show_slice_view(ctx: &Context, app: &mut App) {
+        let slice_view = &mut app.renderer.slice_view;
+        let extent = app.sim_params.bounding_volume_extents[slice_view.axis];
+        let mut open = true;
+        Window::new("Slice view")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let (u, v) = slice_view.image_axes();
+                ui.label(format!(
+                    "{} = {:.2}, {} right, {} up",
+                    SliceView::axis_name(slice_view.axis),
+                    slice_view.offset,
+                    SliceView::axis_name(u),
+                    SliceView::axis_name(v)
+                ));
+                let size = Vec2::splat(SLICE_VIEW_SIZE as f32 / ctx.pixels_per_point());
+                let response = ui
+                    .add(Image::new((slice_view.texture_id, size)).sense(Sense::drag()))
+                    .on_hover_text("drag up and down to move the slice plane");
+                if response.dragged() {
+                    // the height of the image spans the whole bounding volume
+                    let delta = -response.drag_delta().y / size.y * 2.0 * extent;
+                    slice_view.offset = (slice_view.offset + delta).clamp(-extent, extent);
+                }
+            });
+        slice_view.enabled = open;
+    }
+
+    fn edit_camera_speed(ui: &mut Ui, camera: &mut Camera) {
+        ui.horizontal(|ui| {
+            ui.label("camera speed");
+            ui.add(Slider::new(&mut camera.units_per_second, 2.0..=20.0).logarithmic(true));
+        });
+    }
+
+    fn edit_wheel_sensitivity(ui: &mut Ui, camera: &mut Camera) {
+        ui.horizontal(|ui| {
+            ui.label("wheel zoom");
+            ui.add(Slider::new(&mut camera.wheel_zoom_sensitivity, 0.01..=0.5).logarithmic(true))
+                .on_hover_text("zooms towards the point under the mouse");
+        });
+        ui.horizontal(|ui| {
+            ui.label("wheel cursor depth");
+            ui.add(Slider::new(&mut camera.wheel_depth_sensitivity, 0.05..=2.0).logarithmic(true))
+                .on_hover_text(
+                    "moves the cursor along the mouse ray while Ctrl, Shift or Alt is held",
+                );
+        });
+    }
+
+    fn edit_camera_mode(ui: &mut Ui, camera: &mut Camera) {
+        ui.horizontal(|ui| {
+            ui.label("mode: ");
+            for mode in CameraMode::ALL {
+                if ui
+                    .selectable_label(camera.mode() == mode, mode.name())
+                    .clicked()
+                {
+                    camera.set_mode(mode);
+                }
+            }
+        });
+        if camera.mode() != CameraMode::Fly {
+            return;
+        }
+        ui.label("W/S fly forward and back, A/D sideways, E/R up and down");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut camera.mouse_look, "mouse look")
+                .on_hover_text("Tab toggles the mouse look, Escape releases the pointer");
+            ui.label("sensitivity: ");
+            ui.add(Slider::new(&mut camera.mouse_sensitivity, 0.01..=1.0).logarithmic(true));
+        });
+    }
+
+    fn edit_gamepad(ui: &mut Ui, gamepad: &mut GamepadInput) {
+        if !GamepadInput::supported() {
+            ui.label("built without the gamepad feature");
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut gamepad.enabled, "gamepad").on_hover_text(
+                "sticks move and turn the camera, triggers change the speed, \
+                 the d-pad changes the brush strength",
+            );
+            ui.label(gamepad.connected.as_deref().unwrap_or("none connected"));
+        });
+    }
+
+    fn edit_projection(ui: &mut Ui, camera: &mut Camera) {
+        let (mut fov, mut near, mut far) = camera.projection();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("field of view: ");
+            changed |= ui
+                .add(Slider::new(&mut fov, 10.0..=150.0).suffix("°"))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("near plane: ");
+            changed |= ui
+                .add(Slider::new(&mut near, 0.001..=10.0).logarithmic(true))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("far plane: ");
+            changed |= ui
+                .add(Slider::new(&mut far, 10.0..=10000.0).logarithmic(true))
+                .on_hover_text("raise this when a large bounding volume is clipped")
+                .changed();
+        });
+        if changed {
+            camera.set_projection(fov, near, far);
+        }
+    }
+
+    fn edit_velocities(ui: &mut Ui, app: &mut App) {
+        if let Some(result) = app.compute.reduction.result {
+            ui.label(format!(
+                "temperature: {:.4}",
+                result.temperature(app.compute.num_particles)
+            ));
+        }
+        let velocity_ops = &mut app.compute.velocity_ops;
+        ui.horizontal(|ui| {
+            if ui.button("zero velocities").clicked() {
+                velocity_ops.pending = Some(VelocityOp::ZeroVelocities);
+            }
+            if ui.button("zero momentum").clicked() {
+                velocity_ops.pending = Some(VelocityOp::RemoveMomentum);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("target temperature: ");
+            ui.add(
+                Slider::new(&mut velocity_ops.target_temperature, 0.0001..=10.0).logarithmic(true),
+            );
+            if ui.button("set").clicked() {
+                velocity_ops.pending = Some(VelocityOp::SetTemperature);
+            }
+        });
+    }
+
+    fn edit_workgroup_size(ui: &mut Ui, app: &mut App) {
+        let device = &app.renderer.device;
+        let compute = &mut app.compute;
+        ui.horizontal(|ui| {
+            ui.label("workgroup size: ");
+            let mut workgroup_size = compute.workgroup_size;
+            ComboBox::from_id_source("workgroup size")
+                .selected_text(workgroup_size.to_string())
+                .show_ui(ui, |ui| {
+                    for size in WORKGROUP_SIZES {
+                        ui.selectable_value(&mut workgroup_size, size, size.to_string());
+                    }
+                });
+            if workgroup_size != compute.workgroup_size {
+                compute.set_workgroup_size(device, &app.renderer.queue, workgroup_size);
+            }
+            let timings = compute
+                .workgroup_timings
+                .iter()
+                .map(|(size, seconds)| format!("{}: {:.3} ms", size, seconds * 1000.0))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if ui.button("autotune").on_hover_text(timings).clicked() {
+                compute.autotune_workgroup_size(device, &app.renderer.queue);
+            }
+        });
+    }
+
+    fn edit_particle_sort(ui: &mut Ui, particle_sort: &mut ParticleSort) {
+        ui.horizontal(|ui| {
+            ui.label("sort particles every ");
+            ui.add(DragValue::new(&mut particle_sort.interval).clamp_range(0..=600));
+            ui.label("steps");
+        })
+        .response
+        .on_hover_text(
+            "groups close particles in memory, which speeds up the force loop, 0 never sorts",
+        );
+    }
+
+    fn edit_camera_follow(ui: &mut Ui, app: &mut App) {
+        let camera = &mut app.renderer.camera;
+        ui.checkbox(&mut camera.follow_center_of_mass, "follow center of mass");
+        ui.horizontal(|ui| {
+            if ui
+                .button("follow particle at cursor")
+                .on_hover_text("keeps the camera on the particle closest to the cursor")
+                .clicked()
+            {
+                let cursor = app.renderer.camera.cursor.pos;
+                app.renderer.camera.follow_particle = app
+                    .compute
+                    .follow_nearest_particle(&app.renderer.device, &app.renderer.queue, cursor)
+                    .is_some();
+            }
+            let camera = &mut app.renderer.camera;
+            match app.compute.particle_tracker.id {
+                Some(id) if camera.follow_particle => {
+                    ui.label(format!("following particle {}", id));
+                    if ui.button("clear").clicked() {
+                        camera.follow_particle = false;
+                        app.compute.particle_tracker.stop();
+                    }
+                }
+                _ => camera.follow_particle = false,
+            }
+        });
+        let camera = &mut app.renderer.camera;
+        if camera.follow_center_of_mass || camera.follow_particle {
+            ui.horizontal(|ui| {
+                ui.label("follow smoothing: ");
+                ui.add(Slider::new(&mut camera.follow_smoothing, 0.1..=10.0).logarithmic(true));
+            });
+        }
+        if let Some(result) = app.compute.reduction.result {
+            let (c, p) = (result.center_of_mass, result.momentum);
+            ui.label(format!(
+                "center of mass: ({:.2}, {:.2}, {:.2})",
+                c.x, c.y, c.z
+            ));
+            ui.label(format!(
+                "total mass: {:.1}  momentum: ({:.2}, {:.2}, {:.2})  kinetic energy: {:.2}",
+                result.total_mass, p.x, p.y, p.z, result.kinetic_energy
+            ));
+        }
+    }
+
+    fn edit_view_distance(ui: &mut Ui, app: &mut App) {
+        if let Some((_, distance)) = app.renderer.camera.look_at_distance.as_mut() {
+            ui.horizontal(|ui| {
+                ui.label("view distance: ");
+                ui.add(Slider::new(distance, 0.1..=20.0).logarithmic(true));
+            });
+        }
+    }
+
+    fn edit_max_velocity(ui: &mut Ui, sim_params: &mut SimParams) {
+        ui.horizontal(|ui| {
+            ui.label("max velocity: ");
+            ui.add(Slider::new(&mut sim_params.max_velocity, 0.1..=100.0).logarithmic(true));
+        });
+    }
+
+    fn edit_distance_exponent(ui: &mut Ui, sim_params: &mut SimParams) {
+        ui.horizontal(|ui| {
+            ui.label("distance exponent: ");
+            ui.add(Slider::new(&mut sim_params.distance_exponent, -5.0..=5.0));
+        });
+    }
+
+    fn edit_bounding_volume_radius(ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            ui.label("bounding volume size :");
+            let mut val = app.sim_params.bounding_volume_radius * 2.0;
+            if ui.add(Slider::new(&mut val, 0.5..=10.0)).changed() {
+                app.set_bounding_volume_radius(val * 0.5);
+                app.boundary_animation.base_radius = val * 0.5;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("size along x y z: ");
+            let mut size = app.sim_params.bounding_volume_extents.map(|e| e * 2.0);
+            let mut changed = false;
+            for s in size.iter_mut() {
+                changed |= ui
+                    .add(DragValue::new(s).speed(0.02).clamp_range(0.1..=20.0))
+                    .changed();
+            }
+            if changed {
+                app.set_bounding_volume_extents(size.map(|s| s * 0.5));
+                app.boundary_animation.base_radius = app.sim_params.bounding_volume_radius;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("vector field dimensions: ");
+            let mut dimensions = app.sim_params.force_grid_dimensions;
+            let mut changed = false;
+            for d in dimensions.iter_mut() {
+                changed |= ui.add(DragValue::new(d).clamp_range(1..=64)).changed();
+            }
+            if changed {
+                app.set_force_grid_dimensions(dimensions);
+            }
+        })
+        .response
+        .on_hover_text("the vector field is resampled to the new resolution");
+        Self::memory_warnings(ui, &app.memory_warnings);
+    }
+
+    fn edit_boundary_animation(ui: &mut Ui, app: &mut App) {
+        let animation = &mut app.boundary_animation;
+        ui.horizontal(|ui| {
+            ui.label("boundary animation: ");
+            ui.selectable_value(&mut animation.mode, BoundaryAnimationMode::Off, "off");
+            ui.selectable_value(&mut animation.mode, BoundaryAnimationMode::Sine, "sine");
+            ui.selectable_value(
+                &mut animation.mode,
+                BoundaryAnimationMode::Keyframes,
+                "keyframes",
+            );
+        });
+        match animation.mode {
+            BoundaryAnimationMode::Off => {}
+            BoundaryAnimationMode::Sine => {
+                ui.horizontal(|ui| {
+                    ui.label("amplitude: ");
+                    ui.add(Slider::new(&mut animation.amplitude, 0.0..=0.9));
+                    ui.label("period: ");
+                    ui.add(Slider::new(&mut animation.period, 1.0..=120.0).logarithmic(true));
+                });
+            }
+            BoundaryAnimationMode::Keyframes => {
+                let mut remove = None;
+                for (i, (time, radius)) in animation.keyframes.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(DragValue::new(time).prefix("t: ").speed(0.1));
+                        ui.add(
+                            DragValue::new(radius)
+                                .prefix("radius: ")
+                                .speed(0.01)
+                                .clamp_range(0.25..=5.0),
+                        );
+                        if ui.button("remove").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    animation.keyframes.remove(i);
+                }
+                if ui.button("add keyframe at current radius").clicked() {
+                    let time = animation.keyframes.last().map_or(0.0, |k| k.0 + 5.0);
+                    animation.add_keyframe(time, app.sim_params.bounding_volume_radius);
+                }
+                animation.keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+            }
+        }
+    }
+
+    fn edit_force_grid_interpolation(ui: &mut Ui, sim_params: &mut SimParams) {
+        let interpolation = &mut sim_params.force_grid_interpolation;
+        ui.horizontal(|ui| {
+            ui.label("force grid interpolation: ");
+            ui.selectable_value(interpolation, ForceGridInterpolation::Nearest, "nearest");
+            ui.selectable_value(
+                interpolation,
+                ForceGridInterpolation::Trilinear,
+                "trilinear",
+            );
+            ui.selectable_value(interpolation, ForceGridInterpolation::Cubic, "cubic");
+        });
+    }
+
+    fn edit_force_grid_boundary(ui: &mut Ui, sim_params: &mut SimParams) {
+        let boundary = &mut sim_params.force_grid_boundary;
+        ui.horizontal(|ui| {
+            ui.label("outside of the force grid: ");
+            ui.selectable_value(boundary, ForceGridBoundary::Clamp, "clamp to edge");
+            ui.selectable_value(boundary, ForceGridBoundary::Zero, "zero force");
+            ui.selectable_value(boundary, ForceGridBoundary::Wrap, "wrap");
+        });
+    }
+
+    fn edit_confinement(ui: &mut Ui, sim_params: &mut SimParams) {
+        ui.horizontal(|ui| {
+            ui.label("bounding volume: ");
+            let confinement = &mut sim_params.confinement;
+            ui.selectable_value(confinement, Confinement::Wrap, "wrap around");
+            ui.selectable_value(confinement, Confinement::Polynomial, "polynomial");
+            ui.selectable_value(confinement, Confinement::Exponential, "exponential");
+            ui.selectable_value(confinement, Confinement::WindTunnel, "wind tunnel")
+                .on_hover_text(
+                    "particles leaving at either end enter again at the inflow face, \
+                     the solid cursor and sticky materials make obstacles",
+                );
+        });
+        match sim_params.confinement {
+            Confinement::Wrap => return,
+            Confinement::WindTunnel => return Self::edit_wind_tunnel(ui, sim_params),
+            _ => {}
+        }
+        ui.horizontal(|ui| {
+            ui.label("start: ");
+            ui.add(Slider::new(&mut sim_params.confinement_start, 0.0..=0.99));
+            ui.label("strength: ");
+            ui.add(
+                Slider::new(&mut sim_params.confinement_strength, 0.0..=200.0).logarithmic(true),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(match sim_params.confinement {
+                Confinement::Exponential => "sharpness: ",
+                _ => "power: ",
+            });
+            ui.add(Slider::new(
+                &mut sim_params.confinement_exponent,
+                0.1..=10.0,
+            ));
+        });
+        // restoring force over the distance from the center in bounding volume radii
+        let points: PlotPoints = (0..=100)
+            .map(|i| {
+                let x = i as f32 * 0.01;
+                [x as f64, sim_params.confinement_force(x) as f64]
+            })
+            .collect();
+        Plot::new("confinement plot")
+            .view_aspect(3.0)
+            .show(ui, |plot_ui| plot_ui.line(Line::new(points)));
+    }
+
+    fn edit_wind_tunnel(ui: &mut Ui, sim_params: &mut SimParams) {
+        ui.horizontal(|ui| {
+            ui.label("flow along: ");
+            for (i, name) in ["x", "y", "z"].into_iter().enumerate() {
+                ui.selectable_value(&mut sim_params.inflow_axis, i as u32, name);
+            }
+            ui.label("speed: ");
+            ui.add(DragValue::new(&mut sim_params.inflow_speed).speed(0.05))
+                .on_hover_text("negative speeds flow towards the negative side");
+        });
+        ui.add(Slider::new(&mut sim_params.inflow_profile, 0.0..=1.0).text("profile"))
+            .on_hover_text("0 is a uniform stream, 1 a parabolic one, still at the walls");
+        ui.add(Slider::new(&mut sim_params.inflow_turbulence, 0.0..=2.0).text("turbulence"))
+            .on_hover_text("random velocity of the inflowing particles relative to the speed");
+        ui.label("the particles are dragged along with the stream, sticky materials hold it back");
+        ui.horizontal(|ui| {
+            ui.label("emitter: ");
+            ui.add(
+                DragValue::new(&mut sim_params.emitter_rate)
+                    .prefix("rate: ")
+                    .suffix(" /s")
+                    .speed(0.01)
+                    .clamp_range(0.0..=10.0),
+            )
+            .on_hover_text(
+                "fraction of the particles moved onto the emitter per second, 0 disables it",
+            );
+        });
+        if sim_params.emitter_rate > 0.0 {
+            ui.add(Slider::new(&mut sim_params.emitter_position, 0.0..=1.0).text("position"))
+                .on_hover_text("0 at the inflow face, 1 at the outflow face");
+            ui.add(Slider::new(&mut sim_params.emitter_size, 0.01..=1.0).text("size"))
+                .on_hover_text("fraction of the cross section the emitter covers");
+        }
+    }
+
+    fn edit_gravity(ui: &mut Ui, sim_params: &mut SimParams) {
+        ui.horizontal(|ui| {
+            ui.label("gravity: ");
+            for g in sim_params.gravity.iter_mut() {
+                ui.add(DragValue::new(g).speed(0.05));
+            }
+            if ui.button("earth").clicked() {
+                sim_params.gravity = [0.0, -9.81, 0.0];
+            }
+            if ui.button("none").clicked() {
+                sim_params.gravity = [0.0; 3];
+            }
+        });
+        let mut floor = sim_params.floor != 0;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut floor, "floor")
+                .on_hover_text("the particles bounce off a horizontal plane");
+            let extent = sim_params.bounding_volume_extents[1];
+            ui.add_enabled(
+                floor,
+                Slider::new(&mut sim_params.floor_height, -extent..=extent).text("height"),
+            );
+        });
+        sim_params.floor = floor as u32;
+        if !floor {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("restitution: ");
+            ui.add(Slider::new(&mut sim_params.floor_restitution, 0.0..=1.0))
+                .on_hover_text(
+                    "the fraction of the speed into the floor a particle bounces back with",
+                );
+            ui.label("friction: ");
+            ui.add(Slider::new(&mut sim_params.floor_friction, 0.0..=2.0))
+                .on_hover_text("slows the particles along the floor when they hit it");
+        });
+    }
+
+    /// boids terms of the type pair selected in the polynome selection matrix
+    fn edit_flocking(&mut self, ui: &mut Ui, sim_params: &mut SimParams) {
+        let (x, y) = (self.poly_index % 5, self.poly_index / 5);
+        ui.label(format!("type {} towards type {}", y, x))
+            .on_hover_text("select the pair in the polynome selection matrix");
+        let flocking = &mut sim_params.flocking[self.poly_index];
+        ui.horizontal(|ui| {
+            ui.label("alignment: ");
+            ui.add(Slider::new(&mut flocking.alignment, -10.0..=10.0))
+                .on_hover_text("steers towards the mean velocity of the neighbours");
+        });
+        ui.horizontal(|ui| {
+            ui.label("cohesion: ");
+            ui.add(Slider::new(&mut flocking.cohesion, -10.0..=10.0))
+                .on_hover_text("steers towards the mean position of the neighbours");
+        });
+        ui.horizontal(|ui| {
+            ui.label("separation: ");
+            ui.add(Slider::new(&mut flocking.separation, 0.0..=1.0))
+                .on_hover_text("pushes away from close neighbours");
+        });
+        ui.horizontal(|ui| {
+            ui.label("neighbour radius: ");
+            ui.add(Slider::new(&mut flocking.radius, 0.01..=5.0).logarithmic(true));
+        });
+        let flocking = *flocking;
+        ui.horizontal(|ui| {
+            if ui.button("apply to all pairs").clicked() {
+                sim_params.flocking = [flocking; 25];
+            }
+            if ui.button("apply to own type").clicked() {
+                for i in 0..5 {
+                    sim_params.flocking[i + i * 5] = flocking;
+                }
+            }
+        });
+    }
+
+    fn edit_particle_lifetime(&mut self, ui: &mut Ui, app: &mut App) {
+        let params = &mut app.renderer.particle_render_params;
+        ui.horizontal(|ui| {
+            ui.label("particle lifetime (0 = forever): ");
+            ui.add(Slider::new(
+                &mut app.sim_params.particle_lifetime,
+                0.0..=60.0,
+            ));
+        });
+        ui.horizontal(|ui| {
+            ui.label("birth duration: ");
+            ui.add(Slider::new(&mut params.birth_duration, 0.0..=2.0));
+            ui.label("death fade: ");
+            ui.add(Slider::new(&mut params.death_duration, 0.0..=5.0));
+        });
+        ui.horizontal(|ui| {
+            let mut use_color_ramp = params.use_color_ramp != 0;
+            if ui.checkbox(&mut use_color_ramp, "age color ramp").changed() {
+                params.use_color_ramp = use_color_ramp as u32;
+            }
+            ComboBox::from_id_source("color ramp")
+                .selected_text("preset")
+                .show_ui(ui, |ui| {
+                    for color_map in ColorMap::ALL {
+                        ui.horizontal(|ui| {
+                            Self::color_map_preview(ui, color_map);
+                            if ui.selectable_label(false, color_map.name()).clicked() {
+                                app.renderer.color_ramp = ColorRamp::from_map(color_map);
+                            }
+                        });
+                    }
+                });
+        });
+        self.edit_color_ramp(ui, &mut app.renderer.color_ramp);
+        if app.sim_params.particle_lifetime == 0.0 {
+            ui.horizontal(|ui| {
+                ui.label("color ramp duration: ");
+                ui.add(Slider::new(&mut params.ramp_duration, 0.1..=120.0).logarithmic(true));
+            });
+        }
+    }
+
+    fn edit_type_sizes(&self, ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            ui.label("size per type: ");
+            for (i, name) in self.element_text.iter().enumerate() {
+                let size = app.renderer.particle_render_params.type_size_mut(i);
+                ui.add(
+                    DragValue::new(size)
+                        .prefix(format!("{name}: "))
+                        .speed(0.01)
+                        .clamp_range(0.0..=10.0),
+                );
+            }
+        });
+        self.edit_visible_types(ui, &mut app.renderer.particle_render_params);
+    }
+
+    /// isolates some of the types, e.g. to look at the structure inside of a dense cloud
+    fn edit_visible_types(&self, ui: &mut Ui, params: &mut ParticleRenderParams) {
+        ui.horizontal(|ui| {
+            ui.label("visible types: ");
+            for (i, name) in self.element_text.iter().enumerate() {
+                let mut visible = params.type_visible(i);
+                if ui.checkbox(&mut visible, name.as_str()).changed() {
+                    params.set_type_visible(i, visible);
+                }
+            }
+            if ui.button("all").clicked() {
+                params.visible_types = u32::MAX;
+            }
+        });
+    }
+
+    fn edit_type_tints(&self, ui: &mut Ui, app: &mut App) {
+        let tints = &mut app.renderer.particle_render_params.type_tints;
+        ui.horizontal(|ui| {
+            ui.label("tint per type: ");
+            for (tint, name) in tints.iter_mut().zip(self.element_text.iter()) {
+                ui.label(format!("{name}: "));
+                ui.color_edit_button_rgba_unmultiplied(tint);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("palette: ");
+            for palette in Palette::ALL {
+                if ui.button(palette.name()).clicked() {
+                    *tints = palette.tints();
+                }
+            }
+        });
+    }
+
+    fn edit_assets(ui: &mut Ui, assets: &mut AssetManager) {
+        let mut reload = None;
+        Grid::new("asset paths").show(ui, |ui| {
+            for slot in AssetSlot::ALL {
+                ui.label(format!("{}: ", slot.name()));
+                ui.text_edit_singleline(&mut assets.paths[slot as usize]);
+                if ui.button("load").clicked() {
+                    reload = Some(slot);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(slot) = reload {
+            assets.reload(slot);
+        }
+        ui.horizontal(|ui| {
+            ui.label("dropped files replace the: ");
+            ui.selectable_value(&mut assets.drop_on_cursor, true, "cursor");
+            ui.selectable_value(&mut assets.drop_on_cursor, false, "vectors");
+        });
+        if assets.pending() > 0 {
+            ui.label(format!("loading {} assets", assets.pending()));
+        }
+        for (slot, error) in &assets.errors {
+            ui.colored_label(Color32::RED, format!("{}: {}", slot.name(), error));
+        }
+    }
+
+    fn edit_scene_file(ui: &mut Ui, app: &mut App) {
+        ui.horizontal(|ui| {
+            ui.label("file: ");
+            ui.text_edit_singleline(&mut app.scene_file.path);
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button("save")
+                .on_hover_text("forces, masses, limits and particle tints")
+                .clicked()
+            {
+                app.save_scene();
+            }
+            if ui.button("load").clicked() {
+                app.load_scene();
+            }
+        });
+        if let Some(e) = app.scene_file.error.as_ref() {
+            ui.colored_label(Color32::RED, e);
+        }
+        for warning in &app.scene_file.warnings {
+            ui.colored_label(Color32::YELLOW, warning);
+        }
+    }
+
+    fn preset_gallery(ui: &mut Ui, app: &mut App) {
+        let mut applied = None;
+        Grid::new("preset gallery").show(ui, |ui| {
+            for (i, preset) in PRESETS.iter().enumerate() {
+                if Self::force_thumbnail(ui, &preset.thumbnail())
+                    .on_hover_text("click to apply")
+                    .clicked()
+                {
+                    applied = Some(preset);
+                }
+                ui.vertical(|ui| {
+                    if ui.button(preset.name).clicked() {
+                        applied = Some(preset);
+                    }
+                    ui.small(preset.description);
+                });
+                if i % 2 == 1 {
+                    ui.end_row();
+                }
+            }
+        });
+        if let Some(preset) = applied {
+            app.apply_preset(preset);
+        }
+    }
+
+    /// randomizes, mutates and breeds the forces and masses, the sets tried last are listed
+    /// with their thumbnails to go back to or to breed
+    fn explorer(ui: &mut Ui, app: &mut App) {
+        let explorer = &mut app.explorer;
+        ui.add(Slider::new(&mut explorer.force_range, 0.1..=10.0).text("force range"))
+            .on_hover_text("random force profiles lie within plus minus this");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut explorer.randomize_masses, "masses from ");
+            let (min, max) = &mut explorer.mass_range;
+            ui.add(DragValue::new(min).speed(0.01).clamp_range(0.01..=100.0));
+            ui.label("to");
+            ui.add(DragValue::new(max).speed(0.01).clamp_range(0.01..=100.0));
+        });
+        ui.add(Slider::new(&mut explorer.mutation_strength, 0.01..=1.0).text("mutation strength"))
+            .on_hover_text("fraction of the ranges a mutation changes the values by at most");
+        ui.add(Slider::new(&mut explorer.history_len, 2..=64).text("history length"));
+        ui.checkbox(&mut explorer.respawn, "respawn particles")
+            .on_hover_text("every new set starts from freshly spawned particles");
+        let mut explored = None;
+        ui.horizontal(|ui| {
+            let (sim_params, symmetry) = (&app.sim_params, app.matrix_symmetry);
+            let explorer = &mut app.explorer;
+            if ui.button("randomize").clicked() {
+                explored = Some(explorer.randomize(sim_params, symmetry));
+            }
+            if ui
+                .button("mutate")
+                .on_hover_text("changes the current forces and masses a little")
+                .clicked()
+            {
+                explored = Some(explorer.mutate(sim_params, symmetry));
+            }
+            let can_breed = explorer.parents.iter().all(Option::is_some);
+            if ui
+                .add_enabled(can_breed, Button::new("breed"))
+                .on_hover_text("takes every force and mass from parent A or B at random")
+                .on_disabled_hover_text("pick the parents A and B in the history first")
+                .clicked()
+            {
+                explored = explorer.breed(sim_params, symmetry);
+            }
+        });
+        Grid::new("explorer history").show(ui, |ui| {
+            let explorer = &mut app.explorer;
+            for (i, set) in explorer.history.iter().enumerate() {
+                if Self::force_thumbnail(ui, &set.thumbnail)
+                    .on_hover_text("click to revert to this set")
+                    .clicked()
+                {
+                    explored = Some(set.sim_params(&app.sim_params));
+                }
+                ui.label(&set.label);
+                for (parent, name) in explorer.parents.iter_mut().zip(["A", "B"]) {
+                    if ui.selectable_label(*parent == Some(i), name).clicked() {
+                        *parent = (*parent != Some(i)).then_some(i);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(sim_params) = explored {
+            app.apply_explored(sim_params);
+        }
+    }
+
+    fn edit_detector(ui: &mut Ui, app: &mut App) {
+        let detector = &mut app.detector;
+        ui.checkbox(&mut detector.enabled, "detect interesting behavior")
+            .on_hover_text("reads a sample of the particles back every few simulated seconds");
+        ui.add(Slider::new(&mut detector.interval, 0.1..=10.0).text("sample interval"));
+        ui.add(
+            Slider::new(&mut detector.sample_size, 256..=65536)
+                .logarithmic(true)
+                .text("sample size"),
+        );
+        ui.add(
+            Slider::new(&mut detector.link_distance, 0.01..=2.0)
+                .logarithmic(true)
+                .text("cluster link distance"),
+        );
+        ui.add(Slider::new(&mut detector.min_cluster_size, 2..=64).text("min cluster size"));
+        ui.label("interesting ranges:");
+        Grid::new("detector ranges").show(ui, |ui| {
+            let metrics = detector.metrics;
+            ui.label("clusters");
+            ui.add(DragValue::new(&mut detector.clusters.0).clamp_range(0..=1000));
+            ui.add(DragValue::new(&mut detector.clusters.1).clamp_range(0..=1000));
+            ui.label(metrics.map_or(String::new(), |m| m.clusters.to_string()));
+            ui.end_row();
+            ui.label("velocity variance");
+            let (min, max) = &mut detector.velocity_variance;
+            ui.add(DragValue::new(min).speed(0.001).clamp_range(0.0..=1e6));
+            ui.add(DragValue::new(max).speed(0.01).clamp_range(0.0..=1e6));
+            ui.label(metrics.map_or(String::new(), |m| format!("{:.4}", m.velocity_variance)));
+            ui.end_row();
+            ui.label("spatial entropy");
+            let (min, max) = &mut detector.spatial_entropy;
+            ui.add(DragValue::new(min).speed(0.01).clamp_range(0.0..=1.0));
+            ui.add(DragValue::new(max).speed(0.01).clamp_range(0.0..=1.0));
+            ui.label(metrics.map_or(String::new(), |m| format!("{:.3}", m.spatial_entropy)));
+            ui.end_row();
+        });
+        ui.add(Slider::new(&mut detector.required_samples, 1..=20).text("samples in a row"));
+        if detector.flagged {
+            ui.colored_label(Color32::GREEN, "interesting regime");
+        }
+        ui.label(format!("{} regimes flagged", detector.num_flagged));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut detector.auto_save, "save to ")
+                .on_hover_text("writes the scene and a screenshot of every flagged regime");
+            ui.text_edit_singleline(&mut detector.directory);
+        });
+        ui.checkbox(&mut detector.auto_explore, "explore unattended")
+            .on_hover_text("randomizes the forces after a flagged regime or when time runs out");
+        if detector.auto_explore {
+            ui.add(
+                Slider::new(&mut detector.candidate_time, 1.0..=600.0)
+                    .logarithmic(true)
+                    .text("seconds per candidate"),
+            );
+            ui.label(format!(
+                "candidate running for {:.0} s",
+                detector.candidate_age
+            ));
+        }
+        if let Some(e) = detector.error.as_ref() {
+            ui.colored_label(Color32::RED, e);
+        }
+    }
+
+    /// the A/B split screen, the controls edit one side at a time
+    fn edit_comparison(ui: &mut Ui, app: &mut App) {
+        let Some(editing_b) = app.comparison.as_ref().map(|c| c.editing_b) else {
+            if ui
+                .button("start A/B comparison")
+                .on_hover_text(
+                    "runs a second simulation from the current particles beside this one",
+                )
+                .clicked()
+            {
+                app.start_comparison();
+            }
+            return;
+        };
+        let (edited, other) = if editing_b {
+            ("B on the right", "A")
+        } else {
+            ("A on the left", "B")
+        };
+        ui.label(format!("the controls edit {}", edited));
+        ui.horizontal(|ui| {
+            if ui.button(format!("edit {}", other)).clicked() {
+                app.swap_comparison_sides();
+            }
+            if ui.button(format!("copy parameters to {}", other)).clicked() {
+                if let Some(comparison) = app.comparison.as_mut() {
+                    comparison.sim_params = app.sim_params;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button(format!("restart {}", other))
+                .on_hover_text(format!(
+                    "continues {} from the particles of {}",
+                    other, edited
+                ))
+                .clicked()
+            {
+                app.sync_comparison();
+            }
+            if ui
+                .button("stop comparison")
+                .on_hover_text(format!("{} goes on alone", edited))
+                .clicked()
+            {
+                app.comparison = None;
+            }
+        });
+    }
+
+    /// the mean forces between the particle types, attraction in green and repulsion in red
+    fn force_thumbnail(ui: &mut Ui, thumbnail: &[[f32; 5]; 5]) -> Response {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(50.0), Sense::click());
+        let max = thumbnail
+            .iter()
+            .flatten()
+            .fold(f32::EPSILON, |max, force| max.max(force.abs()));
+        let cell = rect.width() / thumbnail.len() as f32;
+        for (y, row) in thumbnail.iter().enumerate() {
+            for (x, force) in row.iter().enumerate() {
+                let t = (force.abs() / max).sqrt();
+                let color = if *force > 0.0 {
+                    Rgba::from_rgb(0.0, t, 0.0)
+                } else {
+                    Rgba::from_rgb(t, 0.0, 0.0)
+                };
+                let min = rect.min + Vec2::new(x as f32, y as f32) * cell;
+                ui.painter()
+                    .rect_filled(Rect::from_min_size(min, Vec2::splat(cell)), 0.0, color);
+            }
+        }
+        if response.hovered() {
+            ui.painter()
+                .rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::WHITE));
+        }
+        response
+    }
+
+    /// a gradient bar with a handle per stop, dragging a handle moves the stop,
+    /// a double click on the bar adds one. the alpha of the stops fades the particles
+    fn edit_color_ramp(&mut self, ui: &mut Ui, ramp: &mut ColorRamp) {
+        const HANDLE_RADIUS: f32 = 5.0;
+        let (rect, response) =
+            ui.allocate_exact_size(Vec2::new(240.0, 28.0), Sense::click_and_drag());
+        let bar = Rect::from_min_max(rect.min, Pos2::new(rect.max.x, rect.max.y - 12.0));
+        let steps = 48;
+        let width = bar.width() / steps as f32;
+        for i in 0..steps {
+            let [r, g, b, a] = ramp.eval((i as f32 + 0.5) / steps as f32);
+            let min = bar.min + Vec2::new(i as f32 * width, 0.0);
+            ui.painter().rect_filled(
+                Rect::from_min_size(min, Vec2::new(width + 0.5, bar.height())),
+                0.0,
+                Rgba::from_rgba_unmultiplied(r, g, b, a),
+            );
+        }
+        let t_at = |x: f32| ((x - bar.min.x) / bar.width()).clamp(0.0, 1.0);
+        let handle = |t: f32| Pos2::new(bar.min.x + t * bar.width(), rect.max.y - HANDLE_RADIUS);
+        if response.drag_started() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let nearest = ramp
+                    .stops
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| {
+                        let da = (handle(a.t).x - pointer.x).abs();
+                        let db = (handle(b.t).x - pointer.x).abs();
+                        da.total_cmp(&db)
+                    })
+                    .map(|(i, _)| i);
+                self.color_stop = nearest.unwrap_or(0);
+            }
+        }
+        if response.dragged() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                self.color_stop = ramp.move_stop(self.color_stop, t_at(pointer.x));
+            }
+        }
+        if response.double_clicked() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                self.color_stop = ramp.insert(t_at(pointer.x));
+            }
+        }
+        self.color_stop = self.color_stop.min(ramp.stops.len() - 1);
+        for (i, stop) in ramp.stops.iter().enumerate() {
+            let [r, g, b, _] = stop.color;
+            let stroke = if i == self.color_stop {
+                Stroke::new(2.0, Color32::WHITE)
+            } else {
+                Stroke::new(1.0, Color32::GRAY)
+            };
+            ui.painter().circle(
+                handle(stop.t),
+                HANDLE_RADIUS - 1.0,
+                Rgba::from_rgb(r, g, b),
+                stroke,
+            );
+        }
+        ui.horizontal(|ui| {
+            ui.label("stop: ");
+            let stop = &mut ramp.stops[self.color_stop];
+            ui.color_edit_button_rgba_unmultiplied(&mut stop.color);
+            let mut t = stop.t;
+            if ui
+                .add(DragValue::new(&mut t).speed(0.01).clamp_range(0.0..=1.0))
+                .changed()
+            {
+                self.color_stop = ramp.move_stop(self.color_stop, t);
+            }
+            if ramp.stops.len() > 2 && ui.button("remove stop").clicked() {
+                ramp.stops.remove(self.color_stop);
+                self.color_stop = self.color_stop.min(ramp.stops.len() - 1);
+            }
+        })
+        .response
+        .on_hover_text("double click the ramp to add a stop, drag the handles to move them");
+    }
+
+    fn color_map_preview(ui: &mut Ui, color_map: ColorMap) {
+        let (rect, _) = ui.allocate_exact_size(Vec2::new(80.0, 12.0), Sense::hover());
+        let steps = 16;
+        let width = rect.width() / steps as f32;
+        for i in 0..steps {
+            let [r, g, b, _] = color_map.eval(i as f32 / (steps - 1) as f32);
+            let min = rect.min + Vec2::new(i as f32 * width, 0.0);
+            ui.painter().rect_filled(
+                Rect::from_min_size(min, Vec2::new(width, rect.height())),
+                0.0,
+                Rgba::from_rgb(r, g, b),
+            );
+        }
+    }
+
+    fn edit_polys(&mut self, ui: &mut Ui, app: &mut App) {
+        ui.vertical(|ui| {
+            ui.colored_label(Color32::GREEN, "polynome selection matrix");
+            ComboBox::from_id_source("matrix symmetry")
+                .selected_text(app.matrix_symmetry.name())
+                .show_ui(ui, |ui| {
+                    for symmetry in MatrixSymmetry::ALL {
+                        if ui
+                            .selectable_value(&mut app.matrix_symmetry, symmetry, symmetry.name())
+                            .changed()
+                        {
+                            app.sim_params.enforce_symmetry(symmetry);
+                            app.force_curves.enforce_symmetry(symmetry);
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("keeps the force of type i on j tied to the force of j on i");
+            ComboBox::from_id_source("force mode")
+                .selected_text(app.sim_params.force_mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in ForceMode::ALL {
+                        ui.selectable_value(&mut app.sim_params.force_mode, mode, mode.name());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "potentials are differentiated into forces, which conserves the energy",
+                );
+            ComboBox::from_id_source("force accumulation")
+                .selected_text(app.sim_params.force_accumulation.name())
+                .show_ui(ui, |ui| {
+                    for accumulation in ForceAccumulation::ALL {
+                        ui.selectable_value(
+                            &mut app.sim_params.force_accumulation,
+                            accumulation,
+                            accumulation.name(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text("a compensated sum reduces the drift of many tiny forces");
+            for y in 0..5 {
+                ui.horizontal(|ui| {
+                    for x in 0..5 {
+                        ui.radio_value(&mut self.poly_index, x + y * 5, "");
+                    }
+                });
+            }
+        });
+    }
+
+    fn edit_pair_forces(ui: &mut Ui, app: &mut App) {
+        let sim_params = &mut app.sim_params;
+        ui.vertical(|ui| {
+            ui.colored_label(Color32::GREEN, "force scale matrix");
+            Grid::new("pair forces").show(ui, |ui| {
+                for y in 0..5 {
+                    for x in 0..5 {
+                        let pair_force = &mut sim_params.pair_forces[x + y * 5];
+                        let changed = ui
+                            .horizontal(|ui| {
+                                let mut enabled = pair_force.enabled != 0;
+                                let toggled = ui.checkbox(&mut enabled, "").changed();
+                                if toggled {
+                                    pair_force.enabled = enabled as u32;
+                                }
+                                let dragged = ui
+                                    .add_enabled(
+                                        enabled,
+                                        DragValue::new(&mut pair_force.scale)
+                                            .speed(0.01)
+                                            .clamp_range(-10.0..=10.0),
+                                    )
+                                    .changed();
+                                toggled || dragged
+                            })
+                            .inner;
+                        if changed {
+                            sim_params.mirror_force(x + y * 5, app.matrix_symmetry);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    /// the cut off distance of every pair of types, the rows are the attracted types
+    fn edit_pair_cut_offs(ui: &mut Ui, app: &mut App) {
+        let sim_params = &mut app.sim_params;
+        Grid::new("pair cut off distances").show(ui, |ui| {
+            for y in 0..5 {
+                for x in 0..5 {
+                    let pair_force = &mut sim_params.pair_forces[x + y * 5];
+                    let changed = ui
+                        .add_enabled(
+                            pair_force.enabled != 0,
+                            DragValue::new(&mut pair_force.cut_off_distance)
+                                .speed(0.01)
+                                .clamp_range(0.1..=5.0),
+                        )
+                        .changed();
+                    if changed {
+                        sim_params.mirror_force(x + y * 5, app.matrix_symmetry);
+                        sim_params.update_cut_off_distance();
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    fn edit_poly(
+        &mut self,
+        ui: &mut Ui,
+        poly: &mut Poly7,
+        force_mode: ForceMode,
+        cut_off_distance: f32,
+        cutoff_window: f32,
+    ) {
+        ui.colored_label(Color32::GREEN, "selected attraction_force polynome");
+        ui.horizontal(|ui| {
+            ui.label("drag the points, snap to: ");
+            ui.add(
+                DragValue::new(&mut self.poly_snap)
+                    .speed(0.01)
+                    .clamp_range(0.0..=1.0),
+            )
+            .on_hover_text("0 disables snapping");
+            ui.label("limit: ");
+            ui.add(
+                DragValue::new(&mut self.poly_limit)
+                    .speed(0.1)
+                    .clamp_range(0.1..=100.0),
+            );
+        });
+        let xs: [f32; 8] = std::array::from_fn(|i| (i as f32) / 7.0);
+        let mut ys = xs.map(|x| poly.eval(x));
+        let limit = self.poly_limit as f64;
+        let potential = force_mode == ForceMode::Potential;
+        let line = Line::new(poly.plot_points()).name(if potential {
+            "potential U(r)"
+        } else {
+            "attraction"
+        });
+        // the attraction the shader derives from a potential, or the potential of a force
+        let companion = if potential {
+            Some(Line::new(poly.derivative().plot_points()).name("attraction dU/dr"))
+        } else {
+            poly.antiderivative()
+                .map(|antiderivative| Line::new(antiderivative.plot_points()).name("potential"))
+        }
+        .map(|line| line.style(LineStyle::dashed_loose()));
+        // what the shader applies, faded out towards the cut off distance
+        let windowed =
+            Line::new(poly.windowed_plot_points(potential, cut_off_distance, cutoff_window))
+                .name("applied attraction")
+                .style(LineStyle::dotted_dense());
+        let samples = Points::new(
+            xs.iter()
+                .zip(&ys)
+                .map(|(x, y)| [*x as f64, *y as f64])
+                .collect::<Vec<_>>(),
+        )
+        .radius(5.0)
+        .color(Color32::YELLOW);
+        let dragged = Plot::new("poly plot")
+            .legend(Legend::default())
+            .view_aspect(2.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .allow_double_click_reset(false)
+            .show(ui, |plot_ui| {
+                plot_ui.set_plot_bounds(PlotBounds::from_min_max([-0.05, -limit], [1.05, limit]));
+                plot_ui.line(line);
+                if let Some(companion) = companion {
+                    plot_ui.line(companion);
+                }
+                plot_ui.line(windowed);
+                plot_ui.points(samples);
+                let response = plot_ui.response().clone();
+                if response.drag_started() {
+                    // the sample point nearest to the pointer, if it was hit
+                    self.dragged_poly_point = response.interact_pointer_pos.and_then(|pointer| {
+                        (0..8)
+                            .map(|i| {
+                                let point = PlotPoint::new(xs[i], ys[i]);
+                                (i, plot_ui.screen_from_plot(point).distance(pointer))
+                            })
+                            .filter(|(_, distance)| *distance < POLY_POINT_GRAB_RADIUS)
+                            .min_by(|a, b| a.1.total_cmp(&b.1))
+                            .map(|(i, _)| i)
+                    });
+                }
+                if !response.dragged() {
+                    self.dragged_poly_point = None;
+                }
+                self.dragged_poly_point
+                    .zip(plot_ui.pointer_coordinate())
+                    .map(|(i, pointer)| (i, pointer.y as f32))
+            })
+            .inner;
+        if let Some((i, y)) = dragged {
+            let y = if self.poly_snap > 0.0 {
+                (y / self.poly_snap).round() * self.poly_snap
+            } else {
+                y
+            };
+            ys[i] = y.clamp(-self.poly_limit, self.poly_limit);
+            let points = std::array::from_fn(|i| Vector2::new(xs[i], ys[i]));
+            if let Some(p) = Poly7::from_points(points) {
+                *poly = p;
+            }
+        }
+        ui.horizontal(|ui| {
+            for (i, n) in (0..8).zip(Poly7::coeff_names()) {
+                Gui::labeled_drag_value(ui, &mut poly.coeffs[i], n);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("copy").clicked() {
+                self.copy_poly = Some(*poly);
+            }
+            if let Some(cp) = self.copy_poly {
+                if ui.button("paste").clicked() {
+                    *poly = cp;
+                }
+            }
+            if ui.button("invert").clicked() {
+                poly.invert();
+            }
+            if ui.button("zero").clicked() {
+                *poly = Poly7::zero();
+            }
+        });
+    }
+
+    /// switches the selected pair between its polynome and a spline
+    fn edit_curve_kind(&mut self, ui: &mut Ui, app: &mut App) {
+        let index = self.poly_index;
+        let kind = match &app.force_curves.curves[index] {
+            ForceCurve::Poly7 => None,
+            ForceCurve::Spline { kind, .. } => Some(*kind),
+        };
+        let mut selected = kind;
+        ui.horizontal(|ui| {
+            ui.label("force curve: ");
+            ComboBox::from_id_source("force curve kind")
+                .selected_text(selected.map_or("polynome", |kind| kind.name()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut selected, None, "polynome");
+                    for kind in SplineKind::ALL {
+                        ui.selectable_value(&mut selected, Some(kind), kind.name());
+                    }
+                })
+                .response
+                .on_hover_text("splines stay between their knots where a polynome overshoots");
+            ui.checkbox(&mut app.force_curves.bake_polys, "bake polynomes")
+                .on_hover_text(
+                    "the compute shader reads the polynomes from a lookup table instead of \
+                     evaluating them for every pair of particles",
+                );
+            ui.label("lookup table resolution: ");
+            ui.add(
+                DragValue::new(&mut app.force_curves.resolution)
+                    .speed(4.0)
+                    .clamp_range(MIN_LUT_RESOLUTION..=MAX_LUT_RESOLUTION),
+            )
+            .on_hover_text("samples per curve, spread up to the cut off distance");
+        });
+        if selected != kind {
+            app.force_curves.curves[index]
+                .set_kind(selected, &app.sim_params.attraction_force[index]);
+            app.force_curves.mirror(index, app.matrix_symmetry);
+            self.selected_knot = None;
+        }
+    }
+
+    /// the spline of the selected pair with draggable knots
+    fn edit_spline(&mut self, ui: &mut Ui, app: &mut App) {
+        let index = self.poly_index;
+        let poly = app.sim_params.attraction_force[index];
+        let potential = app.sim_params.force_mode == ForceMode::Potential;
+        let cut_off_distance = app.sim_params.pair_forces[index].cut_off_distance;
+        let cutoff_window = app.sim_params.cutoff_window;
+        // the knots can be dragged up to the cut off distance, beyond it the curve is not used
+        let x_max = cut_off_distance.max(1.0);
+        let limit = self.poly_limit as f64;
+        let curve = &mut app.force_curves.curves[index];
+        let before = curve.clone();
+        let line = Line::new(curve.plot_points(&poly, x_max)).name(if potential {
+            "potential U(r)"
+        } else {
+            "attraction"
+        });
+        let windowed = Line::new(curve.windowed_plot_points(
+            &poly,
+            potential,
+            cut_off_distance,
+            cutoff_window,
+        ))
+        .name("applied attraction")
+        .style(LineStyle::dotted_dense());
+        let ForceCurve::Spline { kind, knots } = curve else {
+            return;
+        };
+        ui.colored_label(Color32::GREEN, "selected attraction_force spline");
+        ui.label("drag the knots, click one to edit it below");
+        let positions: Vec<PlotPoint> = knots.iter().map(|k| PlotPoint::new(k.x, k.y)).collect();
+        let points = Points::new(positions.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>())
+            .radius(5.0)
+            .color(Color32::YELLOW);
+        let selected = self.selected_knot.and_then(|i| positions.get(i)).map(|p| {
+            Points::new(vec![[p.x, p.y]])
+                .radius(7.0)
+                .color(Color32::RED)
+        });
+        let dragged = Plot::new("spline plot")
+            .legend(Legend::default())
+            .view_aspect(2.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .allow_double_click_reset(false)
+            .show(ui, |plot_ui| {
+                plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+                    [-0.05, -limit],
+                    [x_max as f64 * 1.05, limit],
+                ));
+                plot_ui.line(line);
+                plot_ui.line(windowed);
+                plot_ui.points(points);
+                if let Some(selected) = selected {
+                    plot_ui.points(selected);
+                }
+                let response = plot_ui.response().clone();
+                if response.drag_started() || response.clicked() {
+                    // the knot nearest to the pointer, if it was hit
+                    self.dragged_knot = response.interact_pointer_pos.and_then(|pointer| {
+                        positions
+                            .iter()
+                            .map(|p| plot_ui.screen_from_plot(*p).distance(pointer))
+                            .enumerate()
+                            .filter(|(_, distance)| *distance < POLY_POINT_GRAB_RADIUS)
+                            .min_by(|a, b| a.1.total_cmp(&b.1))
+                            .map(|(i, _)| i)
+                    });
+                    if self.dragged_knot.is_some() {
+                        self.selected_knot = self.dragged_knot;
+                    }
+                }
+                if !response.dragged() {
+                    self.dragged_knot = None;
+                }
+                self.dragged_knot.zip(plot_ui.pointer_coordinate())
+            })
+            .inner;
+        if let Some((i, pointer)) = dragged {
+            let y = pointer.y as f32;
+            let y = if self.poly_snap > 0.0 {
+                (y / self.poly_snap).round() * self.poly_snap
+            } else {
+                y
+            };
+            let y = y.clamp(-self.poly_limit, self.poly_limit);
+            move_knot(knots, i, (pointer.x as f32).clamp(0.0, x_max), y);
+        }
+        if let Some(i) = self.selected_knot.filter(|i| *i < knots.len()) {
+            let (mut x, mut y) = (knots[i].x, knots[i].y);
+            ui.horizontal(|ui| {
+                ui.label(format!("knot {i}: "));
+                ui.add_enabled(i > 0, DragValue::new(&mut x).speed(0.01).prefix("r: "))
+                    .on_hover_text("the first knot stays at distance 0");
+                ui.add(DragValue::new(&mut y).speed(0.01).prefix("value: "));
+                if *kind == SplineKind::Hermite {
+                    ui.add(
+                        DragValue::new(&mut knots[i].tangent)
+                            .speed(0.01)
+                            .prefix("tangent: "),
+                    );
+                }
+            });
+            if (x, y) != (knots[i].x, knots[i].y) {
+                move_knot(knots, i, x.clamp(0.0, x_max), y);
+            }
+        }
+        ui.horizontal(|ui| {
+            if ui.button("add knot").clicked() {
+                insert_knot(*kind, knots);
+            }
+            // the first knot and at least MIN_KNOTS stay
+            let removable = self
+                .selected_knot
+                .filter(|i| *i > 0 && *i < knots.len() && knots.len() > MIN_KNOTS);
+            if ui
+                .add_enabled(removable.is_some(), Button::new("remove knot"))
+                .clicked()
+            {
+                if let Some(i) = removable {
+                    knots.remove(i);
+                    self.selected_knot = None;
+                }
+            }
+        });
+        if app.force_curves.curves[index] != before {
+            app.force_curves.mirror(index, app.matrix_symmetry);
+        }
+    }
+
+    fn labeled_drag_value(ui: &mut Ui, val: &mut f32, label: &str) {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            ui.add(DragValue::new(val).speed(0.01));
+        });
+    }
+
+    fn edit_masses(&self, ui: &mut Ui, sim_params: &mut SimParams) {
+        ui.vertical(|ui| {
+            ui.colored_label(Color32::GREEN, "Masses");
+            ComboBox::from_id_source("mass coupling")
+                .selected_text(sim_params.mass_coupling.name())
+                .show_ui(ui, |ui| {
+                    for coupling in MassCoupling::ALL {
+                        ui.selectable_value(
+                            &mut sim_params.mass_coupling,
+                            coupling,
+                            coupling.name(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "how the masses scale the accelerations of the attraction forces: \
+                     with the mass of the other particle, divided by the own mass or not at all",
+                );
+            for (i, mass) in sim_params.particle_type_masses.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        DragValue::new(&mut mass.mass)
+                            .prefix(&self.element_text[i])
+                            .speed(0.01)
+                            .clamp_range(0.01..=10.0),
+                    );
+                });
+            }
+        });
     }
 
-    fn edit_cutoff(&self, ui: &mut Ui, sim_params: &mut SimParams) {
+    fn edit_cutoff(&mut self, ui: &mut Ui, app: &mut App) {
+        // a scene or the symmetry can part the distances
+        if !app.sim_params.cut_off_distances_linked() {
+            self.link_cut_offs = false;
+        }
         ui.horizontal(|ui| {
             ui.label("polynome cutoff distance: ");
-            ui.add(Slider::new(&mut sim_params.cut_off_distance, 0.1..=5.0));
+            let mut distance = app.sim_params.cut_off_distance;
+            if ui
+                .add_enabled(self.link_cut_offs, Slider::new(&mut distance, 0.1..=5.0))
+                .changed()
+            {
+                app.sim_params.set_cut_off_distance(distance);
+            }
+        });
+        if ui
+            .checkbox(&mut self.link_cut_offs, "link all")
+            .on_hover_text("all pairs of types share the cut off distance")
+            .changed()
+            && self.link_cut_offs
+        {
+            let distance = app.sim_params.cut_off_distance;
+            app.sim_params.set_cut_off_distance(distance);
+        }
+        if !self.link_cut_offs {
+            Self::edit_pair_cut_offs(ui, app);
+        }
+        ui.horizontal(|ui| {
+            ui.label("cutoff window: ");
+            ui.add(Slider::new(&mut app.sim_params.cutoff_window, 0.0..=1.0))
+                .on_hover_text(
+                    "fraction of the cut off distance over which the forces fade out smoothly, \
+                     0 cuts them off abruptly",
+                );
+        });
+        let sphere = &mut app.renderer.cutoff_sphere;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut sphere.enabled, "show cut off sphere");
+            if !sphere.enabled {
+                return;
+            }
+            ComboBox::from_id_source("cutoff anchor")
+                .selected_text(sphere.anchor.name())
+                .show_ui(ui, |ui| {
+                    for anchor in CutoffAnchor::ALL {
+                        ui.selectable_value(&mut sphere.anchor, anchor, anchor.name());
+                    }
+                });
+            ui.color_edit_button_rgb(&mut sphere.color);
+            ui.add(Slider::new(&mut sphere.opacity, 0.05..=1.0).text("opacity"));
+        });
+        if !sphere.enabled || sphere.anchor != CutoffAnchor::Particle {
+            return;
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .button("pick nearest to cursor")
+                .on_hover_text("follows the particle closest to the cursor")
+                .clicked()
+            {
+                let cursor = app.renderer.camera.cursor.pos;
+                app.compute.follow_nearest_particle(
+                    &app.renderer.device,
+                    &app.renderer.queue,
+                    cursor,
+                );
+            }
+            match app.compute.particle_tracker.id {
+                Some(id) => {
+                    ui.label(format!("following particle {}", id));
+                    if ui.button("stop").clicked() {
+                        app.compute.particle_tracker.stop();
+                    }
+                }
+                None => {
+                    ui.label("following no particle");
+                }
+            }
         });
     }
 }
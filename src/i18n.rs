@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+/// the language of the controls
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::German, Language::Spanish];
+
+    /// the name of the language in itself, as it is listed in the language dropdown
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::Spanish => "Español",
+        }
+    }
+
+    /// the ISO 639-1 code, saved in the config file
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::Spanish => "es",
+        }
+    }
+
+    /// the translation file compiled into the binary, english needs none
+    fn source(self) -> Option<&'static str> {
+        match self {
+            Language::English => None,
+            Language::German => Some(include_str!("../assets/lang/de.toml")),
+            Language::Spanish => Some(include_str!("../assets/lang/es.toml")),
+        }
+    }
+}
+
+/// the gui texts of a language, looked up by their english text. texts without a
+/// translation stay english, so a translation file can cover the controls bit by bit
+pub struct Translations {
+    pub language: Language,
+    texts: HashMap<String, String>,
+}
+
+impl Translations {
+    pub fn new(language: Language) -> Self {
+        let texts = language.source().map_or_else(HashMap::new, |source| {
+            parse(source).unwrap_or_else(|e| {
+                log::warn!("translations for {}: {}", language.name(), e);
+                HashMap::new()
+            })
+        });
+        Translations { language, texts }
+    }
+
+    /// the translation of the english `text`, or `text` itself if it has none
+    pub fn tr<'a>(&'a self, text: &'a str) -> &'a str {
+        self.texts.get(text).map_or(text, String::as_str)
+    }
+}
+
+/// the `"english" = "translation"` lines of a translation file, a small subset of toml
+/// with quoted keys. comments and table headers are skipped, the tables only group
+/// the texts for the translators
+fn parse(source: &str) -> Result<HashMap<String, String>, String> {
+    let mut texts = HashMap::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+        let invalid = |what: &str| format!("line {}: {}", line_number + 1, what);
+        let (key, rest) = quoted(line).ok_or_else(|| invalid("expected a quoted key"))?;
+        let value = rest
+            .trim_start()
+            .strip_prefix('=')
+            .and_then(|value| quoted(value.trim_start()))
+            .filter(|(_, rest)| rest.trim().is_empty() || rest.trim().starts_with('#'))
+            .map(|(value, _)| value)
+            .ok_or_else(|| invalid("expected \"english\" = \"translation\""))?;
+        texts.insert(key, value);
+    }
+    Ok(texts)
+}
+
+/// the unescaped string at the start of `text` and the rest after its closing quote
+fn quoted(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    let mut string = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((string, &text[i + 2..])),
+            '\\' => match chars.next()?.1 {
+                'n' => string.push('\n'),
+                't' => string.push('\t'),
+                c => string.push(c),
+            },
+            c => string.push(c),
+        }
+    }
+    None
+}
@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+/// byte offset of the `draw_indexed_indirect` arguments in `IndirectDispatch::args_buffer`,
+/// the `dispatch_workgroups_indirect` arguments come first
+pub const DRAW_ARGS_OFFSET: BufferAddress = 12;
+
+/// the most workgroups a dispatch can have along one dimension, larger dispatches are split
+/// into rows of this many workgroups. mirrored in indirect.wgsl
+pub const MAX_WORKGROUPS_PER_DIMENSION: u32 = 65535;
+
+/// the x and y workgroup counts of a dispatch of `num_workgroups` workgroups,
+/// the shaders number its invocations with `invocation_index`
+pub fn split_workgroups(num_workgroups: u32) -> (u32, u32) {
+    (
+        num_workgroups.min(MAX_WORKGROUPS_PER_DIMENSION),
+        num_workgroups.div_ceil(MAX_WORKGROUPS_PER_DIMENSION),
+    )
+}
+
+/// mirrors `ParticleCount` in indirect.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ParticleCount {
+    alive: u32,
+    capacity: u32,
+    workgroup_size: u32,
+    _padd: u32,
+}
+
+/// keeps the number of alive particles on the GPU and derives the workgroup count of the
+/// simulation pass and the instance count of the particle draw from it every frame
+pub struct IndirectDispatch {
+    /// the alive count read by the simulation pass, changed on the GPU with atomics
+    pub count_buffer: Buffer,
+    /// dispatch arguments followed by the draw arguments at `DRAW_ARGS_OFFSET`
+    pub args_buffer: Buffer,
+    pipeline: ComputePipeline,
+    bind_group: BindGroup,
+}
+
+impl IndirectDispatch {
+    /// `index_count` is the number of indices of the particle mesh
+    pub fn new(device: &Device, index_count: u32) -> Self {
+        let count_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("particle count buffer"),
+            size: std::mem::size_of::<ParticleCount>() as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        // workgroups x, y, z, then index count, instance count, first index,
+        // base vertex and first instance
+        let args: [u32; 8] = [0, 1, 1, index_count, 0, 0, 0, 0];
+        let args_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("indirect args buffer"),
+            contents: bytemuck::cast_slice(&args),
+            usage: BufferUsages::STORAGE | BufferUsages::INDIRECT,
+        });
+        let buffer_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("indirect args bind group layout"),
+            entries: &[buffer_entry(0), buffer_entry(1)],
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("indirect args bind group"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: count_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: args_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("indirect args shader module"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("indirect.wgsl"))),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("indirect args pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("indirect args pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+        IndirectDispatch {
+            count_buffer,
+            args_buffer,
+            pipeline,
+            bind_group,
+        }
+    }
+
+    /// overwrites the count, used after the particle buffers were uploaded or resized
+    pub fn set_count(&self, queue: &Queue, alive: usize, capacity: usize, workgroup_size: u32) {
+        let count = ParticleCount {
+            alive: alive as u32,
+            capacity: capacity as u32,
+            workgroup_size,
+            _padd: 0,
+        };
+        queue.write_buffer(&self.count_buffer, 0, bytemuck::bytes_of(&count));
+    }
+
+    /// overwrites the capacity and keeps the alive count, used after the particle buffers grew
+    pub fn set_capacity(&self, queue: &Queue, capacity: usize) {
+        queue.write_buffer(
+            &self.count_buffer,
+            std::mem::offset_of!(ParticleCount, capacity) as u64,
+            bytemuck::bytes_of(&(capacity as u32)),
+        );
+    }
+
+    /// writes the arguments of this frame, encode it before the simulation pass
+    pub fn encode<'a>(&'a self, cpass: &mut ComputePass<'a>) {
+        cpass.set_pipeline(&self.pipeline);
+        cpass.set_bind_group(0, &self.bind_group, &[]);
+        cpass.dispatch_workgroups(1, 1, 1);
+    }
+}
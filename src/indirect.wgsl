@@ -0,0 +1,42 @@
+// mirrors `ParticleCount` in indirect.rs
+struct ParticleCount {
+  // emitters and kill boundaries change this with atomic operations
+  alive: atomic<u32>,
+  // number of particles the particle buffers can hold
+  capacity: u32,
+  // particles per workgroup of the simulation pass
+  workgroup_size: u32,
+  _padd: u32,
+};
+
+// dispatch_workgroups_indirect arguments followed by draw_indexed_indirect arguments
+struct IndirectArgs {
+  workgroups_x: u32,
+  workgroups_y: u32,
+  workgroups_z: u32,
+  index_count: u32,
+  instance_count: u32,
+  first_index: u32,
+  base_vertex: i32,
+  first_instance: u32,
+};
+
+// mirrors `MAX_WORKGROUPS_PER_DIMENSION` in indirect.rs
+const MAX_WORKGROUPS_PER_DIMENSION: u32 = 65535u;
+
+@group(0) @binding(0) var<storage, read_write> count: ParticleCount;
+@group(0) @binding(1) var<storage, read_write> args: IndirectArgs;
+
+// runs before the simulation pass, so a count changed on the GPU takes effect without the CPU
+@compute
+@workgroup_size(1)
+fn main() {
+  let alive = min(atomicLoad(&count.alive), count.capacity);
+  atomicStore(&count.alive, alive);
+  // larger dispatches are split into rows, like `split_workgroups` in indirect.rs
+  let workgroups = (alive + count.workgroup_size - 1u) / count.workgroup_size;
+  args.workgroups_x = min(workgroups, MAX_WORKGROUPS_PER_DIMENSION);
+  args.workgroups_y = (workgroups + MAX_WORKGROUPS_PER_DIMENSION - 1u) / MAX_WORKGROUPS_PER_DIMENSION;
+  args.workgroups_z = 1u;
+  args.instance_count = alive;
+}
@@ -0,0 +1,154 @@
+use crate::camera::Direction;
+
+/// stick deflections below this are ignored, worn sticks rest slightly off center
+#[cfg(feature = "gamepad")]
+const DEAD_ZONE: f32 = 0.15;
+/// mouse pixels per second the right stick turns the fly camera by at full deflection
+#[cfg(feature = "gamepad")]
+const LOOK_PIXELS_PER_SECOND: f32 = 600.0;
+/// the triggers change the simulation speed and the d-pad the brush strength
+/// by up to e to the power of this per second
+#[cfg(feature = "gamepad")]
+const CHANGE_RATE: f32 = 1.0;
+
+/// what the gamepad asks for in one frame, `App::update` applies it along with the keys
+#[derive(Default)]
+pub struct GamepadFrame {
+    /// camera motions weighted with the deflection of the stick
+    pub motions: Vec<(Direction, f32)>,
+    /// turns the fly camera by mouse pixels per second
+    pub look: (f32, f32),
+    /// the simulation speed changes by e to the power of this per second
+    pub speed: f32,
+    /// the brush strength changes by e to the power of this per second
+    pub brush_strength: f32,
+}
+
+/// gamepads read with gilrs, without the `gamepad` feature none is ever connected.
+/// the left stick moves the camera like WASD, the right stick turns it,
+/// the triggers change the simulation speed and the d-pad the brush strength
+pub struct GamepadInput {
+    pub enabled: bool,
+    /// name of the gamepad used last
+    pub connected: Option<String>,
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+    #[cfg(feature = "gamepad")]
+    active: Option<gilrs::GamepadId>,
+}
+
+impl GamepadInput {
+    pub fn new() -> Self {
+        GamepadInput {
+            enabled: true,
+            connected: None,
+            #[cfg(feature = "gamepad")]
+            gilrs: gilrs::Gilrs::new()
+                .map_err(|e| log::warn!("gamepads are not available: {}", e))
+                .ok(),
+            #[cfg(feature = "gamepad")]
+            active: None,
+        }
+    }
+
+    /// whether the build reads gamepads at all
+    pub fn supported() -> bool {
+        cfg!(feature = "gamepad")
+    }
+
+    /// `fly` maps the sticks onto the motions of the fly camera
+    #[cfg(feature = "gamepad")]
+    pub fn poll(&mut self, fly: bool) -> GamepadFrame {
+        use gilrs::{Axis, Button};
+
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return GamepadFrame::default();
+        };
+        // the cached gamepad state is only updated while draining the events
+        while let Some(event) = gilrs.next_event() {
+            self.active = Some(event.id);
+        }
+        let Some(gamepad) = self
+            .active
+            .map(|id| gilrs.gamepad(id))
+            .filter(|gamepad| gamepad.is_connected())
+        else {
+            self.connected = None;
+            return GamepadFrame::default();
+        };
+        self.connected = Some(gamepad.name().to_string());
+        if !self.enabled {
+            return GamepadFrame::default();
+        }
+        let axis = |axis| {
+            let value: f32 = gamepad.value(axis);
+            if value.abs() < DEAD_ZONE {
+                0.0
+            } else {
+                value
+            }
+        };
+        let button = |button| gamepad.button_data(button).map_or(0.0, |data| data.value());
+        let (left_x, left_y) = (axis(Axis::LeftStickX), axis(Axis::LeftStickY));
+        let (right_x, right_y) = (axis(Axis::RightStickX), axis(Axis::RightStickY));
+
+        let mut frame = GamepadFrame::default();
+        push_motion(
+            &mut frame.motions,
+            left_x,
+            Direction::Right,
+            Direction::Left,
+        );
+        if fly {
+            push_motion(
+                &mut frame.motions,
+                left_y,
+                Direction::Forward,
+                Direction::Backward,
+            );
+            let up = button(Button::RightTrigger) - button(Button::LeftTrigger);
+            push_motion(&mut frame.motions, up, Direction::Up, Direction::Down);
+            frame.look = (
+                right_x * LOOK_PIXELS_PER_SECOND,
+                -right_y * LOOK_PIXELS_PER_SECOND,
+            );
+        } else {
+            push_motion(&mut frame.motions, left_y, Direction::Up, Direction::Down);
+            push_motion(
+                &mut frame.motions,
+                right_x,
+                Direction::RotateRight,
+                Direction::RotateLeft,
+            );
+            push_motion(
+                &mut frame.motions,
+                right_y,
+                Direction::Forward,
+                Direction::Backward,
+            );
+        }
+        frame.speed = (button(Button::RightTrigger2) - button(Button::LeftTrigger2)) * CHANGE_RATE;
+        frame.brush_strength = (button(Button::DPadUp) - button(Button::DPadDown)) * CHANGE_RATE;
+        frame
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn poll(&mut self, _fly: bool) -> GamepadFrame {
+        GamepadFrame::default()
+    }
+}
+
+/// `positive` for a positive deflection, `negative` for a negative one
+#[cfg(feature = "gamepad")]
+fn push_motion(
+    motions: &mut Vec<(Direction, f32)>,
+    value: f32,
+    positive: Direction,
+    negative: Direction,
+) {
+    if value > 0.0 {
+        motions.push((positive, value));
+    } else if value < 0.0 {
+        motions.push((negative, -value));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{
+    precision::ParticlePrecision,
+    readback::{Readback, ReadbackCopy},
+};
+use wgpu::*;
+
+/// most elements shown at once
+pub const MAX_INSPECTED_ELEMENTS: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InspectedBuffer {
+    Particles,
+    ForceGrid,
+    SimParams,
+    /// the id of the particle in every slot of the particle buffer
+    ParticleIds,
+}
+
+impl InspectedBuffer {
+    pub const ALL: [InspectedBuffer; 4] = [
+        InspectedBuffer::Particles,
+        InspectedBuffer::ForceGrid,
+        InspectedBuffer::SimParams,
+        InspectedBuffer::ParticleIds,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InspectedBuffer::Particles => "particles",
+            InspectedBuffer::ForceGrid => "force grid",
+            InspectedBuffer::SimParams => "sim params",
+            InspectedBuffer::ParticleIds => "particle ids",
+        }
+    }
+
+    /// size in bytes of one table row
+    fn stride(&self, precision: ParticlePrecision) -> usize {
+        match self {
+            InspectedBuffer::Particles => precision.particle_size(),
+            InspectedBuffer::ForceGrid => std::mem::size_of::<[f32; 4]>(),
+            InspectedBuffer::SimParams | InspectedBuffer::ParticleIds => std::mem::size_of::<u32>(),
+        }
+    }
+
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            InspectedBuffer::Particles => &[
+                "index", "pos.x", "pos.y", "pos.z", "vel.x", "vel.y", "vel.z", "ty", "age", "mass",
+            ],
+            InspectedBuffer::ForceGrid => &["index", "x", "y", "z", "w"],
+            InspectedBuffer::SimParams => &["byte offset", "f32", "u32", "hex"],
+            InspectedBuffer::ParticleIds => &["slot", "id"],
+        }
+    }
+
+    fn format_rows(
+        &self,
+        bytes: &[u8],
+        first: usize,
+        precision: ParticlePrecision,
+    ) -> Vec<Vec<String>> {
+        match self {
+            InspectedBuffer::Particles => precision
+                .decode_particles(bytes)
+                .iter()
+                .enumerate()
+                .map(|(i, p)| {
+                    let mut row = vec![(first + i).to_string()];
+                    row.extend(p.pos[..3].iter().map(|v| format!("{:.4}", v)));
+                    row.extend(p.vel[..3].iter().map(|v| format!("{:.4}", v)));
+                    row.push(p.ty.to_string());
+                    row.push(format!("{:.3}", p.age));
+                    row.push(format!("{:.3}", p.mass));
+                    row
+                })
+                .collect(),
+            InspectedBuffer::ForceGrid => bytemuck::cast_slice::<u8, [f32; 4]>(bytes)
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let mut row = vec![(first + i).to_string()];
+                    row.extend(v.iter().map(|c| format!("{:.4}", c)));
+                    row
+                })
+                .collect(),
+            InspectedBuffer::SimParams => bytemuck::cast_slice::<u8, u32>(bytes)
+                .iter()
+                .enumerate()
+                .map(|(i, w)| {
+                    vec![
+                        ((first + i) * 4).to_string(),
+                        format!("{:.4}", f32::from_bits(*w)),
+                        w.to_string(),
+                        format!("{:#010x}", w),
+                    ]
+                })
+                .collect(),
+            InspectedBuffer::ParticleIds => bytemuck::cast_slice::<u8, u32>(bytes)
+                .iter()
+                .enumerate()
+                .map(|(i, id)| vec![(first + i).to_string(), id.to_string()])
+                .collect(),
+        }
+    }
+}
+
+/// reads slices of the simulation buffers back and formats them as a table
+pub struct Inspector {
+    pub source: InspectedBuffer,
+    /// index of the first element
+    pub offset: usize,
+    pub count: usize,
+    /// a readback is encoded with the next frame
+    pub requested: bool,
+    /// the source and the formatted rows of the last readback
+    pub rows: Option<(InspectedBuffer, Vec<Vec<String>>)>,
+    readback: Readback,
+    // source, first element and particle precision of the readback in flight
+    pending: (InspectedBuffer, usize, ParticlePrecision),
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Inspector {
+            source: InspectedBuffer::Particles,
+            offset: 0,
+            count: 16,
+            requested: false,
+            rows: None,
+            readback: Readback::new("inspector readback buffer"),
+            pending: (InspectedBuffer::Particles, 0, ParticlePrecision::Full),
+        }
+    }
+
+    /// copies the requested slice of `buffer`, which has to hold the elements of `self.source`
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        buffer: &Buffer,
+        precision: ParticlePrecision,
+    ) {
+        if !self.requested || !self.readback.is_idle() {
+            return;
+        }
+        self.requested = false;
+        let stride = self.source.stride(precision) as u64;
+        let num_elements = buffer.size() / stride;
+        let first = (self.offset as u64).min(num_elements.saturating_sub(1));
+        let count = (self.count.min(MAX_INSPECTED_ELEMENTS) as u64).min(num_elements - first);
+        if count == 0 {
+            return;
+        }
+        self.readback.encode(
+            device,
+            encoder,
+            &[ReadbackCopy {
+                buffer,
+                offset: first * stride,
+                size: count * stride,
+            }],
+        );
+        self.pending = (self.source, first as usize, precision);
+    }
+
+    /// starts mapping the readback buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.readback.after_submit();
+    }
+
+    /// formats a finished readback
+    pub fn poll(&mut self, device: &Device) {
+        let (source, first, precision) = self.pending;
+        if let Some(rows) = self
+            .readback
+            .poll(device, |data| source.format_rows(data, first, precision))
+        {
+            self.rows = Some((source, rows));
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use instant::{Duration, Instant};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Runs the CPU heavy work of a frame (instance generation, brush application, statistics)
+/// on a pool of worker threads. All jobs are joined before the frame is submitted to the GPU.
+pub struct JobSystem {
+    /// None when the worker threads could not be started, the jobs then run on the
+    /// calling thread
+    pool: Option<ThreadPool>,
+    /// time spent in jobs during the last frame
+    pub frame_time: Duration,
+}
+
+impl JobSystem {
+    pub fn new() -> Self {
+        cfg_if::cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                // the browser has no threads, the jobs run on the main thread
+                let builder = ThreadPoolBuilder::new().num_threads(1).use_current_thread();
+            } else {
+                let builder =
+                    ThreadPoolBuilder::new().thread_name(|i| format!("particles worker {i}"));
+            }
+        }
+        let pool = builder
+            .build()
+            .map_err(|e| log::warn!("no worker threads, the jobs run on the main thread: {}", e))
+            .ok();
+        JobSystem {
+            pool,
+            frame_time: Duration::ZERO,
+        }
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.pool
+            .as_ref()
+            .map_or(1, ThreadPool::current_num_threads)
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame_time = Duration::ZERO;
+    }
+
+    /// runs `f` inside the pool, parallel iterators used by `f` are spread over the workers
+    pub fn run<R: Send>(&mut self, f: impl FnOnce() -> R + Send) -> R {
+        let start = Instant::now();
+        let res = match self.pool.as_ref() {
+            Some(pool) => pool.install(f),
+            None => f(),
+        };
+        self.frame_time += start.elapsed();
+        res
+    }
+
+    /// runs two jobs in parallel and waits for both of them
+    pub fn join<A, B, RA, RB>(&mut self, a: A, b: B) -> (RA, RB)
+    where
+        A: FnOnce() -> RA + Send,
+        B: FnOnce() -> RB + Send,
+        RA: Send,
+        RB: Send,
+    {
+        self.run(|| rayon::join(a, b))
+    }
+}
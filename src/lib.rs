@@ -0,0 +1,1378 @@
+use crate::camera::{CameraMode, Direction};
+use adaptive_timestep::AdaptiveTimestep;
+use assets::AssetManager;
+use auto_scale::AutoScale;
+use boundary_animation::BoundaryAnimation;
+use bytemuck::{Pod, Zeroable};
+use cgmath::Vector3;
+use comparison::Comparison;
+use cursor::{get_attractors_raw, DepthMode};
+use detector::BehaviorDetector;
+use explorer::Explorer;
+use field_expression::FieldExpression;
+use field_file::FieldFile;
+use grid_animation::{insert_keyframe, GridAnimation, GridKeyframe};
+use input::GamepadInput;
+use instant::Instant;
+use jobs::JobSystem;
+use memory::MemoryPlan;
+use particle_brush::ParticleBrushMode;
+use presets::{Preset, PRESETS};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use reactions::{get_reaction_rules_raw, Reaction};
+use regions::{get_regions_raw, Region};
+use remote::{PresetRef, RemoteCommand, RemoteControl};
+use safe_mode::SafeMode;
+use scene::SceneFile;
+use shader_watcher::{ShaderKind, ShaderWatcher};
+use sim_params::MatrixSymmetry;
+use startup::StartupConfig;
+use touch::{Gesture, TouchInput};
+use wgpu::{VertexAttribute, VertexBufferLayout, VertexStepMode};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
+
+mod adapters;
+mod adaptive_timestep;
+mod assets;
+mod auto_scale;
+#[cfg(not(target_arch = "wasm32"))]
+mod bench;
+mod boundary_animation;
+mod brush;
+mod camera;
+mod coalescence;
+mod colormap;
+mod comparison;
+mod compute;
+mod compute_features;
+mod config;
+mod cursor;
+mod cutoff_sphere;
+mod detector;
+mod diagnostics;
+mod draw_pass;
+mod engine;
+mod explorer;
+mod field_expression;
+mod field_file;
+mod force_curve;
+mod framework;
+mod gizmos;
+mod glyph_culling;
+mod glyph_lod;
+mod grid;
+mod grid_animation;
+mod gui;
+mod i18n;
+mod indirect;
+mod input;
+mod inspector;
+mod jobs;
+mod links;
+mod material;
+mod memory;
+mod motion_blur;
+mod pair_probe;
+mod palette;
+mod panel_surface;
+mod particle_append;
+mod particle_brush;
+mod particle_sample;
+mod particle_sort;
+mod particle_tracker;
+mod particles_app;
+mod poly7;
+mod precision;
+pub mod prelude;
+mod presets;
+mod ray_density;
+mod reactions;
+mod readback;
+mod recorder;
+mod reduction;
+mod regions;
+mod remote;
+mod render_scale;
+mod renderer;
+mod safe_mode;
+mod scene;
+mod shader_watcher;
+mod sim_params;
+mod slice_view;
+mod startup;
+#[cfg(not(target_arch = "wasm32"))]
+mod sweep;
+mod touch;
+mod velocity_ops;
+
+pub use camera::Camera;
+pub use compute::Compute;
+pub use engine::{EngineBuilder, ParticlesEngine};
+pub use force_curve::{ForceCurve, ForceCurves, Knot, SplineKind};
+pub use grid::{Bounds, Grid};
+pub use material::MaterialGrid;
+pub use particles_app::ParticlesApp;
+pub use poly7::Poly7;
+pub use precision::ParticlePrecision;
+pub use reduction::ReductionResult;
+pub use renderer::Renderer;
+pub use sim_params::SimParams;
+
+pub type V3 = Vector3<f32>;
+type Key = winit::event::VirtualKeyCode;
+
+/// upper limit of the particle count sliders and the auto scaling
+const MAX_NUM_PARTICLES: usize = 1_000_000;
+
+/// touchpad scroll distance that counts as one mouse wheel step
+const PIXELS_PER_WHEEL_STEP: f32 = 40.0;
+
+const fn zero_v3() -> V3 {
+    V3::new(0., 0., 0.)
+}
+
+#[allow(dead_code)]
+fn rand_v3(rng: &mut impl Rng, max: f32) -> V3 {
+    let res = V3::new(
+        rng.gen::<f32>() - 0.5,
+        rng.gen::<f32>() - 0.5,
+        rng.gen::<f32>() - 0.5,
+    );
+    res * max
+}
+
+fn rand_v4(rng: &mut impl Rng, max: f32) -> [f32; 4] {
+    [
+        max * (rng.gen::<f32>() - 0.5),
+        max * (rng.gen::<f32>() - 0.5),
+        max * (rng.gen::<f32>() - 0.5),
+        1.0,
+    ]
+}
+
+/// runs the particles application, or a headless sweep when started with `--sweep`
+/// and a benchmark when started with `--bench`
+pub fn run() {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(config) = bench::BenchConfig::from_args() {
+        env_logger::init();
+        if let Err(e) = bench::run(&config) {
+            eprintln!("benchmark failed: {}", e);
+        }
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(config) = sweep::SweepConfig::from_args() {
+        env_logger::init();
+        if let Err(e) = sweep::run(&config) {
+            eprintln!("sweep failed: {}", e);
+        }
+        return;
+    }
+    ParticlesApp::new().run();
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub enum ParticleType {
+    E = 0,
+    W,
+    F,
+    A,
+    N,
+}
+
+impl From<u32> for ParticleType {
+    fn from(value: u32) -> Self {
+        match value % 5 {
+            0 => ParticleType::E,
+            1 => ParticleType::W,
+            2 => ParticleType::F,
+            3 => ParticleType::A,
+            _ => ParticleType::N,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct Particle {
+    pub pos: [f32; 4],
+    pub vel: [f32; 4],
+    pub ty: u32,
+    /// seconds since the particle was (re)born
+    pub age: f32,
+    /// multiple of the mass of its type, changed when particles coalesce
+    pub mass: f32,
+    _padd: u32,
+}
+
+impl Particle {
+    const fn get_instance_layout() -> wgpu::VertexBufferLayout<'static> {
+        let array_stride = std::mem::size_of::<Particle>() as u64;
+        VertexBufferLayout {
+            // particle_type : 4, position : 4 * 3, velocity: 4 * 3
+            array_stride,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // particle position
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 2,
+                },
+                // particle velocity
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: 4 * 4,
+                    shader_location: 5,
+                },
+                // particle type
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Uint32,
+                    offset: 4 * 4 * 2,
+                    shader_location: 3,
+                },
+                // particle age
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Float32,
+                    offset: 4 * 4 * 2 + 4,
+                    shader_location: 4,
+                },
+            ],
+        }
+    }
+}
+
+/// one of several force grids, the compute shader sums all enabled layers
+struct ForceGridLayer {
+    name: String,
+    grid: Grid<V3>,
+    strength: f32,
+    enabled: bool,
+    /// snapshots of `grid` sorted by time, played back instead of it while animated
+    keyframes: Vec<GridKeyframe>,
+}
+
+impl ForceGridLayer {
+    fn new(name: &str, grid: Grid<V3>) -> Self {
+        ForceGridLayer {
+            name: String::from(name),
+            grid,
+            strength: 1.0,
+            enabled: true,
+            keyframes: Vec::new(),
+        }
+    }
+}
+
+/// the particles the GPU buffers are (re)initialized with and the force grid layers
+pub struct ParticleSystem {
+    /// the particles last uploaded to the compute buffers, which are simulated and drawn
+    /// on the GPU, these only (re)initialize them
+    particles: Vec<Particle>,
+    force_grid_layers: Vec<ForceGridLayer>,
+    /// the layer edited by the cursor
+    active_layer: usize,
+    /// damping factors painted into the cells of the force grid
+    material_grid: MaterialGrid,
+    grid_animation: GridAnimation,
+    /// seed of `rng`, all stochastic elements of the simulation derive from it
+    seed: u64,
+    rng: StdRng,
+}
+
+impl ParticleSystem {
+    fn new(
+        max: V3,
+        num_x: usize,
+        num_y: usize,
+        num_z: usize,
+        seed: u64,
+        sim_params: &SimParams,
+    ) -> Self {
+        let mut particles = Vec::with_capacity(num_x * num_y * num_z);
+        for ix in 0..num_x {
+            for iy in 0..num_y {
+                for iz in 0..num_z {
+                    let index = ix * num_y * num_z + iy * num_z + iz;
+                    particles.push(Particle {
+                        pos: [
+                            (ix as f32 / num_x as f32) * max.x,
+                            (iy as f32 / num_y as f32) * max.y,
+                            (iz as f32 / num_z as f32) * max.z,
+                            1.0,
+                        ],
+                        vel: [0.; 4],
+                        ty: (index % 5) as u32,
+                        age: 0.0,
+                        mass: 1.0,
+                        _padd: 0,
+                    });
+                }
+            }
+        }
+        let force_grid = sim_params.new_force_grid_centered();
+
+        ParticleSystem {
+            particles,
+            force_grid_layers: vec![ForceGridLayer::new("base", force_grid)],
+            active_layer: 0,
+            material_grid: MaterialGrid::new(sim_params),
+            grid_animation: GridAnimation::new(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// restarts the random number generator with `seed`
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// replaces all particles with freshly spawned ones, reproducible for a given seed
+    pub fn respawn_particles(&mut self) {
+        let num_particles = self.particles.len();
+        self.reseed(self.seed);
+        self.particles.clear();
+        self.set_num_particles(num_particles);
+    }
+
+    /// the particles last uploaded to the GPU
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// the force grid layer edited by the cursor
+    pub fn force_grid(&self) -> &Grid<V3> {
+        &self.force_grid_layers[self.active_layer].grid
+    }
+
+    pub fn force_grid_mut(&mut self) -> &mut Grid<V3> {
+        &mut self.force_grid_layers[self.active_layer].grid
+    }
+
+    /// the damping factors of the force grid cells, uploaded with the force grid
+    pub fn material_grid(&self) -> &MaterialGrid {
+        &self.material_grid
+    }
+
+    pub fn material_grid_mut(&mut self) -> &mut MaterialGrid {
+        &mut self.material_grid
+    }
+
+    fn add_force_grid_layer(&mut self, sim_params: &SimParams) {
+        let name = format!("layer {}", self.force_grid_layers.len());
+        self.force_grid_layers
+            .push(ForceGridLayer::new(&name, sim_params.new_force_grid_zero()));
+        self.active_layer = self.force_grid_layers.len() - 1;
+    }
+
+    fn remove_force_grid_layer(&mut self, index: usize) {
+        if self.force_grid_layers.len() > 1 {
+            self.force_grid_layers.remove(index);
+            self.active_layer = self.active_layer.min(self.force_grid_layers.len() - 1);
+        }
+    }
+
+    /// snapshots the active layer at the current time of the grid animation
+    fn add_grid_keyframe(&mut self) {
+        let time = self.grid_animation.time;
+        let layer = &mut self.force_grid_layers[self.active_layer];
+        let grid = layer.grid.clone();
+        insert_keyframe(&mut layer.keyframes, GridKeyframe { time, grid });
+    }
+
+    /// force vectors of all enabled layers scaled by their strength, one layer after the other.
+    /// while the grid animation runs, the layers are followed by the keyframes they blend to
+    /// with the blend factor in w. the enabled material grid comes last, see `MaterialGrid::get_raw`.
+    /// returns the vectors and the number of enabled layers
+    fn get_layered_force_vectors(&self) -> (Vec<[f32; 4]>, u32) {
+        let animation = &self.grid_animation;
+        // the grids blended from and to and the blend factor of every enabled layer
+        let frames: Vec<(f32, &Grid<V3>, &Grid<V3>, f32)> = self
+            .force_grid_layers
+            .iter()
+            .filter(|l| l.enabled)
+            .map(|l| match animation.blend(&l.keyframes) {
+                Some((from, to, blend)) if animation.enabled => {
+                    (l.strength, &from.grid, &to.grid, blend)
+                }
+                _ => (l.strength, &l.grid, &l.grid, 0.0),
+            })
+            .collect();
+        let mut res: Vec<[f32; 4]> = frames
+            .par_iter()
+            .flat_map_iter(|(strength, from, _, _)| from.get_force_vectors(*strength))
+            .collect();
+        if animation.enabled {
+            res.par_extend(frames.par_iter().flat_map_iter(|(strength, _, to, blend)| {
+                to.get_force_vectors(*strength)
+                    .into_iter()
+                    .map(|[x, y, z, _]| [x, y, z, *blend])
+            }));
+        }
+        if self.material_grid.enabled {
+            res.extend(self.material_grid.get_raw());
+        }
+        if res.is_empty() {
+            // storage buffers can not be empty
+            res.push([0.0; 4]);
+        }
+        (res, frames.len() as u32)
+    }
+
+    /// spawns particles at random positions or drops the last ones
+    pub fn set_num_particles(&mut self, num_particles: usize) {
+        let plen = self.particles.len();
+        let spawned = self.spawn_particles(plen, num_particles.saturating_sub(plen));
+        self.particles.extend(spawned);
+        self.particles.truncate(num_particles);
+    }
+
+    /// `count` particles at random positions for the slots from `first` on,
+    /// without adding them to `particles`
+    pub fn spawn_particles(&mut self, first: usize, count: usize) -> Vec<Particle> {
+        (first..first + count)
+            .map(|slot| Particle {
+                pos: rand_v4(&mut self.rng, 2.0),
+                vel: rand_v4(&mut self.rng, 10.0),
+                ty: (slot % 5) as u32,
+                age: 0.0,
+                mass: 1.0,
+                _padd: 0,
+            })
+            .collect()
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Zeroable, Pod)]
+pub struct MassWrap {
+    pub mass: f32,
+    _pad: [f32; 3],
+}
+
+impl MassWrap {
+    pub fn new(mass: f32) -> MassWrap {
+        MassWrap {
+            mass,
+            _pad: [0.; 3],
+        }
+    }
+}
+
+struct App {
+    time_step: Instant,
+    pub psys: ParticleSystem,
+    pub sim_params: SimParams,
+    pub renderer: Renderer,
+    pub compute: Compute,
+    pub jobs: JobSystem,
+    pub boundary_animation: BoundaryAnimation,
+    pub reactions: Vec<Reaction>,
+    /// boxes with simulation parameters of their own
+    pub regions: Vec<Region>,
+    /// splines replacing the attraction polynomes of some pairs of particle types
+    pub force_curves: ForceCurves,
+    pub shader_watcher: ShaderWatcher,
+    pub safe_mode: Option<SafeMode>,
+    pub speed: Option<f32>,
+    pub adaptive_timestep: AdaptiveTimestep,
+    pub scene_file: SceneFile,
+    pub field_file: FieldFile,
+    pub field_expression: FieldExpression,
+    pub assets: AssetManager,
+    pub auto_scale: AutoScale,
+    /// kept by the force matrices while they are edited and when a preset is applied
+    pub matrix_symmetry: MatrixSymmetry,
+    /// randomized and bred forces and masses
+    pub explorer: Explorer,
+    /// flags interesting regimes and drives the explorer through unattended searches
+    pub detector: BehaviorDetector,
+    /// a second simulation shown beside the first, see `start_comparison`
+    pub comparison: Option<Comparison>,
+    pressed_keys: Vec<VirtualKeyCode>,
+    /// set by F11 and Alt+Enter, the framework switches the window in or out of fullscreen
+    pub toggle_fullscreen: bool,
+    pub gamepad: GamepadInput,
+    touch: TouchInput,
+    /// parameter changes, camera moves and preset loads received over the network
+    pub remote: RemoteControl,
+    /// the limits of the device the last particle count or grid size exceeded,
+    /// the buffers were kept as they were
+    pub memory_warnings: Vec<String>,
+}
+
+impl App {
+    fn new(mut renderer: Renderer, config: &StartupConfig, safe_mode: Option<SafeMode>) -> Self {
+        let mut sim_params = SimParams::new();
+        // logged so a run can be repeated with `--seed`
+        log::info!("seed {}", config.seed);
+        sim_params.set_seed(config.seed);
+        sim_params.force_grid_dimensions = config.force_grid_dimensions;
+        // smallest lattice holding all the particles, the surplus is removed afterwards
+        let lattice = (config.num_particles as f32).cbrt().ceil() as usize;
+        let mut psys = ParticleSystem::new(
+            V3::new(5.0, 2.0, 2.0),
+            lattice,
+            lattice,
+            lattice,
+            config.seed,
+            &sim_params,
+        );
+        psys.set_num_particles(config.num_particles);
+        // fire turns into ether near water, off until enabled in the gui
+        let reactions = vec![Reaction {
+            enabled: false,
+            ..Reaction::new(
+                ParticleType::F as u32,
+                ParticleType::W as u32,
+                ParticleType::N as u32,
+            )
+        }];
+        let mut compute = Compute::new(
+            &renderer.device,
+            &renderer.queue,
+            ParticlePrecision::Full,
+            &psys.particles,
+            &psys.get_layered_force_vectors().0,
+            &get_reaction_rules_raw(&reactions).0,
+            &get_attractors_raw(&[]).0,
+        );
+        // the safe mode avoids anything that is not needed to start
+        if safe_mode.is_none() {
+            compute.autotune_workgroup_size(&renderer.device, &renderer.queue);
+        }
+        dbg!(psys.force_grid().num_instances());
+        renderer.recreate_pipelines();
+        let vector_field_inst_raw = psys.force_grid().get_instances_raw(&[]);
+        dbg!(vector_field_inst_raw.len());
+        renderer.sub_rpass_vector_field.update_instance_buffer(
+            &renderer.device,
+            &vector_field_inst_raw,
+            psys.force_grid().num_instances(),
+        );
+        App {
+            time_step: Instant::now(),
+            psys,
+            sim_params,
+            renderer,
+            compute,
+            jobs: JobSystem::new(),
+            boundary_animation: BoundaryAnimation::new(sim_params.bounding_volume_radius),
+            reactions,
+            regions: Vec::new(),
+            force_curves: ForceCurves::new(),
+            shader_watcher: ShaderWatcher::new(),
+            safe_mode,
+            speed: Some(1.0),
+            adaptive_timestep: AdaptiveTimestep::new(),
+            scene_file: SceneFile::new(),
+            field_file: FieldFile::new(),
+            field_expression: FieldExpression::new(),
+            assets: AssetManager::new(),
+            auto_scale: AutoScale::new(MAX_NUM_PARTICLES),
+            matrix_symmetry: MatrixSymmetry::Free,
+            explorer: Explorer::new(),
+            detector: BehaviorDetector::new(),
+            comparison: None,
+            pressed_keys: Vec::new(),
+            toggle_fullscreen: false,
+            gamepad: GamepadInput::new(),
+            touch: TouchInput::new(),
+            remote: RemoteControl::new(),
+            memory_warnings: Vec::new(),
+        }
+    }
+
+    /// scales the bounding volume uniformly to the radius and fits the force grids into it
+    pub fn set_bounding_volume_radius(&mut self, radius: f32) {
+        self.sim_params.set_bounding_volume_radius(radius);
+        self.fit_force_grids();
+    }
+
+    /// sets the half sizes of the bounding volume along every axis
+    /// and fits the force grids into it
+    pub fn set_bounding_volume_extents(&mut self, extents: [f32; 3]) {
+        self.sim_params.set_bounding_volume_extents(extents);
+        self.fit_force_grids();
+    }
+
+    fn fit_force_grids(&mut self) {
+        let size = 2.0 * V3::from(self.sim_params.bounding_volume_extents);
+        for layer in self.psys.force_grid_layers.iter_mut() {
+            layer.grid.bounds.set_centered(size);
+            for keyframe in layer.keyframes.iter_mut() {
+                keyframe.grid.bounds.set_centered(size);
+            }
+        }
+        self.psys.material_grid.fit(&self.sim_params);
+    }
+
+    /// the buffer sizes `num_particles` particles and a force grid with `dimensions` cells
+    /// need with the current layers and precision
+    pub fn memory_plan(&self, num_particles: usize, dimensions: [u32; 3]) -> MemoryPlan {
+        let layers = self
+            .psys
+            .force_grid_layers
+            .iter()
+            .filter(|l| l.enabled)
+            .count();
+        let keyframes = if self.psys.grid_animation.enabled {
+            2.0
+        } else {
+            1.0
+        };
+        let material = if self.psys.material_grid.enabled {
+            0.25
+        } else {
+            0.0
+        };
+        MemoryPlan {
+            num_particles,
+            precision: self.compute.precision,
+            force_grid_dimensions: dimensions,
+            grids_per_cell: layers as f32 * keyframes + material,
+            workgroup_size: self.compute.workgroup_size,
+        }
+    }
+
+    /// keeps the limits of the device `plan` exceeds in `memory_warnings`,
+    /// true when its buffers can be allocated
+    fn check_memory(&mut self, plan: MemoryPlan) -> bool {
+        self.memory_warnings = plan.warnings(&self.renderer.device.limits());
+        for warning in self.memory_warnings.iter() {
+            log::warn!("{}", warning);
+        }
+        self.memory_warnings.is_empty()
+    }
+
+    /// resamples all force grids to `dimensions` cells along the axes, unless the grids
+    /// would exceed the limits of the device
+    pub fn set_force_grid_dimensions(&mut self, dimensions: [u32; 3]) {
+        let dimensions = dimensions.map(|n| n.max(1));
+        let plan = self.memory_plan(self.compute.num_particles, dimensions);
+        if !self.check_memory(plan) {
+            return;
+        }
+        self.sim_params.force_grid_dimensions = dimensions;
+        let size = dimensions.map(|n| n as usize);
+        for layer in self.psys.force_grid_layers.iter_mut() {
+            layer.grid.resize(size);
+            for keyframe in layer.keyframes.iter_mut() {
+                keyframe.grid.resize(size);
+            }
+        }
+        self.psys.material_grid.fit(&self.sim_params);
+    }
+
+    /// replaces the forces and the force grid layers with the ones of `preset`
+    /// and restarts the particles
+    fn apply_preset(&mut self, preset: &Preset) {
+        self.sim_params = preset.sim_params(&self.sim_params);
+        self.sim_params.enforce_symmetry(self.matrix_symmetry);
+        self.force_curves.reset();
+        let grid = preset.force_grid(&self.sim_params);
+        self.psys.force_grid_layers = vec![ForceGridLayer::new("base", grid)];
+        self.psys.active_layer = 0;
+        self.psys.material_grid.fit(&self.sim_params);
+        self.psys.respawn_particles();
+        self.compute.upload_particles(
+            &self.renderer.device,
+            &self.renderer.queue,
+            &self.psys.particles,
+        );
+    }
+
+    /// takes over forces and masses of the explorer, the particles start over if it respawns
+    fn apply_explored(&mut self, sim_params: SimParams) {
+        self.sim_params = sim_params;
+        self.force_curves.reset();
+        if self.explorer.respawn {
+            self.psys.respawn_particles();
+            self.compute.upload_particles(
+                &self.renderer.device,
+                &self.renderer.queue,
+                &self.psys.particles,
+            );
+        }
+    }
+
+    /// starts a second simulation from the current particles with a copy of the parameters,
+    /// drawn beside the first so the effect of changed parameters can be compared
+    fn start_comparison(&mut self) {
+        let (device, queue) = (&self.renderer.device, &self.renderer.queue);
+        let mut compute = Compute::new(
+            device,
+            queue,
+            self.compute.precision,
+            &[],
+            &self.psys.get_layered_force_vectors().0,
+            &get_reaction_rules_raw(&self.reactions).0,
+            &get_attractors_raw(&self.renderer.camera.cursor.attractors).0,
+        );
+        // the lookup table of the force curves is baked for the edited side only
+        let poly_range = self.sim_params.cut_off_distance;
+        compute.update_force_curves(
+            device,
+            queue,
+            &self.force_curves.get_raw(&self.sim_params, poly_range),
+            self.force_curves.lut_width(),
+        );
+        compute.copy_particles_from(device, queue, &self.compute);
+        self.comparison = Some(Comparison::new(compute, self.sim_params));
+    }
+
+    /// lets the controls edit the other side of the comparison
+    fn swap_comparison_sides(&mut self) {
+        if let Some(comparison) = self.comparison.as_mut() {
+            std::mem::swap(&mut self.compute, &mut comparison.compute);
+            std::mem::swap(&mut self.sim_params, &mut comparison.sim_params);
+            comparison.editing_b = !comparison.editing_b;
+            self.force_curves.invalidate();
+        }
+    }
+
+    /// continues the other side of the comparison from the particles of the edited side
+    fn sync_comparison(&mut self) {
+        let (device, queue) = (&self.renderer.device, &self.renderer.queue);
+        if let Some(comparison) = self.comparison.as_mut() {
+            comparison
+                .compute
+                .copy_particles_from(device, queue, &self.compute);
+        }
+    }
+
+    /// requests a sample of the particles for the behavior detector when it is due,
+    /// evaluates the sample once it was read back, saves the regimes it flags and moves
+    /// the explorer on to the next candidate
+    fn run_detector(&mut self, delta_t: f32) {
+        if self.detector.due(delta_t) {
+            self.compute
+                .particle_sample
+                .request(self.detector.sample_size);
+        }
+        let Some((sample, stride)) = self.compute.particle_sample.poll(&self.renderer.device)
+        else {
+            return;
+        };
+        let flagged = self.detector.evaluate(&sample, stride, &self.sim_params);
+        if flagged && self.detector.auto_save {
+            let (scene_path, screenshot_path) = self.detector.save_paths();
+            let scene_file = SceneFile {
+                path: scene_path,
+                ..SceneFile::new()
+            };
+            let result = std::fs::create_dir_all(&self.detector.directory).and_then(|_| {
+                scene_file.save(
+                    &self.sim_params,
+                    &self.renderer.particle_render_params.type_tints,
+                )
+            });
+            self.detector.error = result.err().map(|e| e.to_string());
+            self.renderer.take_screenshot(screenshot_path);
+        }
+        if self.detector.auto_explore && (flagged || self.detector.candidate_expired()) {
+            let sim_params = self
+                .explorer
+                .randomize(&self.sim_params, self.matrix_symmetry);
+            self.apply_explored(sim_params);
+            self.detector.restart();
+        }
+    }
+
+    /// writes the forces and the particle colors to the scene file
+    fn save_scene(&mut self) {
+        let result = self.scene_file.save(
+            &self.sim_params,
+            &self.renderer.particle_render_params.type_tints,
+        );
+        self.scene_file.error = result.err().map(|e| e.to_string());
+    }
+
+    /// reads the forces and the particle colors from the scene file
+    fn load_scene(&mut self) {
+        let mut sim_params = self.sim_params;
+        let result = self.scene_file.load(
+            &mut sim_params,
+            &mut self.renderer.particle_render_params.type_tints,
+        );
+        match result {
+            Ok(warnings) => {
+                self.scene_file.error = None;
+                self.scene_file.warnings = warnings;
+                self.psys.reseed(sim_params.seed());
+            }
+            Err(e) => {
+                self.scene_file.error = Some(e.to_string());
+                self.scene_file.warnings.clear();
+            }
+        }
+        self.sim_params = sim_params;
+        self.set_bounding_volume_radius(sim_params.bounding_volume_radius);
+    }
+
+    /// writes the active force grid layer to the field file
+    fn save_field(&mut self) {
+        let result = self.field_file.save(self.psys.force_grid());
+        self.field_file.error = result.err().map(|e| e.to_string());
+    }
+
+    /// replaces the active force grid layer with the field file
+    fn load_field(&mut self) {
+        let result = self.field_file.load(self.psys.force_grid_mut());
+        self.field_file.error = result.err().map(|e| e.to_string());
+    }
+
+    /// replaces the active force grid layer with the field expression evaluated on it
+    fn apply_field_expression(&mut self) {
+        if let Some(grid) = self.field_expression.evaluate(self.psys.force_grid()) {
+            *self.psys.force_grid_mut() = grid.clone();
+            self.field_expression.preview = false;
+        }
+    }
+
+    /// changes the number of particles, the remaining particles keep their simulated state.
+    /// the count is kept when the buffers would exceed the limits of the device
+    fn resize_particles(&mut self, num_particles: usize) {
+        let plan = self.memory_plan(num_particles, self.sim_params.force_grid_dimensions);
+        if !self.check_memory(plan) {
+            return;
+        }
+        // fewer particles only lower the count on the GPU
+        if self
+            .compute
+            .shrink_particles(&self.renderer.queue, num_particles)
+        {
+            self.psys.particles.truncate(num_particles);
+            return;
+        }
+        // only the new particles are spawned and uploaded behind the alive ones on the GPU
+        let alive = self.compute.num_particles;
+        let spawned = self.psys.spawn_particles(alive, num_particles - alive);
+        self.compute
+            .append_particles(&self.renderer.device, &self.renderer.queue, &spawned);
+        self.psys.particles.truncate(alive);
+        self.psys.particles.extend(spawned);
+    }
+
+    /// spawns particles at the cursor or deletes the ones around it while the particle brush
+    /// is dragged, the deletion runs in the coalescence pass of the next step
+    fn apply_particle_brush(&mut self, elapsed: f32) {
+        let cursor = &mut self.renderer.camera.cursor;
+        let dragging = cursor.mouse_down_on.is_some();
+        self.compute.coalescence.delete_sphere =
+            cursor
+                .particle_brush
+                .delete_sphere(dragging, cursor.pos, cursor.outer_radius);
+        let brush = &mut cursor.particle_brush;
+        if !brush.enabled || !dragging || brush.mode != ParticleBrushMode::Spawn {
+            return;
+        }
+        let room = MAX_NUM_PARTICLES.saturating_sub(self.compute.num_particles);
+        let spawned = brush.spawn(
+            &mut self.psys.rng,
+            cursor.pos,
+            cursor.outer_radius,
+            elapsed,
+            room,
+        );
+        if spawned.is_empty() {
+            return;
+        }
+        self.compute
+            .append_particles(&self.renderer.device, &self.renderer.queue, &spawned);
+        self.psys.particles.extend(spawned);
+    }
+
+    /// converts the particle buffers and rebuilds the pipelines reading them
+    fn set_particle_precision(&mut self, precision: ParticlePrecision) {
+        self.compute
+            .set_precision(&self.renderer.device, &self.renderer.queue, precision);
+        self.renderer.set_particle_precision(precision);
+    }
+
+    /// rebuilds the renderer and the simulation on a new device after the old one was lost.
+    /// the particles on the lost device can not be read back, the simulation restarts
+    /// from the particles last uploaded from the cpu
+    pub fn recover_device(
+        &mut self,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        surface_config: &wgpu::SurfaceConfiguration,
+    ) {
+        let precision = self.compute.precision;
+        self.renderer
+            .recreate(surface_config, device, queue, precision);
+        self.compute = Compute::new(
+            &self.renderer.device,
+            &self.renderer.queue,
+            precision,
+            &self.psys.particles,
+            &self.psys.get_layered_force_vectors().0,
+            &get_reaction_rules_raw(&self.reactions).0,
+            &get_attractors_raw(&self.renderer.camera.cursor.attractors).0,
+        );
+        // the new compute pass starts with an empty lookup table
+        self.force_curves.invalidate();
+        self.compute.update_sim_params(
+            &self.renderer.device,
+            &self.renderer.queue,
+            &self.sim_params,
+        );
+        // the comparison lived on the lost device
+        self.comparison = None;
+        // the new renderer starts with the embedded meshes and textures again
+        self.assets.reload_all();
+    }
+
+    /// applies the commands received by the remote control since the last frame
+    fn apply_remote_commands(&mut self) {
+        for command in self.remote.poll() {
+            let camera = &mut self.renderer.camera;
+            match command {
+                RemoteCommand::Param(name, value) => match name.as_str() {
+                    "speed" => self.speed = (value > 0.0).then_some(value.min(10.0)),
+                    "bounding_volume_radius" => {
+                        let radius = value.clamp(0.25, 5.0);
+                        self.set_bounding_volume_radius(radius);
+                        self.boundary_animation.base_radius = radius;
+                    }
+                    "cut_off_distance" => {
+                        self.sim_params.set_cut_off_distance(value.clamp(0.1, 5.0))
+                    }
+                    _ => {
+                        if let Some(field) = remote::sim_param_mut(&mut self.sim_params, &name) {
+                            *field = value;
+                        }
+                    }
+                },
+                RemoteCommand::Mass(ty, mass) => {
+                    match self.sim_params.particle_type_masses.get_mut(ty) {
+                        Some(wrap) => wrap.mass = mass.clamp(0.01, 10.0),
+                        None => self.remote.error = Some(format!("no particle type {}", ty)),
+                    }
+                }
+                RemoteCommand::Zoom(factor) => camera.zoom(factor),
+                RemoteCommand::Pan(dx, dy) => camera.pan(dx, dy),
+                RemoteCommand::Look(dx, dy) => camera.look(dx, dy),
+                RemoteCommand::Follow(follow) => camera.follow_center_of_mass = follow,
+                RemoteCommand::Preset(preset) => {
+                    let found = match &preset {
+                        PresetRef::Index(i) => PRESETS.get(*i),
+                        PresetRef::Name(name) => PRESETS.iter().find(|p| p.name == name),
+                    };
+                    match found {
+                        Some(preset) => self.apply_preset(preset),
+                        None => self.remote.error = Some(format!("unknown preset {:?}", preset)),
+                    }
+                }
+                RemoteCommand::Respawn => {
+                    self.psys.respawn_particles();
+                    self.compute.upload_particles(
+                        &self.renderer.device,
+                        &self.renderer.queue,
+                        &self.psys.particles,
+                    );
+                }
+            }
+        }
+    }
+
+    /// recompiles shaders whose source files were edited and swaps them in
+    fn reload_shaders(&mut self) {
+        for (kind, source) in self.shader_watcher.changed_shaders() {
+            // compute shaders are compiled with the particle storage, the workgroup size
+            // and the features in use
+            let prefixed = if kind == ShaderKind::Compute {
+                self.compute.shader_source(&source)
+            } else {
+                source.clone()
+            };
+            let Some(shader) = self
+                .shader_watcher
+                .compile(&self.renderer.device, kind, prefixed)
+            else {
+                continue;
+            };
+            if kind == ShaderKind::Compute {
+                self.compute
+                    .reload_source(&self.renderer.device, source, &shader);
+            } else {
+                self.renderer.reload_shader(kind, shader);
+            }
+        }
+    }
+
+    pub fn winit_update(&mut self, event: &winit::event::WindowEvent) {
+        use winit::event;
+        use winit::event::WindowEvent;
+        match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(code),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } if !self.pressed_keys.contains(code) => {
+                self.pressed_keys.push(*code);
+                let alt = self
+                    .pressed_keys
+                    .iter()
+                    .any(|key| matches!(key, Key::LAlt | Key::RAlt));
+                let camera = &mut self.renderer.camera;
+                match code {
+                    Key::F11 => self.toggle_fullscreen = true,
+                    Key::Return if alt => self.toggle_fullscreen = true,
+                    Key::Tab if camera.mode() == CameraMode::Fly => {
+                        camera.mouse_look = !camera.mouse_look;
+                    }
+                    Key::Escape => camera.mouse_look = false,
+                    _ => {}
+                }
+            }
+
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(code),
+                        state: ElementState::Released,
+                        ..
+                    },
+                ..
+            } => {
+                self.pressed_keys.retain(|key| key != code);
+            }
+
+            WindowEvent::DroppedFile(path) => {
+                self.assets.load_dropped(path);
+            }
+
+            // the grabbed pointer only turns the camera
+            WindowEvent::CursorMoved { .. } if self.renderer.camera.mouse_look_active() => {}
+            WindowEvent::CursorMoved { position, .. } => {
+                // both halves of the split screen show the same view
+                let half = self.renderer.camera.screen_width();
+                let x = match position.x as f32 {
+                    x if self.renderer.split_screen() && x >= half => x - half,
+                    x => x,
+                };
+                let cursor = &mut self.renderer.camera.cursor;
+                let force_grid = self.psys.force_grid_mut();
+                // brush application can touch large selections
+                self.jobs
+                    .run(|| cursor.mouse_moved(x, position.y as f32, force_grid));
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let steps = match delta {
+                    event::MouseScrollDelta::LineDelta(hor, ver) => {
+                        if hor.abs() > ver.abs() {
+                            *hor
+                        } else {
+                            *ver
+                        }
+                    }
+                    event::MouseScrollDelta::PixelDelta(position) => {
+                        position.y as f32 / PIXELS_PER_WHEEL_STEP
+                    }
+                };
+                let camera = &mut self.renderer.camera;
+                // with a modifier the wheel moves the cursor along the mouse ray
+                let modifier = self.pressed_keys.iter().any(|key| {
+                    matches!(
+                        key,
+                        VirtualKeyCode::LControl
+                            | VirtualKeyCode::RControl
+                            | VirtualKeyCode::LShift
+                            | VirtualKeyCode::RShift
+                            | VirtualKeyCode::LAlt
+                            | VirtualKeyCode::RAlt
+                    )
+                });
+                if modifier {
+                    camera.cursor.distance_from_camera += steps * camera.wheel_depth_sensitivity;
+                } else {
+                    camera.zoom_to_mouse(steps);
+                }
+            }
+            WindowEvent::MouseInput {
+                state: event::ElementState::Pressed,
+                button: event::MouseButton::Left,
+                ..
+            } => {
+                self.renderer
+                    .camera
+                    .cursor
+                    .mouse_down(self.psys.force_grid());
+            }
+            WindowEvent::MouseInput {
+                state: event::ElementState::Released,
+                button: event::MouseButton::Left,
+                ..
+            } => {
+                self.renderer.camera.cursor.mouse_up(self.psys.force_grid());
+            }
+            WindowEvent::MouseInput {
+                state: event::ElementState::Released,
+                button: event::MouseButton::Right,
+                ..
+            } => {
+                self.renderer.camera.cursor.place_attractor();
+            }
+            WindowEvent::Touch(touch) => self.touch_update(touch),
+            _ => {}
+        }
+    }
+
+    /// raw mouse motion, turns the fly camera while the pointer is grabbed
+    pub fn mouse_motion(&mut self, dx: f64, dy: f64) {
+        let camera = &mut self.renderer.camera;
+        if camera.mouse_look_active() {
+            camera.look(dx as f32, dy as f32);
+        }
+    }
+
+    /// one finger edits the vector field like the left mouse button,
+    /// two fingers pinch to zoom and drag to orbit the camera
+    fn touch_update(&mut self, touch: &winit::event::Touch) {
+        let Some(gesture) = self.touch.event(touch) else {
+            return;
+        };
+        let camera = &mut self.renderer.camera;
+        let force_grid = self.psys.force_grid_mut();
+        match gesture {
+            Gesture::Press(x, y) => {
+                camera.cursor.mouse_moved(x, y, force_grid);
+                camera.cursor.mouse_down(force_grid);
+            }
+            Gesture::Drag(x, y) => {
+                let cursor = &mut camera.cursor;
+                self.jobs.run(|| cursor.mouse_moved(x, y, force_grid));
+            }
+            Gesture::Release => camera.cursor.mouse_up(force_grid),
+            Gesture::TwoFinger { zoom, pan } => {
+                camera.zoom(zoom);
+                camera.pan(pan[0], pan[1]);
+            }
+        }
+    }
+
+    fn update(&mut self) {
+        // get time step
+        let elapsed = self.time_step.elapsed().as_secs_f32();
+        self.time_step = Instant::now();
+        self.jobs.begin_frame();
+        self.apply_remote_commands();
+        // recorded frames may take as long as they need
+        if !self.renderer.recorder.is_recording() {
+            if let Some(n) = self.auto_scale.frame(elapsed, self.compute.num_particles) {
+                self.resize_particles(n);
+            }
+        }
+        // adjust simulation speed
+        if self.renderer.recorder.is_recording() {
+            // recorded frames advance by a fixed step however long they take to render
+            self.sim_params.delta_t = self.renderer.recorder.fixed_delta_t;
+        } else if let Some(speed) = self.speed {
+            self.sim_params.delta_t = speed * elapsed;
+        } else {
+            self.sim_params.delta_t = 0.0;
+        }
+        // keep fast particles from skipping over each other, the limit holds for every
+        // substep, so the frame may go as many times further. the shortest cut off distance
+        // of the pairs keeps short range repulsions from being skipped
+        self.sim_params.delta_t = self.adaptive_timestep.limit(
+            self.sim_params.delta_t,
+            self.compute.reduction.result.map(|result| result.max_speed),
+            self.sim_params.min_cut_off_distance() * self.compute.substeps.max(1) as f32,
+        );
+
+        // scale particle positions along with an animated bounding volume
+        self.sim_params.boundary_scale = 1.0;
+        if let Some(radius) = self.boundary_animation.advance(self.sim_params.delta_t) {
+            self.sim_params.boundary_scale = radius / self.sim_params.bounding_volume_radius;
+            self.set_bounding_volume_radius(radius);
+        }
+        self.psys.grid_animation.advance(self.sim_params.delta_t);
+
+        self.reload_shaders();
+        for (slot, data) in self.assets.poll() {
+            self.renderer.replace_asset(slot, data);
+        }
+
+        // keep the camera on the particle cloud
+        let device = &self.renderer.device;
+        self.compute.reduction.poll(device);
+        self.compute.poll_particle_count(device);
+        self.compute
+            .diagnostics
+            .poll(device, &self.sim_params.particle_type_masses);
+        self.compute.inspector.poll(device);
+        self.compute.pair_probe.poll(device);
+        self.compute.ray_density.poll(device);
+        self.compute.particle_tracker.poll(device);
+        self.run_detector(self.sim_params.delta_t);
+        self.renderer.cutoff_sphere.particle_position = self.compute.particle_tracker.position;
+        self.renderer.camera.follow(
+            self.compute
+                .reduction
+                .result
+                .map(|result| result.center_of_mass),
+            self.compute.particle_tracker.position,
+            elapsed,
+        );
+
+        self.renderer.camera.update_cursor();
+        self.renderer
+            .camera
+            .cursor
+            .process_input(&self.pressed_keys);
+        let cursor = &mut self.renderer.camera.cursor;
+        match cursor.depth_mode {
+            DepthMode::Fixed => {}
+            DepthMode::GridPlane => cursor.snap_to_grid_plane(self.psys.force_grid()),
+            DepthMode::ParticleCluster => {
+                let (origin, dir) = cursor.normalized_ray();
+                self.compute.ray_density.ray = Some((origin, dir, cursor.cluster_radius));
+                if let Some(depth) = self.compute.ray_density.depth {
+                    cursor.snap_to_depth(depth);
+                }
+            }
+        }
+        self.psys.material_grid.paint(cursor, elapsed);
+        self.apply_particle_brush(elapsed);
+
+        self.renderer.gizmos.regions = self
+            .regions
+            .iter()
+            .filter(|region| region.enabled)
+            .map(|region| (region.min, region.max))
+            .collect();
+        self.renderer.set_split_screen(self.comparison.is_some());
+        self.renderer.update_view(&self.sim_params);
+        let glyph_view = self.renderer.glyph_view();
+        let psys = &self.psys;
+        // the arrows of the field expression are previewed in place of the active layer
+        let shown_grid = if self.field_expression.preview {
+            self.field_expression.evaluate(psys.force_grid())
+        } else {
+            None
+        };
+        let shown_grid = shown_grid.unwrap_or(psys.force_grid());
+        let selected_indices = self.renderer.camera.cursor.highlighted_vectors();
+        let culling = &self.renderer.glyph_culling;
+        let ((force_vectors, num_layers), (vector_field_instances, num_glyphs)) = self.jobs.join(
+            || psys.get_layered_force_vectors(),
+            || shown_grid.get_visible_instances_raw(selected_indices, culling, &glyph_view),
+        );
+        self.renderer.glyph_culling.visible = num_glyphs;
+        self.sim_params.num_force_grid_layers = num_layers;
+        self.sim_params.force_grid_animated = self.psys.grid_animation.enabled as u32;
+        self.sim_params.material_grid = self.psys.material_grid.enabled as u32;
+        self.sim_params.set_seed(self.psys.seed);
+        let (reaction_rules, num_reaction_rules) = get_reaction_rules_raw(&self.reactions);
+        self.sim_params.num_reaction_rules = num_reaction_rules;
+        self.compute
+            .update_reaction_rules(&self.renderer.device, &reaction_rules);
+        let (attractors, num_attractors) =
+            get_attractors_raw(&self.renderer.camera.cursor.attractors);
+        self.sim_params.num_attractors = num_attractors;
+        self.compute
+            .update_attractors(&self.renderer.device, &attractors);
+        let (regions, num_regions) = get_regions_raw(&self.regions);
+        self.sim_params.num_regions = num_regions;
+        self.compute.update_regions(&self.renderer.device, &regions);
+        // the polynomes are baked up to the largest cut off distance of the regions too
+        let poly_range = self
+            .regions
+            .iter()
+            .filter(|region| region.enabled)
+            .filter_map(|region| region.cut_off_distance)
+            .fold(self.sim_params.cut_off_distance, f32::max);
+        self.force_curves.apply(&mut self.sim_params, poly_range);
+        if let Some(samples) = self.force_curves.bake(&self.sim_params, poly_range) {
+            self.compute.update_force_curves(
+                &self.renderer.device,
+                &self.renderer.queue,
+                &samples,
+                self.force_curves.lut_width(),
+            );
+        }
+        let (right, up) = self.renderer.camera.right_up();
+        let stir = self.renderer.camera.cursor.stir_force(right.cross(up));
+        self.compute.update_stir(&self.renderer.queue, &stir);
+        self.compute
+            .update_cursor_sphere(&self.renderer.queue, &self.renderer.camera.cursor.sphere());
+        self.compute
+            .update_force_grid(&self.renderer.device, &force_vectors);
+        self.renderer.sub_rpass_vector_field.update_instance_buffer(
+            &self.renderer.device,
+            &vector_field_instances,
+            num_glyphs,
+        );
+        self.compute.update_sim_params(
+            &self.renderer.device,
+            &self.renderer.queue,
+            &self.sim_params,
+        );
+        // the comparison shares everything but its parameters with the edited side
+        if let Some(comparison) = self.comparison.as_mut() {
+            let (device, queue) = (&self.renderer.device, &self.renderer.queue);
+            let sim_params = comparison.frame_params(&self.sim_params);
+            let compute = &mut comparison.compute;
+            compute.update_reaction_rules(device, &reaction_rules);
+            compute.update_attractors(device, &attractors);
+            compute.update_regions(device, &regions);
+            compute.update_stir(queue, &stir);
+            compute.update_cursor_sphere(queue, &self.renderer.camera.cursor.sphere());
+            compute.update_force_grid(device, &force_vectors);
+            compute.substeps = self.compute.substeps;
+            compute.update_sim_params(device, queue, &sim_params);
+        }
+        let fly = self.renderer.camera.mode() == CameraMode::Fly;
+        let gamepad = self.gamepad.poll(fly);
+        let key_motions = self
+            .pressed_keys
+            .iter()
+            .filter_map(|code| key_motion(code, fly))
+            .map(|direction| (direction, 1.0));
+        for (direction, weight) in key_motions.chain(gamepad.motions) {
+            self.renderer.camera.motion(direction, elapsed * weight);
+        }
+        if fly && gamepad.look != (0.0, 0.0) {
+            let (dx, dy) = gamepad.look;
+            self.renderer.camera.look(dx * elapsed, dy * elapsed);
+        }
+        if let Some(speed) = self.speed.as_mut().filter(|_| gamepad.speed != 0.0) {
+            *speed = (*speed * (gamepad.speed * elapsed).exp()).clamp(0.1, 10.0);
+        }
+        if gamepad.brush_strength != 0.0 {
+            let strength = &mut self.renderer.camera.cursor.edit_mode.strength;
+            *strength = (*strength * (gamepad.brush_strength * elapsed).exp()).clamp(0.1, 10.0);
+        }
+    }
+}
+
+/// the camera motion of a held key, the fly camera moves along the view and turns with the mouse
+fn key_motion(code: &Key, fly: bool) -> Option<Direction> {
+    match code {
+        Key::W if fly => Some(Direction::Forward),
+        Key::S if fly => Some(Direction::Backward),
+        Key::E if fly => Some(Direction::Up),
+        Key::R if fly => Some(Direction::Down),
+        Key::W => Some(Direction::Up),
+        Key::S => Some(Direction::Down),
+        Key::A => Some(Direction::Left),
+        Key::D => Some(Direction::Right),
+        Key::E => Some(Direction::RotateRight),
+        Key::R => Some(Direction::RotateLeft),
+        Key::Up => Some(Direction::Forward),
+        Key::Down => Some(Direction::Backward),
+        _ => None,
+    }
+}
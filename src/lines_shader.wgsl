@@ -0,0 +1,48 @@
+struct VertexOutput {
+    @builtin(position) out_pos: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+struct Transform {
+    transform: mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u_transform: Transform;
+
+// colors selected by the x texture coordinate of a vertex
+fn gizmo_color(index: u32) -> vec4<f32> {
+    switch index {
+        case 1u: { return vec4<f32>(1.0, 0.2, 0.2, 1.0); }
+        case 2u: { return vec4<f32>(0.2, 1.0, 0.2, 1.0); }
+        case 3u: { return vec4<f32>(0.3, 0.4, 1.0, 1.0); }
+        case 4u: { return vec4<f32>(0.25, 0.25, 0.25, 1.0); }
+        case 5u: { return vec4<f32>(1.0, 0.8, 0.1, 1.0); }
+        case 6u: { return vec4<f32>(0.1, 0.8, 0.9, 1.0); }
+        case 7u: { return vec4<f32>(0.6, 0.45, 0.3, 1.0); }
+        case 8u: { return vec4<f32>(0.5, 0.9, 1.0, 1.0); }
+        default: { return vec4<f32>(0.7, 0.7, 0.7, 1.0); }
+    }
+}
+
+@vertex
+fn vs_main(
+        @location(0) in_pos: vec3<f32>,
+        @location(1) tex_coord: vec2<f32>,
+        // xyz: offset, w: scale
+        @location(2) instance_pos: vec4<f32>,
+        ) -> VertexOutput {
+    var out: VertexOutput;
+    out.out_pos = u_transform.transform * vec4<f32>(in_pos * instance_pos.w + instance_pos.xyz, 1.0);
+    out.color = gizmo_color(u32(tex_coord.x));
+    return out;
+}
+
+@group(1)@binding(0)
+var texture: texture_2d<f32>;
+@group(1)@binding(1)
+var t_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color * textureSample(texture, t_sampler, vec2<f32>(0.5));
+}
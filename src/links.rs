@@ -0,0 +1,415 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::MetricSpace;
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+use crate::{precision::ParticlePrecision, MassWrap, V3};
+
+/// a spring between two particles, which are found by their ids like the particle tracker
+/// finds its particle after a sort
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Link {
+    pub a: u32,
+    pub b: u32,
+    /// the spring pulls or pushes towards this distance between the particles
+    pub rest_length: f32,
+    pub stiffness: f32,
+}
+
+/// mirrors `LinkParams` in links.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct LinkParams {
+    delta_t: f32,
+    damping: f32,
+    num_ids: u32,
+    _padd: u32,
+    particle_type_masses: [MassWrap; 5],
+}
+
+/// mirrors `LinkEnd` in links.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct LinkEnd {
+    other: u32,
+    rest_length: f32,
+    stiffness: f32,
+    _padd: u32,
+}
+
+/// springs between particles picked in the gui, applied by a pass of their own after
+/// every simulation pass. the links are stored by particle id, which only the particle sort
+/// keeps track of. merged, split and deleted particles lose their id and uploading new
+/// particles starts the ids over, so coalescence pauses the links and uploads drop them
+pub struct Links {
+    pub links: Vec<Link>,
+    /// the ids and the positions of the particles picked for new links, in picking order
+    pub selection: Vec<(u32, V3)>,
+    /// the stiffness of new links
+    pub stiffness: f32,
+    /// damps the relative velocity of linked particles along their link
+    pub damping: f32,
+    /// `link_nearby` connects the picked particles closer than this
+    pub max_length: f32,
+    /// picking adds the particles within this distance of the cursor, at least the nearest
+    pub pick_radius: f32,
+    /// the links changed since they were uploaded
+    dirty: bool,
+    params: LinkParams,
+    params_buffer: Buffer,
+    offsets_buffer: Buffer,
+    ends_buffer: Buffer,
+    bind_group_layouts: [BindGroupLayout; 2],
+    particles_bind_groups: [BindGroup; 2],
+    links_bind_group: BindGroup,
+    pipeline: ComputePipeline,
+}
+
+impl Links {
+    pub fn new(
+        device: &Device,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+        id_buffers: [&Buffer; 2],
+    ) -> Self {
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let read_only = BufferBindingType::Storage { read_only: true };
+        let bind_group_layouts = [
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("links particles bind group layout"),
+                entries: &[
+                    buffer_entry(0, read_only),
+                    buffer_entry(1, BufferBindingType::Storage { read_only: false }),
+                    buffer_entry(2, read_only),
+                    buffer_entry(3, read_only),
+                    buffer_entry(4, read_only),
+                ],
+            }),
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("links bind group layout"),
+                entries: &[
+                    buffer_entry(0, BufferBindingType::Uniform),
+                    buffer_entry(1, read_only),
+                    buffer_entry(2, read_only),
+                ],
+            }),
+        ];
+        let params = LinkParams::zeroed();
+        let params_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("links params buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let (offsets_buffer, ends_buffer) = Self::create_link_buffers(device, &[0], &[]);
+        let particles_bind_groups = Self::create_particles_bind_groups(
+            device,
+            &bind_group_layouts[0],
+            particles_buffers,
+            count_buffer,
+            id_buffers,
+        );
+        let links_bind_group = Self::create_links_bind_group(
+            device,
+            &bind_group_layouts[1],
+            &params_buffer,
+            &offsets_buffer,
+            &ends_buffer,
+        );
+        let pipeline =
+            Self::create_pipeline(device, &bind_group_layouts, precision, workgroup_size);
+        Links {
+            links: Vec::new(),
+            selection: Vec::new(),
+            stiffness: 50.0,
+            damping: 1.0,
+            max_length: 0.1,
+            pick_radius: 0.05,
+            dirty: false,
+            params,
+            params_buffer,
+            offsets_buffer,
+            ends_buffer,
+            bind_group_layouts,
+            particles_bind_groups,
+            links_bind_group,
+            pipeline,
+        }
+    }
+
+    fn create_link_buffers(device: &Device, offsets: &[u32], ends: &[LinkEnd]) -> (Buffer, Buffer) {
+        let offsets_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("link offsets buffer"),
+            contents: bytemuck::cast_slice(offsets),
+            usage: BufferUsages::STORAGE,
+        });
+        // bindings must not be empty
+        let ends = if ends.is_empty() {
+            &[LinkEnd::zeroed()]
+        } else {
+            ends
+        };
+        let ends_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("link ends buffer"),
+            contents: bytemuck::cast_slice(ends),
+            usage: BufferUsages::STORAGE,
+        });
+        (offsets_buffer, ends_buffer)
+    }
+
+    fn create_particles_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+        [ids_buffer, slots_buffer]: [&Buffer; 2],
+    ) -> [BindGroup; 2] {
+        // like the simulation pass, bind group i reads particle buffer i
+        std::array::from_fn(|i| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("links particles bind group"),
+                layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: particles_buffers[i].as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: particles_buffers[(i + 1) % 2].as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: count_buffer.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: ids_buffer.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 4,
+                        resource: slots_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        })
+    }
+
+    fn create_links_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        params_buffer: &Buffer,
+        offsets_buffer: &Buffer,
+        ends_buffer: &Buffer,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("links bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: offsets_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: ends_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        bind_group_layouts: &[BindGroupLayout; 2],
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+    ) -> ComputePipeline {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("links shader module"),
+            source: ShaderSource::Wgsl(Cow::Owned(format!(
+                "const WORKGROUP_SIZE: u32 = {}u;\n{}",
+                workgroup_size,
+                precision.shader_source(include_str!("links.wgsl"))
+            ))),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("links pipeline layout"),
+            bind_group_layouts: &[&bind_group_layouts[0], &bind_group_layouts[1]],
+            push_constant_ranges: &[],
+        });
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("links pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        })
+    }
+
+    /// rebuilds the pipeline for particle buffers stored with `precision`
+    /// and dispatches of `workgroup_size` particles per workgroup
+    pub fn rebuild_pipelines(
+        &mut self,
+        device: &Device,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+    ) {
+        self.pipeline =
+            Self::create_pipeline(device, &self.bind_group_layouts, precision, workgroup_size);
+    }
+
+    /// binds the particle buffers again, call this after they were replaced
+    pub fn update_particles_buffers(
+        &mut self,
+        device: &Device,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+        id_buffers: [&Buffer; 2],
+    ) {
+        self.particles_bind_groups = Self::create_particles_bind_groups(
+            device,
+            &self.bind_group_layouts[0],
+            particles_buffers,
+            count_buffer,
+            id_buffers,
+        );
+    }
+
+    /// the time step of a substep and the masses of the particle types
+    pub fn update_params(&mut self, queue: &Queue, delta_t: f32, masses: &[MassWrap; 5]) {
+        self.params.delta_t = delta_t;
+        self.params.damping = self.damping;
+        self.params.particle_type_masses = *masses;
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+
+    /// uploads the links if they changed, both particles of a link find it under their id
+    pub fn upload(&mut self, device: &Device, queue: &Queue) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        let num_ids = self
+            .links
+            .iter()
+            .map(|link| link.a.max(link.b) + 1)
+            .max()
+            .unwrap_or(0) as usize;
+        let mut counts = vec![0u32; num_ids];
+        for link in &self.links {
+            counts[link.a as usize] += 1;
+            counts[link.b as usize] += 1;
+        }
+        let mut offsets = vec![0u32; num_ids + 1];
+        for (i, count) in counts.iter().enumerate() {
+            offsets[i + 1] = offsets[i] + count;
+        }
+        let mut next = offsets.clone();
+        let mut ends = vec![LinkEnd::zeroed(); 2 * self.links.len()];
+        for link in &self.links {
+            for (id, other) in [(link.a, link.b), (link.b, link.a)] {
+                ends[next[id as usize] as usize] = LinkEnd {
+                    other,
+                    rest_length: link.rest_length,
+                    stiffness: link.stiffness,
+                    _padd: 0,
+                };
+                next[id as usize] += 1;
+            }
+        }
+        (self.offsets_buffer, self.ends_buffer) =
+            Self::create_link_buffers(device, &offsets, &ends);
+        self.links_bind_group = Self::create_links_bind_group(
+            device,
+            &self.bind_group_layouts[1],
+            &self.params_buffer,
+            &self.offsets_buffer,
+            &self.ends_buffer,
+        );
+        self.params.num_ids = num_ids as u32;
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+
+    /// the pass runs while there are links
+    pub fn active(&self) -> bool {
+        !self.links.is_empty()
+    }
+
+    /// encodes the pass reading particle buffer `swap`, which was written by the simulation pass.
+    /// `args_buffer` holds the dispatch arguments of the simulation pass
+    pub fn encode<'a>(&'a self, cpass: &mut ComputePass<'a>, swap: usize, args_buffer: &'a Buffer) {
+        cpass.set_pipeline(&self.pipeline);
+        cpass.set_bind_group(0, &self.particles_bind_groups[swap], &[]);
+        cpass.set_bind_group(1, &self.links_bind_group, &[]);
+        cpass.dispatch_workgroups_indirect(args_buffer, 0);
+    }
+
+    /// links `a` and `b` unless they are linked already
+    pub fn add(&mut self, a: (u32, V3), b: (u32, V3)) {
+        let linked = self
+            .links
+            .iter()
+            .any(|link| (link.a, link.b) == (a.0, b.0) || (link.a, link.b) == (b.0, a.0));
+        if a.0 == b.0 || linked {
+            return;
+        }
+        self.links.push(Link {
+            a: a.0,
+            b: b.0,
+            rest_length: a.1.distance(b.1),
+            stiffness: self.stiffness,
+        });
+        self.dirty = true;
+    }
+
+    /// links every picked particle to the one picked after it, like a strand
+    pub fn link_chain(&mut self) {
+        let selection = self.selection.clone();
+        for pair in selection.windows(2) {
+            self.add(pair[0], pair[1]);
+        }
+    }
+
+    /// links every two picked particles closer than `max_length`, like a cloth
+    pub fn link_nearby(&mut self) {
+        let selection = self.selection.clone();
+        for (i, a) in selection.iter().enumerate() {
+            for b in &selection[i + 1..] {
+                if a.1.distance(b.1) < self.max_length {
+                    self.add(*a, *b);
+                }
+            }
+        }
+    }
+
+    /// adds picked particles to the selection, the ones picked already are skipped
+    pub fn pick(&mut self, picked: impl IntoIterator<Item = (u32, V3)>) {
+        for (id, pos) in picked {
+            if !self.selection.iter().any(|(selected, _)| *selected == id) {
+                self.selection.push((id, pos));
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.links.clear();
+        self.dirty = true;
+    }
+}
@@ -0,0 +1,88 @@
+// pulls the particles connected by links towards the rest length of their links after the
+// simulation pass. every particle sums the springs of its own links, so no two invocations
+// write the same particle.
+// WORKGROUP_SIZE is prepended by `Links` in links.rs
+
+struct MassWrap {
+  @size(16) mass: f32,
+}
+
+// mirrors `LinkParams` in links.rs
+struct LinkParams {
+  delta_t: f32,
+  // damps the relative velocity of the linked particles along their link
+  damping: f32,
+  // the particle ids with an entry in offsets
+  num_ids: u32,
+  _padd: u32,
+  particle_type_masses: array<MassWrap, 5>,
+};
+
+// mirrors `LinkEnd` in links.rs, the other particle of a link
+struct LinkEnd {
+  other: u32,
+  rest_length: f32,
+  stiffness: f32,
+  _padd: u32,
+};
+
+// mirrors `ParticleCount` in indirect.wgsl
+struct ParticleCount {
+  alive: u32,
+  capacity: u32,
+  workgroup_size: u32,
+  _padd: u32,
+};
+
+@group(0) @binding(0) var<storage, read> particlesSrc: array<ParticleStorage>;
+@group(0) @binding(1) var<storage, read_write> particlesDst: array<ParticleStorage>;
+@group(0) @binding(2) var<storage, read> particle_count: ParticleCount;
+// the id of the particle in every slot and the slot of every id, kept by particle_sort.wgsl
+@group(0) @binding(3) var<storage, read> ids: array<u32>;
+@group(0) @binding(4) var<storage, read> slots: array<u32>;
+@group(1) @binding(0) var<uniform> params: LinkParams;
+// the links of the particle with id i are link_ends[offsets[i]..offsets[i + 1]]
+@group(1) @binding(1) var<storage, read> offsets: array<u32>;
+@group(1) @binding(2) var<storage, read> link_ends: array<LinkEnd>;
+
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn main(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let total = min(particle_count.alive, arrayLength(&particlesSrc));
+  let index = invocation_index(global_invocation_id, num_workgroups, WORKGROUP_SIZE);
+  if index >= total {
+    return;
+  }
+  var p = load_particle(particlesSrc[index]);
+  let id = ids[index];
+  if id < params.num_ids {
+    var force = vec3<f32>();
+    for (var k = offsets[id]; k < offsets[id + 1u]; k++) {
+      let end = link_ends[k];
+      if end.other >= arrayLength(&slots) {
+        continue;
+      }
+      let slot = slots[end.other];
+      if slot >= total {
+        continue;
+      }
+      let other = load_particle(particlesSrc[slot]);
+      let d = other.pos.xyz - p.pos.xyz;
+      let len = length(d);
+      if len < 0.000001 {
+        continue;
+      }
+      let dir = d / len;
+      let closing = dot(other.vel.xyz - p.vel.xyz, dir);
+      force += dir * (end.stiffness * (len - end.rest_length) + params.damping * closing);
+    }
+    let acc = force / (params.particle_type_masses[p.ty].mass * p.mass);
+    // semi implicit, the position follows the corrected velocity
+    p.vel = vec4<f32>(p.vel.xyz + acc * params.delta_t, p.vel.w);
+    p.pos = vec4<f32>(p.pos.xyz + acc * params.delta_t * params.delta_t, p.pos.w);
+  }
+  particlesDst[index] = store_particle(p);
+}
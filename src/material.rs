@@ -0,0 +1,104 @@
+use cgmath::MetricSpace;
+
+use crate::{
+    cursor::{Cursor, Falloff},
+    grid::Grid,
+    sim_params::SimParams,
+};
+
+/// the damping factor of cells that were not painted
+pub const NEUTRAL_DAMPING: f32 = 1.0;
+/// how fast the painted cells approach the painted value at the center of the brush,
+/// per second
+const PAINT_RATE: f32 = 8.0;
+
+/// damping factors painted by the gui
+pub const MATERIAL_PRESETS: [(&str, f32); 3] = [
+    ("slippery", 0.0),
+    ("neutral", NEUTRAL_DAMPING),
+    ("sticky", 20.0),
+];
+
+/// a damping factor for every force grid cell, it multiplies the damping of the particles
+/// inside. sticky cells slow the particles down, in slippery ones they keep their speed
+pub struct MaterialGrid {
+    /// the compute shader only samples the grid while it is enabled
+    pub enabled: bool,
+    /// has the cells of the force grid
+    pub grid: Grid<f32>,
+    /// the damping factor painted by the cursor
+    pub paint_value: f32,
+}
+
+impl MaterialGrid {
+    pub fn new(sim_params: &SimParams) -> Self {
+        let [n_x, n_y, n_z] = sim_params.force_grid_dimensions.map(|n| n as usize);
+        MaterialGrid {
+            enabled: false,
+            grid: Grid::new_uniform(
+                n_x,
+                n_y,
+                n_z,
+                sim_params.bounding_volume_bounds(),
+                &NEUTRAL_DAMPING,
+            ),
+            paint_value: MATERIAL_PRESETS[2].1,
+        }
+    }
+
+    /// resamples the grid to the force grid dimensions and fits it into the bounding volume
+    pub fn fit(&mut self, sim_params: &SimParams) {
+        self.grid
+            .resize(sim_params.force_grid_dimensions.map(|n| n as usize));
+        self.grid.bounds = sim_params.bounding_volume_bounds();
+    }
+
+    /// makes every cell neutral again
+    pub fn clear(&mut self) {
+        self.grid.grid.fill(NEUTRAL_DAMPING);
+    }
+
+    /// moves the cells within the brush towards `paint_value` while the cursor
+    /// drags in paint mode, with the falloff of the cursor edit mode
+    pub fn paint(&mut self, cursor: &Cursor, elapsed: f32) {
+        if !cursor.paint || cursor.mouse_down_on.is_none() {
+            return;
+        }
+        let axes = cursor.brush.axes(cursor.rot);
+        let size = cursor.edit_mode.falloff_dist;
+        let rate = 1.0 - (-PAINT_RATE * elapsed).exp();
+        for ix in 0..self.grid.num_instances() {
+            let pos = self.grid.position(ix);
+            if !cursor.brush.contains(cursor.pos, axes, size, pos) {
+                continue;
+            }
+            let distance = pos.distance(cursor.pos);
+            let weight = match cursor.edit_mode.falloff {
+                Falloff::Abrupt => 1.0,
+                Falloff::Linear => 1.0 - distance / size,
+                Falloff::InverseDistance => (size / (distance + 1.0)).min(1.0),
+            };
+            let cell = &mut self.grid.grid[ix];
+            *cell += (self.paint_value - *cell) * rate * weight.max(0.0);
+        }
+    }
+
+    /// sets the cells with the indices `selection` of the force grid to `paint_value`
+    pub fn fill(&mut self, selection: &[usize]) {
+        for ix in selection {
+            if let Some(cell) = self.grid.grid.get_mut(*ix) {
+                *cell = self.paint_value;
+            }
+        }
+    }
+
+    /// the damping factors four to a vector, as they follow the force grid layers
+    /// in the force grid buffer
+    pub fn get_raw(&self) -> Vec<[f32; 4]> {
+        self.grid
+            .grid
+            .chunks(4)
+            .map(|chunk| std::array::from_fn(|i| chunk.get(i).copied().unwrap_or(NEUTRAL_DAMPING)))
+            .collect()
+    }
+}
@@ -0,0 +1,122 @@
+use wgpu::Limits;
+
+use crate::{
+    indirect::{split_workgroups, MAX_WORKGROUPS_PER_DIMENSION},
+    precision::ParticlePrecision,
+};
+
+/// bytes of a force grid entry, a vector and the blend factor or four material cells
+const FORCE_VECTOR_SIZE: u64 = 16;
+/// bytes of a vector field glyph instance, see `INSTANCE_FLOATS` in grid.rs
+const GLYPH_INSTANCE_SIZE: u64 = 48;
+/// bytes of the u32 buffers with an entry per particle: the ids, slots and sorted ids
+/// of the particle sort and the merge partners
+const PARTICLE_INDEX_SIZE: u64 = 16;
+
+/// the configuration the buffers growing with the simulation are sized for,
+/// checked against the limits of the device before the buffers are allocated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryPlan {
+    pub num_particles: usize,
+    pub precision: ParticlePrecision,
+    pub force_grid_dimensions: [u32; 3],
+    /// grids in the force grid buffer: the enabled layers, the keyframes they blend to while
+    /// the grid is animated and a quarter grid for the material grid
+    pub grids_per_cell: f32,
+    pub workgroup_size: u32,
+}
+
+impl MemoryPlan {
+    pub fn num_cells(&self) -> u64 {
+        self.force_grid_dimensions
+            .iter()
+            .map(|&n| n as u64)
+            .product()
+    }
+
+    pub fn particle_buffer_size(&self) -> u64 {
+        self.num_particles.max(1) as u64 * self.precision.particle_size() as u64
+    }
+
+    pub fn force_grid_buffer_size(&self) -> u64 {
+        let entries = (self.num_cells() as f32 * self.grids_per_cell).ceil() as u64;
+        entries.max(1) * FORCE_VECTOR_SIZE
+    }
+
+    pub fn glyph_buffer_size(&self) -> u64 {
+        self.num_cells() * GLYPH_INSTANCE_SIZE
+    }
+
+    /// the x and y workgroup counts of a simulation dispatch
+    pub fn workgroups(&self) -> (u32, u32) {
+        split_workgroups((self.num_particles as u32).div_ceil(self.workgroup_size.max(1)))
+    }
+
+    /// bytes of all buffers growing with the particles and the grid. the particle buffers
+    /// grow by copying into new ones, so the old ones are counted twice
+    pub fn peak_size(&self) -> u64 {
+        let particles = 2 * self.particle_buffer_size();
+        let indices = self.num_particles as u64 * PARTICLE_INDEX_SIZE;
+        2 * particles + indices + self.force_grid_buffer_size() + self.glyph_buffer_size()
+    }
+
+    /// the limits of the device the plan exceeds, empty when its buffers can be allocated
+    pub fn warnings(&self, limits: &Limits) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let storage_limit =
+            (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        let mut check = |name: &str, size: u64, limit: u64, what: &str| {
+            if size > limit {
+                warnings.push(format!(
+                    "the {} buffer needs {}, more than the {} of {}",
+                    name,
+                    format_bytes(size),
+                    what,
+                    format_bytes(limit)
+                ));
+            }
+        };
+        check(
+            "particle",
+            self.particle_buffer_size(),
+            storage_limit,
+            "largest storage buffer",
+        );
+        check(
+            "force grid",
+            self.force_grid_buffer_size(),
+            storage_limit,
+            "largest storage buffer",
+        );
+        check(
+            "glyph instance",
+            self.glyph_buffer_size(),
+            limits.max_buffer_size,
+            "largest buffer",
+        );
+        let (_, rows) = self.workgroups();
+        if rows > limits.max_compute_workgroups_per_dimension {
+            warnings.push(format!(
+                "{} rows of {} workgroups exceed the {} workgroups per dimension",
+                rows, MAX_WORKGROUPS_PER_DIMENSION, limits.max_compute_workgroups_per_dimension
+            ));
+        }
+        warnings
+    }
+}
+
+/// `bytes` in the largest binary unit it has at least one of
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
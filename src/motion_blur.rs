@@ -0,0 +1,239 @@
+use std::borrow::Cow;
+
+use wgpu::*;
+
+use crate::{compute::Compute, draw_pass::DrawPass, indirect::DRAW_ARGS_OFFSET, renderer::Vertex};
+
+/// format of the screen space particle motion
+const VELOCITY_FORMAT: TextureFormat = TextureFormat::Rg16Float;
+
+/// smears the particles along their screen space motion. the particles are drawn into
+/// a texture of their own and drawn a second time stretched over their path, writing
+/// their motion into a velocity texture, which the blur pass averages the scene along
+pub struct MotionBlur {
+    /// fraction of the time step the particles are smeared over
+    pub strength: f32,
+    /// the particles are drawn into this instead of the scene target
+    pub scene_view: TextureView,
+    velocity_view: TextureView,
+    velocity_pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+}
+
+impl MotionBlur {
+    /// `particles` is the draw pass whose shader has the `vs_velocity` and `fs_velocity` entry
+    /// points, `width` and `height` are the size of the scene target
+    pub fn new(
+        device: &Device,
+        particles: &DrawPass,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        strength: f32,
+    ) -> Self {
+        let size = Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        };
+        let create_view = |label, format| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some(label),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+        let scene_view = create_view("motion blur scene texture", format);
+        let velocity_view = create_view("motion blur velocity texture", VELOCITY_FORMAT);
+
+        let velocity_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("velocity pipeline layout"),
+            bind_group_layouts: &particles.bind_group_layouts(),
+            push_constant_ranges: &[],
+        });
+        let vertex_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &vertex_attr_array![0 => Float32x3, 1 => Float32x2],
+        };
+        let velocity_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("velocity pipeline"),
+            layout: Some(&velocity_layout),
+            vertex: VertexState {
+                module: &particles.shader,
+                entry_point: "vs_velocity",
+                buffers: &[vertex_layout, particles.instance_layout.clone()],
+            },
+            fragment: Some(FragmentState {
+                module: &particles.shader,
+                entry_point: "fs_velocity",
+                targets: &[Some(VELOCITY_FORMAT.into())],
+            }),
+            primitive: PrimitiveState {
+                topology: particles.topology,
+                front_face: FrontFace::Cw,
+                ..Default::default()
+            },
+            // the particles in front hide the motion of the ones behind them
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::LessEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("motion blur sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("motion blur bind group layout"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("motion blur bind group"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&scene_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&velocity_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("motion blur shader module"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("motion_blur.wgsl"))),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("motion blur pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("motion blur pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(format.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+        MotionBlur {
+            strength,
+            scene_view,
+            velocity_view,
+            velocity_pipeline,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// writes the motion of the particles drawn into `scene_view` and `depth_view`
+    /// and draws them blurred onto `view`
+    pub fn encode(
+        &self,
+        encoder: &mut CommandEncoder,
+        particles: &DrawPass,
+        compute: &Compute,
+        depth_view: &TextureView,
+        view: &TextureView,
+    ) {
+        {
+            let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("velocity render pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.velocity_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: depth_view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            particles.render_indirect_with(
+                &self.velocity_pipeline,
+                &mut rpass,
+                &compute.particles_buffers[0],
+                &compute.indirect.args_buffer,
+                DRAW_ARGS_OFFSET,
+            );
+        }
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("motion blur render pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,37 @@
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) tex_coord: vec2<f32>,
+};
+
+@group(0) @binding(0) var scene: texture_2d<f32>;
+// motion of the particles over the blur time in texture coordinates, written by fs_velocity in shader.wgsl
+@group(0) @binding(1) var velocity: texture_2d<f32>;
+@group(0) @binding(2) var source_sampler: sampler;
+
+// scene samples averaged along the motion of a pixel
+const SAMPLES: i32 = 12;
+
+// a single triangle covering the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+  let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+  var out: VertexOutput;
+  out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+  out.tex_coord = vec2<f32>(uv.x, 1.0 - uv.y);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let motion = textureSampleLevel(velocity, source_sampler, in.tex_coord, 0.0).xy;
+  if dot(motion, motion) < 1e-10 {
+    return textureSampleLevel(scene, source_sampler, in.tex_coord, 0.0);
+  }
+  // the blur is centered on the particle positions of the frame
+  var sum = vec4<f32>(0.0);
+  for (var i = 0; i < SAMPLES; i++) {
+    let t = f32(i) / f32(SAMPLES - 1) - 0.5;
+    sum += textureSampleLevel(scene, source_sampler, in.tex_coord + motion * t, 0.0);
+  }
+  return sum / f32(SAMPLES);
+}
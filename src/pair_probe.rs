@@ -0,0 +1,199 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+use crate::{
+    compute_features::ComputeFeatures,
+    readback::{Readback, ReadbackCopy},
+};
+
+/// distances the probe samples the pair force at
+pub const PAIR_PROBE_SAMPLES: u32 = 128;
+/// the samples reach a little beyond the cut off distance, so its end is visible
+pub const PAIR_PROBE_RANGE: f32 = 1.1;
+const PAIR_PROBE_WORKGROUP_SIZE: u32 = 64;
+/// the distance and the force of a sample
+const SAMPLE_SIZE: u64 = std::mem::size_of::<[f32; 2]>() as u64;
+
+/// mirrors `PairProbe` in compute.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct PairProbeParams {
+    index: u32,
+    count: u32,
+    range: f32,
+    _pad: u32,
+}
+
+/// the force curve of one pair as the compute shader evaluates it
+pub struct PairProbeResult {
+    /// the index of the pair into the force matrix
+    pub index: usize,
+    /// the distances and the attraction there, before the mass coupling
+    pub samples: Vec<[f32; 2]>,
+}
+
+/// samples the attraction between two probe particles at increasing distances with the
+/// pair force code of the compute shader and reads it back, so the gui can compare it with
+/// the curves evaluated on the CPU. a mismatch means the layout of the parameters or the
+/// windowing differs between both sides
+pub struct PairProbe {
+    /// the pair the next probe samples
+    pub index: usize,
+    /// a probe is encoded with the next frame
+    pub requested: bool,
+    /// probes every frame, so the plot follows the edits of the curves
+    pub live: bool,
+    pub result: Option<PairProbeResult>,
+    bind_group_layout: BindGroupLayout,
+    samples_buffer: Buffer,
+    readback: Readback,
+    // compiled for the features of the simulation pipeline, dropped when they change
+    pipeline: Option<(ComputeFeatures, ComputePipeline)>,
+    // the pair of the probe in flight
+    pending: usize,
+}
+
+impl PairProbe {
+    pub fn new(device: &Device) -> Self {
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("pair probe bind group layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let samples_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("pair probe samples buffer"),
+            size: PAIR_PROBE_SAMPLES as u64 * SAMPLE_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        PairProbe {
+            index: 0,
+            requested: false,
+            live: false,
+            result: None,
+            bind_group_layout,
+            samples_buffer,
+            readback: Readback::new("pair probe readback buffer"),
+            pipeline: None,
+            pending: 0,
+        }
+    }
+
+    /// drops the pipeline, call this when the compute shader was replaced
+    pub fn invalidate(&mut self) {
+        self.pipeline = None;
+    }
+
+    /// dispatches the probe entry point of the compute shader with the bind group of the
+    /// simulation and copies the samples out. `shader` compiles the compute shader for
+    /// `features`, it is only called when the pipeline is not cached
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        features: ComputeFeatures,
+        shader: impl FnOnce() -> ShaderModule,
+        sim_bind_group_layout: &BindGroupLayout,
+        sim_bind_group: &BindGroup,
+    ) {
+        if !(self.requested || self.live) || !self.readback.is_idle() {
+            return;
+        }
+        self.requested = false;
+        if self.pipeline.as_ref().map(|(f, _)| *f) != Some(features) {
+            let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("pair probe pipeline layout"),
+                bind_group_layouts: &[sim_bind_group_layout, &self.bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("pair probe pipeline"),
+                layout: Some(&layout),
+                module: &shader(),
+                entry_point: "probe_pair_force",
+            });
+            self.pipeline = Some((features, pipeline));
+        }
+        let Some((_, pipeline)) = &self.pipeline else {
+            return;
+        };
+        let params = PairProbeParams {
+            index: self.index as u32,
+            count: PAIR_PROBE_SAMPLES,
+            range: PAIR_PROBE_RANGE,
+            _pad: 0,
+        };
+        let params_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("pair probe params buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("pair probe bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: self.samples_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("pair probe compute pass"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(pipeline);
+            cpass.set_bind_group(0, sim_bind_group, &[]);
+            cpass.set_bind_group(1, &bind_group, &[]);
+            cpass.dispatch_workgroups(PAIR_PROBE_SAMPLES.div_ceil(PAIR_PROBE_WORKGROUP_SIZE), 1, 1);
+        }
+        self.readback.encode(
+            device,
+            encoder,
+            &[ReadbackCopy {
+                buffer: &self.samples_buffer,
+                offset: 0,
+                size: self.samples_buffer.size(),
+            }],
+        );
+        self.pending = self.index;
+    }
+
+    /// starts mapping the readback buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.readback.after_submit();
+    }
+
+    /// collects a finished probe into `result`
+    pub fn poll(&mut self, device: &Device) {
+        let Some(samples) = self.readback.poll(device, |data| {
+            bytemuck::cast_slice::<u8, [f32; 2]>(data).to_vec()
+        }) else {
+            return;
+        };
+        self.result = Some(PairProbeResult {
+            index: self.pending,
+            samples,
+        });
+    }
+}
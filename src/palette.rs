@@ -0,0 +1,68 @@
+/// number of particle types tinted by a palette
+pub const NUM_TINTS: usize = 5;
+
+/// built in RGBA tints per particle type, multiplied with the texture atlas
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Palette {
+    /// the colors of the texture atlas unchanged
+    Atlas,
+    Pastel,
+    Neon,
+    Earth,
+    Monochrome,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 5] = [
+        Palette::Atlas,
+        Palette::Pastel,
+        Palette::Neon,
+        Palette::Earth,
+        Palette::Monochrome,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Atlas => "texture atlas",
+            Palette::Pastel => "pastel",
+            Palette::Neon => "neon",
+            Palette::Earth => "earth",
+            Palette::Monochrome => "monochrome",
+        }
+    }
+
+    /// the tint of every particle type, in the order of `ParticleType`
+    pub fn tints(&self) -> [[f32; 4]; NUM_TINTS] {
+        match self {
+            Palette::Atlas => [[1.0; 4]; NUM_TINTS],
+            Palette::Pastel => [
+                [0.8, 0.9, 0.7, 1.0],
+                [0.7, 0.85, 1.0, 1.0],
+                [1.0, 0.75, 0.7, 1.0],
+                [0.95, 0.95, 0.8, 1.0],
+                [0.85, 0.75, 1.0, 1.0],
+            ],
+            Palette::Neon => [
+                [0.2, 1.0, 0.2, 1.0],
+                [0.0, 0.8, 1.0, 1.0],
+                [1.0, 0.2, 0.6, 1.0],
+                [1.0, 1.0, 0.1, 1.0],
+                [0.7, 0.2, 1.0, 1.0],
+            ],
+            Palette::Earth => [
+                [0.55, 0.4, 0.25, 1.0],
+                [0.3, 0.5, 0.6, 1.0],
+                [0.85, 0.45, 0.2, 1.0],
+                [0.75, 0.75, 0.65, 1.0],
+                [0.4, 0.55, 0.3, 1.0],
+            ],
+            Palette::Monochrome => [
+                [1.0, 1.0, 1.0, 1.0],
+                [0.8, 0.8, 0.8, 1.0],
+                [0.6, 0.6, 0.6, 1.0],
+                [0.45, 0.45, 0.45, 1.0],
+                [0.3, 0.3, 0.3, 1.0],
+            ],
+        }
+    }
+}
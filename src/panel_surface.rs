@@ -0,0 +1,101 @@
+use egui::FullOutput;
+use egui_wgpu::renderer::ScreenDescriptor;
+use wgpu::*;
+
+/// the surface of the window the control panel is detached into,
+/// it has an egui renderer of its own since the panel has an egui context of its own
+pub struct PanelSurface {
+    pub surface: Surface,
+    pub config: SurfaceConfiguration,
+    egui_rpass: egui_wgpu::renderer::Renderer,
+}
+
+impl PanelSurface {
+    pub fn new(
+        device: &Device,
+        surface: Surface,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: width.max(1),
+            height: height.max(1),
+            // the main window already waits for the vertical blank
+            present_mode: PresentMode::AutoNoVsync,
+            alpha_mode: CompositeAlphaMode::Auto,
+            view_formats: vec![format],
+        };
+        surface.configure(device, &config);
+        PanelSurface {
+            surface,
+            config,
+            egui_rpass: egui_wgpu::renderer::Renderer::new(device, format, None, 1),
+        }
+    }
+
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        // a minimized window reports a zero size on some platforms
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(device, &self.config);
+    }
+
+    pub fn render(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        frame: &SurfaceTexture,
+        output: FullOutput,
+        context: &egui::Context,
+        scale_factor: f32,
+    ) {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("panel command encoder"),
+        });
+        let clipped_primitives = context.tessellate(output.shapes, scale_factor);
+        let view = frame.texture.create_view(&TextureViewDescriptor::default());
+        let screen_descriptor = ScreenDescriptor {
+            size_in_pixels: [self.config.width, self.config.height],
+            pixels_per_point: scale_factor,
+        };
+        for (id, image_delta) in &output.textures_delta.set {
+            self.egui_rpass
+                .update_texture(device, queue, *id, image_delta);
+        }
+        for id in &output.textures_delta.free {
+            self.egui_rpass.free_texture(id);
+        }
+        self.egui_rpass.update_buffers(
+            device,
+            queue,
+            &mut encoder,
+            &clipped_primitives,
+            &screen_descriptor,
+        );
+        {
+            let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("panel render pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.egui_rpass
+                .render(&mut rpass, &clipped_primitives, &screen_descriptor);
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+}
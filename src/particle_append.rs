@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+
+use crate::{indirect::split_workgroups, precision::ParticlePrecision, Particle};
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+const PLACE_GROUP_SIZE: usize = 64;
+
+/// mirrors `AppendParams` in particle_append.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct AppendParams {
+    count: u32,
+    _padd: [u32; 3],
+}
+
+/// writes new particles behind the ones alive on the GPU and counts them in.
+/// emitters, merges and splits change the alive count on the GPU before the CPU hears of it,
+/// so the slots of the new particles are only known there
+pub struct ParticleAppend {
+    place_pipeline: ComputePipeline,
+    commit_pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+}
+
+impl ParticleAppend {
+    pub fn new(device: &Device, precision: ParticlePrecision) -> Self {
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("particle append bind group layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(3, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(4, BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let (place_pipeline, commit_pipeline) =
+            Self::create_pipelines(device, &bind_group_layout, precision);
+        ParticleAppend {
+            place_pipeline,
+            commit_pipeline,
+            bind_group_layout,
+        }
+    }
+
+    fn create_pipelines(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        precision: ParticlePrecision,
+    ) -> (ComputePipeline, ComputePipeline) {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("particle append shader module"),
+            source: ShaderSource::Wgsl(Cow::Owned(
+                precision.shader_source(include_str!("particle_append.wgsl")),
+            )),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("particle append pipeline layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+        (create_pipeline("place"), create_pipeline("commit"))
+    }
+
+    /// rebuilds the pipelines for particle buffers stored with `precision`
+    pub fn set_precision(&mut self, device: &Device, precision: ParticlePrecision) {
+        (self.place_pipeline, self.commit_pipeline) =
+            Self::create_pipelines(device, &self.bind_group_layout, precision);
+    }
+
+    /// encodes writing `particles` to both particle buffers behind the alive ones
+    /// and adding them to the alive count. the ones beyond the capacity are dropped
+    pub fn encode(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+        precision: ParticlePrecision,
+        particles: &[Particle],
+    ) {
+        if particles.is_empty() {
+            return;
+        }
+        let params = AppendParams {
+            count: particles.len() as u32,
+            _padd: [0; 3],
+        };
+        let params_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("particle append params buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: BufferUsages::UNIFORM,
+        });
+        let appended_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("appended particles buffer"),
+            contents: &precision.encode_particles(particles),
+            usage: BufferUsages::STORAGE,
+        });
+        let buffers = [
+            &params_buffer,
+            &appended_buffer,
+            &particles_buffers[0],
+            &particles_buffers[1],
+            count_buffer,
+        ];
+        let entries: Vec<BindGroupEntry> = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("particle append bind group"),
+            layout: &self.bind_group_layout,
+            entries: &entries,
+        });
+        let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("particle append pass"),
+            timestamp_writes: None,
+        });
+        cpass.set_bind_group(0, &bind_group, &[]);
+        cpass.set_pipeline(&self.place_pipeline);
+        let (x, y) = split_workgroups(particles.len().div_ceil(PLACE_GROUP_SIZE) as u32);
+        cpass.dispatch_workgroups(x, y, 1);
+        cpass.set_pipeline(&self.commit_pipeline);
+        cpass.dispatch_workgroups(1, 1, 1);
+    }
+}
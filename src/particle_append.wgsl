@@ -0,0 +1,45 @@
+// writes new particles behind the alive ones and counts them in,
+// the place pass reads the alive count and the commit pass after it raises it
+
+// mirrors `AppendParams` in particle_append.rs
+struct AppendParams {
+  // number of appended particles
+  count: u32,
+};
+
+// mirrors `ParticleCount` in indirect.wgsl
+struct ParticleCount {
+  alive: atomic<u32>,
+  capacity: u32,
+  workgroup_size: u32,
+  _padd: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: AppendParams;
+@group(0) @binding(1) var<storage, read> appended: array<ParticleStorage>;
+// both particle buffers, the next simulation pass can read either
+@group(0) @binding(2) var<storage, read_write> particlesA: array<ParticleStorage>;
+@group(0) @binding(3) var<storage, read_write> particlesB: array<ParticleStorage>;
+@group(0) @binding(4) var<storage, read_write> particle_count: ParticleCount;
+
+@compute
+@workgroup_size(64)
+fn place(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = invocation_index(global_invocation_id, num_workgroups, 64u);
+  let slot = atomicLoad(&particle_count.alive) + index;
+  if index >= params.count || slot >= min(particle_count.capacity, arrayLength(&particlesA)) {
+    return;
+  }
+  particlesA[slot] = appended[index];
+  particlesB[slot] = appended[index];
+}
+
+@compute
+@workgroup_size(1)
+fn commit() {
+  let alive = atomicLoad(&particle_count.alive) + params.count;
+  atomicStore(&particle_count.alive, min(alive, particle_count.capacity));
+}
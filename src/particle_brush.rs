@@ -0,0 +1,106 @@
+use cgmath::InnerSpace;
+use rand::Rng;
+
+use crate::{Particle, V3};
+
+/// what dragging with the particle brush does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParticleBrushMode {
+    /// new particles appear at random positions within the cursor radius
+    Spawn,
+    /// the particles within the cursor radius are dropped by the coalescence pass
+    Delete,
+}
+
+impl ParticleBrushMode {
+    pub const ALL: [ParticleBrushMode; 2] = [ParticleBrushMode::Spawn, ParticleBrushMode::Delete];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParticleBrushMode::Spawn => "spawn",
+            ParticleBrushMode::Delete => "delete",
+        }
+    }
+}
+
+/// spawns or deletes particles around the cursor while dragging with the left mouse button.
+/// spawned particles are appended behind the alive ones on the GPU and deleted ones are
+/// compacted away, so the particle buffers are not uploaded again
+pub struct ParticleBrush {
+    /// dragging spawns or deletes particles instead of editing vectors
+    pub enabled: bool,
+    pub mode: ParticleBrushMode,
+    /// the type of spawned particles
+    pub ty: u32,
+    /// spawned particles per second of dragging
+    pub rate: f32,
+    /// the velocities of spawned particles are random up to this speed along every axis
+    pub velocity_spread: f32,
+    /// the fraction of a particle left over from the last frame
+    pending: f32,
+}
+
+impl ParticleBrush {
+    pub fn new() -> Self {
+        ParticleBrush {
+            enabled: false,
+            mode: ParticleBrushMode::Spawn,
+            ty: 0,
+            rate: 200.0,
+            velocity_spread: 1.0,
+            pending: 0.0,
+        }
+    }
+
+    /// the particles spawned within `radius` around `center` over `elapsed` seconds of
+    /// dragging, at most `max` of them
+    pub fn spawn(
+        &mut self,
+        rng: &mut impl Rng,
+        center: V3,
+        radius: f32,
+        elapsed: f32,
+        max: usize,
+    ) -> Vec<Particle> {
+        self.pending += self.rate * elapsed;
+        let count = (self.pending as usize).min(max);
+        self.pending = self.pending.fract();
+        let mut random_v3 = |scale: f32| {
+            V3::new(
+                rng.gen::<f32>() * 2.0 - 1.0,
+                rng.gen::<f32>() * 2.0 - 1.0,
+                rng.gen::<f32>() * 2.0 - 1.0,
+            ) * scale
+        };
+        (0..count)
+            .map(|_| {
+                // uniform within the sphere by rejecting the corners of the cube
+                let mut offset = random_v3(radius);
+                while offset.magnitude2() > radius * radius {
+                    offset = random_v3(radius);
+                }
+                let vel = random_v3(self.velocity_spread);
+                Particle {
+                    pos: (center + offset).extend(1.0).into(),
+                    vel: vel.extend(1.0).into(),
+                    ty: self.ty,
+                    age: 0.0,
+                    mass: 1.0,
+                    _padd: 0,
+                }
+            })
+            .collect()
+    }
+
+    /// the center and the radius of the sphere the coalescence pass deletes the particles in,
+    /// None while the brush does not delete
+    pub fn delete_sphere(&self, dragging: bool, center: V3, radius: f32) -> Option<(V3, f32)> {
+        let active = self.enabled && dragging && self.mode == ParticleBrushMode::Delete;
+        active.then_some((center, radius))
+    }
+
+    /// drops the fraction of a particle left over, call this when the drag ends
+    pub fn reset(&mut self) {
+        self.pending = 0.0;
+    }
+}
@@ -0,0 +1,40 @@
+// particle storage with positions and velocities packed into half floats and the mass
+// in the w component of the position, prepended to every shader reading the particle buffers
+struct Particle {
+  pos: vec4<f32>,
+  vel: vec4<f32>,
+  ty: u32,
+  age: f32,
+  // multiple of the mass of its type
+  mass: f32,
+};
+
+struct ParticleStorage {
+  pos: vec2<u32>,
+  vel: vec2<u32>,
+  ty: u32,
+  age: f32,
+};
+
+fn unpack_half4(v: vec2<u32>) -> vec4<f32> {
+    return vec4<f32>(unpack2x16float(v.x), unpack2x16float(v.y));
+}
+
+fn pack_half4(v: vec4<f32>) -> vec2<u32> {
+    return vec2<u32>(pack2x16float(v.xy), pack2x16float(v.zw));
+}
+
+fn load_particle(s: ParticleStorage) -> Particle {
+    let pos = unpack_half4(s.pos);
+    return Particle(vec4<f32>(pos.xyz, 1.0), unpack_half4(s.vel), s.ty, s.age, pos.w);
+}
+
+fn store_particle(p: Particle) -> ParticleStorage {
+    return ParticleStorage(pack_half4(vec4<f32>(p.pos.xyz, p.mass)), pack_half4(p.vel), p.ty, p.age);
+}
+
+// the index of an invocation of a dispatch split into rows of at most 65535 workgroups,
+// see `split_workgroups` in indirect.rs
+fn invocation_index(id: vec3<u32>, num_workgroups: vec3<u32>, workgroup_size: u32) -> u32 {
+    return id.x + id.y * num_workgroups.x * workgroup_size;
+}
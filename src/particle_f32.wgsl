@@ -0,0 +1,31 @@
+// particle storage in full precision, prepended to every shader reading the particle buffers
+struct Particle {
+  pos: vec4<f32>,
+  vel: vec4<f32>,
+  ty: u32,
+  age: f32,
+  // multiple of the mass of its type
+  mass: f32,
+};
+
+struct ParticleStorage {
+  pos: vec4<f32>,
+  vel: vec4<f32>,
+  ty: u32,
+  age: f32,
+  mass: f32,
+};
+
+fn load_particle(s: ParticleStorage) -> Particle {
+    return Particle(s.pos, s.vel, s.ty, s.age, s.mass);
+}
+
+fn store_particle(p: Particle) -> ParticleStorage {
+    return ParticleStorage(p.pos, p.vel, p.ty, p.age, p.mass);
+}
+
+// the index of an invocation of a dispatch split into rows of at most 65535 workgroups,
+// see `split_workgroups` in indirect.rs
+fn invocation_index(id: vec3<u32>, num_workgroups: vec3<u32>, workgroup_size: u32) -> u32 {
+    return id.x + id.y * num_workgroups.x * workgroup_size;
+}
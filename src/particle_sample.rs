@@ -0,0 +1,87 @@
+use wgpu::*;
+
+use crate::{
+    precision::ParticlePrecision,
+    readback::{Readback, ReadbackCopy},
+    Particle,
+};
+
+/// reads an evenly spaced sample of the alive particles back on request, so code that
+/// only looks at a few thousand particles neither waits for the GPU nor copies all of them
+pub struct ParticleSample {
+    /// the most particles of the requested sample
+    requested: Option<usize>,
+    readback: Readback,
+    /// the precision and the slots between two particles of the sample in flight
+    precision: ParticlePrecision,
+    stride: usize,
+}
+
+impl ParticleSample {
+    pub fn new() -> Self {
+        ParticleSample {
+            requested: None,
+            readback: Readback::new("particle sample readback buffer"),
+            precision: ParticlePrecision::Full,
+            stride: 1,
+        }
+    }
+
+    /// reads at most `size` particles with the next frame
+    pub fn request(&mut self, size: usize) {
+        self.requested = Some(size.max(1));
+    }
+
+    /// copies every few particles of `particles` into the readback buffer when requested
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        particles: &Buffer,
+        num_particles: usize,
+        precision: ParticlePrecision,
+    ) {
+        let Some(size) = self.requested else {
+            return;
+        };
+        if num_particles == 0 || !self.readback.is_idle() {
+            return;
+        }
+        let stride = num_particles.div_ceil(size);
+        let particle_size = precision.particle_size() as u64;
+        let copies: Vec<ReadbackCopy> = if stride == 1 {
+            vec![ReadbackCopy {
+                buffer: particles,
+                offset: 0,
+                size: num_particles as u64 * particle_size,
+            }]
+        } else {
+            (0..num_particles)
+                .step_by(stride)
+                .map(|slot| ReadbackCopy {
+                    buffer: particles,
+                    offset: slot as u64 * particle_size,
+                    size: particle_size,
+                })
+                .collect()
+        };
+        self.readback.encode(device, encoder, &copies);
+        self.requested = None;
+        self.precision = precision;
+        self.stride = stride;
+    }
+
+    /// starts mapping the readback buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.readback.after_submit();
+    }
+
+    /// the sampled particles and the slots between two of them, once they are mapped
+    pub fn poll(&mut self, device: &Device) -> Option<(Vec<Particle>, usize)> {
+        let precision = self.precision;
+        let particles = self
+            .readback
+            .poll(device, |data| precision.decode_particles(data))?;
+        Some((particles, self.stride))
+    }
+}
@@ -0,0 +1,331 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+use crate::{precision::ParticlePrecision, SimParams};
+
+/// the grid the particles are sorted by has at most this many cells
+const MAX_CELLS: u32 = 1 << 16;
+/// the cells are at least this large, however small the cut off distance is
+const MIN_CELL_SIZE: f32 = 0.01;
+
+/// mirrors `SortParams` in particle_sort.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct SortParams {
+    origin: [f32; 3],
+    num_cells: u32,
+    inv_cell_size: [f32; 3],
+    _padd: u32,
+    dims: [u32; 3],
+    _padd2: u32,
+}
+
+/// reorders the particle buffer by the cell of a grid as fine as the cut off distance
+/// every few steps, so the workgroups of the simulation pass hold particles close
+/// to each other and take the same branches in the force loop.
+/// the ids of the particles follow them through the sorts, merging and splitting
+/// particles ends their identity though
+pub struct ParticleSort {
+    /// steps between two sorts, 0 never sorts
+    pub interval: u32,
+    steps_since_sort: u32,
+    /// the id of the particle in every slot of the particle buffer, the slot is the id
+    /// after an upload
+    pub ids_buffer: Buffer,
+    /// the slot of every particle id
+    pub slots_buffer: Buffer,
+    sorted_ids_buffer: Buffer,
+    params_buffer: Buffer,
+    /// the particles per cell while counting, then the next free slot of every cell
+    cells_buffer: Buffer,
+    num_cells: u32,
+    workgroup_size: u32,
+    bind_group_layout: BindGroupLayout,
+    bind_groups: [BindGroup; 2],
+    clear_pipeline: ComputePipeline,
+    count_pipeline: ComputePipeline,
+    scan_pipeline: ComputePipeline,
+    scatter_pipeline: ComputePipeline,
+    commit_ids_pipeline: ComputePipeline,
+}
+
+impl ParticleSort {
+    pub fn new(
+        device: &Device,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+    ) -> Self {
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("particle sort bind group layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(3, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(4, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(5, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(6, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(7, BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let params_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("particle sort params buffer"),
+            contents: bytemuck::bytes_of(&SortParams::zeroed()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let cells_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("particle sort cells buffer"),
+            size: MAX_CELLS as u64 * std::mem::size_of::<u32>() as u64,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let capacity = particles_buffers[0].size() / precision.particle_size() as u64;
+        let [ids_buffer, sorted_ids_buffer, slots_buffer] =
+            Self::create_id_buffers(device, capacity);
+        let bind_groups = Self::create_bind_groups(
+            device,
+            &bind_group_layout,
+            &params_buffer,
+            particles_buffers,
+            count_buffer,
+            &cells_buffer,
+            [&ids_buffer, &sorted_ids_buffer, &slots_buffer],
+        );
+        let [clear_pipeline, count_pipeline, scan_pipeline, scatter_pipeline, commit_ids_pipeline] =
+            Self::create_pipelines(device, &bind_group_layout, precision, workgroup_size);
+        ParticleSort {
+            interval: 0,
+            steps_since_sort: 0,
+            ids_buffer,
+            slots_buffer,
+            sorted_ids_buffer,
+            params_buffer,
+            cells_buffer,
+            num_cells: 1,
+            workgroup_size,
+            bind_group_layout,
+            bind_groups,
+            clear_pipeline,
+            count_pipeline,
+            scan_pipeline,
+            scatter_pipeline,
+            commit_ids_pipeline,
+        }
+    }
+
+    /// the ids, the sorted ids and the slots of `capacity` particles,
+    /// every particle has the id of its slot
+    fn create_id_buffers(device: &Device, capacity: u64) -> [Buffer; 3] {
+        let identity: Vec<u32> = (0..capacity.max(1) as u32).collect();
+        [
+            "particle ids buffer",
+            "sorted particle ids buffer",
+            "particle slots buffer",
+        ]
+        .map(|label| {
+            device.create_buffer_init(&BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(&identity),
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            })
+        })
+    }
+
+    fn create_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        params_buffer: &Buffer,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+        cells_buffer: &Buffer,
+        id_buffers: [&Buffer; 3],
+    ) -> [BindGroup; 2] {
+        // like the simulation pass, bind group i reads particle buffer i
+        std::array::from_fn(|i| {
+            let buffers = [
+                params_buffer,
+                &particles_buffers[i],
+                &particles_buffers[(i + 1) % 2],
+                count_buffer,
+                cells_buffer,
+                id_buffers[0],
+                id_buffers[1],
+                id_buffers[2],
+            ];
+            let entries: Vec<BindGroupEntry> = buffers
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect();
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("particle sort bind group"),
+                layout,
+                entries: &entries,
+            })
+        })
+    }
+
+    /// the clear, count, scan, scatter and commit_ids pipelines
+    fn create_pipelines(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+    ) -> [ComputePipeline; 5] {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("particle sort shader module"),
+            source: ShaderSource::Wgsl(Cow::Owned(format!(
+                "const WORKGROUP_SIZE: u32 = {}u;\n{}",
+                workgroup_size,
+                precision.shader_source(include_str!("particle_sort.wgsl"))
+            ))),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("particle sort pipeline layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        ["clear", "count", "scan", "scatter", "commit_ids"].map(|entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("particle sort pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        })
+    }
+
+    /// rebuilds the pipelines for particle buffers stored with `precision`
+    /// and dispatches of `workgroup_size` particles per workgroup
+    pub fn rebuild_pipelines(
+        &mut self,
+        device: &Device,
+        precision: ParticlePrecision,
+        workgroup_size: u32,
+    ) {
+        self.workgroup_size = workgroup_size;
+        [
+            self.clear_pipeline,
+            self.count_pipeline,
+            self.scan_pipeline,
+            self.scatter_pipeline,
+            self.commit_ids_pipeline,
+        ] = Self::create_pipelines(device, &self.bind_group_layout, precision, workgroup_size);
+    }
+
+    /// binds the particle buffers again, call this after they were replaced.
+    /// with `keep_ids` the particles were carried over to the same slots of buffers at least
+    /// as large and keep their ids, the encoder copies them into the new id buffers and only
+    /// the added slots get the id of their slot. without it every particle does
+    pub fn update_particles_buffers(
+        &mut self,
+        device: &Device,
+        particles_buffers: &[Buffer; 2],
+        count_buffer: &Buffer,
+        precision: ParticlePrecision,
+        keep_ids: Option<&mut CommandEncoder>,
+    ) {
+        let capacity = particles_buffers[0].size() / precision.particle_size() as u64;
+        let old_capacity = self.ids_buffer.size() / std::mem::size_of::<u32>() as u64;
+        if keep_ids.is_none() || capacity != old_capacity {
+            let [ids_buffer, sorted_ids_buffer, slots_buffer] =
+                Self::create_id_buffers(device, capacity);
+            // the old ids are a permutation of the old slots, so they stay unique
+            // next to the identity of the added slots
+            if let Some(encoder) = keep_ids {
+                let size = self.ids_buffer.size().min(ids_buffer.size());
+                encoder.copy_buffer_to_buffer(&self.ids_buffer, 0, &ids_buffer, 0, size);
+                encoder.copy_buffer_to_buffer(&self.slots_buffer, 0, &slots_buffer, 0, size);
+            }
+            self.ids_buffer = ids_buffer;
+            self.sorted_ids_buffer = sorted_ids_buffer;
+            self.slots_buffer = slots_buffer;
+        }
+        self.bind_groups = Self::create_bind_groups(
+            device,
+            &self.bind_group_layout,
+            &self.params_buffer,
+            particles_buffers,
+            count_buffer,
+            &self.cells_buffer,
+            [
+                &self.ids_buffer,
+                &self.sorted_ids_buffer,
+                &self.slots_buffer,
+            ],
+        );
+    }
+
+    /// fits the grid into the bounding volume with cells as large as the cut off distance,
+    /// larger ones if there would be too many
+    pub fn update_params(&mut self, queue: &Queue, sim_params: &SimParams) {
+        let size = sim_params.bounding_volume_extents.map(|e| 2.0 * e);
+        let mut cell_size = sim_params.cut_off_distance.max(MIN_CELL_SIZE);
+        let dims = loop {
+            let dims = size.map(|s| ((s / cell_size).ceil() as u32).max(1));
+            if dims.iter().product::<u32>() <= MAX_CELLS {
+                break dims;
+            }
+            cell_size *= 1.25;
+        };
+        self.num_cells = dims.iter().product();
+        let params = SortParams {
+            origin: sim_params.bounding_volume_extents.map(|e| -e),
+            num_cells: self.num_cells,
+            inv_cell_size: std::array::from_fn(|axis| dims[axis] as f32 / size[axis]),
+            _padd: 0,
+            dims,
+            _padd2: 0,
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+    }
+
+    /// counts the steps and tells whether the particles are sorted after this one
+    pub fn due(&mut self) -> bool {
+        if self.interval == 0 {
+            return false;
+        }
+        self.steps_since_sort += 1;
+        if self.steps_since_sort < self.interval {
+            return false;
+        }
+        self.steps_since_sort = 0;
+        true
+    }
+
+    /// encodes the sort of particle buffer `swap` into the other one.
+    /// `args_buffer` holds the dispatch arguments of the simulation pass
+    pub fn encode<'a>(&'a self, cpass: &mut ComputePass<'a>, swap: usize, args_buffer: &'a Buffer) {
+        cpass.set_bind_group(0, &self.bind_groups[swap], &[]);
+        cpass.set_pipeline(&self.clear_pipeline);
+        cpass.dispatch_workgroups(self.num_cells.div_ceil(self.workgroup_size), 1, 1);
+        cpass.set_pipeline(&self.count_pipeline);
+        cpass.dispatch_workgroups_indirect(args_buffer, 0);
+        cpass.set_pipeline(&self.scan_pipeline);
+        cpass.dispatch_workgroups(1, 1, 1);
+        cpass.set_pipeline(&self.scatter_pipeline);
+        cpass.dispatch_workgroups_indirect(args_buffer, 0);
+        cpass.set_pipeline(&self.commit_ids_pipeline);
+        cpass.dispatch_workgroups_indirect(args_buffer, 0);
+    }
+}
@@ -0,0 +1,136 @@
+// reorders the particles by the cell of a uniform grid over the bounding volume with a counting sort,
+// so the workgroups of the simulation pass hold particles close to each other.
+// WORKGROUP_SIZE is prepended by `ParticleSort` in particle_sort.rs
+
+// mirrors `SortParams` in particle_sort.rs
+struct SortParams {
+  // corner of the grid with the smallest coordinates
+  origin: vec3<f32>,
+  num_cells: u32,
+  inv_cell_size: vec3<f32>,
+  _padd: u32,
+  dims: vec3<u32>,
+  _padd2: u32,
+};
+
+// mirrors `ParticleCount` in indirect.wgsl
+struct ParticleCount {
+  alive: u32,
+  capacity: u32,
+  workgroup_size: u32,
+  _padd: u32,
+};
+
+// the cell sums are scanned by a single workgroup of this size
+const SCAN_SIZE: u32 = 256u;
+
+@group(0) @binding(0) var<uniform> params: SortParams;
+@group(0) @binding(1) var<storage, read> particlesSrc: array<ParticleStorage>;
+@group(0) @binding(2) var<storage, read_write> particlesDst: array<ParticleStorage>;
+@group(0) @binding(3) var<storage, read> particle_count: ParticleCount;
+// the particles per cell, then the next free slot of every cell
+@group(0) @binding(4) var<storage, read_write> cells: array<atomic<u32>>;
+// the id of the particle in every slot
+@group(0) @binding(5) var<storage, read_write> ids: array<u32>;
+@group(0) @binding(6) var<storage, read_write> sorted_ids: array<u32>;
+// the slot of every particle id
+@group(0) @binding(7) var<storage, read_write> slots: array<u32>;
+
+var<workgroup> chunk_sums: array<u32, SCAN_SIZE>;
+
+fn num_alive() -> u32 {
+  return min(particle_count.alive, arrayLength(&particlesSrc));
+}
+
+// particles outside of the bounding volume fall into the cells at its boundary
+fn cell_index(pos: vec3<f32>) -> u32 {
+  let dims = vec3<i32>(params.dims);
+  let c = vec3<u32>(clamp(vec3<i32>(floor((pos - params.origin) * params.inv_cell_size)), vec3<i32>(0), dims - vec3<i32>(1)));
+  return (c.x * params.dims.y + c.y) * params.dims.z + c.z;
+}
+
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn clear(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+  let cell = global_invocation_id.x;
+  if cell < params.num_cells {
+    atomicStore(&cells[cell], 0u);
+  }
+}
+
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn count(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = invocation_index(global_invocation_id, num_workgroups, WORKGROUP_SIZE);
+  if index >= num_alive() {
+    return;
+  }
+  let p = load_particle(particlesSrc[index]);
+  atomicAdd(&cells[cell_index(p.pos.xyz)], 1u);
+}
+
+// turns the counts into the first slot of every cell, every invocation sums a chunk of the cells
+@compute
+@workgroup_size(SCAN_SIZE)
+fn scan(@builtin(local_invocation_index) local: u32) {
+  let chunk = (params.num_cells + SCAN_SIZE - 1u) / SCAN_SIZE;
+  let start = min(local * chunk, params.num_cells);
+  let end = min(start + chunk, params.num_cells);
+  var sum = 0u;
+  for (var c = start; c < end; c++) {
+    sum += atomicLoad(&cells[c]);
+  }
+  chunk_sums[local] = sum;
+  workgroupBarrier();
+  // inclusive scan of the chunk sums
+  for (var offset = 1u; offset < SCAN_SIZE; offset *= 2u) {
+    let add = select(0u, chunk_sums[max(local, offset) - offset], local >= offset);
+    workgroupBarrier();
+    chunk_sums[local] += add;
+    workgroupBarrier();
+  }
+  var slot = chunk_sums[local] - sum;
+  for (var c = start; c < end; c++) {
+    let n = atomicLoad(&cells[c]);
+    atomicStore(&cells[c], slot);
+    slot += n;
+  }
+}
+
+// the order within a cell is arbitrary
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn scatter(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = invocation_index(global_invocation_id, num_workgroups, WORKGROUP_SIZE);
+  if index >= num_alive() {
+    return;
+  }
+  let p = load_particle(particlesSrc[index]);
+  let slot = atomicAdd(&cells[cell_index(p.pos.xyz)], 1u);
+  particlesDst[slot] = particlesSrc[index];
+  sorted_ids[slot] = ids[index];
+}
+
+// takes over the ids of the sorted particles and points their ids to the new slots
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn commit_ids(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = invocation_index(global_invocation_id, num_workgroups, WORKGROUP_SIZE);
+  if index >= num_alive() {
+    return;
+  }
+  let id = sorted_ids[index];
+  ids[index] = id;
+  if id < arrayLength(&slots) {
+    slots[id] = index;
+  }
+}
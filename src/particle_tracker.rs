@@ -0,0 +1,125 @@
+use wgpu::*;
+
+use crate::{
+    precision::ParticlePrecision,
+    readback::{Readback, ReadbackCopy},
+    V3,
+};
+
+const SLOT_SIZE: u64 = std::mem::size_of::<u32>() as u64;
+
+/// follows a single particle by its id and reads its position back every frame.
+/// the slot of the particle is read along with it, so it is found again after a sort
+pub struct ParticleTracker {
+    /// the id of the followed particle, see `ParticleSort`
+    pub id: Option<u32>,
+    /// the slot the particle was read from last
+    slot: u32,
+    /// the position of the particle of the last finished readback
+    pub position: Option<V3>,
+    /// the slot of the followed particle and the particle read from the last slot
+    readback: Readback,
+    /// the precision of the particle in the readback buffer
+    precision: ParticlePrecision,
+    /// a readback of the particle followed before
+    stale: bool,
+}
+
+impl ParticleTracker {
+    pub fn new() -> Self {
+        ParticleTracker {
+            id: None,
+            slot: 0,
+            position: None,
+            readback: Readback::new("particle tracker readback buffer"),
+            precision: ParticlePrecision::Full,
+            stale: false,
+        }
+    }
+
+    /// follows the particle with `id`, which is in `slot` right now
+    pub fn follow(&mut self, id: u32, slot: u32) {
+        self.id = Some(id);
+        self.slot = slot;
+        self.position = None;
+        self.stale = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.id = None;
+        self.position = None;
+        self.stale = true;
+    }
+
+    /// copies the slot of the followed particle and the particle in the slot it was found
+    /// in last, unless the previous readback is still in flight
+    pub fn encode_readback(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        particles: &Buffer,
+        slots: &Buffer,
+        num_particles: usize,
+        precision: ParticlePrecision,
+    ) {
+        let Some(id) = self.id else {
+            return;
+        };
+        if !self.readback.is_idle() {
+            return;
+        }
+        let particle_size = precision.particle_size() as u64;
+        // the particle is gone once its slot is past the alive ones
+        if self.slot as usize >= num_particles || (id as u64 + 1) * SLOT_SIZE > slots.size() {
+            self.position = None;
+            return;
+        }
+        self.readback.encode(
+            device,
+            encoder,
+            &[
+                ReadbackCopy {
+                    buffer: slots,
+                    offset: id as u64 * SLOT_SIZE,
+                    size: SLOT_SIZE,
+                },
+                ReadbackCopy {
+                    buffer: particles,
+                    offset: self.slot as u64 * particle_size,
+                    size: particle_size,
+                },
+            ],
+        );
+        self.precision = precision;
+        self.stale = false;
+    }
+
+    /// starts mapping the readback buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.readback.after_submit();
+    }
+
+    /// collects a finished readback into `position`
+    pub fn poll(&mut self, device: &Device) {
+        let precision = self.precision;
+        let Some((slot, particle)) = self.readback.poll(device, |data| {
+            (
+                bytemuck::pod_read_unaligned::<u32>(&data[..SLOT_SIZE as usize]),
+                precision
+                    .decode_particles(&data[SLOT_SIZE as usize..])
+                    .pop(),
+            )
+        }) else {
+            return;
+        };
+        if self.stale {
+            return;
+        }
+        // a sort moved the particle, it is read from its new slot next time
+        if slot != self.slot {
+            self.slot = slot;
+            return;
+        }
+        self.position = particle.map(|p| V3::new(p.pos[0], p.pos[1], p.pos[2]));
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{config::CONFIG_PATH, framework};
+
+/// configures the interactive application with its window, gui and startup dialog,
+/// for programs which ship the particles as they are. `ParticlesEngine` embeds the
+/// simulation into a window and render loop of their own instead
+#[derive(Clone, Debug)]
+pub struct ParticlesApp {
+    title: String,
+    config_path: String,
+}
+
+impl Default for ParticlesApp {
+    fn default() -> Self {
+        ParticlesApp {
+            title: String::from("Particles"),
+            config_path: String::from(CONFIG_PATH),
+        }
+    }
+}
+
+impl ParticlesApp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// title of the window, the title of the config file takes precedence
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = String::from(title);
+        self
+    }
+
+    /// file the window settings are read from and saved to
+    pub fn config_path(mut self, config_path: &str) -> Self {
+        self.config_path = String::from(config_path);
+        self
+    }
+
+    /// opens the window and runs the event loop, which only returns when the
+    /// application exits, on the web it runs after this returns
+    pub fn run(self) {
+        framework::wgpu_main(&self.title, &self.config_path);
+    }
+}
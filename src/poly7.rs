@@ -9,6 +9,12 @@ pub struct Poly7 {
     pub coeffs: [f32; 8],
 }
 
+impl Default for Poly7 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Poly7 {
     pub fn new() -> Self {
         Poly7 { coeffs: [0.0; 8] }
@@ -67,6 +73,66 @@ impl Poly7 {
         res
     }
 
+    /// the polynome of degree 6 whose value at x is the slope of this one at x
+    pub fn derivative(&self) -> Self {
+        Poly7 {
+            coeffs: std::array::from_fn(|i| match self.coeffs.get(i + 1) {
+                Some(c) => (i + 1) as f32 * c,
+                None => 0.0,
+            }),
+        }
+    }
+
+    /// the polynome whose slope at x is the value of this one at x, 0 at x = 0.
+    /// None if the x^7 coefficient is not 0, that antiderivative would be of degree 8
+    pub fn antiderivative(&self) -> Option<Self> {
+        if self.coeffs[7] != 0.0 {
+            return None;
+        }
+        Some(Poly7 {
+            coeffs: std::array::from_fn(|i| match i {
+                0 => 0.0,
+                i => self.coeffs[i - 1] / i as f32,
+            }),
+        })
+    }
+
+    /// the attraction at distance x the shader applies with the smooth cut off. a potential
+    /// is shifted to 0 at the cut off distance before it is windowed, which keeps the energy
+    pub fn windowed_force(
+        &self,
+        x: f32,
+        potential: bool,
+        cut_off_distance: f32,
+        window: f32,
+    ) -> f32 {
+        if x > cut_off_distance {
+            return 0.0;
+        }
+        let w = smooth_cutoff(x, cut_off_distance, window);
+        if potential {
+            let dw = smooth_cutoff_slope(x, cut_off_distance, window);
+            self.derivative().eval(x) * w + (self.eval(x) - self.eval(cut_off_distance)) * dw
+        } else {
+            self.eval(x) * w
+        }
+    }
+
+    pub fn windowed_plot_points(
+        &self,
+        potential: bool,
+        cut_off_distance: f32,
+        window: f32,
+    ) -> PlotPoints {
+        (0..100)
+            .map(|x| {
+                let x = x as f32 * 0.01;
+                let y = self.windowed_force(x, potential, cut_off_distance, window);
+                [x as f64, y as f64]
+            })
+            .collect()
+    }
+
     pub fn plot_points(&self) -> PlotPoints {
         (0..100)
             .map(|x| [x as f64 * 0.01, self.eval(x as f32 * 0.01) as f64])
@@ -74,6 +140,27 @@ impl Poly7 {
     }
 }
 
+/// 1 up to the last `window` fraction of the cut off distance, from there it falls to 0
+/// at the cut off distance with zero slope, mirrors `smooth_cutoff` in compute.wgsl
+pub fn smooth_cutoff(x: f32, cut_off_distance: f32, window: f32) -> f32 {
+    let width = window * cut_off_distance;
+    if width <= 0.0 {
+        return 1.0;
+    }
+    let t = ((x - (cut_off_distance - width)) / width).clamp(0.0, 1.0);
+    1.0 - t * t * (3.0 - 2.0 * t)
+}
+
+/// the slope of `smooth_cutoff` at x
+pub fn smooth_cutoff_slope(x: f32, cut_off_distance: f32, window: f32) -> f32 {
+    let width = window * cut_off_distance;
+    if width <= 0.0 {
+        return 0.0;
+    }
+    let t = ((x - (cut_off_distance - width)) / width).clamp(0.0, 1.0);
+    -6.0 * t * (1.0 - t) / width
+}
+
 // from and into are row indices
 pub fn matrix_row_mul_add<const N: usize>(
     mul: f32,
@@ -83,16 +170,16 @@ pub fn matrix_row_mul_add<const N: usize>(
 ) {
     dbg!(from, into);
     print_matrix(m);
-    for i in 0..N {
-        let fr = m[from][i];
-        m[into][i] += mul * fr;
+    let fr = m[from];
+    for (elem, f) in m[into].iter_mut().zip(fr) {
+        *elem += mul * f;
     }
     print_matrix(m)
 }
 
 pub fn matrix_row_div<const N: usize>(div: f32, m: &mut [[f32; N]; N], row: usize) {
-    for i in 0..N {
-        m[row][i] /= div;
+    for elem in m[row].iter_mut() {
+        *elem /= div;
     }
 }
 
@@ -120,8 +207,8 @@ fn multiply_vector<const N: usize>(m: &[[f32; N]; N], v: &[f32; N]) -> [f32; N]
 pub fn inverse<const N: usize>(m: &mut [[f32; N]; N]) -> [[f32; N]; N] {
     // identity
     let mut res = [[0.; N]; N];
-    for i in 0..N {
-        res[i][i] = 1.0;
+    for (i, row) in res.iter_mut().enumerate() {
+        row[i] = 1.0;
     }
     for i in 0..N {
         for j in (i + 1)..N {
@@ -0,0 +1,167 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
+
+use crate::{draw_pass::INSTANCE_LAYOUT_PARTICLE, Particle};
+
+/// instance layout of `HalfParticle`, the shader sees the same attributes as with full precision
+const INSTANCE_LAYOUT_HALF_PARTICLE: VertexBufferLayout = VertexBufferLayout {
+    array_stride: std::mem::size_of::<HalfParticle>() as u64,
+    step_mode: VertexStepMode::Instance,
+    attributes: &[
+        // particle position
+        VertexAttribute {
+            format: VertexFormat::Float16x4,
+            offset: 0,
+            shader_location: 2,
+        },
+        // particle velocity
+        VertexAttribute {
+            format: VertexFormat::Float16x4,
+            offset: 2 * 4,
+            shader_location: 5,
+        },
+        // particle type
+        VertexAttribute {
+            format: VertexFormat::Uint32,
+            offset: 2 * 4 * 2,
+            shader_location: 3,
+        },
+        // particle age
+        VertexAttribute {
+            format: VertexFormat::Float32,
+            offset: 2 * 4 * 2 + 4,
+            shader_location: 4,
+        },
+    ],
+};
+
+/// storage format of the particle buffers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParticlePrecision {
+    Full,
+    /// positions and velocities as half floats, halves the memory bandwidth of the simulation
+    Half,
+}
+
+/// mirrors `ParticleStorage` in particle_f16.wgsl, the mass of the particle is stored in
+/// the w component of the position
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct HalfParticle {
+    pos: [u16; 4],
+    vel: [u16; 4],
+    ty: u32,
+    age: f32,
+}
+
+impl ParticlePrecision {
+    pub fn particle_size(&self) -> usize {
+        match self {
+            ParticlePrecision::Full => std::mem::size_of::<Particle>(),
+            ParticlePrecision::Half => std::mem::size_of::<HalfParticle>(),
+        }
+    }
+
+    /// prepends the definition of `ParticleStorage` and its conversions to a shader
+    pub fn shader_source(&self, source: &str) -> String {
+        let prefix = match self {
+            ParticlePrecision::Full => include_str!("particle_f32.wgsl"),
+            ParticlePrecision::Half => include_str!("particle_f16.wgsl"),
+        };
+        format!("{}\n{}", prefix, source)
+    }
+
+    pub fn instance_layout(&self) -> VertexBufferLayout<'static> {
+        match self {
+            ParticlePrecision::Full => INSTANCE_LAYOUT_PARTICLE,
+            ParticlePrecision::Half => INSTANCE_LAYOUT_HALF_PARTICLE,
+        }
+    }
+
+    /// the contents of a particle buffer
+    pub fn encode_particles(&self, particles: &[Particle]) -> Vec<u8> {
+        match self {
+            ParticlePrecision::Full => bytemuck::cast_slice(particles).to_vec(),
+            ParticlePrecision::Half => {
+                let half_particles: Vec<HalfParticle> = particles
+                    .iter()
+                    .map(|p| HalfParticle {
+                        pos: [p.pos[0], p.pos[1], p.pos[2], p.mass].map(f32_to_f16),
+                        vel: p.vel.map(f32_to_f16),
+                        ty: p.ty,
+                        age: p.age,
+                    })
+                    .collect();
+                bytemuck::cast_slice(&half_particles).to_vec()
+            }
+        }
+    }
+
+    /// the particles of a particle buffer read back from the GPU
+    pub fn decode_particles(&self, bytes: &[u8]) -> Vec<Particle> {
+        match self {
+            ParticlePrecision::Full => bytemuck::cast_slice(bytes).to_vec(),
+            ParticlePrecision::Half => bytemuck::cast_slice::<u8, HalfParticle>(bytes)
+                .iter()
+                .map(|p| {
+                    let [x, y, z, mass] = p.pos.map(f16_to_f32);
+                    Particle {
+                        pos: [x, y, z, 1.0],
+                        vel: p.vel.map(f16_to_f32),
+                        ty: p.ty,
+                        age: p.age,
+                        mass,
+                        _padd: 0,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// rounds to the nearest half float, too large values become infinity
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // infinity or NaN, a NaN keeps a mantissa bit
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        // subnormal half float, the implicit leading one becomes explicit
+        let mantissa = mantissa | 0x80_0000;
+        let shift = 14 - exponent;
+        let round = (mantissa >> (shift - 1)) & 1;
+        return sign | ((mantissa >> shift) + round) as u16;
+    }
+    let half = sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16;
+    // a carry out of the mantissa correctly increments the exponent
+    half + ((mantissa >> 12) & 1) as u16
+}
+
+pub(crate) fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    match exponent {
+        0 => {
+            let value = mantissa as f32 / (1 << 24) as f32;
+            if sign != 0 {
+                -value
+            } else {
+                value
+            }
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | (mantissa << 13)),
+        _ => f32::from_bits(sign | ((exponent + 127 - 15) << 23) | (mantissa << 13)),
+    }
+}
@@ -0,0 +1,11 @@
+//! the types needed to embed the simulation, `use particles::prelude::*;`
+
+pub use crate::{
+    sim_params::{
+        Confinement, Flocking, ForceAccumulation, ForceGridBoundary, ForceGridInterpolation,
+        ForceMode, MassCoupling, MatrixSymmetry, PairForce,
+    },
+    Bounds, Camera, Compute, EngineBuilder, ForceCurve, ForceCurves, Grid, Knot, MassWrap,
+    MaterialGrid, Particle, ParticlePrecision, ParticleSystem, ParticleType, ParticlesApp,
+    ParticlesEngine, Poly7, ReductionResult, Renderer, SimParams, SplineKind, V3,
+};
@@ -0,0 +1,186 @@
+use cgmath::{InnerSpace, Vector2};
+
+use crate::{grid::Grid, poly7::Poly7, sim_params::SimParams, zero_v3, ParticleType, V3};
+
+const NUM_TYPES: usize = 5;
+/// distances sampled to summarize a force for the thumbnails
+const THUMBNAIL_SAMPLES: usize = 16;
+
+/// a built in configuration of the particle forces and the force grid
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// sets the forces and limits starting from the default masses and pair forces
+    configure: fn(&mut SimParams),
+    /// the force grid vector at a position, `radius` is the bounding volume radius
+    force_field: fn(V3, f32) -> V3,
+}
+
+pub const PRESETS: [Preset; 5] = [
+    Preset {
+        name: "orbits",
+        description: "loose clumps swirling around the vertical axis",
+        configure: orbits,
+        force_field: swirl,
+    },
+    Preset {
+        name: "cells",
+        description: "every type gathers into cell-like clusters wrapped by the next type",
+        configure: cells,
+        force_field: centering,
+    },
+    Preset {
+        name: "predator-prey chase",
+        description: "each type hunts the next one, which flees",
+        configure: predator_prey,
+        force_field: centering,
+    },
+    Preset {
+        name: "crystal lattice",
+        description: "slow particles settle at a preferred distance to each other",
+        configure: crystal_lattice,
+        force_field: no_field,
+    },
+    Preset {
+        name: "sand pile",
+        description: "gravity drops the particles onto the floor, where they heap up",
+        configure: sand_pile,
+        force_field: no_field,
+    },
+];
+
+impl Preset {
+    /// `sim_params` with the forces of the preset
+    pub fn sim_params(&self, sim_params: &SimParams) -> SimParams {
+        let mut res = *sim_params;
+        let defaults = SimParams::new();
+        res.pair_forces = defaults.pair_forces;
+        res.particle_type_masses = defaults.particle_type_masses;
+        res.flocking = defaults.flocking;
+        res.force_mode = defaults.force_mode;
+        res.gravity = defaults.gravity;
+        res.floor = defaults.floor;
+        (self.configure)(&mut res);
+        res
+    }
+
+    /// a force grid with the dimensions and bounds of `sim_params` holding the preset field
+    pub fn force_grid(&self, sim_params: &SimParams) -> Grid<V3> {
+        let mut grid = sim_params.new_force_grid_zero();
+        let radius = sim_params.bounding_volume_radius;
+        let positions = grid.get_positions();
+        for (v, pos) in grid.grid.iter_mut().zip(positions) {
+            *v = (self.force_field)(V3::new(pos[0], pos[1], pos[2]), radius);
+        }
+        grid
+    }
+
+    /// mean force between every pair of types over the cut off distance, drawn as thumbnail
+    pub fn thumbnail(&self) -> [[f32; NUM_TYPES]; NUM_TYPES] {
+        force_thumbnail(&self.sim_params(&SimParams::new()))
+    }
+}
+
+/// mean force between every pair of types of `sim_params` over the cut off distance
+pub fn force_thumbnail(sim_params: &SimParams) -> [[f32; NUM_TYPES]; NUM_TYPES] {
+    std::array::from_fn(|y| {
+        std::array::from_fn(|x| {
+            let index = x + y * NUM_TYPES;
+            let pair_force = sim_params.pair_forces[index];
+            if pair_force.enabled == 0 {
+                return 0.0;
+            }
+            let poly = sim_params.attraction_force[index];
+            let sum: f32 = (0..THUMBNAIL_SAMPLES)
+                .map(|i| poly.eval((i as f32 + 0.5) / THUMBNAIL_SAMPLES as f32))
+                .sum();
+            sum / THUMBNAIL_SAMPLES as f32 * pair_force.scale
+        })
+    })
+}
+
+/// force polynome through 8 values at equidistant distances from 0 to 1
+fn profile(values: [f32; 8]) -> Poly7 {
+    let points = std::array::from_fn(|i| Vector2::new(i as f32 / 7.0, values[i]));
+    Poly7::from_points(points).unwrap_or_else(Poly7::zero)
+}
+
+/// force of particles of type `ty` towards particles of type `other`
+fn force_mut(sim_params: &mut SimParams, ty: usize, other: usize) -> &mut Poly7 {
+    &mut sim_params.attraction_force[other + ty * NUM_TYPES]
+}
+
+fn orbits(sim_params: &mut SimParams) {
+    let cohesion = profile([-3.0, -1.0, 0.4, 0.6, 0.5, 0.3, 0.1, 0.0]);
+    sim_params.attraction_force = [cohesion; 25];
+    sim_params.particle_type_masses[ParticleType::E as usize].mass = 3.0;
+    sim_params.set_cut_off_distance(1.0);
+    sim_params.max_velocity = 20.0;
+    sim_params.distance_exponent = 0.0;
+}
+
+fn cells(sim_params: &mut SimParams) {
+    let repulsion = profile([-2.0, -1.0, -0.3, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    sim_params.attraction_force = [repulsion; 25];
+    for ty in 0..NUM_TYPES {
+        *force_mut(sim_params, ty, ty) = profile([-2.0, 1.0, 2.0, 1.5, 1.0, 0.5, 0.2, 0.0]);
+        // the next type sits on the surface of a cluster like a membrane
+        let next = (ty + 1) % NUM_TYPES;
+        *force_mut(sim_params, next, ty) = profile([-3.0, -2.0, -0.5, 0.8, 0.8, 0.3, 0.0, 0.0]);
+    }
+    sim_params.set_cut_off_distance(1.0);
+    sim_params.max_velocity = 10.0;
+    sim_params.distance_exponent = 0.0;
+}
+
+fn predator_prey(sim_params: &mut SimParams) {
+    let cohesion = profile([-1.0, 0.2, 0.4, 0.3, 0.2, 0.1, 0.0, 0.0]);
+    sim_params.attraction_force = [Poly7::zero(); 25];
+    for ty in 0..NUM_TYPES {
+        let prey = (ty + 1) % NUM_TYPES;
+        *force_mut(sim_params, ty, ty) = cohesion;
+        *force_mut(sim_params, ty, prey) = Poly7::const_val(1.5);
+        *force_mut(sim_params, prey, ty) = Poly7::const_val(-2.0);
+    }
+    sim_params.set_cut_off_distance(1.0);
+    sim_params.max_velocity = 15.0;
+    sim_params.distance_exponent = 0.0;
+}
+
+fn crystal_lattice(sim_params: &mut SimParams) {
+    // the force changes sign at about 0.25, the preferred distance
+    let bond = profile([-8.0, -2.0, 0.5, 1.0, 0.6, 0.2, 0.0, 0.0]);
+    sim_params.attraction_force = [bond; 25];
+    sim_params.set_cut_off_distance(0.8);
+    sim_params.max_velocity = 1.0;
+    sim_params.distance_exponent = 0.0;
+}
+
+fn sand_pile(sim_params: &mut SimParams) {
+    // the grains only push each other apart when they touch and stick together a little
+    let contact = profile([-12.0, -4.0, 0.3, 0.2, 0.0, 0.0, 0.0, 0.0]);
+    sim_params.attraction_force = [contact; 25];
+    sim_params.set_cut_off_distance(0.5);
+    sim_params.max_velocity = 30.0;
+    sim_params.distance_exponent = 0.0;
+    sim_params.gravity = [0.0, -9.81, 0.0];
+    sim_params.floor = 1;
+    sim_params.floor_height = -0.9 * sim_params.bounding_volume_extents[1];
+    sim_params.floor_restitution = 0.2;
+    sim_params.floor_friction = 0.6;
+}
+
+fn swirl(pos: V3, radius: f32) -> V3 {
+    let tangent = V3::new(-pos.z, 0.0, pos.x) / radius;
+    tangent * 0.5 - pos / radius * 0.1
+}
+
+fn centering(pos: V3, radius: f32) -> V3 {
+    let r = pos.magnitude() / radius;
+    // only particles close to the bounding volume are pulled back noticeably
+    -pos / radius * r.powi(3)
+}
+
+fn no_field(_pos: V3, _radius: f32) -> V3 {
+    zero_v3()
+}
@@ -0,0 +1,188 @@
+use std::borrow::Cow;
+
+use crate::{
+    indirect::split_workgroups,
+    precision::ParticlePrecision,
+    readback::{Readback, ReadbackCopy},
+    V3,
+};
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+const NUM_RAY_BINS: usize = 256;
+const RAY_GROUP_SIZE: usize = 64;
+/// length of the searched ray segment, the far plane of the camera
+const RAY_LENGTH: f32 = 100.0;
+
+/// mirrors `Ray` in ray_density.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct RayRaw {
+    origin_radius: [f32; 4],
+    dir_length: [f32; 4],
+}
+
+/// finds the densest particle cluster along a ray, used to place the cursor in depth
+pub struct RayDensity {
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    bins_buffer: Buffer,
+    readback: Readback,
+    /// origin, normalized direction and search radius of the next search
+    pub ray: Option<(V3, V3, f32)>,
+    /// distance along the ray to the densest cluster of the last search
+    pub depth: Option<f32>,
+}
+
+impl RayDensity {
+    pub fn new(device: &Device, precision: ParticlePrecision) -> Self {
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("ray density bind group layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let pipeline = Self::create_pipeline(device, &bind_group_layout, precision);
+        let bins_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ray density bins buffer"),
+            size: (NUM_RAY_BINS * std::mem::size_of::<u32>()) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        RayDensity {
+            pipeline,
+            bind_group_layout,
+            bins_buffer,
+            readback: Readback::new("ray density readback buffer"),
+            ray: None,
+            depth: None,
+        }
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        precision: ParticlePrecision,
+    ) -> ComputePipeline {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("ray density shader module"),
+            source: ShaderSource::Wgsl(Cow::Owned(
+                precision.shader_source(include_str!("ray_density.wgsl")),
+            )),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("ray density pipeline layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("ray density pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        })
+    }
+
+    /// rebuilds the pipeline for particle buffers stored with `precision`
+    pub fn set_precision(&mut self, device: &Device, precision: ParticlePrecision) {
+        self.pipeline = Self::create_pipeline(device, &self.bind_group_layout, precision);
+    }
+
+    /// encodes the search along `ray` unless the previous result is still in flight
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        particles_buffer: &Buffer,
+        num_particles: usize,
+    ) {
+        if !self.readback.is_idle() || num_particles == 0 {
+            return;
+        }
+        let Some((origin, dir, radius)) = self.ray.take() else {
+            return;
+        };
+        let ray = RayRaw {
+            origin_radius: [origin.x, origin.y, origin.z, radius],
+            dir_length: [dir.x, dir.y, dir.z, RAY_LENGTH],
+        };
+        let ray_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("ray density ray buffer"),
+            contents: bytemuck::bytes_of(&ray),
+            usage: BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("ray density bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: ray_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: particles_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: self.bins_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        encoder.clear_buffer(&self.bins_buffer, 0, None);
+        {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("ray density pass"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(&self.pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            let (x, y) = split_workgroups(num_particles.div_ceil(RAY_GROUP_SIZE) as u32);
+            cpass.dispatch_workgroups(x, y, 1);
+        }
+        self.readback.encode(
+            device,
+            encoder,
+            &[ReadbackCopy {
+                buffer: &self.bins_buffer,
+                offset: 0,
+                size: self.bins_buffer.size(),
+            }],
+        );
+    }
+
+    /// starts mapping the readback buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.readback.after_submit();
+    }
+
+    /// collects a finished search into `depth`
+    pub fn poll(&mut self, device: &Device) {
+        let Some(densest) = self.readback.poll(device, |data| {
+            let bins: &[u32] = bytemuck::cast_slice(data);
+            bins.iter()
+                .enumerate()
+                .max_by_key(|(_, count)| **count)
+                .filter(|(_, count)| **count > 0)
+                .map(|(bin, _)| bin)
+        }) else {
+            return;
+        };
+        self.depth = densest.map(|bin| (bin as f32 + 0.5) / NUM_RAY_BINS as f32 * RAY_LENGTH);
+    }
+}
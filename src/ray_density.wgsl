@@ -0,0 +1,35 @@
+struct Ray {
+  // xyz: origin, w: search radius around the ray
+  origin_radius: vec4<f32>,
+  // xyz: normalized direction, w: length of the searched ray segment
+  dir_length: vec4<f32>,
+};
+
+const NUM_RAY_BINS: u32 = 256u;
+
+@group(0) @binding(0) var<uniform> ray: Ray;
+@group(0) @binding(1) var<storage, read> particles: array<ParticleStorage>;
+@group(0) @binding(2) var<storage, read_write> bins: array<atomic<u32>, 256>;
+
+// counts the particles close to the ray in bins along its length
+@compute
+@workgroup_size(64)
+fn main(
+    @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let index = invocation_index(global_invocation_id, num_workgroups, 64u);
+    if index >= arrayLength(&particles) {
+        return;
+    }
+    let rel = load_particle(particles[index]).pos.xyz - ray.origin_radius.xyz;
+    let t = dot(rel, ray.dir_length.xyz);
+    if t <= 0.0 || t >= ray.dir_length.w {
+        return;
+    }
+    if length(rel - ray.dir_length.xyz * t) > ray.origin_radius.w {
+        return;
+    }
+    let bin = min(u32(t / ray.dir_length.w * f32(NUM_RAY_BINS)), NUM_RAY_BINS - 1u);
+    atomicAdd(&bins[bin], 1u);
+}
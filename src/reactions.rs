@@ -0,0 +1,51 @@
+use bytemuck::{Pod, Zeroable};
+
+/// the compute shader tracks matching rules in a 32 bit mask
+pub const MAX_REACTION_RULES: usize = 32;
+
+/// a particle of type `ty` within `radius` of a `catalyst` particle
+/// turns into `product` with `rate` probability per second
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ReactionRule {
+    pub ty: u32,
+    pub catalyst: u32,
+    pub product: u32,
+    pub radius: f32,
+    pub rate: f32,
+}
+
+pub struct Reaction {
+    pub rule: ReactionRule,
+    pub enabled: bool,
+}
+
+impl Reaction {
+    pub fn new(ty: u32, catalyst: u32, product: u32) -> Self {
+        Reaction {
+            rule: ReactionRule {
+                ty,
+                catalyst,
+                product,
+                radius: 0.3,
+                rate: 0.5,
+            },
+            enabled: true,
+        }
+    }
+}
+
+/// the enabled rules for the reaction buffer, which always holds at least one rule
+pub fn get_reaction_rules_raw(reactions: &[Reaction]) -> (Vec<ReactionRule>, u32) {
+    let mut rules: Vec<ReactionRule> = reactions
+        .iter()
+        .filter(|r| r.enabled)
+        .map(|r| r.rule)
+        .take(MAX_REACTION_RULES)
+        .collect();
+    let num_rules = rules.len() as u32;
+    if rules.is_empty() {
+        rules.push(ReactionRule::zeroed());
+    }
+    (rules, num_rules)
+}
@@ -0,0 +1,115 @@
+use std::sync::{Arc, OnceLock};
+
+use wgpu::*;
+
+enum ReadbackState {
+    Idle,
+    /// the copies into the staging buffer were encoded but not yet submitted
+    Encoded,
+    /// the staging buffer is being mapped, the flag is set to whether that succeeded
+    Mapping(Arc<OnceLock<bool>>),
+}
+
+/// `size` bytes of `buffer` from `offset` on
+pub struct ReadbackCopy<'a> {
+    pub buffer: &'a Buffer,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// copies parts of GPU buffers into a staging buffer and maps it without stalling the frame.
+/// the copies are encoded with `encode`, mapped by `after_submit` once the encoder was
+/// submitted and handed over by `poll` a few frames later
+pub struct Readback {
+    label: &'static str,
+    staging_buffer: Option<Buffer>,
+    /// the number of bytes copied by the readback in flight
+    len: u64,
+    state: ReadbackState,
+}
+
+impl Readback {
+    pub fn new(label: &'static str) -> Self {
+        Readback {
+            label,
+            staging_buffer: None,
+            len: 0,
+            state: ReadbackState::Idle,
+        }
+    }
+
+    /// no readback is in flight, so a new one can be encoded
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, ReadbackState::Idle)
+    }
+
+    /// encodes `copies` back to back into the staging buffer, which grows to hold them.
+    /// does nothing while the previous readback is in flight
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        copies: &[ReadbackCopy],
+    ) {
+        let len = copies.iter().map(|copy| copy.size).sum();
+        if !self.is_idle() || len == 0 {
+            return;
+        }
+        if self.staging_buffer.as_ref().is_none_or(|b| b.size() < len) {
+            self.staging_buffer = Some(device.create_buffer(&BufferDescriptor {
+                label: Some(self.label),
+                size: len,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        let Some(staging_buffer) = self.staging_buffer.as_ref() else {
+            return;
+        };
+        let mut dst_offset = 0;
+        for copy in copies {
+            encoder.copy_buffer_to_buffer(
+                copy.buffer,
+                copy.offset,
+                staging_buffer,
+                dst_offset,
+                copy.size,
+            );
+            dst_offset += copy.size;
+        }
+        self.len = len;
+        self.state = ReadbackState::Encoded;
+    }
+
+    /// starts mapping the staging buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        let (ReadbackState::Encoded, Some(staging_buffer)) =
+            (&self.state, self.staging_buffer.as_ref())
+        else {
+            return;
+        };
+        let mapped = Arc::new(OnceLock::new());
+        let mapped_cb = mapped.clone();
+        staging_buffer
+            .slice(..self.len)
+            .map_async(MapMode::Read, move |res| {
+                let _ = mapped_cb.set(res.is_ok());
+            });
+        self.state = ReadbackState::Mapping(mapped);
+    }
+
+    /// polls `device` and hands the copied bytes to `read` once they are mapped.
+    /// a readback that failed to map is dropped
+    pub fn poll<R>(&mut self, device: &Device, read: impl FnOnce(&[u8]) -> R) -> Option<R> {
+        let ReadbackState::Mapping(mapped) = &self.state else {
+            return None;
+        };
+        device.poll(Maintain::Poll);
+        let success = *mapped.get()?;
+        self.state = ReadbackState::Idle;
+        let staging_buffer = self.staging_buffer.as_ref().filter(|_| success)?;
+        let res = read(&staging_buffer.slice(..self.len).get_mapped_range());
+        staging_buffer.unmap();
+        Some(res)
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    io::Write,
+    process::{Child, Command, Stdio},
+};
+
+use wgpu::*;
+
+use crate::precision::f16_to_f32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordOutput {
+    /// numbered png files in a directory
+    PngSequence,
+    /// raw frames piped into an ffmpeg process
+    Ffmpeg,
+    /// a single png file, the recording ends after its first frame
+    Screenshot,
+}
+
+/// offscreen render target of a running recording
+struct RecordTarget {
+    view: TextureView,
+    depth_view: TextureView,
+    texture: Texture,
+    readback_buffer: Buffer,
+    padded_bytes_per_row: u32,
+    /// the surface format the frames are rendered in, converted to 8 bit rgba when written
+    format: TextureFormat,
+    ffmpeg: Option<Child>,
+}
+
+/// renders frames at a fixed resolution and time step independent of the window
+pub struct Recorder {
+    pub width: u32,
+    pub height: u32,
+    /// simulated seconds per recorded frame
+    pub fixed_delta_t: f32,
+    pub output: RecordOutput,
+    /// directory of the png sequence or file name of the ffmpeg video
+    pub path: String,
+    pub frame_index: u32,
+    pub error: Option<String>,
+    target: Option<RecordTarget>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Recorder {
+            width: 1920,
+            height: 1080,
+            fixed_delta_t: 1.0 / 60.0,
+            output: RecordOutput::PngSequence,
+            path: String::from("frames"),
+            frame_index: 0,
+            error: None,
+            target: None,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.target.is_some()
+    }
+
+    pub fn start(&mut self, device: &Device, format: TextureFormat) {
+        self.error = None;
+        self.frame_index = 0;
+        let size = Extent3d {
+            width: self.width.max(1),
+            height: self.height.max(1),
+            depth_or_array_layers: 1,
+        };
+        let create_texture = |label, format, usage| {
+            device.create_texture(&TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let texture = create_texture(
+            "recorder texture",
+            format,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        );
+        let depth_texture = create_texture(
+            "recorder depth texture",
+            TextureFormat::Depth32Float,
+            TextureUsages::RENDER_ATTACHMENT,
+        );
+        // rows of a texture to buffer copy have to be aligned
+        let bytes_per_pixel = format.block_size(None).unwrap_or(4);
+        let padded_bytes_per_row = (size.width * bytes_per_pixel)
+            .div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT)
+            * COPY_BYTES_PER_ROW_ALIGNMENT;
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("recorder readback buffer"),
+            size: (padded_bytes_per_row * size.height) as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let ffmpeg = match self.output {
+            RecordOutput::PngSequence => {
+                if let Err(e) = std::fs::create_dir_all(&self.path) {
+                    self.error = Some(e.to_string());
+                    return;
+                }
+                None
+            }
+            RecordOutput::Screenshot => {
+                let parent = std::path::Path::new(&self.path).parent();
+                if let Some(Err(e)) = parent.map(std::fs::create_dir_all) {
+                    self.error = Some(e.to_string());
+                    return;
+                }
+                None
+            }
+            RecordOutput::Ffmpeg => match self.spawn_ffmpeg(size.width, size.height) {
+                Ok(child) => Some(child),
+                Err(e) => {
+                    self.error = Some(format!("could not start ffmpeg: {}", e));
+                    return;
+                }
+            },
+        };
+        self.target = Some(RecordTarget {
+            view: texture.create_view(&TextureViewDescriptor::default()),
+            depth_view: depth_texture.create_view(&TextureViewDescriptor::default()),
+            texture,
+            readback_buffer,
+            padded_bytes_per_row,
+            format,
+            ffmpeg,
+        });
+    }
+
+    fn spawn_ffmpeg(&self, width: u32, height: u32) -> std::io::Result<Child> {
+        let frame_rate = (1.0 / self.fixed_delta_t).round().max(1.0);
+        Command::new("ffmpeg")
+            .args(["-y", "-f", "rawvideo", "-pix_fmt", "rgba", "-s"])
+            .arg(format!("{}x{}", width, height))
+            .arg("-r")
+            .arg(frame_rate.to_string())
+            .args(["-i", "-", "-pix_fmt", "yuv420p"])
+            .arg(&self.path)
+            .stdin(Stdio::piped())
+            .spawn()
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut ffmpeg) = self.target.take().and_then(|t| t.ffmpeg) {
+            // closing stdin lets ffmpeg finish the video
+            drop(ffmpeg.stdin.take());
+            if let Err(e) = ffmpeg.wait() {
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// color and depth views to render the next frame into
+    pub fn views(&self) -> Option<(&TextureView, &TextureView)> {
+        self.target.as_ref().map(|t| (&t.view, &t.depth_view))
+    }
+
+    pub fn encode_copy(&self, encoder: &mut CommandEncoder) {
+        let Some(target) = self.target.as_ref() else {
+            return;
+        };
+        encoder.copy_texture_to_buffer(
+            target.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &target.readback_buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(target.padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            target.texture.size(),
+        );
+    }
+
+    /// waits for the copied frame and writes it out, recording stops on the first error
+    pub fn write_frame(&mut self, device: &Device) {
+        let Some(target) = self.target.as_mut() else {
+            return;
+        };
+        let size = target.texture.size();
+        let row_bytes = size.width * target.format.block_size(None).unwrap_or(4);
+        let slice = target.readback_buffer.slice(..);
+        slice.map_async(MapMode::Read, |_| {});
+        // recording trades real time performance for complete frames
+        device.poll(Maintain::Wait);
+        let pixels: Vec<u8> = slice
+            .get_mapped_range()
+            .chunks(target.padded_bytes_per_row as usize)
+            .flat_map(|row| to_rgba8(target.format, &row[..row_bytes as usize]))
+            .collect();
+        target.readback_buffer.unmap();
+        let res = match target.ffmpeg.as_mut().and_then(|f| f.stdin.as_mut()) {
+            Some(stdin) => stdin.write_all(&pixels).map_err(|e| e.to_string()),
+            None => {
+                let file = match self.output {
+                    RecordOutput::Screenshot => self.path.clone(),
+                    _ => format!("{}/frame_{:06}.png", self.path, self.frame_index),
+                };
+                image::save_buffer(
+                    file,
+                    &pixels,
+                    size.width,
+                    size.height,
+                    image::ColorType::Rgba8,
+                )
+                .map_err(|e| e.to_string())
+            }
+        };
+        self.frame_index += 1;
+        if let Err(e) = res {
+            self.error = Some(e);
+            self.stop();
+        } else if self.output == RecordOutput::Screenshot {
+            self.stop();
+        }
+    }
+}
+
+/// a row of pixels in `format` as the 8 bit srgb rgba png and ffmpeg are given,
+/// hdr colors brighter than white are clipped
+fn to_rgba8(format: TextureFormat, row: &[u8]) -> Vec<u8> {
+    match format {
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => row
+            .chunks_exact(4)
+            .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+            .collect(),
+        // a float surface holds linear colors
+        TextureFormat::Rgba16Float => row
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(i, half)| {
+                let value = f16_to_f32(u16::from_le_bytes([half[0], half[1]])).clamp(0.0, 1.0);
+                let encoded = if i % 4 == 3 {
+                    value
+                } else if value <= 0.003_130_8 {
+                    value * 12.92
+                } else {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                };
+                (encoded * 255.0).round() as u8
+            })
+            .collect(),
+        _ => row.to_vec(),
+    }
+}
@@ -0,0 +1,230 @@
+use std::borrow::Cow;
+
+use crate::{
+    indirect::split_workgroups,
+    precision::ParticlePrecision,
+    readback::{Readback, ReadbackCopy},
+    MassWrap, V3,
+};
+use bytemuck::{Pod, Zeroable};
+use cgmath::InnerSpace;
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+const REDUCTION_GROUP_SIZE: usize = 256;
+
+/// partial sums of one workgroup, mirrors `Sums` in reduction.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Sums {
+    mass_pos: [f32; 4],
+    momentum_energy: [f32; 4],
+    /// largest squared speed in the first component
+    max_speed: [f32; 4],
+}
+
+/// whole system quantities computed by the reduction pass
+#[derive(Clone, Copy, Debug)]
+pub struct ReductionResult {
+    pub center_of_mass: V3,
+    pub total_mass: f32,
+    pub momentum: V3,
+    pub kinetic_energy: f32,
+    /// speed of the fastest particle
+    pub max_speed: f32,
+}
+
+impl ReductionResult {
+    /// temperature of the motion relative to the center of mass,
+    /// with three degrees of freedom per particle and k_B = 1
+    pub fn temperature(&self, num_particles: usize) -> f32 {
+        if num_particles == 0 {
+            return 0.0;
+        }
+        let center_of_mass_energy = self.momentum.magnitude2() / (2.0 * self.total_mass);
+        (self.kinetic_energy - center_of_mass_energy).max(0.0) / (1.5 * num_particles as f32)
+    }
+}
+
+/// sums mass, position, momentum and kinetic energy and finds the largest speed
+/// over all particles on the GPU
+/// and reads the result back without stalling the frame
+pub struct Reduction {
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    masses_buffer: Buffer,
+    partials_buffer: Buffer,
+    num_partials: usize,
+    readback: Readback,
+    pub result: Option<ReductionResult>,
+}
+
+impl Reduction {
+    pub fn new(device: &Device, masses: &[MassWrap; 5], precision: ParticlePrecision) -> Self {
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("reduction bind group layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let pipeline = Self::create_pipeline(device, &bind_group_layout, precision);
+        let masses_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("reduction masses buffer"),
+            contents: bytemuck::cast_slice(masses),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let partials_buffer = Self::create_partials_buffer(device, 1);
+        Reduction {
+            pipeline,
+            bind_group_layout,
+            masses_buffer,
+            partials_buffer,
+            num_partials: 1,
+            readback: Readback::new("reduction readback buffer"),
+            result: None,
+        }
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        precision: ParticlePrecision,
+    ) -> ComputePipeline {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("reduction shader module"),
+            source: ShaderSource::Wgsl(Cow::Owned(
+                precision.shader_source(include_str!("reduction.wgsl")),
+            )),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("reduction pipeline layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("reduction pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        })
+    }
+
+    /// rebuilds the pipeline for particle buffers stored with `precision`
+    pub fn set_precision(&mut self, device: &Device, precision: ParticlePrecision) {
+        self.pipeline = Self::create_pipeline(device, &self.bind_group_layout, precision);
+    }
+
+    fn create_partials_buffer(device: &Device, num_partials: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("reduction partials buffer"),
+            size: (num_partials * std::mem::size_of::<Sums>()) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn update_masses(&mut self, queue: &Queue, masses: &[MassWrap; 5]) {
+        queue.write_buffer(&self.masses_buffer, 0, bytemuck::cast_slice(masses));
+    }
+
+    /// encodes the reduction of `particles_buffer` unless the previous result is still in flight
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        particles_buffer: &Buffer,
+        num_particles: usize,
+    ) {
+        if !self.readback.is_idle() || num_particles == 0 {
+            return;
+        }
+        let num_partials = num_particles.div_ceil(REDUCTION_GROUP_SIZE);
+        if num_partials != self.num_partials {
+            self.partials_buffer = Self::create_partials_buffer(device, num_partials);
+            self.num_partials = num_partials;
+        }
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("reduction bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: self.masses_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: particles_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: self.partials_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("reduction pass"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(&self.pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            let (x, y) = split_workgroups(num_partials as u32);
+            cpass.dispatch_workgroups(x, y, 1);
+        }
+        self.readback.encode(
+            device,
+            encoder,
+            &[ReadbackCopy {
+                buffer: &self.partials_buffer,
+                offset: 0,
+                size: self.partials_buffer.size(),
+            }],
+        );
+    }
+
+    /// starts mapping the readback buffer, call this after the encoder was submitted
+    pub fn after_submit(&mut self) {
+        self.readback.after_submit();
+    }
+
+    /// collects a finished readback into `result`
+    pub fn poll(&mut self, device: &Device) {
+        let Some(total) = self.readback.poll(device, |data| {
+            let partials: &[Sums] = bytemuck::cast_slice(data);
+            partials.iter().fold([0.0f32; 9], |mut acc, s| {
+                for i in 0..4 {
+                    acc[i] += s.mass_pos[i];
+                    acc[i + 4] += s.momentum_energy[i];
+                }
+                acc[8] = acc[8].max(s.max_speed[0]);
+                acc
+            })
+        }) else {
+            return;
+        };
+        let total_mass = total[3];
+        if total_mass > 0.0 {
+            self.result = Some(ReductionResult {
+                center_of_mass: V3::new(total[0], total[1], total[2]) / total_mass,
+                total_mass,
+                momentum: V3::new(total[4], total[5], total[6]),
+                kinetic_energy: total[7],
+                max_speed: total[8].sqrt(),
+            });
+        }
+    }
+}
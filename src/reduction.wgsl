@@ -0,0 +1,58 @@
+struct MassWrap {
+  @size(16) mass: f32,
+}
+
+// partial sums of one workgroup
+struct Sums {
+  // mass weighted position, total mass
+  mass_pos: vec4<f32>,
+  // momentum, kinetic energy
+  momentum_energy: vec4<f32>,
+  // largest squared speed in x
+  max_speed: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> particle_type_masses: array<MassWrap, 5>;
+@group(0) @binding(1) var<storage, read> particles: array<ParticleStorage>;
+@group(0) @binding(2) var<storage, read_write> partials: array<Sums>;
+
+var<workgroup> scratch: array<Sums, 256>;
+
+@compute
+@workgroup_size(256)
+fn main(
+    @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+    @builtin(local_invocation_id) local_invocation_id: vec3<u32>,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let index = invocation_index(global_invocation_id, num_workgroups, 256u);
+    let local_index = local_invocation_id.x;
+    var sums = Sums(vec4<f32>(), vec4<f32>(), vec4<f32>());
+    if index < arrayLength(&particles) {
+        let p = load_particle(particles[index]);
+        let m = particle_type_masses[p.ty].mass * p.mass;
+        sums.mass_pos = vec4<f32>(p.pos.xyz * m, m);
+        sums.momentum_energy = vec4<f32>(p.vel.xyz * m, 0.5 * m * dot(p.vel.xyz, p.vel.xyz));
+        sums.max_speed.x = dot(p.vel.xyz, p.vel.xyz);
+    }
+    scratch[local_index] = sums;
+    workgroupBarrier();
+
+    // tree reduction in workgroup memory
+    for (var stride = 128u; stride > 0u; stride >>= 1u) {
+        if local_index < stride {
+            let other = scratch[local_index + stride];
+            scratch[local_index].mass_pos += other.mass_pos;
+            scratch[local_index].momentum_energy += other.momentum_energy;
+            scratch[local_index].max_speed = max(scratch[local_index].max_speed, other.max_speed);
+        }
+        workgroupBarrier();
+    }
+
+    // the last row of a split dispatch can have more workgroups than partials
+    let partial = workgroup_id.x + workgroup_id.y * num_workgroups.x;
+    if local_index == 0u && partial < arrayLength(&partials) {
+        partials[partial] = scratch[0];
+    }
+}
@@ -0,0 +1,121 @@
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    cursor::{Marquee, SelectionShape},
+    V3,
+};
+
+/// the compute shader looks up every region for every particle, keep it small
+pub const MAX_REGIONS: usize = 16;
+
+// bits of `RegionParams::overrides`, mirror the constants in compute.wgsl
+const OVERRIDE_DAMPING: u32 = 1;
+const OVERRIDE_MAX_VELOCITY: u32 = 2;
+const OVERRIDE_CUT_OFF_DISTANCE: u32 = 4;
+const OVERRIDE_TIME_SCALE: u32 = 8;
+
+/// an axis aligned box of the bounding volume with parameters of its own,
+/// mirrors `Region` in compute.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct RegionParams {
+    pub min: [f32; 3],
+    /// the `OVERRIDE_*` bits of the parameters replaced inside of the box
+    pub overrides: u32,
+    pub max: [f32; 3],
+    pub damping: f32,
+    pub max_velocity: f32,
+    pub cut_off_distance: f32,
+    pub time_scale: f32,
+    _pad: f32,
+}
+
+/// overrides some of the `SimParams` for the particles inside of an axis aligned box,
+/// where regions overlap the last one wins
+#[derive(Clone, Debug)]
+pub struct Region {
+    pub min: V3,
+    pub max: V3,
+    /// the velocity decays by e to the power of minus this per second, 1.0 outside of regions
+    pub damping: Option<f32>,
+    pub max_velocity: Option<f32>,
+    /// the largest cut off distance of the pairs inside, the others are scaled along
+    pub cut_off_distance: Option<f32>,
+    /// multiplies the time step, slowing down or speeding up the motion, the aging,
+    /// the reactions and the emission of the particles inside
+    pub time_scale: Option<f32>,
+    pub enabled: bool,
+}
+
+impl Region {
+    /// a region that does not override anything yet
+    pub fn new(min: V3, max: V3) -> Self {
+        Region {
+            min,
+            max,
+            damping: None,
+            max_velocity: None,
+            cut_off_distance: None,
+            time_scale: None,
+            enabled: true,
+        }
+    }
+
+    /// the box dragged out with the cursor, `None` for a spherical selection
+    pub fn from_marquee(marquee: &Marquee) -> Option<Self> {
+        match marquee.shape {
+            SelectionShape::Box => Some(Region::new(
+                V3::new(
+                    marquee.start.x.min(marquee.end.x),
+                    marquee.start.y.min(marquee.end.y),
+                    marquee.start.z.min(marquee.end.z),
+                ),
+                V3::new(
+                    marquee.start.x.max(marquee.end.x),
+                    marquee.start.y.max(marquee.end.y),
+                    marquee.start.z.max(marquee.end.z),
+                ),
+            )),
+            SelectionShape::Sphere => None,
+        }
+    }
+
+    fn params(&self) -> RegionParams {
+        let mut overrides = 0;
+        let mut value = |value: Option<f32>, bit: u32| {
+            if value.is_some() {
+                overrides |= bit;
+            }
+            value.unwrap_or_default()
+        };
+        let damping = value(self.damping, OVERRIDE_DAMPING);
+        let max_velocity = value(self.max_velocity, OVERRIDE_MAX_VELOCITY);
+        let cut_off_distance = value(self.cut_off_distance, OVERRIDE_CUT_OFF_DISTANCE);
+        let time_scale = value(self.time_scale, OVERRIDE_TIME_SCALE);
+        RegionParams {
+            min: self.min.into(),
+            overrides,
+            max: self.max.into(),
+            damping,
+            max_velocity,
+            cut_off_distance,
+            time_scale,
+            _pad: 0.0,
+        }
+    }
+}
+
+/// the enabled regions for the region buffer, which always holds at least one region
+pub fn get_regions_raw(regions: &[Region]) -> (Vec<RegionParams>, u32) {
+    let mut params: Vec<RegionParams> = regions
+        .iter()
+        .filter(|r| r.enabled)
+        .map(Region::params)
+        .take(MAX_REGIONS)
+        .collect();
+    let num_regions = params.len() as u32;
+    if params.is_empty() {
+        params.push(RegionParams::zeroed());
+    }
+    (params, num_regions)
+}
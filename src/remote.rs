@@ -0,0 +1,321 @@
+use std::{
+    io::{Error, ErrorKind},
+    sync::mpsc::Receiver,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    net::UdpSocket,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::sim_params::SimParams;
+
+/// the port VJ software usually sends OSC to
+pub const DEFAULT_PORT: u16 = 9000;
+/// how often the listener thread checks whether it was stopped
+#[cfg(not(target_arch = "wasm32"))]
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+/// larger OSC packets are cut off
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_PACKET_SIZE: usize = 4096;
+
+/// the SimParams fields and the simulation speed, which can be set with `/param/<name> <value>`
+pub const PARAM_NAMES: [&str; 15] = [
+    "max_velocity",
+    "bounding_volume_radius",
+    "cut_off_distance",
+    "cutoff_window",
+    "distance_exponent",
+    "particle_lifetime",
+    "confinement_start",
+    "confinement_strength",
+    "confinement_exponent",
+    "merge_radius",
+    "split_mass",
+    "split_distance",
+    "inflow_speed",
+    "inflow_turbulence",
+    "speed",
+];
+
+/// what a message received over the network asks for, `App::update` applies it
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemoteCommand {
+    /// `/param/<name> <value>`, one of `PARAM_NAMES`
+    Param(String, f32),
+    /// `/mass <type> <mass>`
+    Mass(usize, f32),
+    /// `/camera/zoom <factor>`
+    Zoom(f32),
+    /// `/camera/pan <dx> <dy>` in screen pixels
+    Pan(f32, f32),
+    /// `/camera/look <dx> <dy>` in mouse pixels, turns the fly camera
+    Look(f32, f32),
+    /// `/camera/follow <0 or 1>`, follows the center of mass
+    Follow(bool),
+    /// `/preset <index or name>`
+    Preset(PresetRef),
+    /// `/respawn`, restarts the particles
+    Respawn,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PresetRef {
+    Index(usize),
+    Name(String),
+}
+
+/// an argument of an OSC message, the numbers are converted to f32
+#[derive(Clone, Debug, PartialEq)]
+enum OscArg {
+    Number(f32),
+    String(String),
+}
+
+impl OscArg {
+    fn number(&self) -> Option<f32> {
+        match self {
+            OscArg::Number(x) => Some(*x),
+            OscArg::String(_) => None,
+        }
+    }
+}
+
+/// receives OSC messages over UDP on a thread of its own and hands them to the main thread
+/// as `RemoteCommand`s, so VJ software or scripts can drive the simulation live
+pub struct RemoteControl {
+    pub port: u16,
+    /// the last error of the socket or of a message that could not be understood
+    pub error: Option<String>,
+    /// address of the last message that was understood
+    pub last_address: Option<String>,
+    pub num_received: usize,
+    receiver: Option<Receiver<Result<(String, RemoteCommand), String>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    listener: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl RemoteControl {
+    pub fn new() -> Self {
+        RemoteControl {
+            port: DEFAULT_PORT,
+            error: None,
+            last_address: None,
+            num_received: 0,
+            receiver: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            listener: None,
+        }
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// listens on `self.port` of all interfaces until `stop` is called
+    pub fn start(&mut self) -> std::io::Result<()> {
+        self.stop();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let socket = UdpSocket::bind(("0.0.0.0", self.port))?;
+            socket.set_read_timeout(Some(STOP_CHECK_INTERVAL))?;
+            let (sender, receiver) = mpsc::channel();
+            let stop = Arc::new(AtomicBool::new(false));
+            let stop_thread = stop.clone();
+            let handle = std::thread::Builder::new()
+                .name(String::from("particles remote control"))
+                .spawn(move || listen(socket, sender, stop_thread))?;
+            log::info!("listening for OSC messages on port {}", self.port);
+            self.receiver = Some(receiver);
+            self.listener = Some((stop, handle));
+            self.error = None;
+            Ok(())
+        }
+        #[cfg(target_arch = "wasm32")]
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "the browser cannot receive UDP messages",
+        ))
+    }
+
+    pub fn stop(&mut self) {
+        self.receiver = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((stop, handle)) = self.listener.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+    }
+
+    /// the commands received since the last call
+    pub fn poll(&mut self) -> Vec<RemoteCommand> {
+        let Some(receiver) = &self.receiver else {
+            return Vec::new();
+        };
+        let mut commands = Vec::new();
+        for message in receiver.try_iter() {
+            self.num_received += 1;
+            match message {
+                Ok((address, command)) => {
+                    self.last_address = Some(address);
+                    commands.push(command);
+                }
+                Err(e) => self.error = Some(e),
+            }
+        }
+        commands
+    }
+}
+
+impl Drop for RemoteControl {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn listen(
+    socket: UdpSocket,
+    sender: Sender<Result<(String, RemoteCommand), String>>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut buf = [0u8; MAX_PACKET_SIZE];
+    while !stop.load(Ordering::Relaxed) {
+        let len = match socket.recv(&mut buf) {
+            Ok(len) => len,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => {
+                let _ = sender.send(Err(e.to_string()));
+                return;
+            }
+        };
+        let mut messages = Vec::new();
+        let result = parse_packet(&buf[..len], &mut messages);
+        for (address, args) in messages {
+            let command = to_command(&address, &args)
+                .map(|command| (address.clone(), command))
+                .map_err(|e| format!("{}: {}", address, e));
+            if sender.send(command).is_err() {
+                return;
+            }
+        }
+        if let Err(e) = result {
+            let _ = sender.send(Err(e.to_string()));
+        }
+    }
+}
+
+/// the messages of an OSC packet, the elements of bundles are flattened
+/// and their time tags ignored
+fn parse_packet(packet: &[u8], messages: &mut Vec<(String, Vec<OscArg>)>) -> std::io::Result<()> {
+    let invalid = |what: &str| Error::new(ErrorKind::InvalidData, what.to_string());
+    if let Some(mut rest) = packet.strip_prefix(b"#bundle\0") {
+        // the time tag
+        rest = rest.get(8..).ok_or_else(|| invalid("truncated bundle"))?;
+        while !rest.is_empty() {
+            let size = read_u32(&mut rest).ok_or_else(|| invalid("truncated bundle"))? as usize;
+            let element = rest
+                .get(..size)
+                .ok_or_else(|| invalid("truncated bundle"))?;
+            parse_packet(element, messages)?;
+            rest = &rest[size..];
+        }
+        return Ok(());
+    }
+    let mut rest = packet;
+    let address = read_string(&mut rest).ok_or_else(|| invalid("missing address"))?;
+    // a message without a type tag string has no arguments
+    let type_tags = read_string(&mut rest).unwrap_or_default();
+    let mut args = Vec::new();
+    for tag in type_tags.chars().skip_while(|&c| c == ',') {
+        let arg = match tag {
+            'f' => read_u32(&mut rest).map(|bits| OscArg::Number(f32::from_bits(bits))),
+            'i' => read_u32(&mut rest).map(|bits| OscArg::Number(bits as i32 as f32)),
+            'd' => read_u64(&mut rest).map(|bits| OscArg::Number(f64::from_bits(bits) as f32)),
+            'h' => read_u64(&mut rest).map(|bits| OscArg::Number(bits as i64 as f32)),
+            's' | 'S' => read_string(&mut rest).map(OscArg::String),
+            'T' => Some(OscArg::Number(1.0)),
+            'F' | 'N' | 'I' => Some(OscArg::Number(0.0)),
+            _ => return Err(invalid("unsupported argument type")),
+        };
+        args.push(arg.ok_or_else(|| invalid("truncated arguments"))?);
+    }
+    messages.push((address, args));
+    Ok(())
+}
+
+fn read_u32(rest: &mut &[u8]) -> Option<u32> {
+    let (bytes, tail) = rest.split_first_chunk::<4>()?;
+    *rest = tail;
+    Some(u32::from_be_bytes(*bytes))
+}
+
+fn read_u64(rest: &mut &[u8]) -> Option<u64> {
+    let (bytes, tail) = rest.split_first_chunk::<8>()?;
+    *rest = tail;
+    Some(u64::from_be_bytes(*bytes))
+}
+
+/// a zero terminated string padded to a multiple of 4 bytes
+fn read_string(rest: &mut &[u8]) -> Option<String> {
+    let len = rest.iter().position(|&b| b == 0)?;
+    let string = String::from_utf8_lossy(&rest[..len]).into_owned();
+    *rest = rest.get((len / 4 + 1) * 4..).unwrap_or_default();
+    Some(string)
+}
+
+fn to_command(address: &str, args: &[OscArg]) -> Result<RemoteCommand, String> {
+    let number = |i: usize| {
+        args.get(i)
+            .and_then(OscArg::number)
+            .filter(|x| x.is_finite())
+            .ok_or_else(|| format!("argument {} has to be a number", i + 1))
+    };
+    let command = match address {
+        "/mass" => RemoteCommand::Mass(number(0)? as usize, number(1)?),
+        "/camera/zoom" => RemoteCommand::Zoom(number(0)?),
+        "/camera/pan" => RemoteCommand::Pan(number(0)?, number(1)?),
+        "/camera/look" => RemoteCommand::Look(number(0)?, number(1)?),
+        "/camera/follow" => RemoteCommand::Follow(number(0)? != 0.0),
+        "/preset" => RemoteCommand::Preset(match args.first() {
+            Some(OscArg::String(name)) => PresetRef::Name(name.clone()),
+            _ => PresetRef::Index(number(0)? as usize),
+        }),
+        "/respawn" => RemoteCommand::Respawn,
+        _ => match address.strip_prefix("/param/") {
+            Some(name) if PARAM_NAMES.contains(&name) => {
+                RemoteCommand::Param(String::from(name), number(0)?)
+            }
+            Some(_) => return Err(String::from("unknown parameter")),
+            None => return Err(String::from("unknown address")),
+        },
+    };
+    Ok(command)
+}
+
+/// the field of `sim_params` with one of the `PARAM_NAMES`, except for `speed`,
+/// `bounding_volume_radius` and `cut_off_distance`, which have to be applied with their
+/// setters
+pub fn sim_param_mut<'a>(sim_params: &'a mut SimParams, name: &str) -> Option<&'a mut f32> {
+    Some(match name {
+        "max_velocity" => &mut sim_params.max_velocity,
+        "cutoff_window" => &mut sim_params.cutoff_window,
+        "distance_exponent" => &mut sim_params.distance_exponent,
+        "particle_lifetime" => &mut sim_params.particle_lifetime,
+        "confinement_start" => &mut sim_params.confinement_start,
+        "confinement_strength" => &mut sim_params.confinement_strength,
+        "confinement_exponent" => &mut sim_params.confinement_exponent,
+        "merge_radius" => &mut sim_params.merge_radius,
+        "split_mass" => &mut sim_params.split_mass,
+        "split_distance" => &mut sim_params.split_distance,
+        "inflow_speed" => &mut sim_params.inflow_speed,
+        "inflow_turbulence" => &mut sim_params.inflow_turbulence,
+        _ => return None,
+    })
+}
@@ -1,15 +1,38 @@
+use crate::assets::{AssetData, AssetSlot, Mesh};
+use crate::brush::{brush_mesh, BrushShape};
 use crate::camera::Camera;
+use crate::colormap::{ColorMap, ColorRamp, NUM_STOPS};
+use crate::comparison::Comparison;
 use crate::compute::Compute;
+use crate::cutoff_sphere::{icosphere, CutoffSphere};
 use crate::draw_pass::DrawBuffer;
 use crate::draw_pass::DrawPass;
+use crate::draw_pass::DrawPassDescriptor;
 use crate::draw_pass::INSTANCE_LAYOUT_POSITION;
 use crate::draw_pass::INSTANCE_LAYOUT_VECTOR_FIELD;
+use crate::gizmos::Gizmos;
+use crate::glyph_culling::{Frustum, GlyphCulling, GlyphView};
+use crate::glyph_lod::GlyphLod;
+use crate::indirect::DRAW_ARGS_OFFSET;
+use crate::motion_blur::MotionBlur;
+use crate::palette::{Palette, NUM_TINTS};
+use crate::panel_surface::PanelSurface;
+use crate::precision::ParticlePrecision;
+use crate::recorder::{RecordOutput, Recorder};
+use crate::render_scale::ScaledTarget;
+use crate::shader_watcher::ShaderKind;
+use crate::sim_params::{Confinement, SimParams};
+use crate::slice_view::SliceView;
+use crate::V3;
 use bytemuck::{Pod, Zeroable};
-use cgmath::Vector3;
+use cgmath::{Matrix4, Vector3};
 use egui::FullOutput;
 use egui_wgpu::renderer::ScreenDescriptor;
 use wgpu::*;
 
+/// two triangles spanning a particle quad
+pub const PARTICLE_INDICES: [u16; 6] = [0, 1, 2, 1, 2, 3];
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct Vertex {
@@ -19,10 +42,103 @@ pub struct Vertex {
 unsafe impl Pod for Vertex {}
 unsafe impl Zeroable for Vertex {}
 
+/// uniform parameters of the particle shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ParticleRenderParams {
+    pub color_ramp: [[f32; 4]; NUM_STOPS],
+    /// particle quads are spanned by the camera right and up vectors
+    pub camera_right: [f32; 4],
+    pub camera_up: [f32; 4],
+    /// size factor per particle type, 5 used
+    pub type_sizes: [[f32; 4]; 2],
+    /// RGBA tint per particle type, multiplied with the texture atlas
+    pub type_tints: [[f32; 4]; NUM_TINTS],
+    /// 0.0 means particles live forever
+    pub lifetime: f32,
+    /// particles scale in over this many seconds after birth
+    pub birth_duration: f32,
+    /// particles fade out over this many seconds before death
+    pub death_duration: f32,
+    /// age range covered by the color ramp for particles that live forever
+    pub ramp_duration: f32,
+    pub use_color_ramp: u32,
+    /// half the side length of the particle quads in world units
+    pub particle_size: f32,
+    /// the quads are kept between these side lengths on screen in pixels, 0.0 disables a limit
+    pub min_pixels: f32,
+    pub max_pixels: f32,
+    /// pixels covered by one world unit at a distance of one unit from the camera
+    pub pixels_per_unit: f32,
+    /// seconds of motion smeared by the motion blur, 0.0 while it is off
+    pub motion_blur_time: f32,
+    /// bit `i` set draws the particles of type `i`, the others are collapsed in the shader
+    pub visible_types: u32,
+    pub _padding: u32,
+}
+
+impl ParticleRenderParams {
+    pub fn type_size_mut(&mut self, particle_type: usize) -> &mut f32 {
+        &mut self.type_sizes[particle_type / 4][particle_type % 4]
+    }
+
+    pub fn type_visible(&self, particle_type: usize) -> bool {
+        self.visible_types & (1 << particle_type) != 0
+    }
+
+    pub fn set_type_visible(&mut self, particle_type: usize, visible: bool) {
+        if visible {
+            self.visible_types |= 1 << particle_type;
+        } else {
+            self.visible_types &= !(1 << particle_type);
+        }
+    }
+
+    pub fn new(color_ramp: &ColorRamp) -> Self {
+        ParticleRenderParams {
+            color_ramp: color_ramp.stops(),
+            camera_right: [1.0, 0.0, 0.0, 0.0],
+            camera_up: [0.0, 1.0, 0.0, 0.0],
+            type_sizes: [[1.0; 4]; 2],
+            type_tints: Palette::Atlas.tints(),
+            lifetime: 0.0,
+            birth_duration: 0.2,
+            death_duration: 0.5,
+            ramp_duration: 10.0,
+            use_color_ramp: 0,
+            particle_size: 0.01,
+            min_pixels: 0.0,
+            max_pixels: 0.0,
+            pixels_per_unit: 1.0,
+            motion_blur_time: 0.0,
+            visible_types: u32::MAX,
+            _padding: 0,
+        }
+    }
+}
+
 pub struct Renderer {
     pub sub_rpass_particles: DrawPass,
     pub sub_rpass_cursor: DrawPass,
     pub sub_rpass_vector_field: DrawPass,
+    pub sub_rpass_lines: DrawPass,
+    pub sub_rpass_cutoff_sphere: DrawPass,
+    pub cutoff_sphere: CutoffSphere,
+    /// the particles in a slab of the volume, shown in a window of the gui
+    pub slice_view: SliceView,
+    /// the cursor mesh asset, drawn while the brush is a sphere
+    cursor_mesh: Mesh,
+    /// the brush shape the cursor draw pass holds the mesh of
+    cursor_shape: BrushShape,
+    pub gizmos: Gizmos,
+    pub glyph_culling: GlyphCulling,
+    pub glyph_lod: GlyphLod,
+    pub particle_render_params: ParticleRenderParams,
+    /// colors of the particles over their age
+    pub color_ramp: ColorRamp,
+    pub recorder: Recorder,
+    /// renders a single frame at the window resolution into a png, see `take_screenshot`
+    pub screenshot: Recorder,
     pub device: Device,
     pub queue: Queue,
     egui_rpass: egui_wgpu::renderer::Renderer,
@@ -31,7 +147,15 @@ pub struct Renderer {
     depth_texture: Texture,
     depth_view: TextureView,
     depth_sampler: Sampler,
+    /// offscreen target of the scene when it is not drawn at the window resolution
+    scaled_target: Option<ScaledTarget>,
+    /// smears the particles along their motion while the strength is above 0
+    motion_blur: Option<MotionBlur>,
+    /// the control panel is drawn onto this surface instead of the window while detached
+    pub panel: Option<PanelSurface>,
     pub recreate_pipelines: bool,
+    /// the window shows two simulations side by side, each in half of its width
+    split_screen: bool,
 }
 
 impl Renderer {
@@ -41,11 +165,6 @@ impl Renderer {
         queue: Queue, // we might need to meddle with the command queue
     ) -> Self {
         use std::borrow::Cow;
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Renderer: wgsl shader module"),
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("shader.wgsl"))),
-        });
-
         let mut camera: Camera = Camera::new(
             surface_config.width as f32,
             surface_config.height as f32,
@@ -56,66 +175,127 @@ impl Renderer {
 
         let draw_buffer = DrawBuffer::new(&device, &queue, texture_as_bytes);
 
+        let color_ramp = ColorRamp::from_map(ColorMap::Viridis);
+        let particle_render_params = ParticleRenderParams::new(&color_ramp);
         let mut sub_rpass_particles = DrawPass::new(
             surface_config,
             &device,
             &queue,
             draw_buffer,
-            shader,
             &mut camera,
-            PrimitiveTopology::TriangleList,
-            crate::draw_pass::INSTANCE_LAYOUT_PARTICLE,
-            true,
-            true,
-            "particles",
+            DrawPassDescriptor {
+                prefix: "particles",
+                shader_src: Cow::Borrowed(include_str!("shader.wgsl")),
+                topology: PrimitiveTopology::TriangleList,
+                instance_layout: crate::draw_pass::INSTANCE_LAYOUT_PARTICLE,
+                params: Some(bytemuck::bytes_of(&particle_render_params)),
+                bcreate_viewmatrix: true,
+                bcreate_camera_rotation: true,
+            },
         );
         dbg!(crate::draw_pass::INSTANCE_LAYOUT_PARTICLE);
         dbg!(crate::draw_pass::INSTANCE_LAYOUT_VECTOR_FIELD);
         dbg!(crate::draw_pass::INSTANCE_LAYOUT_POSITION);
-        let d = 0.01;
-        let md = -0.01;
+        // a unit quad, the particle shader scales it to `ParticleRenderParams::particle_size`
         sub_rpass_particles.update_vertex_buffer(
             &device,
             &[
-                (Vector3::new(md, d, d), [0.0, 1.0]),
-                (Vector3::new(d, d, d), [1.0, 1.0]),
-                (Vector3::new(md, md, d), [0.0, 0.0]),
-                (Vector3::new(d, md, d), [1.0, 0.0]),
+                (Vector3::new(-1.0, 1.0, 0.0), [0.0, 1.0]),
+                (Vector3::new(1.0, 1.0, 0.0), [1.0, 1.0]),
+                (Vector3::new(-1.0, -1.0, 0.0), [0.0, 0.0]),
+                (Vector3::new(1.0, -1.0, 0.0), [1.0, 0.0]),
             ],
         );
-        sub_rpass_particles.update_index_buffer(&device, &[0, 1, 2, 1, 2, 3]);
+        sub_rpass_particles.update_index_buffer(&device, &PARTICLE_INDICES);
 
-        let cursor_texture_bytes = include_bytes!("../assets/cursor.png");
+        // the asset files replace the embedded meshes and textures once they are loaded
+        let cursor_mesh = embedded_mesh(AssetSlot::CursorMesh);
         let sub_rpass_cursor = DrawPass::from_object_and_texture(
             surface_config,
             &device,
             &queue,
-            Cow::Borrowed(include_str!("cursor_shader.wgsl")),
-            "./assets/cursor.obj",
-            cursor_texture_bytes,
+            &cursor_mesh,
+            &embedded_texture(AssetSlot::CursorTexture),
             &mut camera,
-            INSTANCE_LAYOUT_POSITION,
-            true,
-            true,
-            "cursor",
+            DrawPassDescriptor {
+                prefix: "cursor",
+                shader_src: Cow::Borrowed(include_str!("cursor_shader.wgsl")),
+                topology: PrimitiveTopology::TriangleList,
+                instance_layout: INSTANCE_LAYOUT_POSITION,
+                params: None,
+                bcreate_viewmatrix: true,
+                bcreate_camera_rotation: true,
+            },
         );
 
-        let vector_texture_bytes = include_bytes!("../assets/vector.png");
+        let mut glyph_lod = GlyphLod::new();
+        let glyph_meshes = glyph_lod.pack(&embedded_mesh(AssetSlot::VectorMesh));
+        let glyph_lod_params = glyph_lod.params(camera.pixels_per_unit());
         let sub_rpass_vector_field = DrawPass::from_object_and_texture(
             surface_config,
             &device,
             &queue,
-            Cow::Borrowed(include_str!("vector_field_shader.wgsl")),
-            "./assets/vector.obj",
-            vector_texture_bytes,
+            &glyph_meshes,
+            &embedded_texture(AssetSlot::VectorTexture),
+            &mut camera,
+            DrawPassDescriptor {
+                prefix: "vector field",
+                shader_src: Cow::Borrowed(include_str!("vector_field_shader.wgsl")),
+                topology: PrimitiveTopology::TriangleList,
+                instance_layout: INSTANCE_LAYOUT_VECTOR_FIELD,
+                params: Some(bytemuck::bytes_of(&glyph_lod_params)),
+                bcreate_viewmatrix: true,
+                bcreate_camera_rotation: false,
+            },
+        );
+
+        let sub_rpass_lines = DrawPass::new(
+            surface_config,
+            &device,
+            &queue,
+            DrawBuffer::new_untextured(&device, &queue),
             &mut camera,
-            INSTANCE_LAYOUT_VECTOR_FIELD,
-            true,
-            false,
-            "vector field",
+            DrawPassDescriptor {
+                prefix: "lines",
+                shader_src: Cow::Borrowed(include_str!("lines_shader.wgsl")),
+                topology: PrimitiveTopology::LineList,
+                instance_layout: INSTANCE_LAYOUT_POSITION,
+                params: None,
+                bcreate_viewmatrix: true,
+                bcreate_camera_rotation: false,
+            },
         );
 
-        let egui_rpass = egui_wgpu::renderer::Renderer::new(&device, surface_config.format, None, 1);
+        let cutoff_sphere = CutoffSphere::new();
+        let cutoff_sphere_params = cutoff_sphere.params(camera.eye());
+        let sub_rpass_cutoff_sphere = DrawPass::from_object_and_texture(
+            surface_config,
+            &device,
+            &queue,
+            &icosphere(),
+            &image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])),
+            &mut camera,
+            DrawPassDescriptor {
+                prefix: "cutoff sphere",
+                shader_src: Cow::Borrowed(include_str!("cutoff_sphere.wgsl")),
+                topology: PrimitiveTopology::TriangleList,
+                instance_layout: INSTANCE_LAYOUT_POSITION,
+                params: Some(bytemuck::bytes_of(&cutoff_sphere_params)),
+                bcreate_viewmatrix: true,
+                bcreate_camera_rotation: false,
+            },
+        );
+
+        let mut egui_rpass =
+            egui_wgpu::renderer::Renderer::new(&device, surface_config.format, None, 1);
+        let slice_view = SliceView::new(
+            surface_config,
+            &device,
+            &queue,
+            &mut camera,
+            &mut egui_rpass,
+            &particle_render_params,
+        );
 
         let (depth_texture, depth_view, depth_sampler) =
             Self::create_depth_texture(&device, surface_config);
@@ -124,6 +304,19 @@ impl Renderer {
             sub_rpass_particles,
             sub_rpass_cursor,
             sub_rpass_vector_field,
+            sub_rpass_lines,
+            sub_rpass_cutoff_sphere,
+            cutoff_sphere,
+            slice_view,
+            cursor_mesh,
+            cursor_shape: BrushShape::Sphere,
+            gizmos: Gizmos::new(),
+            glyph_culling: GlyphCulling::new(),
+            glyph_lod,
+            particle_render_params,
+            color_ramp,
+            recorder: Recorder::new(),
+            screenshot: Recorder::new(),
             egui_rpass,
             device,
             queue,
@@ -132,7 +325,11 @@ impl Renderer {
             depth_texture,
             depth_view,
             depth_sampler,
+            scaled_target: None,
+            motion_blur: None,
+            panel: None,
             recreate_pipelines: false,
+            split_screen: false,
         }
     }
 
@@ -150,6 +347,93 @@ impl Renderer {
             &self.queue,
             &mut self.camera,
         );
+        self.sub_rpass_lines.recreate_pipeline(
+            &self.surface_config,
+            &self.device,
+            &self.queue,
+            &mut self.camera,
+        );
+        self.sub_rpass_cutoff_sphere.recreate_pipeline(
+            &self.surface_config,
+            &self.device,
+            &self.queue,
+            &mut self.camera,
+        );
+        self.slice_view.draw_pass.recreate_pipeline(
+            &self.surface_config,
+            &self.device,
+            &self.queue,
+            &mut self.camera,
+        );
+        self.rebuild_motion_blur();
+    }
+
+    /// swaps the shader of the draw pass using the shader file `kind`
+    pub fn reload_shader(&mut self, kind: ShaderKind, shader: ShaderModule) {
+        let draw_pass = match kind {
+            ShaderKind::Particles => &mut self.sub_rpass_particles,
+            ShaderKind::Cursor => &mut self.sub_rpass_cursor,
+            ShaderKind::VectorField => &mut self.sub_rpass_vector_field,
+            ShaderKind::Lines => &mut self.sub_rpass_lines,
+            ShaderKind::Compute => return,
+        };
+        draw_pass.set_shader(
+            &self.surface_config,
+            &self.device,
+            &self.queue,
+            &mut self.camera,
+            shader,
+        );
+        // the velocity pipeline uses the particle shader
+        self.rebuild_motion_blur();
+    }
+
+    /// swaps a mesh or texture of the cursor or the vector field
+    pub fn replace_asset(&mut self, slot: AssetSlot, data: AssetData) {
+        let draw_pass = match slot {
+            AssetSlot::CursorMesh | AssetSlot::CursorTexture => &mut self.sub_rpass_cursor,
+            AssetSlot::VectorMesh | AssetSlot::VectorTexture => &mut self.sub_rpass_vector_field,
+        };
+        match data {
+            AssetData::Mesh(mesh) if slot == AssetSlot::VectorMesh => {
+                let glyph_meshes = self.glyph_lod.pack(&mesh);
+                draw_pass.set_mesh(&self.device, &glyph_meshes);
+            }
+            AssetData::Mesh(mesh) if slot == AssetSlot::CursorMesh => {
+                if self.cursor_shape == BrushShape::Sphere {
+                    draw_pass.set_mesh(&self.device, &mesh);
+                }
+                self.cursor_mesh = mesh;
+            }
+            AssetData::Mesh(mesh) => draw_pass.set_mesh(&self.device, &mesh),
+            AssetData::Texture(rgba) => draw_pass.set_texture(
+                &self.surface_config,
+                &self.device,
+                &self.queue,
+                &mut self.camera,
+                &rgba,
+            ),
+        }
+    }
+
+    /// shapes, turns and scales the cursor mesh like the brush
+    fn update_cursor_brush(&mut self) {
+        let cursor = &self.camera.cursor;
+        if cursor.brush.shape != self.cursor_shape {
+            self.cursor_shape = cursor.brush.shape;
+            match brush_mesh(self.cursor_shape) {
+                Some(mesh) => self.sub_rpass_cursor.set_mesh(&self.device, &mesh),
+                None => self
+                    .sub_rpass_cursor
+                    .set_mesh(&self.device, &self.cursor_mesh),
+            }
+        }
+        let axes = Matrix4::from(cursor.brush.axes(cursor.rot));
+        self.sub_rpass_cursor
+            .update_rotation_matrix(&self.queue, axes);
+        let (p, size) = (cursor.pos, cursor.edit_mode.falloff_dist);
+        self.sub_rpass_cursor
+            .update_instance_buffer(&self.device, &[p.x, p.y, p.z, size], 1);
     }
 
     pub fn create_depth_texture(
@@ -199,16 +483,132 @@ impl Renderer {
         self.depth_texture = depth_texture;
         self.depth_view = depth_view;
         self.depth_sampler = depth_sampler;
-        self.camera
-            .resize(surface_config.width as f32, surface_config.height as f32);
+        self.set_render_scale(self.render_scale());
+        self.resize_camera();
         self.recreate_pipelines();
     }
 
+    pub fn split_screen(&self) -> bool {
+        self.split_screen
+    }
+
+    /// shows two simulations side by side, the camera projects onto half the window then
+    pub fn set_split_screen(&mut self, split_screen: bool) {
+        if split_screen != self.split_screen {
+            self.split_screen = split_screen;
+            self.resize_camera();
+        }
+    }
+
+    /// fits the camera to the window, or to one half of it for the split screen
+    fn resize_camera(&mut self) {
+        let width = if self.split_screen {
+            (self.surface_config.width / 2).max(1)
+        } else {
+            self.surface_config.width
+        };
+        self.camera
+            .resize(width as f32, self.surface_config.height as f32);
+    }
+
+    pub fn surface_format(&self) -> TextureFormat {
+        self.surface_config.format
+    }
+
+    /// fraction of the window resolution the particles and the vector field are drawn at
+    pub fn render_scale(&self) -> f32 {
+        self.scaled_target
+            .as_ref()
+            .map_or(1.0, |target| target.scale)
+    }
+
+    pub fn set_render_scale(&mut self, scale: f32) {
+        self.scaled_target = if scale == 1.0 {
+            None
+        } else {
+            Some(ScaledTarget::new(&self.device, &self.surface_config, scale))
+        };
+        self.rebuild_motion_blur();
+    }
+
+    /// fraction of the time step the particles are smeared over, 0.0 while the blur is off
+    pub fn motion_blur_strength(&self) -> f32 {
+        self.motion_blur.as_ref().map_or(0.0, |blur| blur.strength)
+    }
+
+    pub fn set_motion_blur_strength(&mut self, strength: f32) {
+        if strength <= 0.0 {
+            self.motion_blur = None;
+        } else if let Some(blur) = self.motion_blur.as_mut() {
+            blur.strength = strength;
+        } else {
+            self.motion_blur = Some(self.create_motion_blur(strength));
+        }
+    }
+
+    /// the motion blur for a new scene size or particle pipeline
+    fn rebuild_motion_blur(&mut self) {
+        if let Some(strength) = self.motion_blur.as_ref().map(|blur| blur.strength) {
+            self.motion_blur = Some(self.create_motion_blur(strength));
+        }
+    }
+
+    fn create_motion_blur(&self, strength: f32) -> MotionBlur {
+        let (width, height) = match &self.scaled_target {
+            Some(target) => (target.size.width, target.size.height),
+            None => (self.surface_config.width, self.surface_config.height),
+        };
+        MotionBlur::new(
+            &self.device,
+            &self.sub_rpass_particles,
+            self.surface_config.format,
+            width,
+            height,
+            strength,
+        )
+    }
+
+    /// bytes of the glyph instances and the render targets, the targets with the texel
+    /// sizes of their formats
+    pub fn buffer_sizes(&self) -> Vec<(&'static str, u64)> {
+        let texels = |width: u32, height: u32, format: TextureFormat| {
+            width as u64 * height as u64 * format.block_size(None).unwrap_or(4) as u64
+        };
+        let (width, height) = (self.surface_config.width, self.surface_config.height);
+        let mut sizes = vec![
+            (
+                "glyph instances",
+                self.sub_rpass_vector_field
+                    .draw_buffer
+                    .instance_buffer
+                    .size(),
+            ),
+            ("depth", texels(width, height, TextureFormat::Depth32Float)),
+        ];
+        let (width, height) = match &self.scaled_target {
+            Some(target) => {
+                let (w, h) = (target.size.width, target.size.height);
+                let size = texels(w, h, self.surface_config.format)
+                    + texels(w, h, TextureFormat::Depth32Float);
+                sizes.push(("scaled target", size));
+                (w, h)
+            }
+            None => (width, height),
+        };
+        if self.motion_blur.is_some() {
+            let size = texels(width, height, self.surface_config.format)
+                + texels(width, height, TextureFormat::Rg16Float);
+            sizes.push(("motion blur", size));
+        }
+        sizes
+    }
+
     pub fn render(
         &mut self,
         frame: &SurfaceTexture,
         output: FullOutput,
         compute: &mut Compute,
+        mut comparison: Option<&mut Comparison>,
         context: &egui::Context,
         scale_factor: f32,
     ) {
@@ -223,41 +623,40 @@ impl Renderer {
                 timestamp_writes: None,
             });
             compute.compute(&mut cpass);
+            if let Some(comparison) = comparison.as_deref_mut() {
+                comparison.compute.compute(&mut cpass);
+            }
         }
+        compute.encode_velocity_ops(&self.device, &mut encoder);
+        compute.encode_readbacks(&self.device, &mut encoder);
         let clipped_primitives = context.tessellate(output.shapes, 1.0);
         let view = frame.texture.create_view(&TextureViewDescriptor::default());
 
-        {
-            let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("rpass: RenderPassDescriptor"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color::BLACK),
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                    view: &self.depth_view,
-                    depth_ops: Some(Operations {
-                        load: LoadOp::Clear(1.0),
-                        store: StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            self.sub_rpass_particles.render_with_instance_buffer(
-                &mut rpass,
-                &compute.particles_buffers[0],
-                compute.num_particles,
+        if let Some(comparison) = comparison.as_deref() {
+            let sides = comparison.sides(compute);
+            if let Some(target) = &self.scaled_target {
+                let (width, height) = (target.size.width, target.size.height);
+                let (scene_view, depth_view) = (&target.view, &target.depth_view);
+                self.encode_split_scene(&mut encoder, scene_view, depth_view, width, height, sides);
+                target.encode_blit(&mut encoder, &view);
+            } else {
+                let (width, height) = (self.surface_config.width, self.surface_config.height);
+                let depth_view = &self.depth_view;
+                self.encode_split_scene(&mut encoder, &view, depth_view, width, height, sides);
+            }
+        } else if let Some(target) = &self.scaled_target {
+            self.encode_scene_into(
+                &mut encoder,
+                &target.view,
+                &target.depth_view,
+                compute,
+                true,
             );
-            self.sub_rpass_vector_field.render(&mut rpass);
-            self.sub_rpass_cursor.render(&mut rpass);
+            target.encode_blit(&mut encoder, &view);
+        } else {
+            self.encode_scene(&mut encoder, &view, compute, true);
         }
+        self.slice_view.encode(&mut encoder, compute);
         {
             // Upload all resources for the GPU.
             let screen_descriptor = ScreenDescriptor {
@@ -300,5 +699,387 @@ impl Renderer {
         }
 
         self.queue.submit(Some(encoder.finish()));
+        compute.after_submit();
+        if self.recorder.is_recording() {
+            self.record_frame(compute, false);
+        }
+        if self.screenshot.is_recording() {
+            self.record_frame(compute, true);
+        }
+    }
+
+    /// draws the detached control panel onto `frame` of the panel surface
+    pub fn render_panel(
+        &mut self,
+        frame: &SurfaceTexture,
+        output: FullOutput,
+        context: &egui::Context,
+        scale_factor: f32,
+    ) {
+        if let Some(panel) = self.panel.as_mut() {
+            panel.render(
+                &self.device,
+                &self.queue,
+                frame,
+                output,
+                context,
+                scale_factor,
+            );
+        }
+    }
+
+    /// renders the particles into `view`, the cursor, vector field and gizmos with `overlays`
+    pub fn encode_scene(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        compute: &Compute,
+        overlays: bool,
+    ) {
+        self.encode_scene_into(encoder, view, &self.depth_view, compute, overlays);
+    }
+
+    fn encode_scene_into(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        depth_view: &TextureView,
+        compute: &Compute,
+        overlays: bool,
+    ) {
+        let Some(blur) = &self.motion_blur else {
+            let mut rpass = Self::begin_scene_pass(encoder, view, depth_view, true);
+            self.draw_particles(&mut rpass, compute);
+            if overlays {
+                self.draw_overlays(&mut rpass);
+            }
+            return;
+        };
+        {
+            let mut rpass = Self::begin_scene_pass(encoder, &blur.scene_view, depth_view, true);
+            self.draw_particles(&mut rpass, compute);
+        }
+        blur.encode(
+            encoder,
+            &self.sub_rpass_particles,
+            compute,
+            depth_view,
+            view,
+        );
+        // the overlays are drawn sharp over the blurred particles
+        if overlays {
+            let mut rpass = Self::begin_scene_pass(encoder, view, depth_view, false);
+            self.draw_overlays(&mut rpass);
+        }
+    }
+
+    /// draws the two simulations of the comparison side by side with the overlays over each,
+    /// `width` and `height` are the size of `view`. motion blur is left out
+    fn encode_split_scene(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        depth_view: &TextureView,
+        width: u32,
+        height: u32,
+        sides: [&Compute; 2],
+    ) {
+        let mut rpass = Self::begin_scene_pass(encoder, view, depth_view, true);
+        let half = (width / 2).max(1) as f32;
+        for (i, compute) in sides.into_iter().enumerate() {
+            rpass.set_viewport(i as f32 * half, 0.0, half, height as f32, 0.0, 1.0);
+            self.draw_particles(&mut rpass, compute);
+            self.draw_overlays(&mut rpass);
+        }
+    }
+
+    /// a render pass onto `view` and `depth_view`, which are cleared if `clear` is set
+    fn begin_scene_pass<'a>(
+        encoder: &'a mut CommandEncoder,
+        view: &'a TextureView,
+        depth_view: &'a TextureView,
+        clear: bool,
+    ) -> RenderPass<'a> {
+        let (load, depth_load) = if clear {
+            (LoadOp::Clear(Color::BLACK), LoadOp::Clear(1.0))
+        } else {
+            (LoadOp::Load, LoadOp::Load)
+        };
+        encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("rpass: RenderPassDescriptor"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(Operations {
+                    load: depth_load,
+                    store: StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+
+    fn draw_particles<'a>(&'a self, rpass: &mut RenderPass<'a>, compute: &'a Compute) {
+        self.sub_rpass_particles.render_indirect(
+            rpass,
+            &compute.particles_buffers[0],
+            &compute.indirect.args_buffer,
+            DRAW_ARGS_OFFSET,
+        );
+    }
+
+    /// the vector field, the gizmo lines, the cursor and the cut off sphere
+    fn draw_overlays<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        let vector_field = &self.sub_rpass_vector_field;
+        if vector_field.draw_buffer.instance_buffer_length > 0 {
+            vector_field.render_index_ranges(rpass, self.glyph_lod.drawn_ranges());
+        }
+        if self.sub_rpass_lines.draw_buffer.index_buffer_length > 0 {
+            self.sub_rpass_lines.render(rpass);
+        }
+        self.sub_rpass_cursor.render(rpass);
+        // last, it would hide the cursor inside of it from the depth test
+        if self
+            .sub_rpass_cutoff_sphere
+            .draw_buffer
+            .instance_buffer_length
+            > 0
+        {
+            self.sub_rpass_cutoff_sphere.render(rpass);
+        }
+    }
+
+    /// writes the camera matrices and the particle render parameters of the next frame
+    pub fn update_view(&mut self, sim_params: &SimParams) {
+        self.sub_rpass_particles
+            .update_view_matrix(&self.queue, &mut self.camera);
+        self.sub_rpass_particles
+            .update_camera_rotation_matrix(&self.queue, &mut self.camera);
+        self.sub_rpass_cursor
+            .update_view_matrix(&self.queue, &mut self.camera);
+        self.update_cursor_brush();
+        self.sub_rpass_vector_field
+            .update_view_matrix(&self.queue, &mut self.camera);
+        let glyph_lod_params = self.glyph_lod.params(self.camera.pixels_per_unit());
+        self.sub_rpass_vector_field
+            .update_params_buffer(&self.queue, bytemuck::bytes_of(&glyph_lod_params));
+        self.sub_rpass_lines
+            .update_view_matrix(&self.queue, &mut self.camera);
+        self.update_gizmos(sim_params);
+        self.update_cutoff_sphere(sim_params.cut_off_distance);
+        let (right, up) = self.camera.right_up();
+        self.particle_render_params.camera_right = right.extend(0.0).into();
+        self.particle_render_params.camera_up = up.extend(0.0).into();
+        self.particle_render_params.pixels_per_unit = self.camera.pixels_per_unit();
+        self.particle_render_params.motion_blur_time =
+            self.motion_blur_strength() * sim_params.delta_t;
+        self.particle_render_params.lifetime = sim_params.particle_lifetime;
+        self.particle_render_params.color_ramp = self.color_ramp.stops();
+        self.sub_rpass_particles.update_params_buffer(
+            &self.queue,
+            bytemuck::bytes_of(&self.particle_render_params),
+        );
+        self.slice_view
+            .update(&self.queue, &self.particle_render_params, sim_params);
+    }
+
+    /// the view the vector field glyphs are culled against
+    pub fn glyph_view(&mut self) -> GlyphView {
+        GlyphView {
+            frustum: Frustum::from_matrix(self.camera.get_view_matrix()),
+            eye: self.camera.eye(),
+        }
+    }
+
+    /// moves the cut off sphere to its anchor, or hides it
+    fn update_cutoff_sphere(&mut self, cut_off_distance: f32) {
+        let Some(center) = self.cutoff_sphere.center(self.camera.cursor.pos) else {
+            self.sub_rpass_cutoff_sphere
+                .draw_buffer
+                .instance_buffer_length = 0;
+            return;
+        };
+        self.sub_rpass_cutoff_sphere
+            .update_view_matrix(&self.queue, &mut self.camera);
+        let params = self.cutoff_sphere.params(self.camera.eye());
+        self.sub_rpass_cutoff_sphere
+            .update_params_buffer(&self.queue, bytemuck::bytes_of(&params));
+        self.sub_rpass_cutoff_sphere.update_instance_buffer(
+            &self.device,
+            &[center.x, center.y, center.z, cut_off_distance],
+            1,
+        );
+    }
+
+    /// rebuilds the gizmo lines and scales them to the bounding volume
+    pub fn update_gizmos(&mut self, sim_params: &SimParams) {
+        let bounding_volume_radius = sim_params.bounding_volume_radius;
+        self.gizmos.slice_plane = self.slice_view.plane();
+        self.gizmos.floor = (sim_params.floor != 0).then_some(sim_params.floor_height);
+        let emitting =
+            sim_params.confinement == Confinement::WindTunnel && sim_params.emitter_rate > 0.0;
+        self.gizmos.emitter = emitting.then(|| sim_params.emitter_corners());
+        let (vertices, indices) = self.gizmos.get_lines(
+            self.camera.cursor.marquee.as_ref(),
+            bounding_volume_radius,
+            V3::from(sim_params.bounding_volume_extents),
+        );
+        self.sub_rpass_lines
+            .update_vertex_buffer(&self.device, &vertices);
+        self.sub_rpass_lines
+            .update_index_buffer(&self.device, &indices);
+        self.sub_rpass_lines.update_instance_buffer(
+            &self.device,
+            &[0.0, 0.0, 0.0, bounding_volume_radius],
+            1,
+        );
+    }
+
+    /// renders the particles into the target of the recorder or of the screenshot with a
+    /// camera matching its resolution
+    fn record_frame(&mut self, compute: &Compute, screenshot: bool) {
+        let recorder = if screenshot {
+            &mut self.screenshot
+        } else {
+            &mut self.recorder
+        };
+        self.camera
+            .resize(recorder.width as f32, recorder.height as f32);
+        self.sub_rpass_particles
+            .update_view_matrix(&self.queue, &mut self.camera);
+        self.sub_rpass_particles
+            .update_camera_rotation_matrix(&self.queue, &mut self.camera);
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("recorder command encoder"),
+        });
+        if let Some((view, depth_view)) = recorder.views() {
+            let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("recorder render pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: depth_view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.sub_rpass_particles.render_indirect(
+                &mut rpass,
+                &compute.particles_buffers[0],
+                &compute.indirect.args_buffer,
+                DRAW_ARGS_OFFSET,
+            );
+        }
+        recorder.encode_copy(&mut encoder);
+        self.queue.submit(Some(encoder.finish()));
+        recorder.write_frame(&self.device);
+        // the window matrices are written again in the next update
+        self.resize_camera();
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recorder
+            .start(&self.device, self.surface_config.format);
+    }
+
+    /// writes the next frame at the window resolution to the png file at `path`
+    pub fn take_screenshot(&mut self, path: String) {
+        self.screenshot.output = RecordOutput::Screenshot;
+        self.screenshot.path = path;
+        self.screenshot.width = self.surface_config.width;
+        self.screenshot.height = self.surface_config.height;
+        self.screenshot
+            .start(&self.device, self.surface_config.format);
+    }
+
+    /// rebuilds every GPU resource on a new device after the old one was lost,
+    /// the camera, gizmos and render settings are kept
+    pub fn recreate(
+        &mut self,
+        surface_config: &SurfaceConfiguration,
+        device: Device,
+        queue: Queue,
+        precision: ParticlePrecision,
+    ) {
+        // the recording target lived on the lost device
+        self.recorder.stop();
+        self.screenshot.stop();
+        let mut renderer = Renderer::init(surface_config, device, queue);
+        std::mem::swap(&mut renderer.camera, &mut self.camera);
+        std::mem::swap(&mut renderer.gizmos, &mut self.gizmos);
+        std::mem::swap(&mut renderer.glyph_culling, &mut self.glyph_culling);
+        renderer.glyph_lod.enabled = self.glyph_lod.enabled;
+        renderer.glyph_lod.full_arrow_pixels = self.glyph_lod.full_arrow_pixels;
+        renderer.glyph_lod.cone_pixels = self.glyph_lod.cone_pixels;
+        renderer.slice_view.enabled = self.slice_view.enabled;
+        renderer.slice_view.axis = self.slice_view.axis;
+        renderer.slice_view.offset = self.slice_view.offset;
+        renderer.slice_view.half_thickness = self.slice_view.half_thickness;
+        std::mem::swap(&mut renderer.recorder, &mut self.recorder);
+        renderer.particle_render_params = self.particle_render_params;
+        renderer.color_ramp = self.color_ramp.clone();
+        let render_scale = self.render_scale();
+        let motion_blur_strength = self.motion_blur_strength();
+        *self = renderer;
+        self.set_render_scale(render_scale);
+        self.camera
+            .resize(surface_config.width as f32, surface_config.height as f32);
+        self.sub_rpass_particles.instance_layout = precision.instance_layout();
+        self.slice_view.draw_pass.instance_layout = precision.instance_layout();
+        self.recreate_pipelines();
+        self.set_motion_blur_strength(motion_blur_strength);
+    }
+
+    /// rebuilds the particle pipeline for the instance layout of `precision`
+    pub fn set_particle_precision(&mut self, precision: ParticlePrecision) {
+        self.sub_rpass_particles.instance_layout = precision.instance_layout();
+        self.sub_rpass_particles.recreate_pipeline(
+            &self.surface_config,
+            &self.device,
+            &self.queue,
+            &mut self.camera,
+        );
+        self.slice_view.draw_pass.instance_layout = precision.instance_layout();
+        self.slice_view.draw_pass.recreate_pipeline(
+            &self.surface_config,
+            &self.device,
+            &self.queue,
+            &mut self.camera,
+        );
+        self.rebuild_motion_blur();
+    }
+}
+
+fn embedded_mesh(slot: AssetSlot) -> Mesh {
+    match slot.embedded() {
+        AssetData::Mesh(mesh) => mesh,
+        AssetData::Texture(_) => unreachable!("{} is a mesh", slot.name()),
+    }
+}
+
+fn embedded_texture(slot: AssetSlot) -> image::RgbaImage {
+    match slot.embedded() {
+        AssetData::Texture(rgba) => rgba,
+        AssetData::Mesh(_) => unreachable!("{} is a texture", slot.name()),
     }
 }
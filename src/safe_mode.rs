@@ -0,0 +1,56 @@
+/// particles per lattice axis when starting in safe mode
+pub const SAFE_MODE_PARTICLES_PER_AXIS: usize = 4;
+
+/// why the app was started with minimal settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SafeMode {
+    /// `--safe-mode` was passed on the command line
+    Requested,
+    /// the previous run did not shut down cleanly
+    Crashed,
+}
+
+impl SafeMode {
+    pub fn description(&self) -> &'static str {
+        match self {
+            SafeMode::Requested => "safe mode (--safe-mode)",
+            SafeMode::Crashed => "safe mode (the last run crashed)",
+        }
+    }
+
+    /// checks the command line and the crash lock left behind by the previous run
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn detect() -> Option<SafeMode> {
+        if std::env::args().any(|arg| arg == "--safe-mode") {
+            Some(SafeMode::Requested)
+        } else if crash_lock_path().exists() {
+            Some(SafeMode::Crashed)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn detect() -> Option<SafeMode> {
+        None
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn crash_lock_path() -> std::path::PathBuf {
+    std::env::temp_dir().join("particles.lock")
+}
+
+/// the lock file exists while the app is running, finding it at startup means the last run crashed
+pub fn acquire_crash_lock() {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(e) = std::fs::write(crash_lock_path(), std::process::id().to_string()) {
+        log::warn!("could not create the crash lock: {}", e);
+    }
+}
+
+/// removes the lock file on a clean shutdown
+pub fn release_crash_lock() {
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = std::fs::remove_file(crash_lock_path());
+}
@@ -0,0 +1,467 @@
+use std::{
+    collections::HashSet,
+    io::{Error, ErrorKind},
+    str::FromStr,
+};
+
+use crate::{
+    palette::NUM_TINTS,
+    sim_params::{
+        Confinement, ForceAccumulation, ForceGridBoundary, ForceGridInterpolation, ForceMode,
+        MassCoupling, SimParams,
+    },
+};
+
+/// the version written into new scene files. the history of the format:
+/// 1. no header, the attraction forces of the first files are cubic with 4 coefficients
+/// 2. `version` and `types` header lines, bounding volume extents
+/// 3. `cutoff_window`, the forces of older files end abruptly at the cut off distance
+/// 4. `mass_coupling`, older files couple the masses gravitationally, which is the default
+/// 5. `gravity` and the floor plane, older files have neither, which is the default
+/// 6. the wind tunnel inflow and emitter
+/// 7. a cut off distance per pair in the `pair_force` lines, in older files all pairs share
+///    `cut_off_distance`
+/// 8. the `seed` of the particle spawning and the random numbers generated on the GPU
+pub const SCENE_VERSION: u32 = 8;
+/// the force matrices hold the forces between this many particle types
+const NUM_TYPES: usize = 5;
+/// the parameters of the 64 bit FNV-1a hash behind `reproducibility_hash`
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// the forces, limits and particle colors of a simulation, saved as a plain text file
+/// with one `key values..` line per setting after a header with the format version
+pub struct SceneFile {
+    pub path: String,
+    pub error: Option<String>,
+    /// what was migrated or skipped when the file was last loaded
+    pub warnings: Vec<String>,
+}
+
+impl SceneFile {
+    pub fn new() -> Self {
+        SceneFile {
+            path: String::from("scene.txt"),
+            error: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn save(
+        &self,
+        sim_params: &SimParams,
+        type_tints: &[[f32; 4]; NUM_TINTS],
+    ) -> std::io::Result<()> {
+        std::fs::write(&self.path, to_text(sim_params, type_tints))
+    }
+
+    /// overwrites the settings found in the file, the others keep their values.
+    /// files of older versions are migrated, the settings they lack get their defaults.
+    /// returns what was migrated or skipped
+    pub fn load(
+        &self,
+        sim_params: &mut SimParams,
+        type_tints: &mut [[f32; 4]; NUM_TINTS],
+    ) -> std::io::Result<Vec<String>> {
+        let text = std::fs::read_to_string(&self.path)?;
+        from_text(&text, sim_params, type_tints)
+    }
+}
+
+/// a fingerprint of the seed and the simulation settings, the same in every build,
+/// so a run can be matched to the settings and the seed it was started with.
+/// hashes the lines of the scene file without the particle colors
+pub fn reproducibility_hash(sim_params: &SimParams) -> u64 {
+    settings_lines(sim_params)
+        .iter()
+        .flat_map(|line| line.bytes().chain(Some(b'\n')))
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
+
+fn to_text(sim_params: &SimParams, type_tints: &[[f32; 4]; NUM_TINTS]) -> String {
+    let mut lines = settings_lines(sim_params);
+    for (i, tint) in type_tints.iter().enumerate() {
+        lines.push(format!("tint {} {}", i, join(tint)));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn join(values: &[f32]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// the header and the lines of every setting but the particle colors
+fn settings_lines(sim_params: &SimParams) -> Vec<String> {
+    let mut lines = vec![
+        String::from("# particles scene"),
+        format!("version {}", SCENE_VERSION),
+        format!("types {}", NUM_TYPES),
+        format!("max_velocity {}", sim_params.max_velocity),
+        format!(
+            "bounding_volume_radius {}",
+            sim_params.bounding_volume_radius
+        ),
+        format!(
+            "bounding_volume_extents {}",
+            join(&sim_params.bounding_volume_extents)
+        ),
+        format!("cut_off_distance {}", sim_params.cut_off_distance),
+        format!("cutoff_window {}", sim_params.cutoff_window),
+        format!("distance_exponent {}", sim_params.distance_exponent),
+        format!("particle_lifetime {}", sim_params.particle_lifetime),
+        format!(
+            "force_grid_interpolation {}",
+            sim_params.force_grid_interpolation as u32
+        ),
+        format!(
+            "force_grid_boundary {}",
+            sim_params.force_grid_boundary as u32
+        ),
+        format!("confinement {}", sim_params.confinement as u32),
+        format!("confinement_start {}", sim_params.confinement_start),
+        format!("confinement_strength {}", sim_params.confinement_strength),
+        format!("confinement_exponent {}", sim_params.confinement_exponent),
+        format!("force_mode {}", sim_params.force_mode as u32),
+        format!(
+            "force_accumulation {}",
+            sim_params.force_accumulation as u32
+        ),
+        format!("mass_coupling {}", sim_params.mass_coupling as u32),
+        format!("merge_radius {}", sim_params.merge_radius),
+        format!("split_mass {}", sim_params.split_mass),
+        format!("split_distance {}", sim_params.split_distance),
+        format!("gravity {}", join(&sim_params.gravity)),
+        format!("floor {}", sim_params.floor),
+        format!("floor_height {}", sim_params.floor_height),
+        format!("floor_restitution {}", sim_params.floor_restitution),
+        format!("floor_friction {}", sim_params.floor_friction),
+        format!("inflow_axis {}", sim_params.inflow_axis),
+        format!("inflow_speed {}", sim_params.inflow_speed),
+        format!("inflow_profile {}", sim_params.inflow_profile),
+        format!("inflow_turbulence {}", sim_params.inflow_turbulence),
+        format!("emitter_rate {}", sim_params.emitter_rate),
+        format!("emitter_position {}", sim_params.emitter_position),
+        format!("emitter_size {}", sim_params.emitter_size),
+        format!("seed {}", sim_params.seed()),
+    ];
+    for (i, mass) in sim_params.particle_type_masses.iter().enumerate() {
+        lines.push(format!("mass {} {}", i, mass.mass));
+    }
+    for (i, poly) in sim_params.attraction_force.iter().enumerate() {
+        lines.push(format!("attraction_force {} {}", i, join(&poly.coeffs)));
+    }
+    for (i, pair_force) in sim_params.pair_forces.iter().enumerate() {
+        lines.push(format!(
+            "pair_force {} {} {} {}",
+            i, pair_force.scale, pair_force.enabled, pair_force.cut_off_distance
+        ));
+    }
+    for (i, flocking) in sim_params.flocking.iter().enumerate() {
+        lines.push(format!(
+            "flocking {} {} {} {} {}",
+            i, flocking.alignment, flocking.cohesion, flocking.separation, flocking.radius
+        ));
+    }
+    lines
+}
+
+fn from_text(
+    text: &str,
+    sim_params: &mut SimParams,
+    type_tints: &mut [[f32; 4]; NUM_TINTS],
+) -> std::io::Result<Vec<String>> {
+    // parse into a copy, a broken file leaves the settings untouched
+    let mut params = *sim_params;
+    let mut tints = *type_tints;
+    let mut warnings = Vec::new();
+    // files without a header are of the first version, which had 5 types as well
+    let mut version = 1;
+    let mut num_types = NUM_TYPES;
+    let mut keys = HashSet::new();
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |what: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", line_number + 1, what),
+            )
+        };
+        let mut words = line.split_whitespace();
+        let key = words.next().unwrap_or_default();
+        let number = words.next().and_then(|word| u32::from_str(word).ok());
+        match key {
+            "version" => version = number.ok_or_else(|| invalid("invalid version"))?,
+            "types" => {
+                num_types = number
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| invalid("invalid number of types"))?
+                    as usize;
+            }
+            _ => {
+                keys.insert(key);
+                let mut migration = Migration {
+                    num_types,
+                    warnings: &mut warnings,
+                };
+                apply_line(line, &mut params, &mut tints, &mut migration)
+                    .map_err(|what| invalid(&what))?;
+            }
+        }
+    }
+    if version > SCENE_VERSION {
+        warnings.push(format!(
+            "written by the newer scene version {}, settings this version does not know are skipped",
+            version
+        ));
+    } else if version < SCENE_VERSION {
+        // the settings added since the file was written get their defaults
+        // instead of keeping the current values, the tints keep them
+        let defaults = to_text(&SimParams::new(), &tints);
+        for line in defaults.lines() {
+            let key = line.split_whitespace().next().unwrap_or_default();
+            if line.starts_with('#')
+                || ["version", "types", "tint"].contains(&key)
+                || keys.contains(key)
+            {
+                continue;
+            }
+            if key == "cutoff_window" {
+                params.cutoff_window = 0.0;
+                warnings.push(String::from(
+                    "cutoff_window is missing, the forces are cut off abruptly as before",
+                ));
+                continue;
+            }
+            let message = format!("{} is missing, it is set to its default", key);
+            if !warnings.contains(&message) {
+                warnings.push(message);
+            }
+            let mut migration = Migration {
+                num_types: NUM_TYPES,
+                warnings: &mut warnings,
+            };
+            apply_line(line, &mut params, &mut tints, &mut migration)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        }
+    }
+    for warning in &warnings {
+        log::warn!("scene file: {}", warning);
+    }
+    params.update_cut_off_distance();
+    *sim_params = params;
+    *type_tints = tints;
+    Ok(warnings)
+}
+
+/// what a line of an older file needs to be read into the current settings
+struct Migration<'a> {
+    /// the particle types the file was written for
+    num_types: usize,
+    warnings: &'a mut Vec<String>,
+}
+
+impl Migration<'_> {
+    fn warn(&mut self, message: &str) {
+        if !self.warnings.iter().any(|w| w == message) {
+            self.warnings.push(String::from(message));
+        }
+    }
+
+    /// the index of a particle type, None for a type beyond the supported ones
+    fn type_index(&mut self, i: usize) -> Result<Option<usize>, String> {
+        if i < NUM_TYPES {
+            Ok(Some(i))
+        } else if i < self.num_types {
+            self.warn(&format!(
+                "the settings of the types past the first {} are dropped",
+                NUM_TYPES
+            ));
+            Ok(None)
+        } else {
+            Err(String::from("particle type out of range"))
+        }
+    }
+
+    /// the index into the current type matrices of the entry `i` of a matrix
+    /// of the types the file was written for, None if a type is beyond the supported ones
+    fn force_index(&mut self, i: usize) -> Result<Option<usize>, String> {
+        if i >= self.num_types * self.num_types {
+            return Err(String::from("force index out of range"));
+        }
+        let (x, y) = (i % self.num_types, i / self.num_types);
+        Ok(match (self.type_index(x)?, self.type_index(y)?) {
+            (Some(x), Some(y)) => Some(x + y * NUM_TYPES),
+            _ => None,
+        })
+    }
+}
+
+/// reads one `key values..` line into `params` and `tints`, unknown keys are skipped
+fn apply_line(
+    line: &str,
+    params: &mut SimParams,
+    tints: &mut [[f32; 4]; NUM_TINTS],
+    migration: &mut Migration,
+) -> Result<(), String> {
+    let mut words = line.split_whitespace();
+    let key = words.next().unwrap_or_default();
+    let mut next = || -> Result<f32, String> {
+        words
+            .next()
+            .and_then(|word| f32::from_str(word).ok())
+            .ok_or_else(|| String::from("missing or invalid number"))
+    };
+    match key {
+        "max_velocity" => params.max_velocity = next()?,
+        "bounding_volume_radius" => params.bounding_volume_radius = next()?,
+        "bounding_volume_extents" => {
+            params.bounding_volume_extents = [next()?, next()?, next()?];
+        }
+        "cut_off_distance" => params.set_cut_off_distance(next()?),
+        "cutoff_window" => params.cutoff_window = next()?,
+        "distance_exponent" => params.distance_exponent = next()?,
+        "particle_lifetime" => params.particle_lifetime = next()?,
+        "force_grid_interpolation" => {
+            params.force_grid_interpolation = match next()? as u32 {
+                0 => ForceGridInterpolation::Nearest,
+                1 => ForceGridInterpolation::Trilinear,
+                2 => ForceGridInterpolation::Cubic,
+                _ => return Err(String::from("unknown force grid interpolation")),
+            }
+        }
+        "force_grid_boundary" => {
+            params.force_grid_boundary = match next()? as u32 {
+                0 => ForceGridBoundary::Clamp,
+                1 => ForceGridBoundary::Zero,
+                2 => ForceGridBoundary::Wrap,
+                _ => return Err(String::from("unknown force grid boundary")),
+            }
+        }
+        "confinement" => {
+            params.confinement = match next()? as u32 {
+                0 => Confinement::Wrap,
+                1 => Confinement::Polynomial,
+                2 => Confinement::Exponential,
+                3 => Confinement::WindTunnel,
+                _ => return Err(String::from("unknown confinement")),
+            }
+        }
+        "confinement_start" => params.confinement_start = next()?,
+        "confinement_strength" => params.confinement_strength = next()?,
+        "confinement_exponent" => params.confinement_exponent = next()?,
+        "force_mode" => {
+            params.force_mode = match next()? as u32 {
+                0 => ForceMode::Force,
+                1 => ForceMode::Potential,
+                _ => return Err(String::from("unknown force mode")),
+            }
+        }
+        "force_accumulation" => {
+            params.force_accumulation = match next()? as u32 {
+                0 => ForceAccumulation::Plain,
+                1 => ForceAccumulation::Compensated,
+                _ => return Err(String::from("unknown force accumulation")),
+            }
+        }
+        "mass_coupling" => {
+            params.mass_coupling = match next()? as u32 {
+                0 => MassCoupling::Gravitational,
+                1 => MassCoupling::Inertial,
+                2 => MassCoupling::Direct,
+                _ => return Err(String::from("unknown mass coupling")),
+            }
+        }
+        "merge_radius" => params.merge_radius = next()?,
+        "split_mass" => params.split_mass = next()?,
+        "split_distance" => params.split_distance = next()?,
+        "gravity" => params.gravity = [next()?, next()?, next()?],
+        "floor" => params.floor = (next()? != 0.0) as u32,
+        "floor_height" => params.floor_height = next()?,
+        "floor_restitution" => params.floor_restitution = next()?,
+        "floor_friction" => params.floor_friction = next()?,
+        "inflow_axis" => params.inflow_axis = (next()? as u32).min(2),
+        "inflow_speed" => params.inflow_speed = next()?,
+        "inflow_profile" => params.inflow_profile = next()?,
+        "inflow_turbulence" => params.inflow_turbulence = next()?,
+        "emitter_rate" => params.emitter_rate = next()?,
+        "emitter_position" => params.emitter_position = next()?,
+        "emitter_size" => params.emitter_size = next()?,
+        // a float would round seeds past 24 bits
+        "seed" => {
+            let seed = words
+                .next()
+                .and_then(|word| u64::from_str(word).ok())
+                .ok_or_else(|| String::from("missing or invalid seed"))?;
+            params.set_seed(seed);
+        }
+        "mass" => {
+            let i = next()? as usize;
+            let mass = next()?;
+            if let Some(i) = migration.type_index(i)? {
+                params.particle_type_masses[i].mass = mass;
+            }
+        }
+        "attraction_force" => {
+            let i = next()? as usize;
+            let values: Vec<f32> = std::iter::from_fn(|| next().ok()).collect();
+            let mut coeffs = [0.0; 8];
+            match values.len() {
+                8 => coeffs.copy_from_slice(&values),
+                // the cubic polynomes of the first versions
+                4 => {
+                    coeffs[..4].copy_from_slice(&values);
+                    migration.warn("cubic attraction forces are widened to degree 7");
+                }
+                _ => return Err(String::from("expected 4 or 8 coefficients")),
+            }
+            if let Some(i) = migration.force_index(i)? {
+                params.attraction_force[i].coeffs = coeffs;
+            }
+        }
+        "pair_force" => {
+            let i = next()? as usize;
+            let (scale, enabled) = (next()?, next()? as u32);
+            // older files have no cut off distance per pair
+            let cut_off_distance = next().ok();
+            if let Some(i) = migration.force_index(i)? {
+                let pair_force = &mut params.pair_forces[i];
+                pair_force.scale = scale;
+                pair_force.enabled = enabled;
+                if let Some(distance) = cut_off_distance {
+                    pair_force.cut_off_distance = distance;
+                }
+            }
+        }
+        "flocking" => {
+            let i = next()? as usize;
+            let values = [next()?, next()?, next()?, next()?];
+            if let Some(i) = migration.force_index(i)? {
+                let flocking = &mut params.flocking[i];
+                [
+                    flocking.alignment,
+                    flocking.cohesion,
+                    flocking.separation,
+                    flocking.radius,
+                ] = values;
+            }
+        }
+        "tint" => {
+            let i = next()? as usize;
+            let tint = [next()?, next()?, next()?, next()?];
+            if let Some(i) = migration.type_index(i)? {
+                tints[i] = tint;
+            }
+        }
+        _ => migration.warn(&format!("unknown setting {}", key)),
+    }
+    Ok(())
+}
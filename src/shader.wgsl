@@ -1,29 +1,151 @@
 struct VertexOutput {
     @builtin(position) out_pos: vec4<f32>,
     @location(0) tex_coord: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    // motion over the blur time in texture coordinates
+    @location(2) screen_velocity: vec2<f32>,
 };
 
 struct Transform {
     transform: mat4x4<f32>,
 };
 
+struct ParticleRenderParams {
+    color_ramp: array<vec4<f32>, 8>,
+    // particle quads are spanned by the camera right and up vectors
+    camera_right: vec4<f32>,
+    camera_up: vec4<f32>,
+    // size factor per particle type
+    type_sizes: array<vec4<f32>, 2>,
+    // RGBA tint per particle type, multiplied with the texture atlas
+    type_tints: array<vec4<f32>, 5>,
+    // 0.0 means particles live forever
+    lifetime: f32,
+    // particles scale in over this many seconds after birth
+    birth_duration: f32,
+    // particles fade out over this many seconds before death
+    death_duration: f32,
+    // age range covered by the color ramp for particles that live forever
+    ramp_duration: f32,
+    use_color_ramp: u32,
+    // half the side length of the particle quads in world units
+    particle_size: f32,
+    // the quads are kept between these side lengths on screen in pixels, 0.0 disables a limit
+    min_pixels: f32,
+    max_pixels: f32,
+    // pixels covered by one world unit at a distance of one unit from the camera
+    pixels_per_unit: f32,
+    // seconds of motion smeared by the motion blur
+    motion_blur_time: f32,
+    // bit i set draws the particles of type i
+    visible_types: u32,
+};
+
 @group(0)
 @binding(0)
 var<uniform> u_transform: Transform;
 
 @group(0) @binding(1) var<uniform> camera_rotation: Transform;
 
+@group(2) @binding(0) var<uniform> render_params: ParticleRenderParams;
+
+fn sample_color_ramp(t: f32) -> vec4<f32> {
+    let x = clamp(t, 0.0, 1.0) * 7.0;
+    let i = min(u32(x), 6u);
+    return mix(render_params.color_ramp[i], render_params.color_ramp[i + 1u], x - f32(i));
+}
+
+// half the side length of a quad at `distance` from the camera in world units
+fn quad_size(distance: f32) -> f32 {
+    // world units covered by one pixel at the distance of the particle
+    let unit_per_pixel = max(distance, 0.0001) / max(render_params.pixels_per_unit, 0.0001);
+    var pixels = 2.0 * render_params.particle_size / unit_per_pixel;
+    if render_params.min_pixels > 0.0 {
+        pixels = max(pixels, render_params.min_pixels);
+    }
+    if render_params.max_pixels > 0.0 {
+        pixels = min(pixels, render_params.max_pixels);
+    }
+    return 0.5 * pixels * unit_per_pixel;
+}
+
+fn particle_vertex(
+        in_pos: vec3<f32>,
+        tex_coord: vec2<f32>,
+        instance_pos: vec4<f32>,
+        instance_vel: vec4<f32>,
+        particle_type: u32,
+        particle_age: f32,
+        ) -> VertexOutput {
+    var out: VertexOutput;
+    var scale = 1.0;
+    if render_params.birth_duration > 0.0 {
+        scale = clamp(particle_age / render_params.birth_duration, 0.0, 1.0);
+    }
+    var alpha = 1.0;
+    if render_params.lifetime > 0.0 && render_params.death_duration > 0.0 {
+        alpha = clamp((render_params.lifetime - particle_age) / render_params.death_duration, 0.0, 1.0);
+    }
+    scale *= render_params.type_sizes[particle_type / 4u][particle_type % 4u];
+    // a hidden type collapses its quads, they neither cover nor hide anything
+    if (render_params.visible_types & (1u << particle_type)) == 0u {
+        scale = 0.0;
+        alpha = 0.0;
+    }
+    let center = u_transform.transform * vec4<f32>(instance_pos.xyz, 1.0);
+    // the clip space w is the distance along the view direction
+    scale *= quad_size(center.w);
+    // billboard: the quad always faces the camera
+    let billboard_vertex = (in_pos.x * render_params.camera_right.xyz + in_pos.y * render_params.camera_up.xyz) * scale;
+    out.out_pos = u_transform.transform * vec4<f32>(billboard_vertex + instance_pos.xyz, 1.0);
+    out.tex_coord = vec2<f32>((tex_coord.x + f32(particle_type)) * 0.2, tex_coord.y);
+    out.color = vec4<f32>(1.0, 1.0, 1.0, alpha);
+    if render_params.use_color_ramp != 0u {
+        var span = render_params.ramp_duration;
+        if render_params.lifetime > 0.0 {
+            span = render_params.lifetime;
+        }
+        out.color = sample_color_ramp(particle_age / span) * vec4<f32>(1.0, 1.0, 1.0, alpha);
+    }
+    out.color *= render_params.type_tints[particle_type];
+    let moved = u_transform.transform * vec4<f32>(instance_pos.xyz + instance_vel.xyz * render_params.motion_blur_time, 1.0);
+    out.screen_velocity = vec2<f32>(0.0);
+    if center.w > 0.0001 && moved.w > 0.0001 {
+        let motion = moved.xy / moved.w - center.xy / center.w;
+        out.screen_velocity = vec2<f32>(0.5 * motion.x, -0.5 * motion.y);
+    }
+    return out;
+}
+
 @vertex
 fn vs_main(
         @location(0) in_pos: vec3<f32>,
         @location(1) tex_coord: vec2<f32>,
         @location(2) instance_pos: vec4<f32>,
         @location(3) particle_type: u32,
+        @location(4) particle_age: f32,
+        @location(5) instance_vel: vec4<f32>,
         ) -> VertexOutput {
-    var out: VertexOutput;
-    let rotated_vertex = camera_rotation.transform * vec4<f32>(in_pos, 1.0);
-    out.out_pos = u_transform.transform * vec4<f32>(rotated_vertex.xyz + instance_pos.xyz, 1.0);
-    out.tex_coord = vec2<f32>((tex_coord.x + f32(particle_type)) * 0.2, tex_coord.y);
+    return particle_vertex(in_pos, tex_coord, instance_pos, instance_vel, particle_type, particle_age);
+}
+
+// the quad stretched over the path of the particle during the blur time,
+// the corners ahead of the center move forward and the others backward
+@vertex
+fn vs_velocity(
+        @location(0) in_pos: vec3<f32>,
+        @location(1) tex_coord: vec2<f32>,
+        @location(2) instance_pos: vec4<f32>,
+        @location(3) particle_type: u32,
+        @location(4) particle_age: f32,
+        @location(5) instance_vel: vec4<f32>,
+        ) -> VertexOutput {
+    var out = particle_vertex(in_pos, tex_coord, instance_pos, instance_vel, particle_type, particle_age);
+    let center = u_transform.transform * vec4<f32>(instance_pos.xyz, 1.0);
+    let motion = vec2<f32>(2.0 * out.screen_velocity.x, -2.0 * out.screen_velocity.y);
+    let offset = out.out_pos.xy / max(out.out_pos.w, 0.0001) - center.xy / max(center.w, 0.0001);
+    let side = select(-0.5, 0.5, dot(offset, motion) > 0.0);
+    out.out_pos = vec4<f32>(out.out_pos.xy + side * motion * out.out_pos.w, out.out_pos.zw);
     return out;
 }
 
@@ -35,5 +157,20 @@ var t_sampler: sampler;
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let tex = textureSample(texture, t_sampler, in.tex_coord);
-    return tex;
+    if render_params.use_color_ramp != 0u {
+        // keep the brightness of the texture but replace its hue
+        let brightness = max(tex.r, max(tex.g, tex.b));
+        return vec4<f32>(in.color.rgb * brightness, tex.a * in.color.a);
+    }
+    return tex * in.color;
+}
+
+// the screen space motion of the visible parts of the particles for the motion blur
+@fragment
+fn fs_velocity(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex = textureSample(texture, t_sampler, in.tex_coord);
+    if tex.a * in.color.a < 0.1 {
+        discard;
+    }
+    return vec4<f32>(in.screen_velocity, 0.0, 1.0);
 }
@@ -0,0 +1,136 @@
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use instant::Instant;
+use wgpu::{
+    naga::{
+        front::wgsl,
+        valid::{Capabilities, ValidationFlags, Validator},
+    },
+    Device, ShaderModule, ShaderModuleDescriptor, ShaderSource,
+};
+
+/// how often the shader files are checked for modifications
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderKind {
+    Compute,
+    Particles,
+    Cursor,
+    VectorField,
+    Lines,
+}
+
+impl ShaderKind {
+    pub const ALL: [ShaderKind; 5] = [
+        ShaderKind::Compute,
+        ShaderKind::Particles,
+        ShaderKind::Cursor,
+        ShaderKind::VectorField,
+        ShaderKind::Lines,
+    ];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ShaderKind::Compute => "compute.wgsl",
+            ShaderKind::Particles => "shader.wgsl",
+            ShaderKind::Cursor => "cursor_shader.wgsl",
+            ShaderKind::VectorField => "vector_field_shader.wgsl",
+            ShaderKind::Lines => "lines_shader.wgsl",
+        }
+    }
+}
+
+struct WatchedShader {
+    kind: ShaderKind,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+/// watches the wgsl sources in the src directory and reports edits made while the app is running
+pub struct ShaderWatcher {
+    shaders: Vec<WatchedShader>,
+    last_check: Instant,
+    /// the last compile error of every shader that failed to reload
+    pub errors: Vec<(ShaderKind, String)>,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> Self {
+        let src_dir = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src"));
+        let shaders = ShaderKind::ALL
+            .iter()
+            .map(|&kind| {
+                let path = src_dir.join(kind.file_name());
+                let modified = Self::modified(&path);
+                WatchedShader {
+                    kind,
+                    path,
+                    modified,
+                }
+            })
+            .collect();
+        ShaderWatcher {
+            shaders,
+            last_check: Instant::now(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn modified(path: &PathBuf) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// sources of all shaders whose files changed since the last call
+    pub fn changed_shaders(&mut self) -> Vec<(ShaderKind, String)> {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return Vec::new();
+        }
+        self.last_check = Instant::now();
+        let mut res = Vec::new();
+        for shader in self.shaders.iter_mut() {
+            let modified = Self::modified(&shader.path);
+            if modified.is_none() || modified == shader.modified {
+                continue;
+            }
+            shader.modified = modified;
+            if let Ok(source) = std::fs::read_to_string(&shader.path) {
+                res.push((shader.kind, source));
+            }
+        }
+        res
+    }
+
+    /// compiles `source`, a failure is recorded in `errors` instead of panicking
+    pub fn compile(
+        &mut self,
+        device: &Device,
+        kind: ShaderKind,
+        source: String,
+    ) -> Option<ShaderModule> {
+        self.errors.retain(|(k, _)| *k != kind);
+        match Self::validate(&source) {
+            Ok(()) => Some(device.create_shader_module(ShaderModuleDescriptor {
+                label: Some(kind.file_name()),
+                source: ShaderSource::Wgsl(Cow::Owned(source)),
+            })),
+            Err(e) => {
+                log::error!("could not reload {}:\n{}", kind.file_name(), e);
+                self.errors.push((kind, e));
+                None
+            }
+        }
+    }
+
+    fn validate(source: &str) -> Result<(), String> {
+        let module = wgsl::parse_str(source).map_err(|e| e.emit_to_string(source))?;
+        Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|e| e.emit_to_string(source))?;
+        Ok(())
+    }
+}
@@ -1,25 +1,328 @@
 use crate::grid::{Bounds, Grid};
 use crate::poly7::Poly7;
 use crate::{zero_v3, MassWrap, V3};
-use bytemuck::{NoUninit, Zeroable};
+use bytemuck::{NoUninit, Pod, Zeroable};
+
+/// smallest half size of the bounding volume along an axis
+pub const MIN_BOUNDING_VOLUME_EXTENT: f32 = 0.05;
+
+/// how the compute shader samples the force grid between cell centers
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, NoUninit)]
+pub enum ForceGridInterpolation {
+    Nearest = 0,
+    Trilinear,
+    Cubic,
+}
+
+unsafe impl Zeroable for ForceGridInterpolation {}
+
+/// what particles outside of the force grid bounds sample
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, NoUninit)]
+pub enum ForceGridBoundary {
+    /// the nearest cell on the edge of the grid
+    Clamp = 0,
+    /// no force at all
+    Zero,
+    /// the grid repeats periodically
+    Wrap,
+}
+
+unsafe impl Zeroable for ForceGridBoundary {}
+
+/// how particles are kept inside of the bounding volume
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, NoUninit)]
+pub enum Confinement {
+    /// particles leaving the bounding volume enter it on the opposite side
+    Wrap = 0,
+    /// a restoring force growing with a power of the depth into the boundary layer
+    Polynomial,
+    /// a restoring force growing exponentially with the depth into the boundary layer
+    Exponential,
+    /// a wind tunnel along `inflow_axis`, particles leaving through either end enter again
+    /// through the inflow face with the stream velocity, across the flow they wrap around
+    WindTunnel,
+}
+
+unsafe impl Zeroable for Confinement {}
+
+/// how the compute shader reads the attraction force polynomes
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, NoUninit)]
+pub enum ForceMode {
+    /// the polynomes are the attraction along the distance, positive values attract
+    Force = 0,
+    /// the polynomes are potentials U(r), the particles accelerate towards lower potential
+    /// along dU/dr. with a distance exponent of 0 and symmetric forces the energy is conserved,
+    /// the potential is shifted to 0 at the cut off distance before it is windowed
+    Potential,
+}
+
+unsafe impl Zeroable for ForceMode {}
+
+impl ForceMode {
+    pub const ALL: [ForceMode; 2] = [ForceMode::Force, ForceMode::Potential];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForceMode::Force => "force",
+            ForceMode::Potential => "potential",
+        }
+    }
+}
+
+/// how the compute shader sums the forces of the other particles
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, NoUninit)]
+pub enum ForceAccumulation {
+    /// a plain f32 sum
+    Plain = 0,
+    /// Kahan summation carries the low order bits lost when thousands of
+    /// small contributions are added to a large sum, at the cost of three more additions each
+    Compensated,
+}
+
+unsafe impl Zeroable for ForceAccumulation {}
+
+impl ForceAccumulation {
+    pub const ALL: [ForceAccumulation; 2] =
+        [ForceAccumulation::Plain, ForceAccumulation::Compensated];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForceAccumulation::Plain => "plain sum",
+            ForceAccumulation::Compensated => "compensated sum",
+        }
+    }
+}
+
+/// how the masses of the particles enter the accelerations of the pair forces
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, NoUninit)]
+pub enum MassCoupling {
+    /// the force between two particles grows with both of their masses like gravity,
+    /// so a particle accelerates with the mass of the other one
+    Gravitational = 0,
+    /// the attraction is a force, heavier particles accelerate less, F = m a
+    Inertial,
+    /// the attraction is the acceleration itself, the masses are ignored
+    Direct,
+}
+
+unsafe impl Zeroable for MassCoupling {}
+
+impl MassCoupling {
+    pub const ALL: [MassCoupling; 3] = [
+        MassCoupling::Gravitational,
+        MassCoupling::Inertial,
+        MassCoupling::Direct,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MassCoupling::Gravitational => "m_i m_j coupling",
+            MassCoupling::Inertial => "F = m a",
+            MassCoupling::Direct => "direct acceleration",
+        }
+    }
+}
+
+/// how the force of type i on type j is tied to the force of type j on type i
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatrixSymmetry {
+    Free,
+    /// both forces are the same, as between Newtonian bodies
+    Symmetric,
+    /// one force is the negative of the other, a type has no force on its own kind
+    Antisymmetric,
+}
+
+impl MatrixSymmetry {
+    pub const ALL: [MatrixSymmetry; 3] = [
+        MatrixSymmetry::Free,
+        MatrixSymmetry::Symmetric,
+        MatrixSymmetry::Antisymmetric,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MatrixSymmetry::Free => "free",
+            MatrixSymmetry::Symmetric => "symmetric",
+            MatrixSymmetry::Antisymmetric => "antisymmetric",
+        }
+    }
+}
+
+/// overall strength and on/off switch of the force between two particle types
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct PairForce {
+    pub scale: f32,
+    /// 0 disables the force
+    pub enabled: u32,
+    /// the distance the row of the force curve texture reaches, set by `ForceCurves::apply`.
+    /// 0.0 evaluates the polynome instead
+    pub curve_range: f32,
+    /// particles of the two types farther apart than this do not attract each other,
+    /// set with `SimParams::set_cut_off_distance` or followed by `update_cut_off_distance`
+    pub cut_off_distance: f32,
+}
+
+impl Default for PairForce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PairForce {
+    pub fn new() -> Self {
+        PairForce {
+            scale: 1.0,
+            enabled: 1,
+            curve_range: 0.0,
+            cut_off_distance: 1.0,
+        }
+    }
+}
+
+/// boids terms of a particle of one type towards its neighbours of another type,
+/// layered on top of the attraction force
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Flocking {
+    /// steers towards the mean velocity of the neighbours
+    pub alignment: f32,
+    /// steers towards the mean position of the neighbours
+    pub cohesion: f32,
+    /// pushes away from the neighbours, growing with the inverse of the distance
+    pub separation: f32,
+    /// neighbours closer than this are taken into account
+    pub radius: f32,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Flocking {
+    /// no flocking at all
+    pub fn new() -> Self {
+        Flocking {
+            alignment: 0.0,
+            cohesion: 0.0,
+            separation: 0.0,
+            radius: 1.0,
+        }
+    }
+}
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, NoUninit, Zeroable)]
 pub struct SimParams {
     pub attraction_force: [Poly7; 25],
+    /// multiplies the attraction force polynome with the same index
+    pub pair_forces: [PairForce; 25],
     pub particle_type_masses: [MassWrap; 5],
     pub force_grid_dimensions: [u32; 3],
     pub delta_t: f32,
     pub max_velocity: f32,
+    /// the largest of `bounding_volume_extents`
     pub bounding_volume_radius: f32,
+    /// the largest cut off distance of `pair_forces`, the cells of the particle sort and
+    /// the baked force curves reach this far
     pub cut_off_distance: f32,
     pub distance_exponent: f32,
+    pub force_grid_interpolation: ForceGridInterpolation,
+    /// particles are respawned after this many seconds, 0.0 means they live forever
+    pub particle_lifetime: f32,
+    pub force_grid_boundary: ForceGridBoundary,
+    /// number of force grid layers in the force grid buffer
+    pub num_force_grid_layers: u32,
+    /// the lower 32 bits of the seed of the random numbers generated on the GPU,
+    /// set with `set_seed`
+    pub random_seed: u32,
+    /// particle positions are scaled by this factor when the bounding volume is resized
+    pub boundary_scale: f32,
+    /// number of type change rules in the reaction buffer
+    pub num_reaction_rules: u32,
+    /// number of point attractors in the attractor buffer
+    pub num_attractors: u32,
+    pub confinement: Confinement,
+    /// the soft confinement starts at this fraction of the bounding volume extents
+    pub confinement_start: f32,
+    /// acceleration towards the center at the bounding volume boundary
+    pub confinement_strength: f32,
+    /// power of the polynomial or sharpness of the exponential confinement
+    pub confinement_exponent: f32,
+    /// boids terms with the same indices as `attraction_force`
+    pub flocking: [Flocking; 25],
+    /// number of regions in the region buffer
+    pub num_regions: u32,
+    pub force_mode: ForceMode,
+    /// 1 while the force grid keyframes are animated, the force grid buffer then holds
+    /// the keyframes blended to after the layers
+    pub force_grid_animated: u32,
+    pub force_accumulation: ForceAccumulation,
+    /// particles of the same type closer than this merge into one, 0.0 disables merging
+    pub merge_radius: f32,
+    /// particles heavier than this multiple of the mass of their type split in two,
+    /// 0.0 disables splitting
+    pub split_mass: f32,
+    /// distance between the two halves of a split particle, at least twice `merge_radius`
+    /// so they do not merge again right away
+    pub split_distance: f32,
+    /// 1 while the force grid buffer holds the material grid after the layers and keyframes
+    pub material_grid: u32,
+    /// half the size of the bounding volume along every axis,
+    /// set with `set_bounding_volume_extents` to keep the radius in step
+    pub bounding_volume_extents: [f32; 3],
+    /// fraction of the cut off distance at its end over which the attraction forces fade out,
+    /// so they reach zero with zero slope. 0.0 cuts them off abruptly
+    pub cutoff_window: f32,
+    pub mass_coupling: MassCoupling,
+    /// the height of the floor plane in world space
+    pub floor_height: f32,
+    /// the fraction of its speed into the floor a particle bounces back with
+    pub floor_restitution: f32,
+    /// a bounce slows a particle along the floor by this multiple of the speed change into it
+    pub floor_friction: f32,
+    /// uniform acceleration of every particle
+    pub gravity: [f32; 3],
+    /// 1 while the particles bounce off the floor plane
+    pub floor: u32,
+    /// the axis the wind tunnel flows along, 0 for x, 1 for y and 2 for z
+    pub inflow_axis: u32,
+    /// speed of the wind tunnel stream, negative values flow towards the negative side
+    pub inflow_speed: f32,
+    /// 0.0 for a uniform stream, 1.0 for a parabolic profile still at the walls
+    pub inflow_profile: f32,
+    /// random velocity of the inflowing particles as a fraction of the stream speed
+    pub inflow_turbulence: f32,
+    /// fraction of the particles moved to the emitter plane per second, 0.0 disables it
+    pub emitter_rate: f32,
+    /// position of the emitter plane along the flow, 0.0 at the inflow face, 1.0 at the outflow face
+    pub emitter_position: f32,
+    /// fraction of the cross section of the wind tunnel the emitter covers
+    pub emitter_size: f32,
+    /// the upper 32 bits of the seed, in the padding at the end to keep the layout
+    pub random_seed_high: u32,
+}
+
+impl Default for SimParams {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SimParams {
     pub fn new() -> Self {
         SimParams {
             attraction_force: [Poly7::new(); 25],
+            pair_forces: [PairForce::new(); 25],
             particle_type_masses: [MassWrap::new(1.0); 5],
             force_grid_dimensions: [10; 3],
             delta_t: 0.,
@@ -27,34 +330,215 @@ impl SimParams {
             bounding_volume_radius: 10.,
             cut_off_distance: 1.0,
             distance_exponent: 0.,
+            force_grid_interpolation: ForceGridInterpolation::Nearest,
+            particle_lifetime: 0.,
+            force_grid_boundary: ForceGridBoundary::Clamp,
+            num_force_grid_layers: 1,
+            random_seed: 0,
+            boundary_scale: 1.0,
+            num_reaction_rules: 0,
+            num_attractors: 0,
+            confinement: Confinement::Polynomial,
+            confinement_start: 0.8,
+            confinement_strength: 20.0,
+            confinement_exponent: 2.0,
+            flocking: [Flocking::new(); 25],
+            num_regions: 0,
+            force_mode: ForceMode::Force,
+            force_grid_animated: 0,
+            force_accumulation: ForceAccumulation::Plain,
+            merge_radius: 0.0,
+            split_mass: 0.0,
+            split_distance: 0.1,
+            material_grid: 0,
+            bounding_volume_extents: [10.; 3],
+            cutoff_window: 0.2,
+            mass_coupling: MassCoupling::Gravitational,
+            floor_height: -9.0,
+            floor_restitution: 0.5,
+            floor_friction: 0.3,
+            gravity: [0.0; 3],
+            floor: 0,
+            inflow_axis: 0,
+            inflow_speed: 2.0,
+            inflow_profile: 0.0,
+            inflow_turbulence: 0.1,
+            emitter_rate: 0.0,
+            emitter_position: 0.1,
+            emitter_size: 0.2,
+            random_seed_high: 0,
+        }
+    }
+
+    /// the 64 bit seed held by `random_seed` and `random_seed_high`
+    pub fn seed(&self) -> u64 {
+        (self.random_seed_high as u64) << 32 | self.random_seed as u64
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random_seed = seed as u32;
+        self.random_seed_high = (seed >> 32) as u32;
+    }
+
+    /// particles merge or split after every step
+    pub fn coalescence(&self) -> bool {
+        self.merge_radius > 0.0 || self.split_mass > 0.0
+    }
+
+    /// sets the half sizes of the bounding volume, the radius becomes the largest one
+    pub fn set_bounding_volume_extents(&mut self, extents: [f32; 3]) {
+        self.bounding_volume_extents = extents.map(|e| e.max(MIN_BOUNDING_VOLUME_EXTENT));
+        self.bounding_volume_radius = self.bounding_volume_extents.into_iter().fold(0.0, f32::max);
+    }
+
+    /// sets the cut off distance of every pair of types
+    pub fn set_cut_off_distance(&mut self, distance: f32) {
+        for pair_force in self.pair_forces.iter_mut() {
+            pair_force.cut_off_distance = distance;
+        }
+        self.cut_off_distance = distance;
+    }
+
+    /// `cut_off_distance` becomes the largest cut off distance of the pairs,
+    /// call it after changing one of them
+    pub fn update_cut_off_distance(&mut self) {
+        self.cut_off_distance = self
+            .pair_forces
+            .iter()
+            .map(|pair_force| pair_force.cut_off_distance)
+            .fold(0.0, f32::max);
+    }
+
+    /// the shortest cut off distance of the enabled pairs, `cut_off_distance` if none is
+    pub fn min_cut_off_distance(&self) -> f32 {
+        self.pair_forces
+            .iter()
+            .filter(|pair_force| pair_force.enabled != 0)
+            .map(|pair_force| pair_force.cut_off_distance)
+            .fold(self.cut_off_distance, f32::min)
+    }
+
+    /// all pairs of types share the same cut off distance
+    pub fn cut_off_distances_linked(&self) -> bool {
+        self.pair_forces
+            .iter()
+            .all(|pair_force| pair_force.cut_off_distance == self.cut_off_distance)
+    }
+
+    /// scales the bounding volume uniformly until its largest half size is `radius`
+    pub fn set_bounding_volume_radius(&mut self, radius: f32) {
+        let largest = self.bounding_volume_extents.into_iter().fold(0.0, f32::max);
+        let scale = radius / largest.max(MIN_BOUNDING_VOLUME_EXTENT);
+        self.set_bounding_volume_extents(self.bounding_volume_extents.map(|e| e * scale));
+    }
+
+    /// the bounding volume centered at the origin
+    pub fn bounding_volume_bounds(&self) -> Bounds {
+        let half = V3::from(self.bounding_volume_extents);
+        Bounds {
+            pos: -half,
+            dir: 2.0 * half,
+        }
+    }
+
+    /// acceleration towards the center at `x` times the bounding volume extent along an axis,
+    /// mirrors `confinement_acceleration` in compute.wgsl
+    pub fn confinement_force(&self, x: f32) -> f32 {
+        let depth =
+            ((x - self.confinement_start) / (1.0 - self.confinement_start).max(0.001)).max(0.0);
+        let exponent = self.confinement_exponent.max(0.01);
+        let shape = match self.confinement {
+            Confinement::Wrap | Confinement::WindTunnel => 0.0,
+            Confinement::Polynomial => depth.powf(exponent),
+            Confinement::Exponential => (exponent * depth).exp_m1() / exponent.exp_m1(),
+        };
+        shape * self.confinement_strength
+    }
+
+    /// the corners of the wind tunnel emitter in world space, mirrors `tunnel_position`
+    /// in compute.wgsl
+    pub fn emitter_corners(&self) -> [V3; 4] {
+        let axis = self.inflow_axis as usize % 3;
+        let extents = self.bounding_volume_extents;
+        let flow = if self.inflow_speed >= 0.0 { 1.0 } else { -1.0 };
+        let along = flow * extents[axis] * (2.0 * self.emitter_position - 1.0);
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(u, v)| {
+            let mut corner = [0.0; 3];
+            corner[axis] = along;
+            corner[a] = u * self.emitter_size * extents[a];
+            corner[b] = v * self.emitter_size * extents[b];
+            V3::from(corner)
+        })
+    }
+
+    /// copies the force at `index` of the 5x5 matrices onto its transposed entry,
+    /// the polynome is negated for `MatrixSymmetry::Antisymmetric`
+    pub fn mirror_force(&mut self, index: usize, symmetry: MatrixSymmetry) {
+        let (x, y) = (index % 5, index / 5);
+        let transposed = y + x * 5;
+        match symmetry {
+            MatrixSymmetry::Free => return,
+            MatrixSymmetry::Symmetric => {
+                self.attraction_force[transposed] = self.attraction_force[index];
+            }
+            MatrixSymmetry::Antisymmetric if x == y => {
+                self.attraction_force[index] = Poly7::zero();
+            }
+            MatrixSymmetry::Antisymmetric => {
+                let mut poly = self.attraction_force[index];
+                poly.invert();
+                self.attraction_force[transposed] = poly;
+            }
+        }
+        self.pair_forces[transposed] = self.pair_forces[index];
+    }
+
+    /// makes the forces satisfy `symmetry` by averaging every pair of transposed entries
+    pub fn enforce_symmetry(&mut self, symmetry: MatrixSymmetry) {
+        let sign = match symmetry {
+            MatrixSymmetry::Free => return,
+            MatrixSymmetry::Symmetric => 1.0,
+            MatrixSymmetry::Antisymmetric => -1.0,
+        };
+        for y in 0..5 {
+            for x in y..5 {
+                let (index, transposed) = (x + y * 5, y + x * 5);
+                let (a, b) = (
+                    self.attraction_force[index],
+                    self.attraction_force[transposed],
+                );
+                let coeffs = std::array::from_fn(|i| 0.5 * (a.coeffs[i] + sign * b.coeffs[i]));
+                self.attraction_force[index] = Poly7 { coeffs };
+                self.attraction_force[transposed] = Poly7 {
+                    coeffs: coeffs.map(|c| sign * c),
+                };
+                let (a, b) = (self.pair_forces[index], self.pair_forces[transposed]);
+                let pair_force = &mut self.pair_forces[index];
+                pair_force.scale = 0.5 * (a.scale + b.scale);
+                pair_force.enabled = (a.enabled != 0 || b.enabled != 0) as u32;
+                pair_force.cut_off_distance = 0.5 * (a.cut_off_distance + b.cut_off_distance);
+                self.pair_forces[transposed] = self.pair_forces[index];
+            }
         }
+        self.update_cut_off_distance();
     }
 
     pub fn new_force_grid_centered(&self) -> Grid<V3> {
-        let bvr = self.bounding_volume_radius;
-        let bvr_vec = V3::new(bvr, bvr, bvr);
         Grid::new_centered(
             self.force_grid_dimensions[0] as usize,
             self.force_grid_dimensions[1] as usize,
             self.force_grid_dimensions[2] as usize,
-            Bounds {
-                pos: -bvr_vec,
-                dir: 2.0 * bvr_vec,
-            },
+            self.bounding_volume_bounds(),
         )
     }
 
     pub fn new_force_grid_zero(&self) -> Grid<V3> {
-        let bvr = self.bounding_volume_radius;
-        let bvr_vec = V3::new(bvr, bvr, bvr);
         Grid::new_uniform(
             self.force_grid_dimensions[0] as usize,
             self.force_grid_dimensions[1] as usize,
             self.force_grid_dimensions[2] as usize,
-            Bounds {
-                pos: -bvr_vec,
-                dir: 2.0 * bvr_vec,
-            },
+            self.bounding_volume_bounds(),
             &zero_v3(),
         )
     }
@@ -0,0 +1,233 @@
+use cgmath::{Matrix4, Point3};
+use wgpu::*;
+
+use crate::{
+    camera::Camera,
+    compute::Compute,
+    draw_pass::{DrawBuffer, DrawPass, DrawPassDescriptor, INSTANCE_LAYOUT_PARTICLE},
+    framework,
+    indirect::DRAW_ARGS_OFFSET,
+    renderer::{ParticleRenderParams, PARTICLE_INDICES},
+    sim_params::SimParams,
+    V3,
+};
+
+/// side length of the square slice texture in pixels
+pub const SLICE_VIEW_SIZE: u32 = 384;
+/// the slice shows a little more than the bounding volume, so its walls stay visible
+const SLICE_MARGIN: f32 = 1.05;
+/// distance of the orthographic camera from the near side of the slab
+const SLICE_EYE_DISTANCE: f32 = 1.0;
+
+const AXIS_NAMES: [&str; 3] = ["x", "y", "z"];
+
+/// an orthographic view of the particles in a slab of the volume, looking along one axis.
+/// it is drawn into a texture of its own, which the gui shows as an image, so the inside
+/// of dense clusters can be seen
+pub struct SliceView {
+    pub enabled: bool,
+    /// the slab is perpendicular to this axis
+    pub axis: usize,
+    /// position of the middle of the slab along the axis in world space
+    pub offset: f32,
+    /// the slab reaches this far to either side of the middle
+    pub half_thickness: f32,
+    /// the image of the slice registered with the egui renderer
+    pub texture_id: egui::TextureId,
+    pub draw_pass: DrawPass,
+    view: TextureView,
+    depth_view: TextureView,
+}
+
+impl SliceView {
+    pub fn new(
+        surface_config: &SurfaceConfiguration,
+        device: &Device,
+        queue: &Queue,
+        camera: &mut Camera,
+        egui_rpass: &mut egui_wgpu::renderer::Renderer,
+        params: &ParticleRenderParams,
+    ) -> Self {
+        let mut draw_pass = DrawPass::new(
+            surface_config,
+            device,
+            queue,
+            DrawBuffer::new(device, queue, include_bytes!("../assets/all_textures.png")),
+            camera,
+            DrawPassDescriptor {
+                prefix: "slice view",
+                shader_src: std::borrow::Cow::Borrowed(include_str!("shader.wgsl")),
+                topology: PrimitiveTopology::TriangleList,
+                instance_layout: INSTANCE_LAYOUT_PARTICLE,
+                params: Some(bytemuck::bytes_of(params)),
+                bcreate_viewmatrix: true,
+                bcreate_camera_rotation: true,
+            },
+        );
+        draw_pass.update_vertex_buffer(
+            device,
+            &[
+                (V3::new(-1.0, 1.0, 0.0), [0.0, 1.0]),
+                (V3::new(1.0, 1.0, 0.0), [1.0, 1.0]),
+                (V3::new(-1.0, -1.0, 0.0), [0.0, 0.0]),
+                (V3::new(1.0, -1.0, 0.0), [1.0, 0.0]),
+            ],
+        );
+        draw_pass.update_index_buffer(device, &PARTICLE_INDICES);
+        let size = Extent3d {
+            width: SLICE_VIEW_SIZE,
+            height: SLICE_VIEW_SIZE,
+            depth_or_array_layers: 1,
+        };
+        let create_view = |label, format, usage| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some(label),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+        let view = create_view(
+            "slice view texture",
+            surface_config.format,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+        let depth_view = create_view(
+            "slice view depth texture",
+            TextureFormat::Depth32Float,
+            TextureUsages::RENDER_ATTACHMENT,
+        );
+        let texture_id = egui_rpass.register_native_texture(device, &view, FilterMode::Linear);
+        SliceView {
+            enabled: false,
+            axis: 2,
+            offset: 0.0,
+            half_thickness: 0.5,
+            texture_id,
+            draw_pass,
+            view,
+            depth_view,
+        }
+    }
+
+    pub fn axis_name(axis: usize) -> &'static str {
+        AXIS_NAMES[axis]
+    }
+
+    /// the axes of the volume along the right and up direction of the image
+    pub fn image_axes(&self) -> (usize, usize) {
+        ((self.axis + 1) % 3, (self.axis + 2) % 3)
+    }
+
+    /// half the side length of the square covered by the image in world units
+    pub fn half_extent(&self, sim_params: &SimParams) -> f32 {
+        let (u, v) = self.image_axes();
+        let extents = sim_params.bounding_volume_extents;
+        extents[u].max(extents[v]) * SLICE_MARGIN
+    }
+
+    /// keeps the slab inside of the bounding volume
+    pub fn clamp_offset(&mut self, sim_params: &SimParams) {
+        let extent = sim_params.bounding_volume_extents[self.axis];
+        self.offset = self.offset.clamp(-extent, extent);
+    }
+
+    /// the orthographic matrix looking along the negative axis, the near and far planes
+    /// are the sides of the slab
+    fn view_matrix(&self, half_extent: f32) -> Matrix4<f32> {
+        let (_, v) = self.image_axes();
+        let mut normal = V3::new(0.0, 0.0, 0.0);
+        normal[self.axis] = 1.0;
+        let mut up = V3::new(0.0, 0.0, 0.0);
+        up[v] = 1.0;
+        let center = normal * self.offset;
+        let eye = center + normal * (self.half_thickness + SLICE_EYE_DISTANCE);
+        let projection = cgmath::ortho(
+            -half_extent,
+            half_extent,
+            -half_extent,
+            half_extent,
+            SLICE_EYE_DISTANCE,
+            SLICE_EYE_DISTANCE + 2.0 * self.half_thickness,
+        );
+        let look_at = Matrix4::look_at_rh(
+            Point3::new(eye.x, eye.y, eye.z),
+            Point3::new(center.x, center.y, center.z),
+            up,
+        );
+        framework::OPENGL_TO_WGPU_MATRIX * projection * look_at
+    }
+
+    /// writes the matrix and the render parameters of the next frame, the particles look
+    /// like in the main view apart from the motion blur
+    pub fn update(
+        &mut self,
+        queue: &Queue,
+        render_params: &ParticleRenderParams,
+        sim_params: &SimParams,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        self.clamp_offset(sim_params);
+        let half_extent = self.half_extent(sim_params);
+        let mx = self.view_matrix(half_extent);
+        self.draw_pass.write_view_matrix(queue, mx);
+        let (u, v) = self.image_axes();
+        let mut params = *render_params;
+        params.camera_right = [0.0; 4];
+        params.camera_right[u] = 1.0;
+        params.camera_up = [0.0; 4];
+        params.camera_up[v] = 1.0;
+        // the clip space w of an orthographic view is 1
+        params.pixels_per_unit = SLICE_VIEW_SIZE as f32 / (2.0 * half_extent);
+        params.motion_blur_time = 0.0;
+        self.draw_pass
+            .update_params_buffer(queue, bytemuck::bytes_of(&params));
+    }
+
+    /// draws the particles inside of the slab into the slice texture
+    pub fn encode(&self, encoder: &mut CommandEncoder, compute: &Compute) {
+        if !self.enabled {
+            return;
+        }
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("slice view render pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &self.view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(1.0),
+                    store: StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        self.draw_pass.render_indirect(
+            &mut rpass,
+            &compute.particles_buffers[0],
+            &compute.indirect.args_buffer,
+            DRAW_ARGS_OFFSET,
+        );
+    }
+
+    /// the axis and the offset of the slice plane while it is shown
+    pub fn plane(&self) -> Option<(usize, f32)> {
+        self.enabled.then_some((self.axis, self.offset))
+    }
+}
@@ -0,0 +1,206 @@
+use egui::{Context, FullOutput};
+use egui_wgpu::renderer::ScreenDescriptor;
+use wgpu::*;
+
+use crate::adapters::AdapterEntry;
+use crate::safe_mode::{SafeMode, SAFE_MODE_PARTICLES_PER_AXIS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QualityPreset {
+    Low,
+    Medium,
+    High,
+}
+
+impl QualityPreset {
+    pub const ALL: [QualityPreset; 3] = [
+        QualityPreset::Low,
+        QualityPreset::Medium,
+        QualityPreset::High,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QualityPreset::Low => "low",
+            QualityPreset::Medium => "medium",
+            QualityPreset::High => "high",
+        }
+    }
+
+    pub fn num_particles(&self) -> usize {
+        match self {
+            QualityPreset::Low => 250,
+            QualityPreset::Medium => 1000,
+            QualityPreset::High => 8000,
+        }
+    }
+
+    pub fn force_grid_dimensions(&self) -> [u32; 3] {
+        match self {
+            QualityPreset::Low => [6; 3],
+            QualityPreset::Medium => [10; 3],
+            QualityPreset::High => [16; 3],
+        }
+    }
+}
+
+/// settings chosen in the startup dialog before the simulation is initialized
+#[derive(Clone, Copy, Debug)]
+pub struct StartupConfig {
+    pub quality: QualityPreset,
+    pub num_particles: usize,
+    pub force_grid_dimensions: [u32; 3],
+    /// seeds the particle spawning, the same seed spawns the same particles
+    pub seed: u64,
+}
+
+impl StartupConfig {
+    pub fn new(safe_mode: Option<SafeMode>) -> Self {
+        let seed = seed_from_args().unwrap_or_else(rand::random);
+        if safe_mode.is_some() {
+            let mut config = Self::from_preset(QualityPreset::Low, seed);
+            config.num_particles = SAFE_MODE_PARTICLES_PER_AXIS.pow(3);
+            config
+        } else {
+            Self::from_preset(QualityPreset::Medium, seed)
+        }
+    }
+
+    pub fn from_preset(quality: QualityPreset, seed: u64) -> Self {
+        StartupConfig {
+            quality,
+            num_particles: quality.num_particles(),
+            force_grid_dimensions: quality.force_grid_dimensions(),
+            seed,
+        }
+    }
+}
+
+/// the seed passed as `--seed <n>`
+#[cfg(not(target_arch = "wasm32"))]
+fn seed_from_args() -> Option<u64> {
+    let args: Vec<String> = std::env::args().collect();
+    let value = args
+        .iter()
+        .position(|arg| arg == "--seed")
+        .and_then(|i| args.get(i + 1))?;
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(e) => {
+            log::warn!("ignoring the invalid seed {}: {}", value, e);
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn seed_from_args() -> Option<u64> {
+    None
+}
+
+/// draws the startup dialog until the simulation is launched,
+/// owns the device and queue until they are handed to the renderer
+pub struct Launcher {
+    pub device: Device,
+    pub queue: Queue,
+    pub config: StartupConfig,
+    pub safe_mode: Option<SafeMode>,
+    /// adapters that can draw to the window
+    pub adapters: Vec<AdapterEntry>,
+    /// the adapter the device was opened on
+    pub current_adapter: AdapterInfo,
+    /// set by the dialog, the event loop opens a device on this adapter
+    pub switch_adapter: Option<AdapterEntry>,
+    egui_rpass: egui_wgpu::renderer::Renderer,
+}
+
+impl Launcher {
+    pub fn new(
+        device: Device,
+        queue: Queue,
+        surface_config: &SurfaceConfiguration,
+        safe_mode: Option<SafeMode>,
+        adapters: Vec<AdapterEntry>,
+        current_adapter: AdapterInfo,
+    ) -> Self {
+        let egui_rpass =
+            egui_wgpu::renderer::Renderer::new(&device, surface_config.format, None, 1);
+        Launcher {
+            device,
+            queue,
+            config: StartupConfig::new(safe_mode),
+            safe_mode,
+            adapters,
+            current_adapter,
+            switch_adapter: None,
+            egui_rpass,
+        }
+    }
+
+    /// takes over a new device after the old one was lost, the chosen settings are kept
+    pub fn recover_device(
+        &mut self,
+        device: Device,
+        queue: Queue,
+        surface_config: &SurfaceConfiguration,
+    ) {
+        self.egui_rpass =
+            egui_wgpu::renderer::Renderer::new(&device, surface_config.format, None, 1);
+        self.device = device;
+        self.queue = queue;
+    }
+
+    pub fn render(
+        &mut self,
+        frame: &SurfaceTexture,
+        output: FullOutput,
+        context: &Context,
+        surface_config: &SurfaceConfiguration,
+        scale_factor: f32,
+    ) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("launcher command encoder"),
+            });
+        let clipped_primitives = context.tessellate(output.shapes, 1.0);
+        let view = frame.texture.create_view(&TextureViewDescriptor::default());
+        let screen_descriptor = ScreenDescriptor {
+            size_in_pixels: [surface_config.width, surface_config.height],
+            pixels_per_point: scale_factor,
+        };
+        for (id, image_delta) in &output.textures_delta.set {
+            self.egui_rpass
+                .update_texture(&self.device, &self.queue, *id, image_delta);
+        }
+        for id in &output.textures_delta.free {
+            self.egui_rpass.free_texture(id);
+        }
+        self.egui_rpass.update_buffers(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            &clipped_primitives,
+            &screen_descriptor,
+        );
+        {
+            let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("launcher render pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.egui_rpass
+                .render(&mut rpass, &clipped_primitives, &screen_descriptor);
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+}
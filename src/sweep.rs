@@ -0,0 +1,210 @@
+use std::{
+    fs::File,
+    io::Write,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    compute::Compute, cursor::get_attractors_raw, framework, poly7::Poly7,
+    precision::ParticlePrecision, reactions::get_reaction_rules_raw, reduction::ReductionResult,
+    ParticleSystem, SimParams, V3,
+};
+use cgmath::{InnerSpace, Vector2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use wgpu::*;
+
+/// simulated seconds per step of a candidate
+const SWEEP_DELTA_T: f32 = 1.0 / 60.0;
+
+/// a headless run of many simulations with random attraction forces,
+/// started with `--sweep <candidates>`
+pub struct SweepConfig {
+    pub num_candidates: usize,
+    pub num_steps: usize,
+    pub num_particles: usize,
+    /// devices requested from every adapter, more than one keeps an adapter busy
+    /// while another candidate is being set up
+    pub devices_per_adapter: usize,
+    pub output: String,
+}
+
+impl SweepConfig {
+    /// parses `--sweep <candidates> [--steps n] [--particles n] [--devices-per-adapter n]
+    /// [--output file]`, the adapters can be restricted with WGPU_BACKEND
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let value = |name: &str| {
+            args.iter()
+                .position(|arg| arg == name)
+                .and_then(|i| args.get(i + 1))
+        };
+        let parse = |name: &str, default| {
+            value(name)
+                .and_then(|v| usize::from_str(v).ok())
+                .unwrap_or(default)
+        };
+        value("--sweep")?;
+        Some(SweepConfig {
+            num_candidates: parse("--sweep", 16),
+            num_steps: parse("--steps", 600),
+            num_particles: parse("--particles", 1000),
+            devices_per_adapter: parse("--devices-per-adapter", 1).max(1),
+            output: value("--output")
+                .cloned()
+                .unwrap_or_else(|| String::from("sweep.csv")),
+        })
+    }
+}
+
+struct CandidateResult {
+    seed: u64,
+    adapter: String,
+    result: Option<ReductionResult>,
+}
+
+/// simulates all candidates on every available adapter in parallel and writes one csv line each
+pub fn run(config: &SweepConfig) -> std::io::Result<()> {
+    let instance = framework::create_instance();
+    let adapters: Vec<Adapter> = instance.enumerate_adapters(Backends::all()).collect();
+    if adapters.is_empty() {
+        return Err(std::io::Error::other("no GPU adapters found"));
+    }
+    // candidates are handed out one at a time, so faster devices simulate more of them
+    let next_candidate = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(config.num_candidates));
+    thread::scope(|scope| {
+        for adapter in &adapters {
+            for _ in 0..config.devices_per_adapter {
+                scope.spawn(|| worker(adapter, config, &next_candidate, &results));
+            }
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|r| r.seed);
+    let mut file = File::create(&config.output)?;
+    writeln!(
+        file,
+        "seed,adapter,kinetic_energy,momentum,center_of_mass_distance"
+    )?;
+    for r in &results {
+        match r.result {
+            Some(result) => writeln!(
+                file,
+                "{},\"{}\",{},{},{}",
+                r.seed,
+                r.adapter,
+                result.kinetic_energy,
+                result.momentum.magnitude(),
+                result.center_of_mass.magnitude()
+            )?,
+            None => writeln!(file, "{},\"{}\",,,", r.seed, r.adapter)?,
+        }
+    }
+    println!(
+        "{} of {} candidates written to {}",
+        results.len(),
+        config.num_candidates,
+        config.output
+    );
+    Ok(())
+}
+
+/// owns one device of `adapter` and simulates candidates until none are left
+fn worker(
+    adapter: &Adapter,
+    config: &SweepConfig,
+    next_candidate: &AtomicUsize,
+    results: &Mutex<Vec<CandidateResult>>,
+) {
+    let info = adapter.get_info();
+    let adapter_name = format!("{} ({:?})", info.name, info.backend);
+    let device = pollster::block_on(adapter.request_device(
+        &DeviceDescriptor {
+            label: Some("sweep device descriptor"),
+            features: Features::empty(),
+            limits: Limits::default().using_resolution(adapter.limits()),
+        },
+        None,
+    ));
+    let (device, queue) = match device {
+        Ok(device) => device,
+        Err(e) => {
+            log::warn!("skipping {}: {}", adapter_name, e);
+            return;
+        }
+    };
+    loop {
+        let index = next_candidate.fetch_add(1, Ordering::Relaxed);
+        if index >= config.num_candidates {
+            break;
+        }
+        let seed = index as u64;
+        let result = simulate(&device, &queue, config, seed);
+        println!("candidate {} finished on {}", seed, adapter_name);
+        results.lock().unwrap().push(CandidateResult {
+            seed,
+            adapter: adapter_name.clone(),
+            result,
+        });
+    }
+}
+
+/// attraction force polynome through random points, reproducible for a given rng state
+fn random_poly(rng: &mut StdRng) -> Poly7 {
+    let points = std::array::from_fn(|i| Vector2::new(i as f32 / 7.0, rng.gen_range(-1.0..1.0)));
+    Poly7::from_points(points).unwrap_or_else(Poly7::zero)
+}
+
+fn simulate(
+    device: &Device,
+    queue: &Queue,
+    config: &SweepConfig,
+    seed: u64,
+) -> Option<ReductionResult> {
+    let mut sim_params = SimParams::new();
+    sim_params.set_seed(seed);
+    sim_params.delta_t = SWEEP_DELTA_T;
+    let mut rng = StdRng::seed_from_u64(seed);
+    for poly in sim_params.attraction_force.iter_mut() {
+        *poly = random_poly(&mut rng);
+    }
+    let mut psys = ParticleSystem::new(V3::new(5.0, 2.0, 2.0), 0, 0, 0, seed, &sim_params);
+    psys.set_num_particles(config.num_particles);
+    let mut compute = Compute::new(
+        device,
+        queue,
+        ParticlePrecision::Full,
+        &psys.particles,
+        &psys.get_layered_force_vectors().0,
+        &get_reaction_rules_raw(&[]).0,
+        &get_attractors_raw(&[]).0,
+    );
+    compute.update_sim_params(device, queue, &sim_params);
+    for _ in 0..config.num_steps {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("sweep command encoder"),
+        });
+        {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("sweep compute pass"),
+                timestamp_writes: None,
+            });
+            compute.compute(&mut cpass);
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("sweep readback encoder"),
+    });
+    compute.encode_readbacks(device, &mut encoder);
+    queue.submit(Some(encoder.finish()));
+    compute.after_submit();
+    device.poll(Maintain::Wait);
+    compute.reduction.poll(device);
+    compute.reduction.result
+}
@@ -0,0 +1,80 @@
+use winit::event::{Touch, TouchPhase};
+
+/// what a touch event does, one finger edits like the left mouse button,
+/// two fingers move the camera
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    /// the first finger touched the screen at (x, y)
+    Press(f32, f32),
+    /// the editing finger moved to (x, y)
+    Drag(f32, f32),
+    /// the editing finger was lifted or a second finger took over
+    Release,
+    /// the distance between two fingers changed by `zoom`, a ratio below 1 when they
+    /// spread apart, and their midpoint moved by `pan` pixels
+    TwoFinger { zoom: f32, pan: [f32; 2] },
+}
+
+/// turns the touch events of the window into gestures
+pub struct TouchInput {
+    /// id and position of every finger on the screen
+    touches: Vec<(u64, [f32; 2])>,
+    /// a single finger is editing, it ends when a second finger touches
+    editing: bool,
+}
+
+impl TouchInput {
+    pub fn new() -> Self {
+        TouchInput {
+            touches: Vec::new(),
+            editing: false,
+        }
+    }
+
+    pub fn event(&mut self, touch: &Touch) -> Option<Gesture> {
+        let pos = [touch.location.x as f32, touch.location.y as f32];
+        match touch.phase {
+            TouchPhase::Started => {
+                self.touches.push((touch.id, pos));
+                match self.touches.len() {
+                    1 => {
+                        self.editing = true;
+                        Some(Gesture::Press(pos[0], pos[1]))
+                    }
+                    _ => self.stop_editing(),
+                }
+            }
+            TouchPhase::Moved => {
+                let before = self.two_fingers();
+                let (_, p) = self.touches.iter_mut().find(|(id, _)| *id == touch.id)?;
+                *p = pos;
+                if self.editing {
+                    return Some(Gesture::Drag(pos[0], pos[1]));
+                }
+                let ((mid_before, dist_before), (mid, dist)) = before.zip(self.two_fingers())?;
+                Some(Gesture::TwoFinger {
+                    zoom: if dist > 0.0 { dist_before / dist } else { 1.0 },
+                    pan: [mid[0] - mid_before[0], mid[1] - mid_before[1]],
+                })
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.retain(|(id, _)| *id != touch.id);
+                self.stop_editing()
+            }
+        }
+    }
+
+    fn stop_editing(&mut self) -> Option<Gesture> {
+        std::mem::replace(&mut self.editing, false).then_some(Gesture::Release)
+    }
+
+    /// midpoint and distance of the first two fingers
+    fn two_fingers(&self) -> Option<([f32; 2], f32)> {
+        let [(_, a), (_, b), ..] = self.touches[..] else {
+            return None;
+        };
+        let mid = [(a[0] + b[0]) * 0.5, (a[1] + b[1]) * 0.5];
+        let dist = ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+        Some((mid, dist))
+    }
+}
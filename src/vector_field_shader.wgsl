@@ -12,6 +12,39 @@ struct Transform {
 @binding(0)
 var<uniform> u_transform: Transform;
 
+// mirrors `GlyphLodParams` in glyph_lod.rs
+struct GlyphLodParams {
+    // pixels covered by one world unit at a distance of one unit from the camera
+    pixels_per_unit: f32,
+    full_arrow_pixels: f32,
+    cone_pixels: f32,
+    // length of the arrow mesh before it is scaled by the vector
+    glyph_length: f32,
+    // the cone and line segment vertices start here in the vertex buffer
+    cone_first_vertex: u32,
+    line_first_vertex: u32,
+    num_lods: u32,
+    _padd: u32,
+};
+
+@group(2)
+@binding(0)
+var<uniform> lod_params: GlyphLodParams;
+
+// 0 for the full arrow, 1 for the cone and 2 for the line segment
+fn glyph_lod(arrow_pos: vec3<f32>, arrow_dir: vec3<f32>) -> u32 {
+    let center = u_transform.transform * vec4<f32>(arrow_pos, 1.0);
+    let length_world = length(arrow_dir) * 0.1 * lod_params.glyph_length;
+    let pixels = length_world * lod_params.pixels_per_unit / max(center.w, 0.0001);
+    var lod = 2u;
+    if pixels >= lod_params.full_arrow_pixels {
+        lod = 0u;
+    } else if pixels >= lod_params.cone_pixels {
+        lod = 1u;
+    }
+    return min(lod, lod_params.num_lods - 1u);
+}
+
 fn rotation_between(a: vec3<f32>, b: vec3<f32>) -> mat3x3<f32> {
     let an = normalize(a);
     let bn = normalize(b);
@@ -36,6 +69,7 @@ fn rotation_from_010_to(up: vec3<f32>) -> mat3x3<f32> {
 
 @vertex
 fn vs_main(
+        @builtin(vertex_index) vertex_index: u32,
         @location(0) in_pos: vec3<f32>,
         @location(1) tex_coord: vec2<f32>,
         @location(2) arrow_pos: vec4<f32>,
@@ -58,6 +92,13 @@ fn vs_main(
     out.out_pos = u_transform.transform * vec4<f32>(rot * in_pos_alen + arrow_pos.xyz , 1.0);
     out.tex_coord = tex_coord;
     out.color = color;
+    // every variant is drawn for every instance, the ones not matching the size on screen
+    // are moved behind the far plane
+    let vertex_lod = u32(vertex_index >= lod_params.cone_first_vertex)
+        + u32(vertex_index >= lod_params.line_first_vertex);
+    if vertex_lod != glyph_lod(arrow_pos.xyz, arrow_dir.xyz) {
+        out.out_pos = vec4<f32>(0.0, 0.0, 2.0, 1.0);
+    }
     return out;
 }
 
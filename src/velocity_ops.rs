@@ -0,0 +1,183 @@
+use std::borrow::Cow;
+
+use crate::{indirect::split_workgroups, precision::ParticlePrecision, MassWrap};
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    *,
+};
+
+const APPLY_GROUP_SIZE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VelocityOp {
+    ZeroVelocities,
+    /// subtracts the mass weighted mean velocity
+    RemoveMomentum,
+    /// rescales the velocities relative to the center of mass to `target_temperature`
+    SetTemperature,
+}
+
+/// mirrors `VelocityOpParams` in velocity_ops.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct VelocityOpParams {
+    op: u32,
+    target_temperature: f32,
+    _padd: [u32; 2],
+}
+
+/// one shot edits of the particle velocities, done entirely on the GPU
+pub struct VelocityOps {
+    sum_pipeline: ComputePipeline,
+    apply_pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    masses_buffer: Buffer,
+    totals_buffer: Buffer,
+    pub target_temperature: f32,
+    /// applied to the particles with the next frame
+    pub pending: Option<VelocityOp>,
+}
+
+impl VelocityOps {
+    pub fn new(device: &Device, masses: &[MassWrap; 5], precision: ParticlePrecision) -> Self {
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("velocity ops bind group layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Uniform),
+                buffer_entry(2, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(3, BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let (sum_pipeline, apply_pipeline) =
+            Self::create_pipelines(device, &bind_group_layout, precision);
+        let masses_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("velocity ops masses buffer"),
+            contents: bytemuck::cast_slice(masses),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let totals_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("velocity ops totals buffer"),
+            size: 32,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        VelocityOps {
+            sum_pipeline,
+            apply_pipeline,
+            bind_group_layout,
+            masses_buffer,
+            totals_buffer,
+            target_temperature: 0.1,
+            pending: None,
+        }
+    }
+
+    fn create_pipelines(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        precision: ParticlePrecision,
+    ) -> (ComputePipeline, ComputePipeline) {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("velocity ops shader module"),
+            source: ShaderSource::Wgsl(Cow::Owned(
+                precision.shader_source(include_str!("velocity_ops.wgsl")),
+            )),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("velocity ops pipeline layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+        (create_pipeline("sum"), create_pipeline("apply"))
+    }
+
+    /// rebuilds the pipelines for particle buffers stored with `precision`
+    pub fn set_precision(&mut self, device: &Device, precision: ParticlePrecision) {
+        (self.sum_pipeline, self.apply_pipeline) =
+            Self::create_pipelines(device, &self.bind_group_layout, precision);
+    }
+
+    pub fn update_masses(&mut self, queue: &Queue, masses: &[MassWrap; 5]) {
+        queue.write_buffer(&self.masses_buffer, 0, bytemuck::cast_slice(masses));
+    }
+
+    /// encodes the pending operation on `particles_buffer`
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        particles_buffer: &Buffer,
+        num_particles: usize,
+    ) {
+        let Some(op) = self.pending.take() else {
+            return;
+        };
+        if num_particles == 0 {
+            return;
+        }
+        let params = VelocityOpParams {
+            op: op as u32,
+            target_temperature: self.target_temperature,
+            _padd: [0; 2],
+        };
+        let params_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("velocity ops params buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("velocity ops bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: self.masses_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: particles_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: self.totals_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("velocity ops pass"),
+            timestamp_writes: None,
+        });
+        cpass.set_bind_group(0, &bind_group, &[]);
+        if op != VelocityOp::ZeroVelocities {
+            cpass.set_pipeline(&self.sum_pipeline);
+            cpass.dispatch_workgroups(1, 1, 1);
+        }
+        cpass.set_pipeline(&self.apply_pipeline);
+        let (x, y) = split_workgroups(num_particles.div_ceil(APPLY_GROUP_SIZE) as u32);
+        cpass.dispatch_workgroups(x, y, 1);
+    }
+}
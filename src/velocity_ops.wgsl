@@ -0,0 +1,96 @@
+struct MassWrap {
+  @size(16) mass: f32,
+}
+
+struct VelocityOpParams {
+  // 0: zero velocities, 1: remove momentum, 2: set temperature
+  op: u32,
+  target_temperature: f32,
+};
+
+struct Totals {
+  // momentum, total mass
+  momentum_mass: vec4<f32>,
+  kinetic_energy: f32,
+};
+
+@group(0) @binding(0) var<uniform> particle_type_masses: array<MassWrap, 5>;
+@group(0) @binding(1) var<uniform> params: VelocityOpParams;
+@group(0) @binding(2) var<storage, read_write> particles: array<ParticleStorage>;
+@group(0) @binding(3) var<storage, read_write> totals: Totals;
+
+const SUM_GROUP_SIZE: u32 = 256u;
+
+var<workgroup> scratch_momentum_mass: array<vec4<f32>, 256>;
+var<workgroup> scratch_energy: array<f32, 256>;
+
+// a single workgroup strides over all particles, so no second reduction step is needed
+@compute
+@workgroup_size(256)
+fn sum(@builtin(local_invocation_id) local_invocation_id: vec3<u32>) {
+    let local_index = local_invocation_id.x;
+    var momentum_mass = vec4<f32>();
+    var energy = 0.0;
+    for (var i = local_index; i < arrayLength(&particles); i += SUM_GROUP_SIZE) {
+        let p = load_particle(particles[i]);
+        let m = particle_type_masses[p.ty].mass * p.mass;
+        momentum_mass += vec4<f32>(p.vel.xyz * m, m);
+        energy += 0.5 * m * dot(p.vel.xyz, p.vel.xyz);
+    }
+    scratch_momentum_mass[local_index] = momentum_mass;
+    scratch_energy[local_index] = energy;
+    workgroupBarrier();
+
+    for (var stride = SUM_GROUP_SIZE / 2u; stride > 0u; stride >>= 1u) {
+        if local_index < stride {
+            scratch_momentum_mass[local_index] += scratch_momentum_mass[local_index + stride];
+            scratch_energy[local_index] += scratch_energy[local_index + stride];
+        }
+        workgroupBarrier();
+    }
+
+    if local_index == 0u {
+        totals.momentum_mass = scratch_momentum_mass[0];
+        totals.kinetic_energy = scratch_energy[0];
+    }
+}
+
+@compute
+@workgroup_size(64)
+fn apply(
+    @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let index = invocation_index(global_invocation_id, num_workgroups, 64u);
+    let num_particles = arrayLength(&particles);
+    if index >= num_particles {
+        return;
+    }
+    var particle = load_particle(particles[index]);
+    var vel = particle.vel.xyz;
+    let total_mass = totals.momentum_mass.w;
+    switch params.op {
+        case 0u: {
+            vel = vec3<f32>();
+        }
+        case 1u: {
+            if total_mass > 0.0 {
+                vel -= totals.momentum_mass.xyz / total_mass;
+            }
+        }
+        default: {
+            // only the thermal motion is rescaled, the center of mass keeps its velocity
+            if total_mass > 0.0 {
+                let mean_vel = totals.momentum_mass.xyz / total_mass;
+                let thermal_energy = totals.kinetic_energy - 0.5 * total_mass * dot(mean_vel, mean_vel);
+                // equipartition with three degrees of freedom per particle and k_B = 1
+                let target_energy = 1.5 * f32(num_particles) * params.target_temperature;
+                if thermal_energy > 0.0 {
+                    vel = mean_vel + (vel - mean_vel) * sqrt(target_energy / thermal_energy);
+                }
+            }
+        }
+    }
+    particle.vel = vec4<f32>(vel, particle.vel.w);
+    particles[index] = store_particle(particle);
+}